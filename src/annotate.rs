@@ -0,0 +1,103 @@
+//! Sorting with a model-assigned annotation per element.
+//!
+//! Several operations ask the model to reply with the sorted elements wrapped
+//! in objects of the form `{"item": <element>, ...}`, where the extra fields
+//! carry what the model inferred about the element (a confidence score, a
+//! color's HSL values, a distance, ...). This module implements the shared
+//! request, parsing, and verification.
+
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError, parse};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// The annotation fields requested for each element.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Annotation<'s> {
+    /// The extra fields of each object, e.g. `"confidence": <number>`.
+    pub(crate) fields: &'s str,
+
+    /// What the fields mean, e.g. `"confidence" is a number between 0 and 1`.
+    pub(crate) meaning: &'s str,
+
+    /// The approximate serialized size of the fields of one element, in bytes.
+    pub(crate) size: usize,
+}
+
+/// An element of an annotated response.
+#[derive(Debug, Deserialize)]
+struct Annotated<T, A> {
+    item: T,
+    #[serde(flatten)]
+    annotation: A,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts the items and returns each with the model's annotation.
+    ///
+    /// `instruction` completes "Sort the following JSON array ...", e.g.
+    /// "with ascending order". The elements are checked to be a permutation of
+    /// the input if verification is enabled.
+    pub(crate) async fn sort_annotated<T, A>(
+        &self,
+        operation: Operation,
+        items: &[T],
+        instruction: &str,
+        annotation: Annotation<'_>,
+    ) -> Result<Vec<(T, A)>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+        A: DeserializeOwned,
+    {
+        self.sort_annotated_checked(operation, items, instruction, annotation, |_| Ok(()))
+            .await
+    }
+
+    /// Like [`sort_annotated`](Self::sort_annotated), but also runs `check`
+    /// on every reply; an error from it counts as an invalid reply and is
+    /// retried.
+    pub(crate) async fn sort_annotated_checked<T, A>(
+        &self,
+        operation: Operation,
+        items: &[T],
+        instruction: &str,
+        annotation: Annotation<'_>,
+        check: impl Fn(&[(T, A)]) -> Result<(), VibesortError>,
+    ) -> Result<Vec<(T, A)>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+        A: DeserializeOwned,
+    {
+        let json_array = self.json_format.to_string(items)?;
+        // Each element is wrapped in `{"item": ..., <fields>}`
+        let max_tokens = self.max_tokens_for(json_array.len() + annotation.size * items.len());
+
+        let (system_prompt, user_content) = self.render_prompt(operation, json_array, || {
+            format!(
+                "You are a helpful assistant that sorts arrays. Sort the following JSON array {}. Return ONLY a JSON array of objects of the form {{\"item\": <element>, {}}}, in sorted order, where \"item\" is the original element unchanged and {}.",
+                instruction, annotation.fields, annotation.meaning
+            )
+        })?;
+        let (system_prompt, user_content, check) = (&system_prompt, &user_content, &check);
+        self.retrying(|escalation| async move {
+            let completion = self
+                .chat(system_prompt, user_content, max_tokens, escalation)
+                .await?;
+
+            let annotated: Vec<Annotated<T, A>> = parse::parse_array(&completion.content)?;
+            let (mut sorted, annotations): (Vec<T>, Vec<A>) = annotated
+                .into_iter()
+                .map(|annotated| (annotated.item, annotated.annotation))
+                .unzip();
+
+            if self.verify {
+                self.verify_sorted(items, &mut sorted)?;
+            }
+
+            let annotated: Vec<(T, A)> = sorted.into_iter().zip(annotations).collect();
+            check(&annotated)?;
+            Ok(annotated)
+        })
+        .await
+    }
+}
@@ -0,0 +1,75 @@
+//! Sorting fixed-size arrays.
+
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+impl<'a> Vibesort<'a> {
+    /// Sorts a fixed-size array, returning an array of the same size.
+    ///
+    /// The length is part of the return type, so callers never have to check
+    /// it. If the model returns a different number of elements, the sort
+    /// fails with [`VibesortError::VerificationFailed`] even when
+    /// [`verify`](Self::verify) is disabled.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let sorted: [i32; 3] = sorter.sort_array(&[3, 1, 2]).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_array<T, const N: usize>(
+        &self,
+        items: &[T; N],
+    ) -> Result<[T; N], VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let sorted = self.sort_with_report(items).await?.items;
+        let len = sorted.len();
+        sorted.try_into().map_err(|_| {
+            VibesortError::VerificationFailed(format!("expected {} elements, got {}", N, len))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+
+    #[tokio::test]
+    async fn test_sort_array() {
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(MockBackend::new());
+
+        let sorted = sorter.sort_array(&[3, 1, 2]).await.unwrap();
+        assert_eq!(sorted, [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_sort_array_rejects_wrong_length() {
+        let backend = MockBackend::new().respond_with("[1,2]");
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend);
+
+        match sorter.sort_array(&[3, 1, 2]).await.unwrap_err() {
+            VibesortError::VerificationFailed(msg) => {
+                assert_eq!(msg, "expected 3 elements, got 2")
+            }
+            err => panic!("Expected VerificationFailed, got {:?}", err),
+        }
+    }
+}
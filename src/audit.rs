@@ -0,0 +1,152 @@
+//! Sorting locally and asking the LLM to audit the result.
+
+use crate::indexed::{describe_payload, indexed_payload};
+use crate::prompt::Operation;
+use crate::{Order, Vibesort, VibesortError, parse};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// An element the model flagged as suspicious in an audited sort.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditFlag {
+    /// The position of the element in [`Audited::items`].
+    pub index: usize,
+
+    /// Why the model thinks the element is out of place.
+    pub reason: String,
+}
+
+/// The result of [`Vibesort::sort_audited`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Audited<T> {
+    /// The items, sorted locally by their [`Ord`] implementation.
+    pub items: Vec<T>,
+
+    /// The elements the model flagged, in order of position.
+    pub flags: Vec<AuditFlag>,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts the items locally and asks the LLM to flag suspicious placements.
+    ///
+    /// The ordering is deterministic: the items are sorted by their [`Ord`]
+    /// implementation in the configured [`order`](Self::order) and never
+    /// rearranged by the model. The model only reviews the result against the
+    /// configured criterion and flags elements that look out of place, such as
+    /// user-entered dates whose parsed value does not match what was meant.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the model flags a
+    /// position that does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .criterion("by the date the user meant to enter");
+    ///
+    /// let dates = ["2024-03-01", "2024-01-15", "2042-02-10"].map(String::from);
+    /// let audited = sorter.sort_audited(&dates).await?;
+    /// for flag in &audited.flags {
+    ///     println!("{}: {}", audited.items[flag.index], flag.reason);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_audited<T>(&self, items: &[T]) -> Result<Audited<T>, VibesortError>
+    where
+        T: Ord + Clone + Serialize + DeserializeOwned,
+    {
+        let mut sorted = items.to_vec();
+        sorted.sort();
+        if self.order == Order::Descending {
+            sorted.reverse();
+        }
+
+        let json_array = indexed_payload(&sorted)?;
+        let max_tokens = self.max_tokens_for(json_array.len());
+
+        let (system_prompt, user_content) =
+            self.render_prompt(Operation::Audit, json_array, || {
+                format!(
+                    "You are a helpful assistant that audits sorted arrays. {} The elements were sorted mechanically {}. Some elements may be noisy or ambiguous, so their position may not reflect what was meant. Return ONLY a JSON array of objects of the form {{\"index\": <number>, \"reason\": \"...\"}} for the elements that look out of place, or an empty array if the order looks right.",
+                    describe_payload("element"),
+                    self.sort_instruction()
+                )
+            })?;
+        let (system_prompt, user_content) = (&system_prompt, &user_content);
+        let len = sorted.len();
+        let mut flags = self
+            .retrying(|escalation| async move {
+                let completion = self
+                    .chat(system_prompt, user_content, max_tokens, escalation)
+                    .await?;
+
+                let flags: Vec<AuditFlag> = parse::parse_array(&completion.content)?;
+                if let Some(flag) = flags.iter().find(|flag| flag.index >= len) {
+                    return Err(VibesortError::VerificationFailed(format!(
+                        "flagged position {} of {} elements",
+                        flag.index, len
+                    )));
+                }
+                Ok(flags)
+            })
+            .await?;
+        flags.sort_by_key(|flag| flag.index);
+
+        Ok(Audited {
+            items: sorted,
+            flags,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_audited() {
+        let backend = Arc::new(
+            MockBackend::new()
+                .respond_with(r#"[{"index": 2, "reason": "2042 is probably a typo for 2024"}]"#),
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let dates = ["2042-02-10", "2024-03-01", "2024-01-15"].map(String::from);
+        let audited = sorter.sort_audited(&dates).await.unwrap();
+        assert_eq!(
+            audited.items,
+            vec!["2024-01-15", "2024-03-01", "2042-02-10"]
+        );
+        assert_eq!(audited.flags.len(), 1);
+        assert_eq!(audited.items[audited.flags[0].index], "2042-02-10");
+
+        let user = backend.requests()[0].body["messages"][1]["content"].clone();
+        assert!(
+            user.as_str()
+                .unwrap()
+                .starts_with(r#"[{"index":0,"item":"2024-01-15"}"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sort_audited_rejects_unknown_positions() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with(r#"[{"index": 5, "reason": "?"}]"#));
+
+        let result = sorter.sort_audited(&[2, 1]).await;
+        assert!(matches!(result, Err(VibesortError::VerificationFailed(_))));
+    }
+}
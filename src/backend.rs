@@ -0,0 +1,493 @@
+//! Transport layer between [`Vibesort`](crate::Vibesort) and an LLM API.
+//!
+//! A [`Backend`] receives a fully prepared chat completion request and returns
+//! the raw HTTP-style response. Everything above it (prompting, parsing, error
+//! handling) is shared, so swapping the backend changes only how the request
+//! reaches the model.
+//!
+//! Gateways that require signed requests are supported by a
+//! [`RequestSigner`], which adds headers computed from the exact body sent;
+//! [`HmacSigner`] implements the common HMAC-SHA256 scheme.
+
+use crate::digest::{hex, hmac_sha256, sha256};
+use crate::{Order, VibesortError};
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use secrecy::{ExposeSecret, SecretSlice, SecretString};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A boxed future returned by [`Backend::send`].
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A chat completion request ready to be sent to an LLM API.
+#[derive(Debug, Clone)]
+pub struct BackendRequest {
+    /// The full URL of the chat completions endpoint.
+    pub url: String,
+
+    /// The API key used to authenticate the request.
+    pub api_key: SecretString,
+
+    /// The request body in OpenAI's chat completion format.
+    pub body: serde_json::Value,
+
+    /// A structured description of the sort being requested, if any.
+    ///
+    /// Set for plain sort requests so that non-LLM backends (such as
+    /// [`LocalEngine`](crate::engine::LocalEngine)) can honour the requested
+    /// order without parsing the prompt. It is not sent over the network.
+    pub task: Option<SortTask>,
+}
+
+/// The array and order of a plain sort request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortTask {
+    /// The elements to sort, as JSON values.
+    pub items: Vec<serde_json::Value>,
+
+    /// The requested order.
+    pub order: Order,
+}
+
+/// The raw response returned by a [`Backend`].
+#[derive(Debug, Clone)]
+pub struct BackendResponse {
+    /// The HTTP status code of the response.
+    pub status: StatusCode,
+
+    /// The response headers.
+    pub headers: HeaderMap,
+
+    /// The response body.
+    pub body: String,
+}
+
+impl BackendResponse {
+    /// Creates a response with the given status and body and no headers.
+    pub fn new(status: StatusCode, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: body.into(),
+        }
+    }
+}
+
+/// A transport that delivers chat completion requests to an LLM.
+///
+/// Implement this trait to route requests through a custom client, a proxy, or
+/// a test double. The default transport is [`HttpBackend`].
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::backend::{Backend, BackendRequest, BackendResponse, BoxFuture};
+/// use vibesort_rs::VibesortError;
+///
+/// #[derive(Debug)]
+/// struct AlwaysSorted;
+///
+/// impl Backend for AlwaysSorted {
+///     fn send(
+///         &self,
+///         _request: BackendRequest,
+///     ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>> {
+///         Box::pin(async {
+///             let body = r#"{"choices":[{"message":{"content":"[1,2,3]"}}]}"#;
+///             Ok(BackendResponse::new(reqwest::StatusCode::OK, body))
+///         })
+///     }
+/// }
+/// ```
+pub trait Backend: fmt::Debug + Send + Sync {
+    /// Sends the request and returns the raw response.
+    ///
+    /// Non-success status codes should be returned as a response rather than an
+    /// error, so that they are reported consistently as
+    /// [`VibesortError::ApiError`].
+    fn send(
+        &self,
+        request: BackendRequest,
+    ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>>;
+}
+
+impl<B: Backend + ?Sized> Backend for std::sync::Arc<B> {
+    fn send(
+        &self,
+        request: BackendRequest,
+    ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>> {
+        (**self).send(request)
+    }
+}
+
+/// Computes the headers that authenticate a request to a gateway.
+///
+/// The signer is called by [`HttpBackend`] for every request, with the exact
+/// bytes of the body that will be sent.
+///
+/// # Example
+///
+/// ```
+/// use reqwest::header::{HeaderMap, HeaderValue};
+/// use vibesort_rs::VibesortError;
+/// use vibesort_rs::backend::RequestSigner;
+///
+/// /// Sends the body length, for a gateway that checks it.
+/// #[derive(Debug)]
+/// struct LengthSigner;
+///
+/// impl RequestSigner for LengthSigner {
+///     fn sign(&self, _url: &str, body: &[u8]) -> Result<HeaderMap, VibesortError> {
+///         let mut headers = HeaderMap::new();
+///         headers.insert("x-body-length", HeaderValue::from(body.len()));
+///         Ok(headers)
+///     }
+/// }
+/// ```
+pub trait RequestSigner: fmt::Debug + Send + Sync {
+    /// Returns the headers to add to a `POST` of `body` to `url`.
+    ///
+    /// Returned headers replace headers of the same name.
+    fn sign(&self, url: &str, body: &[u8]) -> Result<HeaderMap, VibesortError>;
+}
+
+/// A [`RequestSigner`] that signs the timestamp and body digest of every
+/// request with HMAC-SHA256.
+///
+/// Three headers are added, under configurable names:
+///
+/// - `X-Timestamp`: the current Unix time in seconds,
+/// - `X-Content-SHA256`: the hexadecimal SHA-256 digest of the body,
+/// - `X-Signature`: the hexadecimal HMAC-SHA256 of
+///   `"{timestamp}\n{digest}"` under the shared secret.
+///
+/// # Example
+///
+/// ```no_run
+/// use vibesort_rs::Vibesort;
+/// use vibesort_rs::backend::HmacSigner;
+///
+/// let sorter = Vibesort::new(
+///     "your-api-key",
+///     "gpt-3.5-turbo",
+///     "https://gateway.internal/v1",
+/// )
+/// .request_signer(HmacSigner::new("shared-secret").signature_header("X-Gateway-Signature"));
+/// ```
+#[derive(Clone)]
+pub struct HmacSigner {
+    secret: Arc<SecretSlice<u8>>,
+    timestamp_header: HeaderName,
+    digest_header: HeaderName,
+    signature_header: HeaderName,
+}
+
+impl fmt::Debug for HmacSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HmacSigner")
+            .field("timestamp_header", &self.timestamp_header)
+            .field("digest_header", &self.digest_header)
+            .field("signature_header", &self.signature_header)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HmacSigner {
+    /// Creates a signer with the given shared secret and the default header
+    /// names.
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: Arc::new(SecretSlice::from(secret.into())),
+            timestamp_header: HeaderName::from_static("x-timestamp"),
+            digest_header: HeaderName::from_static("x-content-sha256"),
+            signature_header: HeaderName::from_static("x-signature"),
+        }
+    }
+
+    /// Sets the name of the timestamp header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid header name.
+    pub fn timestamp_header(mut self, name: &str) -> Self {
+        self.timestamp_header = header_name(name);
+        self
+    }
+
+    /// Sets the name of the body digest header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid header name.
+    pub fn digest_header(mut self, name: &str) -> Self {
+        self.digest_header = header_name(name);
+        self
+    }
+
+    /// Sets the name of the signature header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid header name.
+    pub fn signature_header(mut self, name: &str) -> Self {
+        self.signature_header = header_name(name);
+        self
+    }
+
+    /// Returns the headers signing `body` at the given Unix time.
+    fn sign_at(&self, timestamp: u64, body: &[u8]) -> HeaderMap {
+        let timestamp = timestamp.to_string();
+        let digest = hex(&sha256(body));
+        let payload = format!("{}\n{}", timestamp, digest);
+        let signature = hex(&hmac_sha256(
+            self.secret.expose_secret(),
+            payload.as_bytes(),
+        ));
+
+        // Decimal digits and hexadecimal are always valid header values
+        let value = |text: String| HeaderValue::try_from(text).expect("invalid header value");
+        let mut headers = HeaderMap::new();
+        headers.insert(self.timestamp_header.clone(), value(timestamp));
+        headers.insert(self.digest_header.clone(), value(digest));
+        headers.insert(self.signature_header.clone(), value(signature));
+        headers
+    }
+}
+
+impl RequestSigner for HmacSigner {
+    fn sign(&self, _url: &str, body: &[u8]) -> Result<HeaderMap, VibesortError> {
+        Ok(self.sign_at(crate::rt::unix_time().as_secs(), body))
+    }
+}
+
+fn header_name(name: &str) -> HeaderName {
+    HeaderName::try_from(name).unwrap_or_else(|_| panic!("invalid header name {:?}", name))
+}
+
+/// The default [`Backend`], which sends requests over HTTP using `reqwest`.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Default)]
+pub struct HttpBackend {
+    client: reqwest::Client,
+    signer: Option<Arc<dyn RequestSigner>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HttpBackend {
+    /// Creates a backend that sends requests with the given HTTP client.
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            signer: None,
+        }
+    }
+
+    /// Signs every request with the given signer.
+    pub fn signer(self, signer: impl RequestSigner + 'static) -> Self {
+        self.with_signer(Some(Arc::new(signer)))
+    }
+
+    /// Replaces the signer of this backend.
+    pub(crate) fn with_signer(mut self, signer: Option<Arc<dyn RequestSigner>>) -> Self {
+        self.signer = signer;
+        self
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Backend for HttpBackend {
+    fn send(
+        &self,
+        request: BackendRequest,
+    ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>> {
+        Box::pin(async move {
+            // Serialized once, so the signature covers the bytes sent
+            let body = serde_json::to_vec(&request.body)?;
+            let mut builder = self
+                .client
+                .post(&request.url)
+                .bearer_auth(request.api_key.expose_secret())
+                .header(CONTENT_TYPE, "application/json");
+            if let Some(signer) = &self.signer {
+                builder = builder.headers(signer.sign(&request.url, &body)?);
+            }
+            let response = builder.body(body).send().await?;
+
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response.text().await?;
+
+            Ok(BackendResponse {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+}
+
+/// The default [`Backend`] in Cloudflare Workers, which sends requests with
+/// the Workers fetch API.
+///
+/// Available with the `worker` feature on wasm32.
+#[cfg(all(feature = "worker", target_arch = "wasm32"))]
+#[derive(Debug, Clone, Default)]
+pub struct FetchBackend {
+    signer: Option<Arc<dyn RequestSigner>>,
+}
+
+#[cfg(all(feature = "worker", target_arch = "wasm32"))]
+impl FetchBackend {
+    /// Creates a backend that sends requests with the global `fetch`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Signs every request with the given signer.
+    pub fn signer(self, signer: impl RequestSigner + 'static) -> Self {
+        self.with_signer(Some(Arc::new(signer)))
+    }
+
+    /// Replaces the signer of this backend.
+    pub(crate) fn with_signer(mut self, signer: Option<Arc<dyn RequestSigner>>) -> Self {
+        self.signer = signer;
+        self
+    }
+
+    async fn fetch(&self, request: BackendRequest) -> Result<BackendResponse, VibesortError> {
+        use wasm_bindgen::{JsCast, JsValue};
+        use wasm_bindgen_futures::JsFuture;
+
+        let failed = |error: JsValue| VibesortError::ApiError {
+            message: format!(
+                "fetch failed: {}",
+                error
+                    .as_string()
+                    .or_else(|| error.dyn_ref::<js_sys::Error>().map(|e| e.message().into()))
+                    .unwrap_or_else(|| format!("{:?}", error))
+            ),
+            provider: None,
+        };
+
+        // Serialized once, so the signature covers the bytes sent
+        let body = serde_json::to_string(&request.body)?;
+        let headers = web_sys::Headers::new().map_err(failed)?;
+        let authorization = format!("Bearer {}", request.api_key.expose_secret());
+        headers
+            .set("authorization", &authorization)
+            .map_err(failed)?;
+        headers
+            .set(CONTENT_TYPE.as_str(), "application/json")
+            .map_err(failed)?;
+        if let Some(signer) = &self.signer {
+            for (name, value) in &signer.sign(&request.url, body.as_bytes())? {
+                let value = value.to_str().map_err(|_| VibesortError::ApiError {
+                    message: format!("header {} is not valid text", name),
+                    provider: None,
+                })?;
+                headers.set(name.as_str(), value).map_err(failed)?;
+            }
+        }
+
+        let init = web_sys::RequestInit::new();
+        init.set_method("POST");
+        init.set_headers(&headers);
+        init.set_body(&JsValue::from_str(&body));
+        let fetch_request =
+            web_sys::Request::new_with_str_and_init(&request.url, &init).map_err(failed)?;
+        let scope: web_sys::WorkerGlobalScope = js_sys::global().unchecked_into();
+        let response: web_sys::Response = JsFuture::from(scope.fetch_with_request(&fetch_request))
+            .await
+            .map_err(failed)?
+            .unchecked_into();
+
+        let status =
+            StatusCode::from_u16(response.status()).map_err(|_| VibesortError::InvalidResponse)?;
+        let mut response_headers = HeaderMap::new();
+        if let Ok(Some(entries)) = js_sys::try_iter(&response.headers()) {
+            for entry in entries.flatten() {
+                let entry: js_sys::Array = entry.unchecked_into();
+                if let (Some(name), Some(value)) =
+                    (entry.get(0).as_string(), entry.get(1).as_string())
+                    && let (Ok(name), Ok(value)) =
+                        (HeaderName::try_from(name), HeaderValue::try_from(value))
+                {
+                    response_headers.append(name, value);
+                }
+            }
+        }
+        let body = JsFuture::from(response.text().map_err(failed)?)
+            .await
+            .map_err(failed)?
+            .as_string()
+            .unwrap_or_default();
+
+        Ok(BackendResponse {
+            status,
+            headers: response_headers,
+            body,
+        })
+    }
+}
+
+#[cfg(all(feature = "worker", target_arch = "wasm32"))]
+impl Backend for FetchBackend {
+    fn send(
+        &self,
+        request: BackendRequest,
+    ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>> {
+        Box::pin(crate::rt::SendFuture::new(self.fetch(request)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vibesort;
+    use wiremock::matchers::{header, header_exists, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_hmac_signer_headers() {
+        let signer = HmacSigner::new("secret").signature_header("X-Gateway-Signature");
+
+        let headers = signer.sign_at(1_700_000_000, b"{}");
+        assert_eq!(headers["x-timestamp"], "1700000000");
+        assert_eq!(headers["x-content-sha256"], hex(&sha256(b"{}")));
+        let payload = format!("1700000000\n{}", hex(&sha256(b"{}")));
+        assert_eq!(
+            headers["x-gateway-signature"],
+            hex(&hmac_sha256(b"secret", payload.as_bytes()))
+        );
+        assert!(!format!("{:?}", signer).contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_signed_requests() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header_exists("x-timestamp"))
+            .and(header_exists("x-signature"))
+            .and(header("content-type", "application/json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"content": "[1,2]"}}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let base_url = server.uri();
+        let sorter = Vibesort::new("key", "model", base_url.as_str())
+            .request_signer(HmacSigner::new("secret"));
+        assert_eq!(sorter.sort(&[2, 1]).await.unwrap(), vec![1, 2]);
+
+        let received = &server.received_requests().await.unwrap()[0];
+        assert_eq!(
+            received.headers["x-content-sha256"],
+            hex(&sha256(&received.body)).as_str()
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! Sorting by an ordering shown in a hand-sorted sample.
+
+use crate::{Order, Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+impl<'a> Vibesort<'a> {
+    /// Sorts an array the same way as a small hand-sorted example.
+    ///
+    /// The example defines the criterion and direction implicitly: the model
+    /// is asked to infer the ordering it shows and to extend it to all of the
+    /// items. Elements of the example do not need to appear in the items. The
+    /// configured criterion and order are replaced for this call.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort), and
+    /// [`VibesortError::JsonError`] if the example cannot be serialized.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let example = ["breakfast", "lunch", "dinner"].map(String::from);
+    /// let items = ["midnight snack", "brunch", "afternoon tea", "supper"].map(String::from);
+    /// let sorted = sorter.sort_by_example(&items, &example).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_by_example<T>(
+        &self,
+        items: &[T],
+        sorted_example: &[T],
+    ) -> Result<Vec<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let criterion = format!(
+            "the same ordering as this hand-sorted example, from its first to its last element: {}. Infer the ordering the example follows and apply it to all elements",
+            serde_json::to_string(sorted_example)?
+        );
+        let sorter = self.clone().order(Order::Ascending).criterion(criterion);
+        Ok(sorter.sort_with_report(items).await?.items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_by_example_prompt() {
+        let backend =
+            Arc::new(MockBackend::new().respond_with(r#"["brunch","afternoon tea","supper"]"#));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .order(Order::Descending);
+
+        let example = ["breakfast", "lunch", "dinner"].map(String::from);
+        let items = ["supper", "brunch", "afternoon tea"].map(String::from);
+        let sorted = sorter.sort_by_example(&items, &example).await.unwrap();
+        assert_eq!(sorted, vec!["brunch", "afternoon tea", "supper"]);
+
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        let system = system.as_str().unwrap();
+        assert!(system.contains("ascending order"));
+        assert!(system.contains(r#"["breakfast","lunch","dinner"]"#));
+    }
+}
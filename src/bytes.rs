@@ -0,0 +1,221 @@
+//! Sorting binary elements sent as text.
+
+use crate::{Vibesort, VibesortError};
+
+/// The alphabet of standard base64 (RFC 4648).
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// How [`Vibesort::sort_bytes`] encodes binary elements for the model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BytesEncoding {
+    /// Standard base64 with padding (the default), the shortest encoding.
+    #[default]
+    Base64,
+
+    /// Lowercase hexadecimal, which keeps the byte order when compared as
+    /// text.
+    Hex,
+}
+
+impl BytesEncoding {
+    /// Encodes `bytes` as text.
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        match self {
+            Self::Base64 => encode_base64(bytes),
+            Self::Hex => crate::digest::hex(bytes),
+        }
+    }
+
+    /// Decodes text produced by [`encode`](Self::encode), or returns `None`
+    /// if it is not valid in this encoding.
+    ///
+    /// Hexadecimal digits may be in either case.
+    pub fn decode(&self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            Self::Base64 => decode_base64(text),
+            Self::Hex => decode_hex(text),
+        }
+    }
+
+    /// Returns the name of the encoding, for error messages.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Base64 => "base64",
+            Self::Hex => "hexadecimal",
+        }
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts binary elements, such as hashes or raw identifiers, by sending
+    /// them to the model in `encoding`.
+    ///
+    /// The elements are encoded before prompting and the sorted reply is
+    /// decoded, so the configured criterion and [`verify`](Self::verify)
+    /// apply to the encoded text. Choose [`BytesEncoding::Hex`] for criteria
+    /// that depend on the bytes themselves, such as their numeric order.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::ParseError`] is returned if the reply contains an
+    /// element that is not valid in `encoding`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{BytesEncoding, Vibesort};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let ids = vec![vec![0xde, 0xad], vec![0x00, 0x01], vec![0xbe, 0xef]];
+    /// let sorted = sorter.sort_bytes(&ids, BytesEncoding::Hex).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_bytes(
+        &self,
+        items: &[Vec<u8>],
+        encoding: BytesEncoding,
+    ) -> Result<Vec<Vec<u8>>, VibesortError> {
+        let encoded: Vec<String> = items.iter().map(|item| encoding.encode(item)).collect();
+        let sorted = self.sort_with_report(&encoded).await?.items;
+        sorted
+            .iter()
+            .map(|text| {
+                encoding.decode(text).ok_or_else(|| {
+                    VibesortError::ParseError(format!(
+                        "{:?} is not valid {}",
+                        text,
+                        encoding.name()
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | (u32::from(byte) << (16 - 8 * i))
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[((group >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    for (index, chunk) in text.chunks(4).enumerate() {
+        let last = index == text.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+        let mut group = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let digit = BASE64.iter().position(|&d| d == c)?;
+            group = (group << 6) | digit as u32;
+        }
+        group <<= 6 * padding;
+        out.extend_from_slice(&group.to_be_bytes()[1..4 - padding]);
+    }
+    Some(out)
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_base64() {
+        // RFC 4648, section 10
+        for (bytes, text) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(BytesEncoding::Base64.encode(bytes.as_bytes()), text);
+            assert_eq!(
+                BytesEncoding::Base64.decode(text).unwrap(),
+                bytes.as_bytes()
+            );
+        }
+        assert_eq!(BytesEncoding::Base64.decode("Zm9"), None);
+        assert_eq!(BytesEncoding::Base64.decode("Zg==Zm8="), None);
+        assert_eq!(BytesEncoding::Base64.decode("Z!=="), None);
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(BytesEncoding::Hex.encode(&[0x00, 0xab, 0xff]), "00abff");
+        assert_eq!(
+            BytesEncoding::Hex.decode("00ABff").unwrap(),
+            vec![0x00, 0xab, 0xff]
+        );
+        assert_eq!(BytesEncoding::Hex.decode("abc"), None);
+        assert_eq!(BytesEncoding::Hex.decode("zz"), None);
+        assert_eq!(BytesEncoding::Hex.decode("+f"), None);
+    }
+
+    #[tokio::test]
+    async fn test_sort_bytes() {
+        let backend = Arc::new(MockBackend::new().respond_with(r#"["0001", "beef", "dead"]"#));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let items = vec![vec![0xde, 0xad], vec![0x00, 0x01], vec![0xbe, 0xef]];
+        let sorted = sorter.sort_bytes(&items, BytesEncoding::Hex).await.unwrap();
+        assert_eq!(
+            sorted,
+            vec![vec![0x00, 0x01], vec![0xbe, 0xef], vec![0xde, 0xad]]
+        );
+
+        let user = backend.requests()[0].body["messages"][1]["content"].clone();
+        assert_eq!(user, r#"["dead","0001","beef"]"#);
+    }
+
+    #[tokio::test]
+    async fn test_sort_bytes_rejects_invalid_encoding() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with(r#"["AAE=", "not base64"]"#));
+
+        let err = sorter
+            .sort_bytes(&[vec![0xde], vec![0x00, 0x01]], BytesEncoding::Base64)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::ParseError(_)));
+    }
+}
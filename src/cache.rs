@@ -0,0 +1,867 @@
+//! Caching of sort results and pairwise comparisons.
+//!
+//! A [`SortCache`] remembers the result of every sort made through a sorter
+//! configured with [`Vibesort::cache`], so sorting the same items again costs
+//! no request. Entries are scoped by model, criterion, order, prompt
+//! template, and few-shot examples: the same items sorted by another model
+//! or under another criterion are cached separately, and items can be keyed
+//! by only the fields that matter with [`key_with`](SortCache::key_with).
+//! Entries can expire after a time-to-live and can be removed explicitly
+//! with [`invalidate`](SortCache::invalidate) and
+//! [`clear`](SortCache::clear).
+//!
+//! A [`ComparisonCache`] remembers the answers to pairwise comparisons made
+//! by [`VibeHeap`](crate::VibeHeap) and
+//! [`ComparisonOracle`](crate::ComparisonOracle), and can be saved to disk so
+//! that repeated runs over overlapping data, such as nightly re-ranking jobs,
+//! reuse earlier judgments instead of paying for them again.
+//!
+//! A [`ChunkCheckpoint`] records every chunk and merge request of a
+//! [chunked](Vibesort::chunk_size) sort in a file as it completes, so that a
+//! batch job that crashed can resume a long sort instead of paying for it
+//! again.
+//!
+//! All three are shared: clones refer to the same entries.
+
+use crate::rt::Instant;
+use crate::{Order, Vibesort, VibesortError};
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Hashes a string with 64-bit FNV-1a.
+///
+/// A fixed algorithm is used rather than `DefaultHasher` so that hashes
+/// written to disk stay valid across Rust versions.
+pub(crate) fn fnv1a(data: &str) -> u64 {
+    data.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Identifies a cached sort.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey {
+    model: String,
+    criterion: Option<String>,
+    order: Order,
+    /// The rendered sort prompt and the few-shot examples.
+    prompt: String,
+    /// The serialized input items.
+    items: String,
+}
+
+impl CacheKey {
+    /// Returns the key for sorting items, already serialized and projected
+    /// with [`SortCache::project`], with `sorter`.
+    fn new(sorter: &Vibesort<'_>, projected: &[Value]) -> Result<Self, VibesortError> {
+        Ok(Self {
+            model: sorter.model.to_string(),
+            criterion: sorter.criterion.clone(),
+            order: sorter.order,
+            prompt: prompt_of(sorter)?,
+            items: serde_json::to_string(projected)?,
+        })
+    }
+}
+
+/// Returns the sort prompt of `sorter`, rendered without items, followed by
+/// its few-shot examples: everything besides the items that the model sees.
+fn prompt_of(sorter: &Vibesort<'_>) -> Result<String, VibesortError> {
+    let (system_prompt, user_content) = sorter.sort_prompt(String::from("[]"))?;
+    Ok(format!(
+        "{}\n{}\n{}",
+        system_prompt,
+        user_content,
+        serde_json::to_string(&sorter.examples)?
+    ))
+}
+
+/// A function mapping a serialized item to the value it is cached by.
+#[derive(Clone)]
+struct KeyFn(Arc<dyn Fn(&Value) -> Value + Send + Sync>);
+
+impl std::fmt::Debug for KeyFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("KeyFn")
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    sorted: Vec<Value>,
+    expires_at: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    entries: HashMap<CacheKey, Entry>,
+    hits: u64,
+    misses: u64,
+}
+
+/// Counters describing how a [`SortCache`] has been used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of sorts answered from the cache.
+    pub hits: u64,
+
+    /// The number of sorts that were not cached or whose entry had expired.
+    pub misses: u64,
+
+    /// The number of entries currently stored, including expired entries
+    /// that have not been looked up since.
+    pub entries: usize,
+}
+
+/// An in-memory cache of sort results.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use vibesort_rs::Vibesort;
+/// use vibesort_rs::cache::SortCache;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let cache = SortCache::new().ttl(Duration::from_secs(3600));
+/// let sorter = Vibesort::new(
+///     "your-api-key",
+///     "gpt-3.5-turbo",
+///     "https://api.openai.com/v1",
+/// )
+/// .cache(cache.clone());
+///
+/// sorter.sort(&[3, 1, 2]).await?;
+/// // Answered from the cache
+/// let result = sorter.sort_with_report(&[3, 1, 2]).await?;
+/// assert_eq!(result.report.cache_hits, 1);
+///
+/// cache.invalidate(&[3, 1, 2])?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SortCache {
+    inner: Arc<Mutex<Inner>>,
+    ttl: Option<Duration>,
+    key_fn: Option<KeyFn>,
+}
+
+impl SortCache {
+    /// Creates an empty cache whose entries never expire.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the default time-to-live of new entries.
+    ///
+    /// A sorter can override it for the entries it stores with
+    /// [`Vibesort::cache_ttl`].
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Caches items by the value `key` returns for each of them, serialized,
+    /// instead of the whole item.
+    ///
+    /// Sorts of items that differ only in what `key` leaves out, such as
+    /// volatile timestamps or counters, then share an entry. A hit restores
+    /// the cached order onto the items being sorted, so their current values
+    /// are returned. Items with equal keys are treated as interchangeable and
+    /// keep their relative order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vibesort_rs::cache::SortCache;
+    ///
+    /// // Ignore the `fetched_at` field of every item
+    /// let cache = SortCache::new().key_with(|item| {
+    ///     let mut item = item.clone();
+    ///     if let Some(fields) = item.as_object_mut() {
+    ///         fields.remove("fetched_at");
+    ///     }
+    ///     item
+    /// });
+    /// ```
+    pub fn key_with(mut self, key: impl Fn(&Value) -> Value + Send + Sync + 'static) -> Self {
+        self.key_fn = Some(KeyFn(Arc::new(key)));
+        self
+    }
+
+    /// Removes every entry for sorting `items`, in all scopes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::JsonError`] if the items cannot be serialized.
+    pub fn invalidate<T: Serialize>(&self, items: &[T]) -> Result<(), VibesortError> {
+        let values = items
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        let items = serde_json::to_string(&self.project(&values))?;
+        self.lock().entries.retain(|key, _| key.items != items);
+        Ok(())
+    }
+
+    /// Removes every entry.
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    /// Returns the hit and miss counters and the number of entries.
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.lock();
+        CacheStats {
+            hits: inner.hits,
+            misses: inner.misses,
+            entries: inner.entries.len(),
+        }
+    }
+
+    /// Maps serialized items to the values they are cached by.
+    pub(crate) fn project(&self, values: &[Value]) -> Vec<Value> {
+        match &self.key_fn {
+            Some(KeyFn(key)) => values.iter().map(|value| key(value)).collect(),
+            None => values.to_vec(),
+        }
+    }
+
+    /// Returns the key for sorting the serialized items with `sorter`.
+    pub(crate) fn key(
+        &self,
+        sorter: &Vibesort<'_>,
+        values: &[Value],
+    ) -> Result<CacheKey, VibesortError> {
+        CacheKey::new(sorter, &self.project(values))
+    }
+
+    /// Puts the serialized items in the order of a cached result, matching
+    /// them by their projected values.
+    ///
+    /// Returns `None` if the items do not match the result.
+    pub(crate) fn restore(&self, values: Vec<Value>, sorted: &[Value]) -> Option<Vec<Value>> {
+        let projected = self.project(&values);
+        let mut values: Vec<Option<Value>> = values.into_iter().map(Some).collect();
+        sorted
+            .iter()
+            .map(|wanted| {
+                let index = projected
+                    .iter()
+                    .zip(&values)
+                    .position(|(key, value)| key == wanted && value.is_some())?;
+                values[index].take()
+            })
+            .collect()
+    }
+
+    /// Returns the cached result for `key`, removing it if it has expired.
+    pub(crate) fn get(&self, key: &CacheKey) -> Option<Vec<Value>> {
+        let mut inner = self.lock();
+        let expired = match inner.entries.get(key) {
+            Some(entry) => entry
+                .expires_at
+                .is_some_and(|expires_at| Instant::now() >= expires_at),
+            None => {
+                inner.misses += 1;
+                return None;
+            }
+        };
+        if expired {
+            inner.entries.remove(key);
+            inner.misses += 1;
+            return None;
+        }
+        inner.hits += 1;
+        inner.entries.get(key).map(|entry| entry.sorted.clone())
+    }
+
+    /// Stores a result, expiring after `ttl` or else the cache's default TTL.
+    pub(crate) fn insert(&self, key: CacheKey, sorted: Vec<Value>, ttl: Option<Duration>) {
+        let expires_at = ttl.or(self.ttl).map(|ttl| Instant::now() + ttl);
+        self.lock()
+            .entries
+            .insert(key, Entry { sorted, expires_at });
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A cache of pairwise comparisons, optionally persisted to a file.
+///
+/// Every comparison is keyed by a hash of the sorter's model, criterion, and
+/// order together with the two serialized elements, and stores their
+/// [`Ordering`]: `Less` if the first element comes first. Answers from
+/// another model or under another criterion are therefore never reused.
+///
+/// # Example
+///
+/// ```no_run
+/// use vibesort_rs::cache::ComparisonCache;
+/// use vibesort_rs::{ComparisonOracle, Vibesort};
+///
+/// # async fn example(tickets: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+/// let sorter = Vibesort::new(
+///     "your-api-key",
+///     "gpt-3.5-turbo",
+///     "https://api.openai.com/v1",
+/// )
+/// .criterion("by urgency, most urgent first");
+///
+/// let comparisons = ComparisonCache::load("comparisons.json")?;
+/// let oracle = ComparisonOracle::new().comparisons(comparisons.clone());
+/// oracle.prefetch(&sorter, &tickets).await?;
+/// comparisons.save("comparisons.json")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ComparisonCache {
+    orderings: Arc<Mutex<HashMap<u64, Ordering>>>,
+}
+
+impl ComparisonCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a cache saved with [`save`](Self::save).
+    ///
+    /// A missing file yields an empty cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::IoError`] if the file cannot be read, or
+    /// [`VibesortError::JsonError`] if it is not a saved cache.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, VibesortError> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let saved: BTreeMap<String, i8> = serde_json::from_slice(&bytes)?;
+        let orderings = saved
+            .into_iter()
+            .filter_map(|(key, ordering)| {
+                let key = u64::from_str_radix(&key, 16).ok()?;
+                Some((key, ordering.cmp(&0)))
+            })
+            .collect();
+        Ok(Self {
+            orderings: Arc::new(Mutex::new(orderings)),
+        })
+    }
+
+    /// Writes the cache to a file as a JSON object mapping hashes to `-1`,
+    /// `0`, or `1`.
+    ///
+    /// The file is written next to its final location and then renamed, so an
+    /// interrupted save never leaves a truncated cache behind.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::IoError`] if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), VibesortError> {
+        let path = path.as_ref();
+        let saved: BTreeMap<String, i8> = self
+            .lock()
+            .iter()
+            .map(|(key, ordering)| (format!("{:016x}", key), *ordering as i8))
+            .collect();
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, serde_json::to_vec(&saved)?)?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    /// Returns the number of cached comparisons, counting each orientation of
+    /// a pair.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if no comparison is cached.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Removes every cached comparison.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Returns `true` if `a` comes before `b` (or they are equal), asking the
+    /// model only if this pair has not been compared before.
+    pub(crate) async fn precedes(
+        &self,
+        sorter: &Vibesort<'_>,
+        a: &Value,
+        b: &Value,
+    ) -> Result<bool, VibesortError> {
+        let scope = format!(
+            "{}\n{}\n{}",
+            sorter.model,
+            sorter.criterion.as_deref().unwrap_or_default(),
+            sorter.order.as_str()
+        );
+        let (a_json, b_json) = (a.to_string(), b.to_string());
+        let key = fnv1a(&format!("{}\n{}\n{}", scope, a_json, b_json));
+        if let Some(&ordering) = self.lock().get(&key) {
+            return Ok(ordering != Ordering::Greater);
+        }
+
+        let ordering = match sorter.compare(a, b).await? {
+            score if score > 0.5 => Ordering::Less,
+            score if score < 0.5 => Ordering::Greater,
+            _ => Ordering::Equal,
+        };
+        let reversed = fnv1a(&format!("{}\n{}\n{}", scope, b_json, a_json));
+        let mut orderings = self.lock();
+        orderings.insert(key, ordering);
+        orderings.insert(reversed, ordering.reverse());
+        Ok(ordering != Ordering::Greater)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<u64, Ordering>> {
+        self.orderings
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A file recording the completed requests of chunked sorts, for resuming
+/// them after a crash.
+///
+/// Every request of a chunked sort, sorting either a chunk or the merge
+/// frontier of some sorted chunks, is keyed by a hash of the sorter's model,
+/// criterion, order, prompt template, and few-shot examples together with
+/// its serialized input, and its sorted
+/// output is appended to the file as one line of JSON as soon as it
+/// completes. Sorting the same items again with the same checkpoint replays
+/// the recorded requests, which the merge algorithm reaches in the same
+/// order, and sends only the ones that never completed. A line cut short by
+/// a crash is ignored, and a recorded output that is not a permutation of
+/// its input is sorted again.
+///
+/// The file grows with every request; delete it with
+/// [`remove`](Self::remove) once the job has finished.
+///
+/// # Example
+///
+/// ```no_run
+/// use vibesort_rs::Vibesort;
+/// use vibesort_rs::cache::ChunkCheckpoint;
+///
+/// # async fn example(records: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+/// let checkpoint = ChunkCheckpoint::open("nightly-sort.jsonl")?;
+/// let sorter = Vibesort::new(
+///     "your-api-key",
+///     "gpt-3.5-turbo",
+///     "https://api.openai.com/v1",
+/// )
+/// .chunk_size(200)
+/// .checkpoint(checkpoint.clone());
+///
+/// // After a crash, running the job again resumes where it stopped
+/// let sorted = sorter.sort(&records).await?;
+/// checkpoint.remove()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChunkCheckpoint {
+    path: Arc<PathBuf>,
+    results: Arc<Mutex<HashMap<u64, Vec<Value>>>>,
+    /// Held while appending to the file, so lines are written one at a time.
+    file: Arc<tokio::sync::Mutex<()>>,
+}
+
+/// One line of a [`ChunkCheckpoint`] file.
+#[derive(Debug, Serialize, serde::Deserialize)]
+struct CheckpointLine {
+    key: String,
+    sorted: Vec<Value>,
+}
+
+impl ChunkCheckpoint {
+    /// Opens a checkpoint file, loading the requests recorded in it.
+    ///
+    /// A missing file yields an empty checkpoint; the file is created when
+    /// the first request completes. A last line cut short by a crash is
+    /// removed from the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::IoError`] if the file cannot be read or
+    /// repaired.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, VibesortError> {
+        let path = path.as_ref().to_path_buf();
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        // Cut off a line left incomplete by a crash, so the next line
+        // appended starts on a line of its own
+        let complete = text.rfind('\n').map_or(0, |newline| newline + 1);
+        if complete < text.len() {
+            OpenOptions::new()
+                .write(true)
+                .open(&path)?
+                .set_len(complete as u64)?;
+        }
+        let results = text[..complete]
+            .lines()
+            .filter_map(|line| serde_json::from_str::<CheckpointLine>(line).ok())
+            .filter_map(|line| Some((u64::from_str_radix(&line.key, 16).ok()?, line.sorted)))
+            .collect();
+        Ok(Self {
+            path: Arc::new(path),
+            results: Arc::new(Mutex::new(results)),
+            file: Arc::new(tokio::sync::Mutex::new(())),
+        })
+    }
+
+    /// Returns the number of recorded requests.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if no request is recorded.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Forgets every recorded request and deletes the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::IoError`] if the file exists but cannot be
+    /// deleted.
+    pub fn remove(&self) -> Result<(), VibesortError> {
+        self.lock().clear();
+        match std::fs::remove_file(self.path.as_ref()) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the key of sorting `values` with `sorter`.
+    fn key(sorter: &Vibesort<'_>, values: &[Value]) -> Result<u64, VibesortError> {
+        Ok(fnv1a(&format!(
+            "{}\n{}\n{}\n{}\n{}",
+            sorter.model,
+            sorter.criterion.as_deref().unwrap_or_default(),
+            sorter.order.as_str(),
+            prompt_of(sorter)?,
+            serde_json::to_string(values)?
+        )))
+    }
+
+    /// Returns the recorded output of sorting `values` with `sorter`, if it
+    /// passes the sorter's verification.
+    fn get(&self, sorter: &Vibesort<'_>, key: u64, values: &[Value]) -> Option<Vec<Value>> {
+        let mut sorted = self.lock().get(&key)?.clone();
+        sorter.verify_sorted(values, &mut sorted).ok()?;
+        Some(sorted)
+    }
+
+    /// Records the output of a request and appends it to the file.
+    async fn record(&self, key: u64, sorted: &[Value]) -> Result<(), VibesortError> {
+        let line = serde_json::to_string(&CheckpointLine {
+            key: format!("{:016x}", key),
+            sorted: sorted.to_vec(),
+        })?;
+        let _file = self.file.lock().await;
+        let path = self.path.clone();
+        #[cfg(not(target_arch = "wasm32"))]
+        tokio::task::spawn_blocking(move || append_line(&path, &line))
+            .await
+            .map_err(std::io::Error::other)??;
+        #[cfg(target_arch = "wasm32")]
+        append_line(&path, &line)?;
+        self.lock().insert(key, sorted.to_vec());
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<u64, Vec<Value>>> {
+        self.results
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Appends a line to the file at `path` and waits for it to reach the disk.
+fn append_line(path: &Path, line: &str) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(format!("{}\n", line).as_bytes())?;
+    file.sync_data()
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts one chunk or merge frontier of a chunked sort, replaying it from
+    /// the [checkpoint](Self::checkpoint) if it was recorded.
+    ///
+    /// Returns the sorted values and whether the reply had to be repaired.
+    pub(crate) async fn checkpointed_pass(
+        &self,
+        values: &[Value],
+    ) -> Result<(Vec<Value>, bool), VibesortError> {
+        let Some(checkpoint) = &self.checkpoint else {
+            let (completion, sorted) = self.sort_pass(values, true).await?;
+            return Ok((sorted, completion.repaired));
+        };
+        let key = ChunkCheckpoint::key(self, values)?;
+        if let Some(sorted) = checkpoint.get(self, key, values) {
+            return Ok((sorted, false));
+        }
+        let (completion, sorted) = self.sort_pass(values, true).await?;
+        checkpoint.record(key, &sorted).await?;
+        Ok((sorted, completion.repaired))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cache_hits_and_expiry() {
+        let backend = Arc::new(MockBackend::new());
+        let cache = SortCache::new().ttl(Duration::from_secs(60));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .cache(cache.clone());
+
+        let first = sorter.sort_with_report(&[3, 1, 2]).await.unwrap();
+        assert_eq!(first.report.cache_misses, 1);
+        let second = sorter.sort_with_report(&[3, 1, 2]).await.unwrap();
+        assert_eq!(second.items, vec![1, 2, 3]);
+        assert_eq!(second.report.cache_hits, 1);
+        assert_eq!(backend.requests().len(), 1);
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        sorter.sort(&[3, 1, 2]).await.unwrap();
+        assert_eq!(backend.requests().len(), 2);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 2,
+                entries: 1
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache_scoping_and_invalidation() {
+        let backend = Arc::new(MockBackend::new());
+        let cache = SortCache::new();
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .cache(cache.clone());
+        let by_length = sorter.clone().criterion("by length");
+
+        sorter.sort(&[2, 1]).await.unwrap();
+        by_length.sort(&[2, 1]).await.unwrap();
+        sorter.sort(&[1, 2, 3]).await.unwrap();
+        assert_eq!(backend.requests().len(), 3);
+        assert_eq!(cache.stats().entries, 3);
+
+        cache.invalidate(&[2, 1]).unwrap();
+        assert_eq!(cache.stats().entries, 1);
+        sorter.sort(&[2, 1]).await.unwrap();
+        assert_eq!(backend.requests().len(), 4);
+
+        cache.clear();
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cache_scoped_by_prompt_and_examples() {
+        let backend = Arc::new(MockBackend::new());
+        let cache = SortCache::new();
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .cache(cache.clone());
+        let templated = sorter
+            .clone()
+            .prompt_template(crate::PromptTemplate::new("Sort these {order}.", "{array}").unwrap());
+        let with_example = sorter.clone().example(&[2, 1], &[1, 2]).unwrap();
+
+        sorter.sort(&[2, 1]).await.unwrap();
+        templated.sort(&[2, 1]).await.unwrap();
+        with_example.sort(&[2, 1]).await.unwrap();
+        assert_eq!(backend.requests().len(), 3);
+        assert_eq!(cache.stats().entries, 3);
+
+        templated.sort(&[2, 1]).await.unwrap();
+        assert_eq!(backend.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_cache_key_ignores_volatile_fields() {
+        let backend = Arc::new(
+            MockBackend::new()
+                .respond_with(r#"[{"name": "a", "seen": 1}, {"name": "b", "seen": 1}]"#),
+        );
+        let cache = SortCache::new().key_with(|item| item["name"].clone());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .cache(cache.clone());
+
+        let first = vec![
+            serde_json::json!({"name": "b", "seen": 1}),
+            serde_json::json!({"name": "a", "seen": 1}),
+        ];
+        sorter.sort_with_report(&first).await.unwrap();
+        let second = vec![
+            serde_json::json!({"name": "b", "seen": 2}),
+            serde_json::json!({"name": "a", "seen": 2}),
+        ];
+        let result = sorter.sort_with_report(&second).await.unwrap();
+        assert_eq!(result.report.cache_hits, 1);
+        assert_eq!(result.items, vec![second[1].clone(), second[0].clone()]);
+        assert_eq!(backend.requests().len(), 1);
+
+        cache.invalidate(&second).unwrap();
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_comparison_cache_persists() {
+        let path =
+            std::env::temp_dir().join(format!("vibesort-comparisons-{}.json", std::process::id()));
+        let (a, b) = (Value::from("apple"), Value::from("banana"));
+
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+        let comparisons = ComparisonCache::load(&path).unwrap();
+        assert!(comparisons.is_empty());
+        assert!(comparisons.precedes(&sorter, &a, &b).await.unwrap());
+        comparisons.save(&path).unwrap();
+
+        // A later run answers both orientations from the file
+        let loaded = ComparisonCache::load(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert!(!loaded.precedes(&sorter, &b, &a).await.unwrap());
+        assert_eq!(backend.requests().len(), 1);
+
+        // Answers are scoped by criterion
+        let by_length = sorter.clone().criterion("by length");
+        loaded.precedes(&by_length, &a, &b).await.unwrap();
+        assert_eq!(backend.requests().len(), 2);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_chunk_checkpoint_resumes() {
+        const CONTEXT_LENGTH_EXCEEDED: &str =
+            r#"{"error":{"code":"context_length_exceeded","message":"too long"}}"#;
+        let path =
+            std::env::temp_dir().join(format!("vibesort-checkpoint-{}.jsonl", std::process::id()));
+        let items: Vec<i64> = (0..12).map(|i| (i * 5) % 12).collect();
+        let chunked = |backend: Arc<MockBackend>, checkpoint: ChunkCheckpoint| {
+            Vibesort::new("key", "model", "http://mock")
+                .backend(backend)
+                .chunk_size(4)
+                .checkpoint(checkpoint)
+        };
+
+        let backend =
+            Arc::new(MockBackend::new().respond_with_status(400, CONTEXT_LENGTH_EXCEEDED));
+        let checkpoint = ChunkCheckpoint::open(&path).unwrap();
+        let sorted = chunked(backend.clone(), checkpoint.clone())
+            .sort(&items)
+            .await
+            .unwrap();
+        assert_eq!(sorted, (0..12).collect::<Vec<_>>());
+        let requests = backend.requests().len() - 1;
+        assert_eq!(checkpoint.len(), requests);
+
+        // Simulate a crash during the last write, half-way through the job
+        let text = std::fs::read_to_string(&path).unwrap();
+        let kept: Vec<&str> = text.lines().take(requests / 2).collect();
+        std::fs::write(&path, format!("{}\n{{\"key\": \"0", kept.join("\n"))).unwrap();
+
+        let backend =
+            Arc::new(MockBackend::new().respond_with_status(400, CONTEXT_LENGTH_EXCEEDED));
+        let checkpoint = ChunkCheckpoint::open(&path).unwrap();
+        assert_eq!(checkpoint.len(), requests / 2);
+        let sorted = chunked(backend.clone(), checkpoint.clone())
+            .sort(&items)
+            .await
+            .unwrap();
+        assert_eq!(sorted, (0..12).collect::<Vec<_>>());
+        assert_eq!(backend.requests().len() - 1, requests - requests / 2);
+        assert_eq!(ChunkCheckpoint::open(&path).unwrap().len(), requests);
+
+        // Requests sent with other few-shot examples are not replayed
+        let backend =
+            Arc::new(MockBackend::new().respond_with_status(400, CONTEXT_LENGTH_EXCEEDED));
+        chunked(backend.clone(), checkpoint.clone())
+            .example(&[2, 1], &[1, 2])
+            .unwrap()
+            .sort(&items)
+            .await
+            .unwrap();
+        assert_eq!(backend.requests().len() - 1, requests);
+
+        checkpoint.remove().unwrap();
+        assert!(checkpoint.is_empty());
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_chunk_checkpoint_replays_within_tolerance() {
+        const CONTEXT_LENGTH_EXCEEDED: &str =
+            r#"{"error":{"code":"context_length_exceeded","message":"too long"}}"#;
+        let path = std::env::temp_dir().join(format!(
+            "vibesort-checkpoint-tolerance-{}.jsonl",
+            std::process::id()
+        ));
+        let items = [0.75, 0.25, 0.5, 0.125];
+        let chunked = |backend: Arc<MockBackend>, checkpoint: ChunkCheckpoint| {
+            Vibesort::new("key", "model", "http://mock")
+                .backend(backend)
+                .chunk_size(2)
+                .checkpoint(checkpoint)
+                .verify_tolerance(1e-9)
+        };
+
+        let backend =
+            Arc::new(MockBackend::new().respond_with_status(400, CONTEXT_LENGTH_EXCEEDED));
+        let checkpoint = ChunkCheckpoint::open(&path).unwrap();
+        chunked(backend, checkpoint).sort(&items).await.unwrap();
+
+        // The recorded floats drift in their last digits
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, text.replace("0.25", "0.25000000001")).unwrap();
+
+        let backend =
+            Arc::new(MockBackend::new().respond_with_status(400, CONTEXT_LENGTH_EXCEEDED));
+        let checkpoint = ChunkCheckpoint::open(&path).unwrap();
+        let sorted = chunked(backend.clone(), checkpoint.clone())
+            .sort(&items)
+            .await
+            .unwrap();
+        assert_eq!(sorted, [0.125, 0.25, 0.5, 0.75]);
+        assert_eq!(backend.requests().len(), 1);
+        checkpoint.remove().unwrap();
+    }
+}
@@ -0,0 +1,366 @@
+//! Sorting inputs that are too large for a single request.
+
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::VecDeque;
+
+/// How far a sort in chunks got, returned by [`Vibesort::sort_chunks`].
+pub(crate) struct ChunkProgress<T> {
+    /// The elements that were sorted relative to each other.
+    pub(crate) sorted: Vec<T>,
+
+    /// The remaining elements, not ordered relative to `sorted`.
+    pub(crate) remainder: Vec<T>,
+
+    /// The error that stopped the sort, if it did not finish.
+    pub(crate) error: Option<VibesortError>,
+
+    /// Whether the reply to any request had to be repaired.
+    pub(crate) repaired: bool,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts the items in chunks of at most `self.chunk_size` elements, or of
+    /// a size fitting the model's limits if none is set.
+    ///
+    /// Each chunk is sorted with one request. The sorted chunks ("runs") are
+    /// then merged, at most `chunk_size` runs at a time, in rounds: every
+    /// round takes an equal share of the next elements of each run (the merge
+    /// frontier), sorts them together, and emits the prefix that is known to
+    /// precede everything still left in the runs. Every request is checked to
+    /// return a permutation of its input, so no element can be lost or
+    /// duplicated along the way.
+    pub(crate) async fn sort_chunked<T>(&self, items: &[T]) -> Result<Vec<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let progress = self.sort_chunks(items).await?;
+        match progress.error {
+            Some(e) => Err(e),
+            None => Ok(progress.sorted),
+        }
+    }
+
+    /// Sorts the items in chunks like [`sort_chunked`](Self::sort_chunked),
+    /// keeping the completed work if a request fails.
+    ///
+    /// Chunks are sorted until one fails; the chunks sorted before it are
+    /// then still merged. If a merge fails, only the first sorted chunk is
+    /// kept. Only serialization errors are returned as `Err`.
+    pub(crate) async fn sort_chunks<T>(
+        &self,
+        items: &[T],
+    ) -> Result<ChunkProgress<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let values: Vec<Value> = items
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<_, _>>()?;
+        let array_bytes = values.iter().map(|value| value.to_string().len() + 1).sum();
+        let chunk_size = self
+            .chunk_size_for(values.len(), array_bytes)
+            .unwrap_or(items.len())
+            .max(2);
+
+        // Every request is bounded by the chunk timeout
+        let mut sorter = self.clone();
+        sorter.request_timeout = self.chunk_timeout;
+
+        let mut runs = Vec::new();
+        let mut remainder = Vec::new();
+        let mut error = None;
+        let mut repaired = false;
+        let mut chunks = values.chunks(chunk_size);
+        for chunk in chunks.by_ref() {
+            match sorter.checkpointed_pass(chunk).await {
+                Ok((sorted, was_repaired)) => {
+                    repaired |= was_repaired;
+                    runs.push(VecDeque::from(sorted));
+                }
+                Err(e) => {
+                    error = Some(e);
+                    remainder.extend_from_slice(chunk);
+                    break;
+                }
+            }
+        }
+        remainder.extend(chunks.flatten().cloned());
+
+        let merged = sorter
+            .merge_all(runs.clone(), chunk_size, &mut repaired)
+            .await;
+        let sorted = match merged {
+            Ok(merged) => Vec::from(merged),
+            Err(e) => {
+                error.get_or_insert(e);
+                let mut runs = runs.into_iter();
+                let first = runs.next().map(Vec::from).unwrap_or_default();
+                let mut unmerged: Vec<Value> = runs.flatten().collect();
+                unmerged.append(&mut remainder);
+                remainder = unmerged;
+                first
+            }
+        };
+
+        let from_values = |values: Vec<Value>| {
+            values
+                .into_iter()
+                .map(serde_json::from_value)
+                .collect::<Result<Vec<T>, _>>()
+        };
+        Ok(ChunkProgress {
+            sorted: from_values(sorted)?,
+            remainder: from_values(remainder)?,
+            error,
+            repaired,
+        })
+    }
+
+    /// Merges sorted runs into one, in requests of at most `chunk_size`
+    /// elements, setting `repaired` if the reply to any of them had to be
+    /// repaired.
+    pub(crate) async fn merge_all(
+        &self,
+        mut runs: Vec<VecDeque<Value>>,
+        chunk_size: usize,
+        repaired: &mut bool,
+    ) -> Result<VecDeque<Value>, VibesortError> {
+        // Merge at most `chunk_size` runs at a time so that every frontier
+        // fits in one request
+        while runs.len() > 1 {
+            let mut merged_runs = Vec::new();
+            while !runs.is_empty() {
+                let group: Vec<_> = runs.drain(..chunk_size.min(runs.len())).collect();
+                merged_runs.push(self.merge_runs(group, chunk_size, repaired).await?);
+            }
+            runs = merged_runs;
+        }
+        Ok(runs.pop().unwrap_or_default())
+    }
+
+    /// Merges sorted runs (at most `chunk_size` of them) into one.
+    async fn merge_runs(
+        &self,
+        mut runs: Vec<VecDeque<Value>>,
+        chunk_size: usize,
+        repaired: &mut bool,
+    ) -> Result<VecDeque<Value>, VibesortError> {
+        let mut merged = VecDeque::new();
+        while runs.len() > 1 {
+            let share = (chunk_size / runs.len()).max(1);
+            let blocks: Vec<Vec<Value>> = runs
+                .iter_mut()
+                .map(|run| run.drain(..share.min(run.len())).collect())
+                .collect();
+            let frontier: Vec<Value> = blocks.iter().flatten().cloned().collect();
+            let (sorted, was_repaired) = self.checkpointed_pass(&frontier).await?;
+            *repaired |= was_repaired;
+
+            // A run's remaining elements all follow the last element of its
+            // block, so everything up to the earliest such tail is final
+            let cut = blocks
+                .iter()
+                .zip(&runs)
+                .filter(|(_, run)| !run.is_empty())
+                .filter_map(|(block, _)| block.last())
+                .filter_map(|tail| sorted.iter().position(|value| value == tail))
+                .min()
+                .map_or(sorted.len(), |position| position + 1);
+
+            // Return the elements that were not emitted to the front of their runs
+            let mut claimed: Vec<Vec<bool>> = blocks.iter().map(|b| vec![false; b.len()]).collect();
+            for value in &sorted[..cut] {
+                let slot = blocks.iter().enumerate().find_map(|(run, block)| {
+                    let position = block
+                        .iter()
+                        .enumerate()
+                        .position(|(i, v)| v == value && !claimed[run][i])?;
+                    Some((run, position))
+                });
+                if let Some((run, position)) = slot {
+                    claimed[run][position] = true;
+                }
+            }
+            for ((run, block), claimed) in runs.iter_mut().zip(blocks).zip(claimed) {
+                for (value, _) in block.into_iter().zip(claimed).rev().filter(|(_, c)| !c) {
+                    run.push_front(value);
+                }
+            }
+
+            merged.extend(sorted.into_iter().take(cut));
+            runs.retain(|run| !run.is_empty());
+        }
+        merged.extend(runs.into_iter().flatten());
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{Backend, BackendRequest, BackendResponse, BoxFuture};
+    use crate::retry::ExponentialBackoff;
+    use crate::testing::MockBackend;
+    use crate::{Order, Vibesort, VibesortError};
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    const CONTEXT_LENGTH_EXCEEDED: &str = r#"{"error":{"code":"context_length_exceeded","message":"This model's maximum context length is 16 tokens."}}"#;
+
+    #[tokio::test]
+    async fn test_context_length_falls_back_to_chunks() {
+        let items = [7, 3, 9, 1, 3, 8, 2, 6, 5, 4, 0];
+
+        let backend =
+            Arc::new(MockBackend::new().respond_with_status(400, CONTEXT_LENGTH_EXCEEDED));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .chunk_size(3);
+
+        let result = sorter.sort_with_report(&items).await.unwrap();
+        assert_eq!(result.items, vec![0, 1, 2, 3, 3, 4, 5, 6, 7, 8, 9]);
+        assert!(result.report.chunked_fallback);
+
+        // No request after the first one may exceed the chunk size
+        for request in &backend.requests()[1..] {
+            let task = request.task.as_ref().unwrap();
+            assert!(task.items.len() <= 3);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chunked_fallback_reports_repaired_replies() {
+        let backend = MockBackend::new()
+            .respond_with_status(400, CONTEXT_LENGTH_EXCEEDED)
+            .respond_with("[3, 7, 9,");
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend)
+            .chunk_size(3);
+
+        let result = sorter.sort_with_report(&[7, 3, 9, 1, 8]).await.unwrap();
+        assert_eq!(result.items, vec![1, 3, 7, 8, 9]);
+        assert!(result.report.chunked_fallback);
+        assert!(result.report.json_repaired);
+    }
+
+    #[tokio::test]
+    async fn test_context_length_falls_back_to_model_limit_chunks() {
+        let items: Vec<i64> = (0..12).rev().collect();
+
+        // 64 output tokens leave 48, or 144 bytes of 3-byte elements
+        let backend =
+            Arc::new(MockBackend::new().respond_with_status(400, CONTEXT_LENGTH_EXCEEDED));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .model_limits(crate::ModelLimits::new(128, 64));
+        let chunk_size = sorter.chunk_size_for(items.len(), 3 * items.len()).unwrap();
+        assert_eq!(chunk_size, 48);
+
+        let result = sorter.sort_with_report(&items).await.unwrap();
+        assert_eq!(result.items, (0..12).collect::<Vec<_>>());
+        assert!(result.report.chunked_fallback);
+    }
+
+    #[tokio::test]
+    async fn test_chunked_sort_descending() {
+        let items: Vec<i64> = (0..20).map(|i| (i * 7) % 20).collect();
+
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with_status(400, CONTEXT_LENGTH_EXCEEDED))
+            .order(Order::Descending)
+            .chunk_size(4);
+
+        let sorted = sorter.sort(&items).await.unwrap();
+        assert_eq!(sorted, (0..20).rev().collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_context_length_error_without_chunking() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with_status(400, CONTEXT_LENGTH_EXCEEDED));
+
+        let result = sorter.sort(&[2, 1]).await;
+        assert!(matches!(
+            result,
+            Err(VibesortError::ContextLengthExceeded { .. })
+        ));
+    }
+
+    /// A backend that waits a scripted time before every reply.
+    #[derive(Debug)]
+    struct DelayedBackend {
+        inner: MockBackend,
+        delays: Mutex<VecDeque<u64>>,
+    }
+
+    impl DelayedBackend {
+        fn new(delays: impl IntoIterator<Item = u64>) -> Self {
+            Self {
+                inner: MockBackend::new().respond_with_status(400, CONTEXT_LENGTH_EXCEEDED),
+                delays: Mutex::new(delays.into_iter().collect()),
+            }
+        }
+    }
+
+    impl Backend for DelayedBackend {
+        fn send(
+            &self,
+            request: BackendRequest,
+        ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>> {
+            let delay = self.delays.lock().unwrap().pop_front().unwrap_or(0);
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_secs(delay)).await;
+                self.inner.send(request).await
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_chunk_timeout_is_retried() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(DelayedBackend::new([0, 60]))
+            .retry_policy(ExponentialBackoff::new(2))
+            .chunk_size(3)
+            .chunk_timeout(Duration::from_secs(10));
+
+        let sorted = sorter.sort(&[6, 5, 4, 3, 2, 1]).await.unwrap();
+        assert_eq!(sorted, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_time_budget_returns_partial_results() {
+        let items = [9, 8, 7, 6, 5, 4, 3, 2, 1];
+        // The first two chunks take two seconds each, the third does not fit
+        // in what is left of the budget
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .chunk_size(3)
+            .time_budget(Duration::from_secs(5));
+
+        let err = sorter
+            .clone()
+            .backend(DelayedBackend::new([0, 2, 2, 60]))
+            .sort(&items)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            VibesortError::BudgetExhausted(budget) if budget == Duration::from_secs(5)
+        ));
+
+        let result = sorter
+            .backend(DelayedBackend::new([0, 2, 2, 60]))
+            .partial_on_budget(true)
+            .sort_with_report(&items)
+            .await
+            .unwrap();
+        assert_eq!(result.report.sorted_prefix_len, Some(3));
+        assert_eq!(result.items[..3], [7, 8, 9]);
+        let mut all = result.items.clone();
+        all.sort();
+        assert_eq!(all, (1..=9).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,145 @@
+//! Sorting code snippets by complexity or quality.
+
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError};
+
+/// A criterion for [`Vibesort::sort_code`].
+///
+/// Any string converts into [`CodeCriterion::Custom`], so a free-form
+/// criterion can be passed directly.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CodeCriterion {
+    /// The number of independent paths through the code.
+    CyclomaticComplexity,
+
+    /// How hard the code is for a person to follow.
+    CognitiveComplexity,
+
+    /// How easy the code is to read.
+    Readability,
+
+    /// Overall quality: correctness, clarity, and idiomatic style.
+    Quality,
+
+    /// A free-form criterion such as "by how much it allocates".
+    Custom(String),
+}
+
+impl CodeCriterion {
+    /// Returns the criterion as used in the prompt.
+    fn describe(&self) -> &str {
+        match self {
+            CodeCriterion::CyclomaticComplexity => {
+                "by cyclomatic complexity (the number of independent paths through the code)"
+            }
+            CodeCriterion::CognitiveComplexity => {
+                "by cognitive complexity (how hard the code is for a person to follow)"
+            }
+            CodeCriterion::Readability => "by readability",
+            CodeCriterion::Quality => {
+                "by overall code quality (correctness, clarity, and idiomatic style)"
+            }
+            CodeCriterion::Custom(criterion) => criterion,
+        }
+    }
+}
+
+impl From<&str> for CodeCriterion {
+    fn from(criterion: &str) -> Self {
+        CodeCriterion::Custom(criterion.to_string())
+    }
+}
+
+impl From<String> for CodeCriterion {
+    fn from(criterion: String) -> Self {
+        CodeCriterion::Custom(criterion)
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts code snippets by complexity, quality, or a free-form criterion.
+    ///
+    /// The model only replies with the positions of the snippets in sorted
+    /// order, and the snippets are returned verbatim from the input, so no
+    /// code can be altered, reformatted, or truncated along the way. The
+    /// configured [`order`](Self::order) applies; the configured criterion is
+    /// replaced by `criterion`.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the reply does not
+    /// name every snippet exactly once.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{CodeCriterion, Vibesort};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let snippets = [
+    ///     "fn id(x: u32) -> u32 { x }",
+    ///     "fn sign(x: i32) -> i32 { if x > 0 { 1 } else if x < 0 { -1 } else { 0 } }",
+    /// ];
+    /// let simplest_first = sorter
+    ///     .sort_code(&snippets, CodeCriterion::CyclomaticComplexity)
+    ///     .await?;
+    /// let by_allocations = sorter
+    ///     .sort_code(&snippets, "by how much it allocates")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_code(
+        &self,
+        snippets: &[&str],
+        criterion: impl Into<CodeCriterion>,
+    ) -> Result<Vec<String>, VibesortError> {
+        let criterion = criterion.into();
+        let instruction = format!(
+            "(code snippets, each to be judged exactly as written) {}",
+            self.sort_instruction_for(Some(criterion.describe()))
+        );
+        let indices = self
+            .sort_indexed(Operation::Code, snippets, &instruction)
+            .await?;
+        Ok(indices
+            .into_iter()
+            .map(|index| snippets[index].to_string())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_code_returns_snippets_verbatim() {
+        let backend = Arc::new(MockBackend::new().respond_with("[1, 0]"));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let snippets = ["fn f() {\n    if a { b() } else { c() }\n}", "fn g() {}"];
+        let sorted = sorter
+            .sort_code(&snippets, CodeCriterion::CyclomaticComplexity)
+            .await
+            .unwrap();
+        assert_eq!(sorted, vec![snippets[1], snippets[0]]);
+
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        assert!(
+            system
+                .as_str()
+                .unwrap()
+                .contains("by cyclomatic complexity")
+        );
+    }
+}
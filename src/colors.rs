@@ -0,0 +1,120 @@
+//! Sorting colors by perceptual attributes.
+
+use crate::annotate::Annotation;
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError};
+use serde::{Deserialize, Serialize};
+
+/// A color in the HSL color space, as estimated by the model.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Hsl {
+    /// The hue in degrees, from 0 to 360.
+    pub hue: f32,
+
+    /// The saturation in percent, from 0 to 100.
+    pub saturation: f32,
+
+    /// The lightness in percent, from 0 to 100.
+    pub lightness: f32,
+}
+
+/// The annotation requested by [`Vibesort::sort_colors`].
+#[derive(Debug, Deserialize)]
+struct ColorAnnotation {
+    hsl: Hsl,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts colors by a perceptual criterion such as "by hue" or "from warm
+    /// to cool".
+    ///
+    /// Colors may be given as hex codes (`#ff0000`) or CSS color names
+    /// (`salmon`). Each sorted color is returned unchanged together with the
+    /// HSL values the model assigned to it, which can be used to check the
+    /// model's reading of each color. The configured [`order`](Self::order)
+    /// applies; the configured criterion is replaced by `criterion`.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::ParseError`] is returned if any color lacks HSL
+    /// values.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let palette = sorter
+    ///     .sort_colors(&["#ff0000", "salmon", "rebeccapurple"], "by hue")
+    ///     .await?;
+    /// for (color, hsl) in palette {
+    ///     println!("{} (hue {})", color, hsl.hue);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_colors(
+        &self,
+        colors: &[&str],
+        criterion: &str,
+    ) -> Result<Vec<(String, Hsl)>, VibesortError> {
+        let colors: Vec<String> = colors.iter().map(|s| s.to_string()).collect();
+        let instruction = format!(
+            "of colors (hex codes or CSS color names) {}",
+            self.sort_instruction_for(Some(criterion))
+        );
+        let annotation = Annotation {
+            fields: "\"hsl\": {\"hue\": <number>, \"saturation\": <number>, \"lightness\": <number>}",
+            meaning: "\"hsl\" is the color in the HSL color space, with the hue in degrees from 0 to 360 and the saturation and lightness in percent from 0 to 100",
+            size: 64,
+        };
+
+        let sorted: Vec<(String, ColorAnnotation)> = self
+            .sort_annotated(Operation::Colors, &colors, &instruction, annotation)
+            .await?;
+        Ok(sorted
+            .into_iter()
+            .map(|(color, annotation)| (color, annotation.hsl))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_colors() {
+        let backend = Arc::new(MockBackend::new().respond_with(
+            r##"[
+                {"item": "#ff0000", "hsl": {"hue": 0, "saturation": 100, "lightness": 50}},
+                {"item": "salmon", "hsl": {"hue": 6, "saturation": 93, "lightness": 71}},
+                {"item": "rebeccapurple", "hsl": {"hue": 270, "saturation": 50, "lightness": 40}}
+            ]"##,
+        ));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .verify(true);
+
+        let sorted = sorter
+            .sort_colors(&["rebeccapurple", "#ff0000", "salmon"], "by hue")
+            .await
+            .unwrap();
+        let names: Vec<&str> = sorted.iter().map(|(color, _)| color.as_str()).collect();
+        assert_eq!(names, vec!["#ff0000", "salmon", "rebeccapurple"]);
+        assert_eq!(sorted[2].1.hue, 270.0);
+
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        assert!(system.as_str().unwrap().contains("criterion: by hue"));
+    }
+}
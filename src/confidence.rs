@@ -0,0 +1,119 @@
+//! Sorting with a per-element confidence score.
+
+use crate::annotate::Annotation;
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// The annotation requested by [`Vibesort::sort_with_confidence`].
+#[derive(Debug, Deserialize)]
+struct Confidence {
+    confidence: f32,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts an array and returns the model's confidence in each placement.
+    ///
+    /// The model is asked to reply with the sorted elements, each paired with a
+    /// confidence between `0.0` (a guess) and `1.0` (certain) that the element
+    /// is in the right position. Scores outside that range are clamped. Use the
+    /// scores to flag low-confidence regions of the ordering for human review.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::ParseError`] is returned if any element lacks a
+    /// confidence score.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let words = vec!["serendipity".to_string(), "cat".to_string(), "ephemeral".to_string()];
+    /// for (word, confidence) in sorter.sort_with_confidence(&words).await? {
+    ///     if confidence < 0.5 {
+    ///         println!("Please double-check the position of {}", word);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_with_confidence<T>(&self, items: &[T]) -> Result<Vec<(T, f32)>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let annotation = Annotation {
+            fields: "\"confidence\": <number>",
+            meaning: "\"confidence\" is a number between 0 and 1 expressing how confident you are that the element is in the correct position",
+            size: 32,
+        };
+        let scored: Vec<(T, Confidence)> = self
+            .sort_annotated(
+                Operation::Confidence,
+                items,
+                &self.sort_instruction(),
+                annotation,
+            )
+            .await?;
+
+        Ok(scored
+            .into_iter()
+            .map(|(item, score)| (item, score.confidence.clamp(0.0, 1.0)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+
+    #[tokio::test]
+    async fn test_sort_with_confidence() {
+        let backend = MockBackend::new().respond_with(
+            r#"[{"item": 1, "confidence": 0.9}, {"item": 2, "confidence": 1.4}, {"item": 3, "confidence": 0.2}]"#,
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend)
+            .verify(true);
+
+        let scored = sorter.sort_with_confidence(&[3, 1, 2]).await.unwrap();
+        assert_eq!(scored, vec![(1, 0.9), (2, 1.0), (3, 0.2)]);
+    }
+
+    #[tokio::test]
+    async fn test_sort_with_confidence_requires_scores() {
+        let backend = MockBackend::new().respond_with("[1, 2, 3]");
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend);
+
+        let result = sorter.sort_with_confidence(&[3, 1, 2]).await;
+        assert!(matches!(result, Err(VibesortError::ParseError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_sort_with_confidence_within_tolerance() {
+        let reply =
+            r#"[{"item": 0.1, "confidence": 0.9}, {"item": 0.20000000001, "confidence": 0.8}]"#;
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with(reply).respond_with(reply))
+            .verify(true);
+        let err = sorter.sort_with_confidence(&[0.2, 0.1]).await.unwrap_err();
+        assert!(matches!(err, VibesortError::VerificationFailed(_)));
+
+        let scored = sorter
+            .verify_tolerance(1e-9)
+            .sort_with_confidence(&[0.2, 0.1])
+            .await
+            .unwrap();
+        assert_eq!(scored, vec![(0.1, 0.9), (0.2, 0.8)]);
+    }
+}
@@ -0,0 +1,255 @@
+//! Sorting with hard precedence constraints alongside the criterion.
+
+use crate::indexed::{check_positions, describe_payload, indexed_payload};
+use crate::prompt::Operation;
+use crate::{ChatMessage, MessageContent, Vibesort, VibesortError, graph, parse};
+use serde::Serialize;
+
+/// The number of times the model is asked for an order that satisfies the
+/// constraints, including the first request.
+const MAX_ROUNDS: usize = 3;
+
+/// A hard constraint that one element must come before another, given by
+/// their positions in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Precedence {
+    /// The position in the input of the element that must come first.
+    pub before: usize,
+
+    /// The position in the input of the element that must come later.
+    pub after: usize,
+}
+
+impl Precedence {
+    /// Creates a constraint that the element at input position `before` comes
+    /// before the element at input position `after`.
+    pub fn new(before: usize, after: usize) -> Self {
+        Self { before, after }
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts the items by the configured criterion while guaranteeing hard
+    /// precedence constraints such as "element 3 must come before element 0".
+    ///
+    /// The constraints are listed in the prompt and checked locally against
+    /// every reply. If the reply breaks any, the model is shown the broken
+    /// constraints and asked again, up to three requests in total. The model
+    /// only replies with the positions of the elements, which are returned
+    /// from the input.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::InvalidConstraints`] if the constraints form a
+    /// cycle, and [`VibesortError::VerificationFailed`] if the last reply
+    /// still breaks a constraint. Otherwise this method can return the same
+    /// errors as [`sort`](Self::sort).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a constraint refers to a position outside `items`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{Precedence, Vibesort};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-4o-mini",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .criterion("by how much the release depends on it");
+    ///
+    /// let tasks = ["Write docs", "Migrate the database", "Deploy", "Fix login bug"];
+    /// // The migration must happen before the deploy
+    /// let plan = sorter
+    ///     .sort_constrained(&tasks, &[Precedence::new(1, 2)])
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_constrained<T>(
+        &self,
+        items: &[T],
+        constraints: &[Precedence],
+    ) -> Result<Vec<T>, VibesortError>
+    where
+        T: Serialize + Clone,
+    {
+        if let Some((sorter, guard)) = self.admit()? {
+            return guard
+                .run(Box::pin(sorter.sort_constrained(items, constraints)))
+                .await;
+        }
+
+        let edges: Vec<(usize, usize)> = constraints
+            .iter()
+            .map(|constraint| {
+                assert!(
+                    constraint.before < items.len() && constraint.after < items.len(),
+                    "constraint {:?} is out of bounds for {} items",
+                    constraint,
+                    items.len()
+                );
+                (constraint.before, constraint.after)
+            })
+            .collect();
+        if let Some(cycle) = graph::find_cycle(items.len(), &edges) {
+            let cycle: Vec<String> = cycle.iter().map(ToString::to_string).collect();
+            return Err(VibesortError::InvalidConstraints(format!(
+                "elements {} must each come before the next",
+                cycle.join(" -> ")
+            )));
+        }
+
+        let json_array = indexed_payload(items)?;
+        // The reply is a list of numbers, a few bytes per element
+        let max_tokens = self.max_tokens_for(8 * items.len());
+        let rules: Vec<String> = constraints.iter().map(describe).collect();
+
+        let (system_prompt, user_content) =
+            self.render_prompt(Operation::Constrained, json_array, || {
+                format!(
+                    "You are a helpful assistant that sorts arrays. {} Sort the elements {}. These constraints are mandatory and override the sort order: {}. Return ONLY a JSON array of the indices of the elements in sorted order, with every index exactly once.",
+                    describe_payload("element"),
+                    self.sort_instruction(),
+                    rules.join("; ")
+                )
+            })?;
+
+        // The conversation so far: the request, then every rejected reply
+        // and the model's correction
+        let mut transcript: Vec<(&str, String)> = vec![("user", user_content)];
+        let mut broken = Vec::new();
+        for _ in 0..MAX_ROUNDS {
+            let (system_prompt, transcript_ref) = (&system_prompt, &transcript);
+            let (content, indices) = self
+                .retrying(|escalation| async move {
+                    let system_prompt = self.escalated_prompt(system_prompt, escalation);
+                    let mut messages = vec![ChatMessage {
+                        role: "system",
+                        content: MessageContent::Text(&system_prompt),
+                    }];
+                    messages.extend(transcript_ref.iter().map(|(role, content)| ChatMessage {
+                        role,
+                        content: MessageContent::Text(content),
+                    }));
+                    let completion = self
+                        .chat_messages(messages, None, max_tokens, 1, escalation)
+                        .await?;
+
+                    let indices: Vec<usize> = parse::parse_array(&completion.content)?;
+                    check_positions(items.len(), &indices)?;
+                    Ok((completion.content, indices))
+                })
+                .await?;
+
+            let mut rank = vec![0; items.len()];
+            for (position, &index) in indices.iter().enumerate() {
+                rank[index] = position;
+            }
+            broken = constraints
+                .iter()
+                .filter(|constraint| rank[constraint.before] > rank[constraint.after])
+                .map(describe)
+                .collect();
+            if broken.is_empty() {
+                return Ok(indices
+                    .into_iter()
+                    .map(|index| items[index].clone())
+                    .collect());
+            }
+
+            transcript.push(("assistant", content));
+            transcript.push((
+                "user",
+                format!(
+                    "This order breaks these mandatory constraints: {}. Return ONLY the corrected JSON array of indices.",
+                    broken.join("; ")
+                ),
+            ));
+        }
+
+        Err(VibesortError::VerificationFailed(format!(
+            "order breaks constraints after {} attempts: {}",
+            MAX_ROUNDS,
+            broken.join("; ")
+        )))
+    }
+}
+
+/// Describes a constraint for prompts and errors.
+fn describe(constraint: &Precedence) -> String {
+    format!(
+        "element {} must come before element {}",
+        constraint.before, constraint.after
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_constrained_reprompts_with_violations() {
+        let backend = Arc::new(
+            MockBackend::new()
+                .respond_with("[0, 1, 2]")
+                .respond_with("[2, 0, 1]"),
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let items = ["a", "b", "c"].map(String::from);
+        let sorted = sorter
+            .sort_constrained(&items, &[Precedence::new(2, 0)])
+            .await
+            .unwrap();
+        assert_eq!(sorted, ["c", "a", "b"]);
+
+        let requests = backend.requests();
+        assert_eq!(requests.len(), 2);
+        let system = requests[0].body["messages"][0]["content"].clone();
+        assert!(system.as_str().unwrap().contains(
+            "mandatory and override the sort order: element 2 must come before element 0."
+        ));
+        let messages = requests[1].body["messages"].as_array().unwrap().clone();
+        assert_eq!(messages[2]["role"], "assistant");
+        assert_eq!(messages[2]["content"], "[0, 1, 2]");
+        assert!(messages[3]["content"].as_str().unwrap().starts_with(
+            "This order breaks these mandatory constraints: element 2 must come before element 0."
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sort_constrained_gives_up() {
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(
+            MockBackend::new()
+                .respond_with("[0, 1]")
+                .respond_with("[0, 1]")
+                .respond_with("[0, 1]"),
+        );
+
+        let err = sorter
+            .sort_constrained(&[1, 2], &[Precedence::new(1, 0)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::VerificationFailed(_)));
+    }
+
+    #[tokio::test]
+    async fn test_sort_constrained_rejects_cycles() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let err = sorter
+            .sort_constrained(&[1, 2, 3], &[Precedence::new(0, 1), Precedence::new(1, 0)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::InvalidConstraints(_)));
+        assert!(backend.requests().is_empty());
+    }
+}
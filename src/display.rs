@@ -0,0 +1,118 @@
+//! Sorting opaque values by their `Display` form.
+
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError};
+use std::fmt::Display;
+
+impl<'a> Vibesort<'a> {
+    /// Sorts values by showing the model only their [`Display`] form.
+    ///
+    /// This suits types whose JSON serialization is large, or which do not
+    /// implement `Serialize` at all, but whose `to_string()` is a short
+    /// summary. The model replies with the order of the summaries only, and
+    /// the given values are moved into that order, so they never pass
+    /// through the model. The configured criterion and order apply.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the model does not
+    /// return every value exactly once.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fmt;
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// struct Ticket {
+    ///     title: String,
+    ///     attachments: Vec<Vec<u8>>,
+    /// }
+    ///
+    /// impl fmt::Display for Ticket {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         write!(f, "{} ({} attachments)", self.title, self.attachments.len())
+    ///     }
+    /// }
+    ///
+    /// # async fn example(tickets: Vec<Ticket>) -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .criterion("by urgency");
+    ///
+    /// let tickets = sorter.sort_by_display(tickets).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_by_display<T: Display>(
+        &self,
+        items: Vec<T>,
+    ) -> Result<Vec<T>, VibesortError> {
+        let summaries: Vec<String> = items.iter().map(ToString::to_string).collect();
+        let instruction = format!(
+            "(each given as a short description of a larger value) {}",
+            self.sort_instruction()
+        );
+        let indices = self
+            .sort_indexed(Operation::Display, &summaries, &instruction)
+            .await?;
+
+        // The indices are a permutation, so every value is taken once
+        let mut items: Vec<Option<T>> = items.into_iter().map(Some).collect();
+        Ok(indices
+            .into_iter()
+            .filter_map(|index| items[index].take())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::fmt;
+    use std::sync::Arc;
+
+    /// A value that is neither `Serialize` nor `Clone`.
+    #[derive(Debug, PartialEq)]
+    struct Blob {
+        name: &'static str,
+        data: Vec<u8>,
+    }
+
+    impl fmt::Display for Blob {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{} ({} bytes)", self.name, self.data.len())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sort_by_display() {
+        let backend = Arc::new(MockBackend::new().respond_with("[1, 0]"));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let blobs = vec![
+            Blob {
+                name: "large",
+                data: vec![0; 4096],
+            },
+            Blob {
+                name: "small",
+                data: vec![1],
+            },
+        ];
+        let sorted = sorter.sort_by_display(blobs).await.unwrap();
+        assert_eq!(sorted[0].name, "small");
+        assert_eq!(sorted[1].data.len(), 4096);
+
+        let user = backend.requests()[0].body["messages"][1]["content"].clone();
+        assert_eq!(
+            user,
+            r#"[{"index":0,"item":"large (4096 bytes)"},{"index":1,"item":"small (1 bytes)"}]"#
+        );
+    }
+}
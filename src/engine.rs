@@ -0,0 +1,306 @@
+//! Selection between the LLM and a deterministic local sorting engine.
+//!
+//! [`Engine::Local`] sorts with a real comparator and never touches the
+//! network, while still going through the same request building, response
+//! parsing, and error handling as the LLM path. Pipelines can switch to it in
+//! CI with a single flag.
+//!
+//! The local engine only replies with the sorted array itself. That answers
+//! the operations that ask for one: [`sort`](crate::Vibesort::sort) and
+//! [`sort_with_report`](crate::Vibesort::sort_with_report) with any of their
+//! options (including [tagged ids](crate::Vibesort::tag_ids) and
+//! [chunking](crate::Vibesort::chunk_size)), the sorts built on them such as
+//! [`sort_by_pointers`](crate::Vibesort::sort_by_pointers), and
+//! [`sort_with_explanation`](crate::Vibesort::sort_with_explanation), which
+//! then has no explanation. Operations whose replies carry more than the
+//! order, such as annotated sorts like
+//! [`sort_with_confidence`](crate::Vibesort::sort_with_confidence) or
+//! replies of indices and scores like
+//! [`sort_weighted`](crate::Vibesort::sort_weighted), fail to parse the
+//! reply with [`VibesortError::ParseError`].
+
+use crate::backend::{Backend, BackendRequest, BackendResponse, BoxFuture, SortTask};
+use crate::{Order, VibesortError};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+/// The environment variable read by [`Engine::from_env`].
+pub const ENGINE_ENV_VAR: &str = "VIBESORT_ENGINE";
+
+/// The engine that performs the sort.
+#[derive(Debug, Clone, Default)]
+pub enum Engine {
+    /// Sort by asking the configured LLM (the default).
+    #[default]
+    Llm,
+
+    /// Sort locally with a deterministic comparator, without any network access.
+    ///
+    /// Only operations that ask for the sorted array itself are answered; see
+    /// the [module documentation](self).
+    Local(LocalEngine),
+}
+
+impl Engine {
+    /// Returns a local engine using the natural ordering of JSON values.
+    pub fn local() -> Self {
+        Engine::Local(LocalEngine::default())
+    }
+
+    /// Selects the engine from the `VIBESORT_ENGINE` environment variable.
+    ///
+    /// The value `local` selects [`Engine::local`]; anything else (or an unset
+    /// variable) selects [`Engine::Llm`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    /// use vibesort_rs::engine::Engine;
+    ///
+    /// // VIBESORT_ENGINE=local cargo test
+    /// let sorter = Vibesort::new("your-api-key", "gpt-3.5-turbo", "https://api.openai.com/v1")
+    ///     .engine(Engine::from_env());
+    /// ```
+    pub fn from_env() -> Self {
+        match std::env::var(ENGINE_ENV_VAR) {
+            Ok(value) if value.eq_ignore_ascii_case("local") => Engine::local(),
+            _ => Engine::Llm,
+        }
+    }
+}
+
+type Comparator = Arc<dyn Fn(&Value, &Value) -> Ordering + Send + Sync>;
+
+/// A [`Backend`] that sorts the requested array locally with a comparator.
+///
+/// The engine answers with a chat completion just like a model would, so all
+/// of the surrounding parsing code is exercised.
+#[derive(Clone)]
+pub struct LocalEngine {
+    comparator: Comparator,
+}
+
+impl LocalEngine {
+    /// Creates an engine that orders elements with a comparator over their
+    /// JSON representation.
+    pub fn by(comparator: impl Fn(&Value, &Value) -> Ordering + Send + Sync + 'static) -> Self {
+        Self {
+            comparator: Arc::new(comparator),
+        }
+    }
+
+    /// Creates an engine that orders elements by the [`Ord`] implementation of
+    /// `T`.
+    ///
+    /// Elements are deserialized as `T` before being compared. Elements that
+    /// cannot be deserialized fall back to the natural JSON ordering.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vibesort_rs::engine::{Engine, LocalEngine};
+    ///
+    /// let engine = Engine::Local(LocalEngine::by_ord::<(u32, String)>());
+    /// ```
+    pub fn by_ord<T: DeserializeOwned + Ord>() -> Self {
+        Self::by(
+            |a, b| match (T::deserialize(a).ok(), T::deserialize(b).ok()) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                _ => json_cmp(a, b),
+            },
+        )
+    }
+
+    /// Sorts the requested array and wraps it in a chat completion response.
+    ///
+    /// The array and order are taken from the request's [`SortTask`] if it has
+    /// one, and otherwise from the last message of the request body.
+    pub(crate) fn respond(&self, request: &BackendRequest) -> BackendResponse {
+        let task = request.task.clone().or_else(|| {
+            request.body["messages"]
+                .as_array()
+                .and_then(|messages| messages.last())
+                .and_then(|message| message["content"].as_str())
+                .and_then(|content| serde_json::from_str::<Vec<Value>>(content).ok())
+                .map(|items| SortTask {
+                    items,
+                    order: Order::Ascending,
+                })
+        });
+
+        match task {
+            Some(SortTask { mut items, order }) => {
+                items.sort_by(|a, b| (self.comparator)(a, b));
+                if order == Order::Descending {
+                    items.reverse();
+                }
+                completion(&Value::Array(items).to_string())
+            }
+            None => BackendResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Local engine: last message is not a JSON array",
+            ),
+        }
+    }
+}
+
+impl Default for LocalEngine {
+    fn default() -> Self {
+        Self::by(json_cmp)
+    }
+}
+
+impl fmt::Debug for LocalEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalEngine").finish_non_exhaustive()
+    }
+}
+
+impl Backend for LocalEngine {
+    fn send(
+        &self,
+        request: BackendRequest,
+    ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>> {
+        let response = self.respond(&request);
+        Box::pin(async move { Ok(response) })
+    }
+}
+
+/// Builds a successful chat completion response with the given content.
+pub(crate) fn completion(content: &str) -> BackendResponse {
+    let body = serde_json::json!({
+        "choices": [{
+            "message": {
+                "content": content
+            }
+        }]
+    });
+    BackendResponse::new(StatusCode::OK, body.to_string())
+}
+
+/// Compares two JSON values using a natural total ordering.
+///
+/// Values of different types are ordered `null < bool < number < string <
+/// array < object`. Numbers compare numerically, strings lexicographically,
+/// and arrays and objects element by element.
+pub fn json_cmp(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => match (a.as_i64(), b.as_i64()) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => {
+                let a = a.as_f64().unwrap_or(f64::NAN);
+                let b = b.as_f64().unwrap_or(f64::NAN);
+                a.total_cmp(&b)
+            }
+        },
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(a), Value::Array(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| json_cmp(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Object(a), Value::Object(b)) => a
+            .iter()
+            .zip(b)
+            .map(|((ka, va), (kb, vb))| ka.cmp(kb).then_with(|| json_cmp(va, vb)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vibesort;
+
+    #[tokio::test]
+    async fn test_local_engine_sorts_without_network() {
+        // The URL is unreachable; the local engine must never try to use it
+        let sorter = Vibesort::new("key", "model", "http://127.0.0.1:9").engine(Engine::local());
+
+        let sorted = sorter.sort_str(&["pear", "apple", "fig"]).await.unwrap();
+        assert_eq!(sorted, vec!["apple", "fig", "pear"]);
+    }
+
+    #[tokio::test]
+    async fn test_local_engine_custom_comparator() {
+        let by_length = LocalEngine::by(|a, b| {
+            let len = |v: &Value| v.as_str().map_or(0, str::len);
+            len(a).cmp(&len(b))
+        });
+        let sorter = Vibesort::new("key", "model", "http://mock").engine(Engine::Local(by_length));
+
+        let sorted = sorter.sort_str(&["ccc", "a", "bb"]).await.unwrap();
+        assert_eq!(sorted, vec!["a", "bb", "ccc"]);
+    }
+
+    #[tokio::test]
+    async fn test_local_engine_by_ord() {
+        let descending = LocalEngine::by_ord::<std::cmp::Reverse<i64>>();
+        let sorter = Vibesort::new("key", "model", "http://mock").engine(Engine::Local(descending));
+
+        let sorted = sorter.sort(&[2, 9, 4]).await.unwrap();
+        assert_eq!(sorted, vec![9, 4, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_local_engine_supported_operations() {
+        let sorter = Vibesort::new("key", "model", "http://127.0.0.1:9").engine(Engine::local());
+        let items = [3, 1, 5, 2, 4];
+
+        let tagged = sorter.clone().tag_ids(true).sort(&items).await.unwrap();
+        assert_eq!(tagged, [1, 2, 3, 4, 5]);
+        let chunked = sorter.clone().chunk_size(2).sort(&items).await.unwrap();
+        assert_eq!(chunked, [1, 2, 3, 4, 5]);
+        let explained = sorter.sort_with_explanation(&items).await.unwrap();
+        assert_eq!(explained.items, [1, 2, 3, 4, 5]);
+        assert_eq!(explained.explanation, None);
+
+        // Replies with more than the order are not answered
+        let err = sorter.sort_with_confidence(&items).await.unwrap_err();
+        assert!(matches!(err, VibesortError::ParseError(_)));
+        let err = sorter
+            .sort_weighted(&items, &[("size", 1.0)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::ParseError(_)));
+    }
+
+    #[test]
+    fn test_json_cmp_mixed_types() {
+        let mut values = vec![
+            serde_json::json!("b"),
+            serde_json::json!(10),
+            serde_json::json!(null),
+            serde_json::json!(2.5),
+            serde_json::json!("a"),
+        ];
+        values.sort_by(json_cmp);
+        assert_eq!(
+            values,
+            serde_json::json!([null, 2.5, 10, "a", "b"])
+                .as_array()
+                .unwrap()
+                .clone()
+        );
+    }
+}
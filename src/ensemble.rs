@@ -0,0 +1,224 @@
+//! Rank aggregation across several models.
+//!
+//! An [`Ensemble`] sends the same sort to several configured sorters (usually
+//! pointing at different models) and combines their orderings into one. For
+//! subjective criteria, where a single model's answer can swing from call to
+//! call, the aggregate is more stable than any one member.
+
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// How the members' orderings are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Aggregation {
+    /// Borda count: an element scores one point for every element ranked
+    /// below it, summed over all members, and the elements are ordered by
+    /// score (the default).
+    #[default]
+    Borda,
+
+    /// Kemeny-style aggregation: starting from the Borda ordering, adjacent
+    /// elements are swapped while a majority of members ranks them the other
+    /// way round. The result never disagrees with a majority on any adjacent
+    /// pair, which is a cheap approximation of the Kemeny ranking.
+    Kemeny,
+}
+
+/// A group of sorters whose orderings are aggregated.
+///
+/// Every member uses its own configuration (model, endpoint, prompt, ...);
+/// only the items are shared. Member results are always checked to be a
+/// permutation of the input.
+///
+/// # Example
+///
+/// ```no_run
+/// use vibesort_rs::Vibesort;
+/// use vibesort_rs::ensemble::{Aggregation, Ensemble};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let base_url = "https://api.openai.com/v1";
+/// let ensemble = Ensemble::new([
+///     Vibesort::new("your-api-key", "gpt-4o", base_url),
+///     Vibesort::new("your-api-key", "gpt-4o-mini", base_url),
+///     Vibesort::new("your-api-key", "gpt-3.5-turbo", base_url),
+/// ])
+/// .aggregation(Aggregation::Kemeny);
+///
+/// let jokes = vec!["pun".to_string(), "knock-knock".to_string(), "one-liner".to_string()];
+/// let funniest_last = ensemble.sort(&jokes).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Ensemble<'a> {
+    members: Vec<Vibesort<'a>>,
+    aggregation: Aggregation,
+}
+
+impl<'a> Ensemble<'a> {
+    /// Creates an ensemble of the given sorters, using [`Aggregation::Borda`].
+    pub fn new(members: impl IntoIterator<Item = Vibesort<'a>>) -> Self {
+        Self {
+            members: members.into_iter().collect(),
+            aggregation: Aggregation::default(),
+        }
+    }
+
+    /// Sets how the members' orderings are combined.
+    pub fn aggregation(mut self, aggregation: Aggregation) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
+    /// Sorts the items with every member and returns the aggregated ordering.
+    ///
+    /// Members are queried in turn. Ties are broken by the input order.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of any member; see [`Vibesort::sort`].
+    pub async fn sort<T>(&self, items: &[T]) -> Result<Vec<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let values: Vec<Value> = items
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<_, _>>()?;
+
+        // Every member is admitted before the first one is queried, so that
+        // a shutdown starting in between lets the ensemble finish
+        let admissions = self
+            .members
+            .iter()
+            .map(Vibesort::admit)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut rankings = Vec::with_capacity(self.members.len());
+        for (member, admission) in self.members.iter().zip(admissions) {
+            let result = match admission {
+                Some((member, guard)) => {
+                    guard
+                        .run(member.sort_with_report_inner(&values, true))
+                        .await?
+                }
+                None => member.sort_with_report_inner(&values, true).await?,
+            };
+            rankings.push(positions(&values, &result.items));
+        }
+
+        let order = match self.aggregation {
+            Aggregation::Borda => borda(values.len(), &rankings),
+            Aggregation::Kemeny => kemeny(values.len(), &rankings),
+        };
+        Ok(order
+            .into_iter()
+            .map(|index| serde_json::from_value(values[index].clone()))
+            .collect::<Result<_, _>>()?)
+    }
+}
+
+/// Maps a sorted permutation of `values` to the input position of each element.
+///
+/// Equal elements are matched to their input positions in order.
+fn positions(values: &[Value], sorted: &[Value]) -> Vec<usize> {
+    let mut used = vec![false; values.len()];
+    sorted
+        .iter()
+        .filter_map(|value| {
+            let index = (0..values.len()).find(|&i| !used[i] && values[i] == *value)?;
+            used[index] = true;
+            Some(index)
+        })
+        .collect()
+}
+
+/// Orders the input positions by Borda count.
+fn borda(len: usize, rankings: &[Vec<usize>]) -> Vec<usize> {
+    let mut scores = vec![0; len];
+    for ranking in rankings {
+        for (rank, &index) in ranking.iter().enumerate() {
+            scores[index] += len - 1 - rank;
+        }
+    }
+    let mut order: Vec<usize> = (0..len).collect();
+    // Stable, so ties keep the input order
+    order.sort_by(|&a, &b| scores[b].cmp(&scores[a]));
+    order
+}
+
+/// Refines the Borda ordering until no adjacent pair contradicts a majority.
+fn kemeny(len: usize, rankings: &[Vec<usize>]) -> Vec<usize> {
+    // wins[a][b] is the number of members ranking a before b
+    let mut wins = vec![vec![0; len]; len];
+    for ranking in rankings {
+        for (rank, &a) in ranking.iter().enumerate() {
+            for &b in &ranking[rank + 1..] {
+                wins[a][b] += 1;
+            }
+        }
+    }
+
+    // Every swap strictly reduces the total disagreement, so this terminates
+    let mut order = borda(len, rankings);
+    let mut swapped = true;
+    while swapped {
+        swapped = false;
+        for i in 1..order.len() {
+            let (a, b) = (order[i - 1], order[i]);
+            if wins[b][a] > wins[a][b] {
+                order.swap(i - 1, i);
+                swapped = true;
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+
+    fn member(response: &str) -> Vibesort<'static> {
+        Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with(response.to_string()))
+    }
+
+    fn ensemble() -> Ensemble<'static> {
+        Ensemble::new([
+            member(r#"["a","b","c"]"#),
+            member(r#"["a","b","c"]"#),
+            member(r#"["b","c","a"]"#),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_borda_count() {
+        // "a" and "b" tie on 4 points, so the input order decides
+        let items = ["b", "a", "c"].map(String::from);
+        let sorted = ensemble().sort(&items).await.unwrap();
+        assert_eq!(sorted, vec!["b", "a", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_kemeny_follows_pairwise_majority() {
+        // Two of three members rank "a" before "b"
+        let items = ["b", "a", "c"].map(String::from);
+        let sorted = ensemble()
+            .aggregation(Aggregation::Kemeny)
+            .sort(&items)
+            .await
+            .unwrap();
+        assert_eq!(sorted, vec!["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_member_results_are_verified() {
+        let ensemble = Ensemble::new([member(r#"["a","b"]"#), member(r#"["a","a"]"#)]);
+        let result = ensemble.sort(&["b", "a"].map(String::from)).await;
+        assert!(matches!(result, Err(VibesortError::VerificationFailed(_))));
+    }
+}
@@ -0,0 +1,275 @@
+//! Estimating the cost of a sort without calling the API.
+
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+
+/// The price of a model per million tokens, used by
+/// [`Vibesort::estimate`] to estimate costs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pricing {
+    /// The price of one million prompt tokens.
+    pub prompt_per_million: f64,
+
+    /// The price of one million completion tokens.
+    pub completion_per_million: f64,
+}
+
+impl Pricing {
+    /// Creates a pricing from the prices of one million prompt and completion
+    /// tokens.
+    pub fn new(prompt_per_million: f64, completion_per_million: f64) -> Self {
+        Self {
+            prompt_per_million,
+            completion_per_million,
+        }
+    }
+
+    /// Returns the price of the given numbers of tokens.
+    pub fn cost(&self, prompt_tokens: u64, completion_tokens: u64) -> f64 {
+        (prompt_tokens as f64 * self.prompt_per_million
+            + completion_tokens as f64 * self.completion_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// The estimated size and cost of a sort, returned by
+/// [`Vibesort::estimate`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    /// The estimated number of prompt tokens over all requests.
+    pub prompt_tokens: u64,
+
+    /// The estimated number of completion tokens over all requests.
+    pub expected_completion_tokens: u64,
+
+    /// The estimated cost, if [`pricing`](Vibesort::pricing) is configured.
+    pub est_cost: Option<f64>,
+
+    /// The number of chunks the input is split into (1 unless it is sorted in
+    /// chunks).
+    pub chunks: usize,
+
+    /// Whether the single request is expected to exceed the model's context
+    /// window, so that the input is sorted in chunks after it is rejected.
+    pub chunked_fallback: bool,
+}
+
+/// Estimates the number of tokens in `bytes` bytes of text, assuming three
+/// bytes per token as [`Vibesort::max_tokens_for`] does.
+fn tokens(bytes: usize) -> u64 {
+    bytes.div_ceil(3) as u64
+}
+
+/// The number of tokens of framing added to every chat message.
+const MESSAGE_OVERHEAD_TOKENS: u64 = 4;
+
+impl<'a> Vibesort<'a> {
+    /// Estimates the tokens and cost of sorting the items, without calling
+    /// the API.
+    ///
+    /// Token counts assume about three bytes of text per token. A sort first
+    /// tries a single request, and only falls back to
+    /// [sorting in chunks](Self::chunk_size) if the provider rejects it for
+    /// exceeding the context window. The fallback is assumed when the
+    /// [model's limits](Self::model_limits) are known and the single request
+    /// does not fit them; the rejected request is not counted, and merge
+    /// requests are estimated assuming half of every merge frontier is
+    /// emitted per request. If the limits are not known, the single request
+    /// is assumed to fit. Candidates requested with [`n_best`](Self::n_best)
+    /// are counted as completion tokens; reflection passes and retries are
+    /// not counted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::JsonError`] if the items cannot be serialized
+    /// and [`VibesortError::InvalidTemplate`] if the selected prompt template
+    /// does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vibesort_rs::{Pricing, Vibesort};
+    ///
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-4o-mini",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .pricing(Pricing::new(0.15, 0.60));
+    ///
+    /// let estimate = sorter.estimate(&[3, 1, 2])?;
+    /// println!("about ${:.4}", estimate.est_cost.unwrap_or_default());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn estimate<T>(&self, items: &[T]) -> Result<Estimate, VibesortError>
+    where
+        T: Serialize,
+    {
+        // The prompt without the array, and the size of every element
+        let (system_prompt, user_content) = self.sort_prompt(String::from("[]"))?;
+        let examples: usize = self
+            .examples
+            .iter()
+            .map(|(input, output)| input.len() + output.len())
+            .sum();
+        let fixed_tokens = tokens(system_prompt.len() + user_content.len() + examples)
+            + MESSAGE_OVERHEAD_TOKENS * (2 + 2 * self.examples.len() as u64);
+        let sizes = items
+            .iter()
+            .map(|item| Ok(serde_json::to_string(item)?.len()))
+            .collect::<Result<Vec<_>, VibesortError>>()?;
+
+        // Every request carries a JSON array of a known number of elements,
+        // and the reply repeats it
+        let array_bytes = |elements: usize, element_bytes: usize| element_bytes + elements + 1;
+        let mut estimate = Estimate {
+            prompt_tokens: 0,
+            expected_completion_tokens: 0,
+            est_cost: None,
+            chunks: 1,
+            chunked_fallback: false,
+        };
+        let request = |bytes: usize| {
+            let prompt_tokens = fixed_tokens + tokens(bytes);
+            (prompt_tokens, tokens(bytes) * u64::from(self.candidates))
+        };
+        let mut add_request = |bytes: usize| {
+            let (prompt_tokens, completion_tokens) = request(bytes);
+            estimate.prompt_tokens += prompt_tokens;
+            estimate.expected_completion_tokens += completion_tokens;
+        };
+
+        // The single request falls back to chunks if it does not fit the
+        // model's limits, which are only known in advance for some models
+        let array_len = array_bytes(sizes.len(), sizes.iter().sum());
+        let (prompt_tokens, completion_tokens) = request(array_len);
+        let fits = self.limits().is_none_or(|limits| {
+            completion_tokens <= u64::from(limits.max_output)
+                && prompt_tokens + completion_tokens <= u64::from(limits.context_window)
+        });
+        match self.chunk_size_for(sizes.len(), array_len) {
+            Some(chunk_size) if !fits && items.len() > chunk_size => {
+                let average = sizes.iter().sum::<usize>().div_ceil(sizes.len());
+                let mut chunks = Vec::new();
+                for chunk in sizes.chunks(chunk_size) {
+                    add_request(array_bytes(chunk.len(), chunk.iter().sum()));
+                    chunks.push(chunk.len());
+                }
+                for frontier in merge_frontiers(&chunks, chunk_size).concat() {
+                    add_request(array_bytes(frontier, frontier * average));
+                }
+                estimate.chunks = chunks.len();
+                estimate.chunked_fallback = true;
+            }
+            _ => add_request(array_len),
+        }
+
+        estimate.est_cost = self.pricing.map(|pricing| {
+            pricing.cost(estimate.prompt_tokens, estimate.expected_completion_tokens)
+        });
+        Ok(estimate)
+    }
+}
+
+/// Returns the expected number of elements in each merge request when runs
+/// of the given lengths are merged in requests of at most `chunk_size`
+/// elements, assuming half of every frontier is emitted per request.
+///
+/// The requests are grouped by merge round; every round merges groups of up
+/// to `chunk_size` runs.
+pub(crate) fn merge_frontiers(runs: &[usize], chunk_size: usize) -> Vec<Vec<usize>> {
+    let chunk_size = chunk_size.max(2);
+    let mut runs = runs.to_vec();
+    let mut rounds = Vec::new();
+    while runs.len() > 1 {
+        let mut frontiers = Vec::new();
+        runs = runs
+            .chunks(chunk_size)
+            .map(|group| {
+                let total: usize = group.iter().sum();
+                // Once no more than half a chunk is left, it all belongs to
+                // the last run and is appended without a request
+                let mut remaining = total;
+                while group.len() > 1 && remaining > chunk_size / 2 {
+                    let frontier = remaining.min(chunk_size);
+                    frontiers.push(frontier);
+                    remaining -= frontier / 2;
+                }
+                total
+            })
+            .collect();
+        rounds.push(frontiers);
+    }
+    rounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ModelLimits;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_estimate_single_request() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .pricing(Pricing::new(1.0, 2.0));
+
+        let estimate = sorter.estimate(&[300, 100, 200]).unwrap();
+        assert_eq!(estimate.chunks, 1);
+        // "[300,100,200]" is 13 bytes
+        assert_eq!(estimate.expected_completion_tokens, 5);
+        assert!(estimate.prompt_tokens > estimate.expected_completion_tokens);
+        let cost = estimate.est_cost.unwrap();
+        assert_eq!(cost, (estimate.prompt_tokens as f64 + 10.0) / 1_000_000.0);
+        assert!(backend.requests().is_empty());
+    }
+
+    #[test]
+    fn test_estimate_chunked() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .chunk_size(4)
+            .model_limits(ModelLimits::new(40, 40));
+        let items: Vec<u32> = (0..10).collect();
+
+        let single = Vibesort::new("key", "model", "http://mock")
+            .estimate(&items)
+            .unwrap();
+        let chunked = sorter.estimate(&items).unwrap();
+        assert!(chunked.chunked_fallback);
+        assert_eq!(chunked.chunks, 3);
+        assert_eq!(chunked.est_cost, None);
+        assert!(chunked.prompt_tokens > single.prompt_tokens);
+        assert!(chunked.expected_completion_tokens > single.expected_completion_tokens);
+    }
+
+    #[test]
+    fn test_estimate_single_request_that_fits() {
+        let items: Vec<u32> = (0..10).collect();
+        let single = Vibesort::new("key", "model", "http://mock")
+            .estimate(&items)
+            .unwrap();
+
+        // A chunk size only applies once the single request is rejected
+        let sorter = Vibesort::new("key", "model", "http://mock").chunk_size(4);
+        assert_eq!(sorter.estimate(&items).unwrap(), single);
+        let sorter = sorter.model_limits(ModelLimits::new(128_000, 16_384));
+        let estimate = sorter.estimate(&items).unwrap();
+        assert!(!estimate.chunked_fallback);
+        assert_eq!(estimate.chunks, 1);
+    }
+
+    #[test]
+    fn test_merge_frontiers() {
+        assert!(merge_frontiers(&[4], 4).is_empty());
+        // Each request emits two of the eight elements until two are left
+        assert_eq!(merge_frontiers(&[4, 4], 4), vec![vec![4, 4, 4]]);
+        // Three requests merge the first two runs, five more merge the
+        // result with the third
+        assert_eq!(merge_frontiers(&[2, 2, 2], 2), vec![vec![2; 3], vec![2; 5]]);
+    }
+}
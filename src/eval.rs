@@ -0,0 +1,435 @@
+//! Measuring how well models sort against a local oracle.
+//!
+//! An [`Evaluation`] runs a corpus of inputs with a known correct order
+//! through several configured sorters (usually pointing at different models)
+//! and compares every result with [`slice::sort`]. The per-model
+//! [`ModelStats`] make it easy to pick the cheapest model that is accurate
+//! enough for a workload.
+//!
+//! For a single input, [`Vibesort::sort_with_diff`] compares the model's
+//! order with the oracle's and lists where they disagree.
+
+use crate::rt::Instant;
+use crate::{Order, Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+/// Accuracy, agreement, cost, and latency statistics for one model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelStats {
+    /// The model of the sorter.
+    pub model: String,
+
+    /// The number of inputs in the corpus.
+    pub cases: usize,
+
+    /// The number of inputs sorted exactly as the oracle sorts them.
+    pub correct: usize,
+
+    /// The number of inputs whose sort failed or did not return a
+    /// permutation of the input.
+    pub failures: usize,
+
+    /// The share of inputs sorted exactly right, from 0 to 1.
+    pub accuracy: f64,
+
+    /// The mean [Kendall tau](kendall_tau) between the model's order and the
+    /// oracle's, over the inputs that were not failures. 1 if there were
+    /// none.
+    pub mean_kendall_tau: f64,
+
+    /// The estimated total cost, if the sorter has
+    /// [pricing](Vibesort::pricing) configured. See
+    /// [`Vibesort::estimate`].
+    pub est_cost: Option<f64>,
+
+    /// The mean wall-clock time per input, including retries.
+    pub mean_latency: Duration,
+
+    /// The longest wall-clock time for one input.
+    pub max_latency: Duration,
+}
+
+/// A set of sorters evaluated on the same corpus.
+///
+/// # Example
+///
+/// ```no_run
+/// use vibesort_rs::Vibesort;
+/// use vibesort_rs::eval::Evaluation;
+///
+/// # async fn example() {
+/// let base_url = "https://api.openai.com/v1";
+/// let evaluation = Evaluation::new([
+///     Vibesort::new("your-api-key", "gpt-4o", base_url),
+///     Vibesort::new("your-api-key", "gpt-4o-mini", base_url),
+/// ]);
+///
+/// let corpus: Vec<Vec<i64>> = (0..20).map(|seed| vec![seed * 7 % 11, 3, seed]).collect();
+/// for stats in evaluation.run(&corpus).await {
+///     println!("{}: {:.1}% correct", stats.model, stats.accuracy * 100.0);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Evaluation<'a> {
+    sorters: Vec<Vibesort<'a>>,
+}
+
+impl<'a> Evaluation<'a> {
+    /// Creates an evaluation of the given sorters.
+    ///
+    /// Each sorter's [`order`](Vibesort::order) is applied to the oracle as
+    /// well. Sorters should not have a criterion, since the oracle sorts by
+    /// [`Ord`].
+    pub fn new(sorters: impl IntoIterator<Item = Vibesort<'a>>) -> Self {
+        Self {
+            sorters: sorters.into_iter().collect(),
+        }
+    }
+
+    /// Sorts every input of the corpus with every sorter and returns the
+    /// statistics of each sorter, in the order the sorters were given.
+    ///
+    /// Inputs are sorted one at a time. A failed sort counts as a failure
+    /// and does not stop the evaluation.
+    pub async fn run<T>(&self, corpus: &[Vec<T>]) -> Vec<ModelStats>
+    where
+        T: Ord + Clone + Serialize + DeserializeOwned,
+    {
+        let mut all_stats = Vec::with_capacity(self.sorters.len());
+        for sorter in &self.sorters {
+            all_stats.push(evaluate(sorter, corpus).await);
+        }
+        all_stats
+    }
+}
+
+/// A pair of elements the model put in the opposite order of the oracle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inversion<T> {
+    /// The positions of the two elements in the model's order.
+    pub positions: (usize, usize),
+
+    /// The element the model put first, which the oracle puts after
+    /// `second`.
+    pub first: T,
+
+    /// The element the model put second.
+    pub second: T,
+}
+
+/// The model's order of one input compared with the oracle's, returned by
+/// [`Vibesort::sort_with_diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortDiff<T> {
+    /// The items as sorted by the model.
+    pub items: Vec<T>,
+
+    /// The items as sorted by [`slice::sort`], in the configured order.
+    pub expected: Vec<T>,
+
+    /// The [Kendall tau](kendall_tau) between the model's order and the
+    /// oracle's, from -1 (reversed) to 1 (identical).
+    pub kendall_tau: f64,
+
+    /// Every pair of elements the model put in the wrong order, by their
+    /// positions in the model's order.
+    pub inversions: Vec<Inversion<T>>,
+}
+
+impl<T> SortDiff<T> {
+    /// Returns whether the model sorted the items exactly as the oracle did.
+    pub fn is_exact(&self) -> bool {
+        self.inversions.is_empty()
+    }
+
+    /// Returns the Kendall tau distance: the number of inversions.
+    pub fn distance(&self) -> usize {
+        self.inversions.len()
+    }
+
+    /// Returns the number of inversions divided by the number of pairs, from
+    /// 0 (identical) to 1 (reversed). 0 for fewer than two items.
+    pub fn normalized_distance(&self) -> f64 {
+        let n = self.items.len();
+        if n < 2 {
+            0.0
+        } else {
+            self.inversions.len() as f64 / (n * (n - 1) / 2) as f64
+        }
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts the items and compares the result with [`slice::sort`], to
+    /// measure how far the model's order is from the deterministic one.
+    ///
+    /// The oracle applies the configured [`order`](Self::order); pairs of
+    /// equal elements are never counted as inversions. Sorters with a
+    /// criterion can still be diffed, but the oracle sorts by [`Ord`].
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the model does not
+    /// return a permutation of the input, since the orders cannot be compared
+    /// then.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let diff = sorter.sort_with_diff(&[3, 1, 4, 1, 5, 9, 2, 6]).await?;
+    /// println!("tau {:.2}, {} inversions", diff.kendall_tau, diff.distance());
+    /// for inversion in &diff.inversions {
+    ///     println!("{} placed before {}", inversion.first, inversion.second);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_with_diff<T>(&self, items: &[T]) -> Result<SortDiff<T>, VibesortError>
+    where
+        T: Ord + Clone + Serialize + DeserializeOwned,
+    {
+        let sorted = self.sort_with_report(items).await?.items;
+        let mut expected = items.to_vec();
+        expected.sort();
+        if self.order == Order::Descending {
+            expected.reverse();
+        }
+        if !is_permutation(&expected, &sorted) {
+            return Err(VibesortError::VerificationFailed(String::from(
+                "the sorted items are not a permutation of the input",
+            )));
+        }
+
+        let ranks = ranks(&expected, &sorted);
+        let mut inversions = Vec::new();
+        for (i, a) in ranks.iter().enumerate() {
+            for (j, b) in ranks.iter().enumerate().skip(i + 1) {
+                if a > b {
+                    inversions.push(Inversion {
+                        positions: (i, j),
+                        first: sorted[i].clone(),
+                        second: sorted[j].clone(),
+                    });
+                }
+            }
+        }
+        Ok(SortDiff {
+            kendall_tau: kendall_tau(&expected, &sorted),
+            items: sorted,
+            expected,
+            inversions,
+        })
+    }
+}
+
+/// Evaluates one sorter on the corpus.
+async fn evaluate<T>(sorter: &Vibesort<'_>, corpus: &[Vec<T>]) -> ModelStats
+where
+    T: Ord + Clone + Serialize + DeserializeOwned,
+{
+    let mut correct = 0;
+    let mut failures = 0;
+    let mut taus = Vec::new();
+    let mut est_cost = None;
+    let mut total_latency = Duration::ZERO;
+    let mut max_latency = Duration::ZERO;
+
+    for input in corpus {
+        let mut expected = input.clone();
+        expected.sort();
+        if sorter.order == Order::Descending {
+            expected.reverse();
+        }
+
+        if let Ok(estimate) = sorter.estimate(input) {
+            est_cost = estimate.est_cost.map(|cost| est_cost.unwrap_or(0.0) + cost);
+        }
+
+        let start = Instant::now();
+        let result = sorter
+            .sort_with_report(input)
+            .await
+            .map(|result| result.items);
+        let latency = start.elapsed();
+        total_latency += latency;
+        max_latency = max_latency.max(latency);
+
+        match result {
+            Ok(sorted) if is_permutation(&expected, &sorted) => {
+                if sorted == expected {
+                    correct += 1;
+                }
+                taus.push(kendall_tau(&expected, &sorted));
+            }
+            _ => failures += 1,
+        }
+    }
+
+    let cases = corpus.len();
+    ModelStats {
+        model: sorter.model.to_string(),
+        cases,
+        correct,
+        failures,
+        accuracy: if cases == 0 {
+            1.0
+        } else {
+            correct as f64 / cases as f64
+        },
+        mean_kendall_tau: if taus.is_empty() {
+            1.0
+        } else {
+            taus.iter().sum::<f64>() / taus.len() as f64
+        },
+        est_cost,
+        mean_latency: total_latency / u32::try_from(cases.max(1)).unwrap_or(u32::MAX),
+        max_latency,
+    }
+}
+
+/// Returns whether `actual` contains exactly the elements of the sorted
+/// `expected`.
+fn is_permutation<T: Ord + Clone>(expected: &[T], actual: &[T]) -> bool {
+    let mut actual = actual.to_vec();
+    actual.sort();
+    let mut expected = expected.to_vec();
+    expected.sort();
+    actual == expected
+}
+
+/// Returns the Kendall rank correlation between two orderings of the same
+/// elements, from -1 (reversed) to 1 (identical).
+///
+/// Every pair of elements that `expected` ranks differently counts as
+/// concordant if `actual` ranks it the same way and as discordant otherwise;
+/// pairs of equal elements are ignored. The result is 1 if there are no such
+/// pairs.
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::eval::kendall_tau;
+///
+/// assert_eq!(kendall_tau(&[1, 2, 3], &[1, 2, 3]), 1.0);
+/// assert_eq!(kendall_tau(&[1, 2, 3], &[3, 2, 1]), -1.0);
+/// assert_eq!(kendall_tau(&[1, 2, 3], &[2, 1, 3]), 1.0 / 3.0);
+/// ```
+pub fn kendall_tau<T: PartialEq>(expected: &[T], actual: &[T]) -> f64 {
+    let ranks = ranks(expected, actual);
+    let (mut concordant, mut discordant) = (0u64, 0u64);
+    for (i, a) in ranks.iter().enumerate() {
+        for b in &ranks[i + 1..] {
+            match (a, b) {
+                (Some(a), Some(b)) if a < b => concordant += 1,
+                (Some(a), Some(b)) if a > b => discordant += 1,
+                _ => {}
+            }
+        }
+    }
+    if concordant + discordant == 0 {
+        1.0
+    } else {
+        (concordant as f64 - discordant as f64) / (concordant + discordant) as f64
+    }
+}
+
+/// Returns the rank in `expected` of every element of `actual`: the first
+/// position of an equal element, so equal elements share a rank.
+fn ranks<T: PartialEq>(expected: &[T], actual: &[T]) -> Vec<Option<usize>> {
+    actual
+        .iter()
+        .map(|item| expected.iter().position(|candidate| candidate == item))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pricing;
+    use crate::testing::MockBackend;
+
+    #[tokio::test]
+    async fn test_evaluation_stats() {
+        let accurate = Vibesort::new("key", "accurate", "http://mock")
+            .backend(MockBackend::new())
+            .pricing(Pricing::new(1.0, 1.0));
+        let sloppy = Vibesort::new("key", "sloppy", "http://mock").backend(
+            MockBackend::new()
+                .respond_with("[2, 1, 3]")
+                .respond_with("[1, 2]")
+                .respond_with("[1, 2]")
+                .respond_with("[1, 2]"),
+        );
+        let corpus = vec![vec![3, 1, 2], vec![2, 1, 3]];
+
+        let stats = Evaluation::new([accurate, sloppy]).run(&corpus).await;
+        assert_eq!(stats[0].model, "accurate");
+        assert_eq!(stats[0].correct, 2);
+        assert_eq!(stats[0].accuracy, 1.0);
+        assert_eq!(stats[0].mean_kendall_tau, 1.0);
+        assert!(stats[0].est_cost.unwrap() > 0.0);
+
+        assert_eq!(stats[1].model, "sloppy");
+        assert_eq!(stats[1].correct, 0);
+        assert_eq!(stats[1].failures, 1);
+        assert_eq!(stats[1].accuracy, 0.0);
+        assert_eq!(stats[1].mean_kendall_tau, 1.0 / 3.0);
+        assert_eq!(stats[1].est_cost, None);
+    }
+
+    #[tokio::test]
+    async fn test_sort_with_diff() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with("[1, 3, 2, 1]"));
+
+        let diff = sorter.sort_with_diff(&[3, 1, 2, 1]).await.unwrap();
+        assert_eq!(diff.expected, vec![1, 1, 2, 3]);
+        assert!(!diff.is_exact());
+        assert_eq!(
+            diff.inversions,
+            vec![
+                Inversion {
+                    positions: (1, 2),
+                    first: 3,
+                    second: 2
+                },
+                Inversion {
+                    positions: (1, 3),
+                    first: 3,
+                    second: 1
+                },
+                Inversion {
+                    positions: (2, 3),
+                    first: 2,
+                    second: 1
+                },
+            ]
+        );
+        assert_eq!(diff.normalized_distance(), 0.5);
+        assert_eq!(diff.kendall_tau, kendall_tau(&diff.expected, &diff.items));
+
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with("[1, 2, 2]"));
+        let err = sorter.sort_with_diff(&[2, 1, 3]).await.unwrap_err();
+        assert!(matches!(err, VibesortError::VerificationFailed(_)));
+    }
+
+    #[test]
+    fn test_kendall_tau_with_ties() {
+        assert_eq!(kendall_tau(&[1, 1, 2], &[1, 2, 1]), 0.0);
+        assert_eq!(kendall_tau(&[1, 1], &[1, 1]), 1.0);
+    }
+}
@@ -0,0 +1,160 @@
+//! Sorting numbers that must not lose precision.
+//!
+//! JSON numbers are commonly read as 64-bit floats, by models and by
+//! `serde_json` alike, which silently rounds large integers and long
+//! decimals. [`Vibesort::sort_exact`] sends the numbers as strings instead
+//! and checks that every string comes back unchanged.
+
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError, parse, verify};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// A number type whose text form round-trips exactly, for
+/// [`Vibesort::sort_exact`].
+///
+/// Implemented for the 64- and 128-bit integer types, for
+/// `rust_decimal::Decimal` with the `decimal` feature, and for
+/// `num_bigint::BigInt` and `BigUint` with the `bigint` feature.
+pub trait ExactNumber: Display + FromStr {}
+
+impl ExactNumber for i64 {}
+impl ExactNumber for u64 {}
+impl ExactNumber for i128 {}
+impl ExactNumber for u128 {}
+
+#[cfg(feature = "decimal")]
+impl ExactNumber for rust_decimal::Decimal {}
+
+#[cfg(feature = "bigint")]
+impl ExactNumber for num_bigint::BigInt {}
+
+#[cfg(feature = "bigint")]
+impl ExactNumber for num_bigint::BigUint {}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts numbers at their full precision, sending them as strings.
+    ///
+    /// The model is told to compare the exact values and to copy every
+    /// string unchanged. The reply is always checked to be a permutation of
+    /// the strings sent, whether or not [`verify`](Self::verify) is enabled,
+    /// and every string is parsed back and checked to format exactly as
+    /// sent, so trailing zeros of decimals such as `1.50` are kept. The
+    /// configured criterion and order apply.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the model alters,
+    /// drops, or invents a number.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// // Both round to the same 64-bit float
+    /// let ids: [u64; 2] = [9_007_199_254_740_993, 9_007_199_254_740_992];
+    /// let sorted = sorter.sort_exact(&ids).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_exact<T: ExactNumber>(&self, items: &[T]) -> Result<Vec<T>, VibesortError> {
+        let texts: Vec<String> = items.iter().map(ToString::to_string).collect();
+        let json_array = serde_json::to_string(&texts)?;
+        let max_tokens = self.max_tokens_for(json_array.len());
+
+        let (system_prompt, user_content) =
+            self.render_prompt(Operation::Exact, json_array, || {
+                format!(
+                    "You are a helpful assistant that sorts arrays. The following JSON array contains exact numbers written as strings to preserve their precision. Sort them {}, comparing their exact numeric values without rounding and not as text. Return ONLY the sorted JSON array, with every string copied exactly as given, nothing else.",
+                    self.sort_instruction()
+                )
+            })?;
+        let (system_prompt, user_content, texts) = (&system_prompt, &user_content, &texts);
+        self.retrying(|escalation| async move {
+            let completion = self
+                .chat(system_prompt, user_content, max_tokens, escalation)
+                .await?;
+
+            let sorted: Vec<String> = parse::parse_array(&completion.content)?;
+            verify::check_permutation(texts, &sorted)?;
+            sorted.iter().map(|text| round_trip(text)).collect()
+        })
+        .await
+    }
+}
+
+/// Parses `text` and checks that the number formats back to it.
+fn round_trip<T: ExactNumber>(text: &str) -> Result<T, VibesortError> {
+    match text.parse::<T>() {
+        Ok(number) if number.to_string() == text => Ok(number),
+        _ => Err(VibesortError::VerificationFailed(format!(
+            "{:?} does not round-trip exactly",
+            text
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_exact() {
+        let backend = Arc::new(
+            MockBackend::new().respond_with(r#"["9007199254740992", "9007199254740993"]"#),
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let sorted = sorter
+            .sort_exact(&[9_007_199_254_740_993u64, 9_007_199_254_740_992])
+            .await
+            .unwrap();
+        assert_eq!(sorted, [9_007_199_254_740_992, 9_007_199_254_740_993]);
+
+        let user = backend.requests()[0].body["messages"][1]["content"].clone();
+        assert_eq!(user, r#"["9007199254740993","9007199254740992"]"#);
+    }
+
+    #[tokio::test]
+    async fn test_sort_exact_rejects_rounded_numbers() {
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(
+            MockBackend::new().respond_with(r#"["9007199254740992", "9007199254740992"]"#),
+        );
+
+        let err = sorter
+            .sort_exact(&[9_007_199_254_740_993u64, 9_007_199_254_740_992])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::VerificationFailed(_)));
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_decimal_round_trip() {
+        use rust_decimal::Decimal;
+
+        assert_eq!(round_trip::<Decimal>("1.50").unwrap(), Decimal::new(150, 2));
+        assert!(round_trip::<Decimal>("1.5e2").is_err());
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_bigint_round_trip() {
+        use num_bigint::BigInt;
+
+        let big = "-123456789012345678901234567890";
+        assert_eq!(round_trip::<BigInt>(big).unwrap().to_string(), big);
+        assert!(round_trip::<BigInt>("+1").is_err());
+    }
+}
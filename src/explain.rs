@@ -0,0 +1,262 @@
+//! Sorting with an explanation of the chosen order.
+
+use crate::prompt::Operation;
+use crate::{SortResult, Vibesort, VibesortError, parse};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// The response shape requested by [`Vibesort::sort_with_explanation`].
+///
+/// A bare array is also accepted, in which case no explanation is returned.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ExplainedResponse<T> {
+    Explained {
+        sorted: Vec<T>,
+        #[serde(default)]
+        explanation: Option<String>,
+    },
+    Bare(Vec<T>),
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts an array and asks the LLM to explain why it chose that order.
+    ///
+    /// The model is instructed to reply with a JSON object of the form
+    /// `{ "sorted": [...], "explanation": "..." }`. The explanation is returned
+    /// in [`SortResult::explanation`]; if the model replies with a bare array
+    /// instead, the result is still returned without an explanation.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let complaints = vec![
+    ///     "The app crashed and I lost my invoice".to_string(),
+    ///     "The logo color is a bit off".to_string(),
+    /// ];
+    /// let result = sorter.sort_with_explanation(&complaints).await?;
+    /// println!("{:?}", result.items);
+    /// println!("Why: {}", result.explanation.unwrap_or_default());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_with_explanation<T>(
+        &self,
+        items: &[T],
+    ) -> Result<SortResult<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let json_array = self.json_format.to_string(items)?;
+        // Leave room for the explanation next to the array
+        let max_tokens = self.max_tokens_for(json_array.len() + 2048);
+
+        let (system_prompt, user_content) =
+            self.render_prompt(Operation::Explain, json_array, || {
+                format!(
+                    "You are a helpful assistant that sorts arrays. Sort the following JSON array {}. Return ONLY a JSON object of the form {{\"sorted\": [...], \"explanation\": \"...\"}}, where \"sorted\" is the sorted JSON array and \"explanation\" briefly explains why the elements are in that order.",
+                    self.sort_instruction()
+                )
+            })?;
+        let (system_prompt, user_content) = (&system_prompt, &user_content);
+        let (completion, sorted, explanation) = self
+            .retrying(|escalation| async move {
+                let completion = self
+                    .chat(system_prompt, user_content, max_tokens, escalation)
+                    .await?;
+
+                let (mut sorted, explanation) =
+                    match parse::parse_json(&completion.content, "object")? {
+                        ExplainedResponse::Explained {
+                            sorted,
+                            explanation,
+                        } => (sorted, explanation),
+                        ExplainedResponse::Bare(sorted) => (sorted, None),
+                    };
+
+                self.accept_sorted(items, &mut sorted, self.verify)?;
+                Ok((completion, sorted, explanation))
+            })
+            .await?;
+
+        let mut result = SortResult::new(sorted, self.report(&completion));
+        result.explanation = explanation;
+        Ok(result)
+    }
+
+    /// Asks the LLM why one element of a previous sort was placed before
+    /// another.
+    ///
+    /// `i` and `j` are positions in [`SortResult::items`], in either order;
+    /// the question is always why the earlier element precedes the later one.
+    /// The sorted items, the configured order and criterion, and the
+    /// [explanation](SortResult::explanation) of the sort, if any, are sent
+    /// as context. The reply is a short justification suitable for audit
+    /// trails.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::InvalidResponse`] is returned if the reply is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .criterion("by severity");
+    ///
+    /// let complaints = ["The logo color is a bit off", "The app crashed"].map(String::from);
+    /// let result = sorter.sort_with_report(&complaints).await?;
+    /// let why = sorter.explain_order(&result, 0, 1).await?;
+    /// println!("{:?} before {:?}: {}", result.items[0], result.items[1], why);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn explain_order<T>(
+        &self,
+        result: &SortResult<T>,
+        i: usize,
+        j: usize,
+    ) -> Result<String, VibesortError>
+    where
+        T: Serialize,
+    {
+        let len = result.items.len();
+        assert!(
+            i < len && j < len,
+            "positions {} and {} out of bounds for {} items",
+            i,
+            j,
+            len
+        );
+        let (first, second) = (i.min(j), i.max(j));
+
+        let mut request = serde_json::json!({
+            "sorted": result.items,
+            "first": first,
+            "second": second,
+        });
+        if let Some(explanation) = &result.explanation {
+            request["explanation"] = explanation.as_str().into();
+        }
+        let (system_prompt, user_content) =
+            self.render_prompt(Operation::ExplainOrder, request.to_string(), || {
+                format!(
+                    "You are a helpful assistant that justifies sorting decisions. The following JSON object contains an array (\"sorted\") that was sorted {}, possibly with an explanation of the order (\"explanation\"), and two positions in the array (\"first\" and \"second\", counted from 0). Explain in one or two sentences why the element at position \"first\" was placed before the element at position \"second\". Return ONLY the explanation as plain text.",
+                    self.sort_instruction()
+                )
+            })?;
+        let (system_prompt, user_content) = (&system_prompt, &user_content);
+        self.retrying(|escalation| async move {
+            let completion = self
+                .chat(
+                    system_prompt,
+                    user_content,
+                    self.max_tokens_for(512),
+                    escalation,
+                )
+                .await?;
+            let justification = completion.content.trim();
+            if justification.is_empty() {
+                return Err(VibesortError::InvalidResponse);
+            }
+            Ok(justification.to_string())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+
+    #[tokio::test]
+    async fn test_sort_with_explanation() {
+        let backend = MockBackend::new().respond_with(
+            "```json\n{\"sorted\": [\"crash\", \"typo\"], \"explanation\": \"Crashes lose data.\"}\n```",
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend);
+
+        let items = vec!["typo".to_string(), "crash".to_string()];
+        let result = sorter.sort_with_explanation(&items).await.unwrap();
+        assert_eq!(result.items, vec!["crash", "typo"]);
+        assert_eq!(result.explanation.as_deref(), Some("Crashes lose data."));
+    }
+
+    #[tokio::test]
+    async fn test_explain_order() {
+        let backend = std::sync::Arc::new(
+            MockBackend::new().respond_with("  A crash loses data; a typo does not.\n"),
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let mut result = SortResult::new(
+            vec![
+                "crash".to_string(),
+                "layout".to_string(),
+                "typo".to_string(),
+            ],
+            Default::default(),
+        );
+        result.explanation = Some("Crashes lose data.".to_string());
+        let why = sorter.explain_order(&result, 2, 0).await.unwrap();
+        assert_eq!(why, "A crash loses data; a typo does not.");
+
+        let user = backend.requests()[0].body["messages"][1]["content"].clone();
+        let request: serde_json::Value = serde_json::from_str(user.as_str().unwrap()).unwrap();
+        assert_eq!(request["first"], 0);
+        assert_eq!(request["second"], 2);
+        assert_eq!(request["explanation"], "Crashes lose data.");
+    }
+
+    #[tokio::test]
+    async fn test_sort_with_explanation_accepts_bare_array() {
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(MockBackend::new());
+
+        let result = sorter.sort_with_explanation(&[2, 1]).await.unwrap();
+        assert_eq!(result.items, vec![1, 2]);
+        assert_eq!(result.explanation, None);
+    }
+
+    #[tokio::test]
+    async fn test_sort_with_explanation_within_tolerance() {
+        let reply = r#"{"sorted": [0.1, 0.20000000001], "explanation": "Smaller first."}"#;
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with(reply).respond_with(reply))
+            .verify(true);
+        let err = sorter.sort_with_explanation(&[0.2, 0.1]).await.unwrap_err();
+        assert!(matches!(err, VibesortError::VerificationFailed(_)));
+
+        let result = sorter
+            .verify_tolerance(1e-9)
+            .sort_with_explanation(&[0.2, 0.1])
+            .await
+            .unwrap();
+        assert_eq!(result.items, vec![0.1, 0.2]);
+    }
+}
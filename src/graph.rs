@@ -0,0 +1,106 @@
+//! Small graph algorithms over element indices.
+
+/// Returns a cycle in the directed graph on `0..nodes` with the given edges,
+/// as the list of nodes along it with the first node repeated at the end, or
+/// `None` if the graph is acyclic.
+pub(crate) fn find_cycle(nodes: usize, edges: &[(usize, usize)]) -> Option<Vec<usize>> {
+    let mut successors = vec![Vec::new(); nodes];
+    for &(from, to) in edges {
+        successors[from].push(to);
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unvisited,
+        OnPath,
+        Done,
+    }
+    let mut state = vec![State::Unvisited; nodes];
+    for start in 0..nodes {
+        if state[start] != State::Unvisited {
+            continue;
+        }
+        // Depth-first search with an explicit stack of (node, next successor)
+        let mut path = vec![(start, 0)];
+        state[start] = State::OnPath;
+        while let Some((node, next)) = path.last_mut() {
+            let node = *node;
+            match successors[node].get(*next) {
+                Some(&successor) => {
+                    *next += 1;
+                    match state[successor] {
+                        State::OnPath => {
+                            let from = path.iter().position(|&(n, _)| n == successor).unwrap_or(0);
+                            let mut cycle: Vec<usize> =
+                                path[from..].iter().map(|&(n, _)| n).collect();
+                            cycle.push(successor);
+                            return Some(cycle);
+                        }
+                        State::Unvisited => {
+                            state[successor] = State::OnPath;
+                            path.push((successor, 0));
+                        }
+                        State::Done => {}
+                    }
+                }
+                None => {
+                    state[node] = State::Done;
+                    path.pop();
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Returns an order of `0..nodes` in which every edge `(from, to)` has `from`
+/// before `to`, or `None` if the graph has a cycle.
+///
+/// Among the nodes that are free to come next, the smallest comes first, so
+/// unrelated nodes keep their relative order.
+pub(crate) fn topological_order(nodes: usize, edges: &[(usize, usize)]) -> Option<Vec<usize>> {
+    let mut successors = vec![Vec::new(); nodes];
+    let mut predecessors = vec![0usize; nodes];
+    for &(from, to) in edges {
+        successors[from].push(to);
+        predecessors[to] += 1;
+    }
+
+    let mut ready: std::collections::BTreeSet<usize> =
+        (0..nodes).filter(|&node| predecessors[node] == 0).collect();
+    let mut order = Vec::with_capacity(nodes);
+    while let Some(node) = ready.pop_first() {
+        order.push(node);
+        for &successor in &successors[node] {
+            predecessors[successor] -= 1;
+            if predecessors[successor] == 0 {
+                ready.insert(successor);
+            }
+        }
+    }
+    (order.len() == nodes).then_some(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_cycle() {
+        assert_eq!(find_cycle(3, &[(0, 1), (1, 2), (0, 2)]), None);
+        assert_eq!(
+            find_cycle(4, &[(3, 1), (1, 2), (2, 1)]),
+            Some(vec![1, 2, 1])
+        );
+        assert_eq!(find_cycle(1, &[(0, 0)]), Some(vec![0, 0]));
+    }
+
+    #[test]
+    fn test_topological_order() {
+        assert_eq!(
+            topological_order(4, &[(3, 0), (2, 3)]),
+            Some(vec![1, 2, 3, 0])
+        );
+        assert_eq!(topological_order(2, &[(0, 1), (1, 0)]), None);
+    }
+}
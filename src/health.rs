@@ -0,0 +1,125 @@
+//! Checking that the model can sort, for readiness probes.
+
+use crate::prompt::Order;
+use crate::rt::Instant;
+use crate::{Vibesort, VibesortError};
+use std::time::Duration;
+
+/// The array sorted by [`Vibesort::healthcheck`].
+const CANARY: [u32; 5] = [42, 7, 19, 3, 28];
+
+/// The outcome of [`Vibesort::healthcheck`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthReport {
+    /// How long the canary sort took, including retries.
+    pub latency: Duration,
+
+    /// Whether the model returned the canary correctly sorted.
+    pub correct: bool,
+}
+
+impl HealthReport {
+    /// Returns `true` if the canary was sorted correctly within
+    /// `max_latency`.
+    pub fn is_healthy(&self, max_latency: Duration) -> bool {
+        self.correct && self.latency <= max_latency
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts a tiny canary array of numbers and reports how long it took and
+    /// whether the result was correct.
+    ///
+    /// The canary goes through the same path as every sort (backend, limits,
+    /// retries, and authentication), so a service can gate its readiness on
+    /// it, or call it once at startup to warm up the connection. The
+    /// configured criterion, order, and cache are not used, so that the
+    /// expected result is known and the model is always asked.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`sort`](Self::sort) that keep the model from
+    /// replying at all, such as [`VibesortError::HttpError`] or
+    /// [`VibesortError::AuthFailed`]. A reply that is wrong or cannot be
+    /// parsed is reported as incorrect instead.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let health = sorter.healthcheck().await?;
+    /// if !health.is_healthy(Duration::from_secs(5)) {
+    ///     eprintln!("LLM path degraded: {:?}", health);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn healthcheck(&self) -> Result<HealthReport, VibesortError> {
+        let mut sorter = self.clone();
+        sorter.criterion = None;
+        sorter.order = Order::Ascending;
+        sorter.cache = None;
+
+        let start = Instant::now();
+        let result = sorter.sort_with_report_inner(&CANARY, false).await;
+        let latency = start.elapsed();
+
+        let mut expected = CANARY;
+        expected.sort_unstable();
+        let correct = match result {
+            Ok(result) => result.items == expected,
+            Err(
+                VibesortError::ParseError(_)
+                | VibesortError::VerificationFailed(_)
+                | VibesortError::InvalidResponse,
+            ) => false,
+            Err(e) => return Err(e),
+        };
+        Ok(HealthReport { latency, correct })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_healthcheck() {
+        let backend = Arc::new(MockBackend::new().respond_with("[3, 7, 19, 28, 42]"));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .criterion("by length")
+            .order(Order::Descending);
+
+        let health = sorter.healthcheck().await.unwrap();
+        assert!(health.correct);
+        assert!(health.is_healthy(Duration::from_secs(60)));
+
+        let body = backend.requests()[0].body.to_string();
+        assert!(!body.contains("by length"));
+        assert!(body.contains("ascending"));
+    }
+
+    #[tokio::test]
+    async fn test_healthcheck_reports_wrong_and_failed_sorts() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with("[42, 7, 19, 3, 28]"));
+        assert!(!sorter.healthcheck().await.unwrap().correct);
+
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with_status(401, "invalid key"));
+        let err = sorter.healthcheck().await.unwrap_err();
+        assert!(matches!(err, VibesortError::AuthFailed(_)));
+    }
+}
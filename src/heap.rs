@@ -0,0 +1,232 @@
+//! A priority queue ordered by LLM comparisons.
+
+use crate::cache::ComparisonCache;
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde_json::Value;
+
+/// A priority queue whose order is decided by the model.
+///
+/// The heap keeps the element that comes first under the sorter's order and
+/// criterion at the top, like a binary heap whose comparisons are pairwise
+/// sorts. Comparisons are only requested when a [`push`](Self::push) or
+/// [`pop`](Self::pop) needs them, about `log2 n` per operation, and every
+/// answer is cached for the lifetime of the heap, so elements that meet again
+/// cost no further requests. Use [`comparisons`](Self::comparisons) to share
+/// the cache or persist it across runs.
+///
+/// # Example
+///
+/// ```no_run
+/// use vibesort_rs::{VibeHeap, Vibesort};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let sorter = Vibesort::new(
+///     "your-api-key",
+///     "gpt-3.5-turbo",
+///     "https://api.openai.com/v1",
+/// )
+/// .criterion("by urgency, most urgent first");
+///
+/// let mut tickets = VibeHeap::new(sorter);
+/// tickets.push("Typo on the pricing page".to_string()).await?;
+/// tickets.push("Checkout is down for all users".to_string()).await?;
+///
+/// while let Some(ticket) = tickets.pop().await? {
+///     println!("Next up: {}", ticket);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct VibeHeap<'a, T> {
+    sorter: Vibesort<'a>,
+    /// The elements with their serialized form, in binary heap order.
+    items: Vec<(T, Value)>,
+    comparisons: ComparisonCache,
+}
+
+impl<'a, T: Serialize> VibeHeap<'a, T> {
+    /// Creates an empty heap ordered by `sorter`'s order and criterion.
+    pub fn new(sorter: Vibesort<'a>) -> Self {
+        Self {
+            sorter,
+            items: Vec::new(),
+            comparisons: ComparisonCache::default(),
+        }
+    }
+
+    /// Uses `comparisons` to look up and store the answers to comparisons.
+    pub fn comparisons(mut self, comparisons: ComparisonCache) -> Self {
+        self.comparisons = comparisons;
+        self
+    }
+
+    /// Returns the number of elements in the heap.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the heap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the element at the top of the heap without removing it.
+    pub fn peek(&self) -> Option<&T> {
+        self.items.first().map(|(item, _)| item)
+    }
+
+    /// Adds an element to the heap.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::JsonError`] if the element cannot be
+    /// serialized, or the error of a comparison (see [`Vibesort::sort`]). The
+    /// element is not added if a comparison fails.
+    pub async fn push(&mut self, item: T) -> Result<(), VibesortError> {
+        let value = serde_json::to_value(&item)?;
+
+        // Find the element's position before moving anything, so that a
+        // failed comparison leaves the heap intact
+        let position = match self.sorter.admit()? {
+            Some((sorter, guard)) => guard.run(self.position_of(&sorter, &value)).await?,
+            None => self.position_of(&self.sorter, &value).await?,
+        };
+
+        self.items.push((item, value));
+        let mut child = self.items.len() - 1;
+        while child > position {
+            let parent = (child - 1) / 2;
+            self.items.swap(parent, child);
+            child = parent;
+        }
+        Ok(())
+    }
+
+    /// Removes and returns the element at the top of the heap.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of a comparison (see [`Vibesort::sort`]). The heap is
+    /// left unchanged if a comparison fails.
+    pub async fn pop(&mut self) -> Result<Option<T>, VibesortError> {
+        if self.items.is_empty() {
+            return Ok(None);
+        }
+
+        // Record the path of the last element from the top first, so that a
+        // failed comparison leaves the heap intact
+        let path = match self.sorter.admit()? {
+            Some((sorter, guard)) => guard.run(self.sift_path(&sorter)).await?,
+            None => self.sift_path(&self.sorter).await?,
+        };
+
+        let (top, _) = self.items.swap_remove(0);
+        let mut hole = 0;
+        for child in path {
+            self.items.swap(hole, child);
+            hole = child;
+        }
+        Ok(Some(top))
+    }
+
+    /// Returns the position at which `value` is pushed, asking `sorter` for
+    /// the comparisons.
+    async fn position_of(
+        &self,
+        sorter: &Vibesort<'_>,
+        value: &Value,
+    ) -> Result<usize, VibesortError> {
+        let mut position = self.items.len();
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if self
+                .comparisons
+                .precedes(sorter, &self.items[parent].1, value)
+                .await?
+            {
+                break;
+            }
+            position = parent;
+        }
+        Ok(position)
+    }
+
+    /// Returns the positions the last element moves through when sifted down
+    /// from the top, asking `sorter` for the comparisons.
+    async fn sift_path(&self, sorter: &Vibesort<'_>) -> Result<Vec<usize>, VibesortError> {
+        let last = self.items.len() - 1;
+        let mut path = Vec::new();
+        let mut position = 0;
+        loop {
+            let mut first: Option<usize> = None;
+            for child in [2 * position + 1, 2 * position + 2] {
+                if child >= last {
+                    continue;
+                }
+                let leader = first.map_or(&self.items[last].1, |first| &self.items[first].1);
+                if !self
+                    .comparisons
+                    .precedes(sorter, leader, &self.items[child].1)
+                    .await?
+                {
+                    first = Some(child);
+                }
+            }
+            match first {
+                Some(child) => {
+                    path.push(child);
+                    position = child;
+                }
+                None => return Ok(path),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Order;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_vibe_heap_pops_in_order() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .order(Order::Descending);
+
+        let mut heap = VibeHeap::new(sorter);
+        for item in [3, 8, 1, 5, 9, 2] {
+            heap.push(item).await.unwrap();
+        }
+        assert_eq!(heap.peek(), Some(&9));
+
+        let mut popped = Vec::new();
+        while let Some(item) = heap.pop().await.unwrap() {
+            popped.push(item);
+        }
+        assert_eq!(popped, vec![9, 8, 5, 3, 2, 1]);
+
+        // Every pair is compared at most once
+        let requests = backend.requests().len();
+        assert_eq!(heap.comparisons.len(), 2 * requests);
+    }
+
+    #[tokio::test]
+    async fn test_failed_push_leaves_heap_intact() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with_status(500, "boom"));
+
+        let mut heap = VibeHeap::new(sorter);
+        heap.push(2).await.unwrap();
+        assert!(heap.push(1).await.is_err());
+        assert_eq!(heap.len(), 1);
+
+        heap.push(1).await.unwrap();
+        assert_eq!(heap.pop().await.unwrap(), Some(1));
+    }
+}
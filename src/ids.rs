@@ -0,0 +1,248 @@
+//! Sorting time-ordered identifiers by their embedded timestamps.
+
+use crate::annotate::Annotation;
+use crate::prompt::{Operation, Order};
+use crate::{Vibesort, VibesortError, verify};
+use serde::Deserialize;
+
+/// The alphabet of Crockford's base32, as used by ULIDs.
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// The annotation requested by [`Vibesort::sort_ids_by_time`].
+#[derive(Debug, Deserialize)]
+struct TimestampAnnotation {
+    timestamp_ms: u64,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts UUIDv7s and ULIDs by the creation time embedded in them, for
+    /// reconstructing the order of events from identifiers alone.
+    ///
+    /// The model extracts the timestamp of every identifier, and each one is
+    /// checked against the timestamp decoded locally, as is the order of the
+    /// reply; a reply failing either check is retried like a malformed one.
+    /// Each sorted identifier is returned with its timestamp in milliseconds
+    /// since the Unix epoch. The configured [`order`](Self::order) applies;
+    /// the configured criterion is not used.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::InvalidInput`] is returned before any request if an
+    /// identifier is neither a UUIDv7 nor a ULID, and
+    /// [`VibesortError::VerificationFailed`] if the model's timestamps or
+    /// order are wrong.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let events = sorter
+    ///     .sort_ids_by_time(&[
+    ///         "01ARZ3NDEKTSV4RRFFQ69G5FAV",
+    ///         "018f0f5c-6f1a-7cc2-9b3e-2f6d1c0a4b5e",
+    ///     ])
+    ///     .await?;
+    /// for (id, timestamp_ms) in events {
+    ///     println!("{} at {}", id, timestamp_ms);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_ids_by_time(
+        &self,
+        ids: &[&str],
+    ) -> Result<Vec<(String, u64)>, VibesortError> {
+        let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        for id in &ids {
+            if embedded_timestamp(id).is_none() {
+                return Err(VibesortError::InvalidInput(format!(
+                    "{:?} is neither a UUIDv7 nor a ULID",
+                    id
+                )));
+            }
+        }
+
+        let instruction = format!(
+            "of time-ordered identifiers (UUIDv7 or ULID) {}",
+            self.sort_instruction_for(Some("by the creation time embedded in each identifier"))
+        );
+        let annotation = Annotation {
+            fields: "\"timestamp_ms\": <number>",
+            meaning: "\"timestamp_ms\" is the Unix timestamp in milliseconds embedded in the identifier: the first 48 bits of a UUIDv7, or the first 10 characters of a ULID in Crockford's base32",
+            size: 32,
+        };
+        let order = self.order;
+        let sorted: Vec<(String, TimestampAnnotation)> = self
+            .sort_annotated_checked(
+                Operation::TimeIds,
+                &ids,
+                &instruction,
+                annotation,
+                |sorted| {
+                    let returned: Vec<&String> = sorted.iter().map(|(id, _)| id).collect();
+                    verify::check_permutation(&ids.iter().collect::<Vec<_>>(), &returned)?;
+                    check_timestamps(sorted, order)
+                },
+            )
+            .await?;
+        Ok(sorted
+            .into_iter()
+            .map(|(id, annotation)| (id, annotation.timestamp_ms))
+            .collect())
+    }
+}
+
+/// Checks the model's timestamps against the decoded ones, and their order.
+fn check_timestamps(
+    sorted: &[(String, TimestampAnnotation)],
+    order: Order,
+) -> Result<(), VibesortError> {
+    for (id, annotation) in sorted {
+        // Every id was decoded before sending, and the reply is a permutation
+        let expected = embedded_timestamp(id).unwrap_or_default();
+        if annotation.timestamp_ms != expected {
+            return Err(VibesortError::VerificationFailed(format!(
+                "timestamp of {} is {}, not {}",
+                id, expected, annotation.timestamp_ms
+            )));
+        }
+    }
+    for pair in sorted.windows(2) {
+        let (first, second) = (&pair[0], &pair[1]);
+        let in_order = match order {
+            Order::Ascending => first.1.timestamp_ms <= second.1.timestamp_ms,
+            Order::Descending => first.1.timestamp_ms >= second.1.timestamp_ms,
+        };
+        if !in_order {
+            return Err(VibesortError::VerificationFailed(format!(
+                "{} is not in {} order after {}",
+                second.0,
+                order.as_str(),
+                first.0
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the Unix timestamp in milliseconds of a UUIDv7 or ULID, or `None`
+/// if `id` is neither.
+fn embedded_timestamp(id: &str) -> Option<u64> {
+    uuid_v7_timestamp(id).or_else(|| ulid_timestamp(id))
+}
+
+/// Decodes a hyphenated UUIDv7, whose first 48 bits are the timestamp.
+fn uuid_v7_timestamp(id: &str) -> Option<u64> {
+    let bytes = id.as_bytes();
+    if bytes.len() != 36 {
+        return None;
+    }
+    for (i, &c) in bytes.iter().enumerate() {
+        let valid = match i {
+            8 | 13 | 18 | 23 => c == b'-',
+            _ => c.is_ascii_hexdigit(),
+        };
+        if !valid {
+            return None;
+        }
+    }
+    // The version nibble and the RFC 9562 variant bits
+    if bytes[14] != b'7' || !matches!(bytes[19].to_ascii_lowercase(), b'8' | b'9' | b'a' | b'b') {
+        return None;
+    }
+    let hex = format!("{}{}", &id[..8], &id[9..13]);
+    u64::from_str_radix(&hex, 16).ok()
+}
+
+/// Decodes a ULID, whose first 10 characters are the timestamp.
+fn ulid_timestamp(id: &str) -> Option<u64> {
+    if id.len() != 26 {
+        return None;
+    }
+    let mut timestamp = 0u64;
+    for (i, c) in id.bytes().enumerate() {
+        let digit = CROCKFORD
+            .iter()
+            .position(|&d| d == c.to_ascii_uppercase())?;
+        if i < 10 {
+            timestamp = (timestamp << 5) | digit as u64;
+        }
+    }
+    // 26 characters hold 130 bits, so the first one is at most 7
+    if id.as_bytes()[0] > b'7' {
+        return None;
+    }
+    Some(timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    const ULID: &str = "01ARZ3NDEKTSV4RRFFQ69G5FAV";
+    const UUID: &str = "018f0f5c-6f1a-7cc2-9b3e-2f6d1c0a4b5e";
+
+    #[test]
+    fn test_embedded_timestamp() {
+        assert_eq!(embedded_timestamp(ULID), Some(1_469_922_850_259));
+        assert_eq!(
+            embedded_timestamp(&ULID.to_lowercase()),
+            Some(1_469_922_850_259)
+        );
+        assert_eq!(embedded_timestamp(UUID), Some(0x018f_0f5c_6f1a));
+
+        // A UUIDv4, a ULID with an I, and an overflowing ULID
+        assert_eq!(
+            embedded_timestamp("0b1f2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d"),
+            None
+        );
+        assert_eq!(embedded_timestamp("01ARZ3NDEKTSV4RRFFQ69G5FAI"), None);
+        assert_eq!(embedded_timestamp("81ARZ3NDEKTSV4RRFFQ69G5FAV"), None);
+    }
+
+    #[tokio::test]
+    async fn test_sort_ids_by_time() {
+        let reply = format!(
+            r#"[{{"item": "{}", "timestamp_ms": 1469922850259}}, {{"item": "{}", "timestamp_ms": {}}}]"#,
+            ULID, UUID, 0x018f_0f5c_6f1au64
+        );
+        let backend = Arc::new(MockBackend::new().respond_with(&reply));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let sorted = sorter.sort_ids_by_time(&[UUID, ULID]).await.unwrap();
+        assert_eq!(
+            sorted,
+            vec![
+                (ULID.to_string(), 1_469_922_850_259),
+                (UUID.to_string(), 0x018f_0f5c_6f1a)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sort_ids_by_time_rejects_wrong_timestamps() {
+        let reply = format!(
+            r#"[{{"item": "{}", "timestamp_ms": 1}}, {{"item": "{}", "timestamp_ms": 2}}]"#,
+            ULID, UUID
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with(&reply));
+
+        let err = sorter.sort_ids_by_time(&[UUID, ULID]).await.unwrap_err();
+        assert!(matches!(err, VibesortError::VerificationFailed(_)));
+
+        let err = sorter.sort_ids_by_time(&["not-an-id"]).await.unwrap_err();
+        assert!(matches!(err, VibesortError::InvalidInput(_)));
+    }
+}
@@ -0,0 +1,196 @@
+//! Sorting images with vision-capable models.
+
+use crate::indexed::check_positions;
+use crate::parse;
+use crate::prompt::Operation;
+use crate::{ChatMessage, ContentPart, ImageUrl, MessageContent, Vibesort, VibesortError};
+
+/// An image to sort with [`Vibesort::sort_images`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ImageInput {
+    /// An image the provider fetches from a URL.
+    Url(String),
+
+    /// An image sent inline as base64-encoded data.
+    Base64 {
+        /// The media type of the image, e.g. `image/png`.
+        media_type: String,
+
+        /// The base64-encoded image data.
+        data: String,
+    },
+}
+
+impl ImageInput {
+    /// Creates an image the provider fetches from a URL.
+    pub fn url(url: impl Into<String>) -> Self {
+        ImageInput::Url(url.into())
+    }
+
+    /// Creates an image from base64-encoded data of the given media type.
+    pub fn base64(media_type: impl Into<String>, data: impl Into<String>) -> Self {
+        ImageInput::Base64 {
+            media_type: media_type.into(),
+            data: data.into(),
+        }
+    }
+
+    /// Returns the URL sent for this image, which is a `data:` URL for inline
+    /// images.
+    fn to_url(&self) -> String {
+        match self {
+            ImageInput::Url(url) => url.clone(),
+            ImageInput::Base64 { media_type, data } => {
+                format!("data:{};base64,{}", media_type, data)
+            }
+        }
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts images with a vision-capable model and returns their indices in
+    /// sorted order.
+    ///
+    /// The images are sent in one multi-part message, each labelled with its
+    /// index, and the model answers with the sorted indices. The result is
+    /// always checked to contain every index exactly once. The configured
+    /// [`order`](Self::order) applies; the configured criterion is replaced
+    /// by `criterion`.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// Providers or models without image support usually fail with
+    /// [`VibesortError::ApiError`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{ImageInput, Vibesort};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-4o",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let thumbnails = [
+    ///     ImageInput::url("https://example.com/desk.jpg"),
+    ///     ImageInput::url("https://example.com/beach.jpg"),
+    /// ];
+    /// let order = sorter
+    ///     .sort_images(&thumbnails, "from most to least cluttered")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_images(
+        &self,
+        images: &[ImageInput],
+        criterion: &str,
+    ) -> Result<Vec<usize>, VibesortError> {
+        let instruction = self.sort_instruction_for(Some(criterion));
+        let payload = format!(
+            "Sort these {} images, labelled with their indices.",
+            images.len()
+        );
+        let (system_prompt, user_content) =
+            self.render_prompt(Operation::Images, payload, || {
+                format!(
+                    "You are a helpful assistant that sorts images. Each image is preceded by its index. Sort the images {}. Return ONLY a JSON array of the indices of the images in sorted order, with every index exactly once.",
+                    instruction
+                )
+            })?;
+        let labels: Vec<String> = (0..images.len())
+            .map(|index| format!("Image {}:", index))
+            .collect();
+        let urls: Vec<String> = images.iter().map(ImageInput::to_url).collect();
+        let max_tokens = self.max_tokens_for(8 * images.len());
+
+        let len = images.len();
+        let (system_prompt, user_content, labels, urls) =
+            (&system_prompt, &user_content, &labels, &urls);
+        self.retrying(|escalation| async move {
+            let system_prompt = self.escalated_prompt(system_prompt, escalation);
+            let mut parts = vec![ContentPart::Text { text: user_content }];
+            for (label, url) in labels.iter().zip(urls) {
+                parts.push(ContentPart::Text { text: label });
+                parts.push(ContentPart::ImageUrl {
+                    image_url: ImageUrl { url: url.clone() },
+                });
+            }
+            let messages = vec![
+                ChatMessage {
+                    role: "system",
+                    content: MessageContent::Text(&system_prompt),
+                },
+                ChatMessage {
+                    role: "user",
+                    content: MessageContent::Parts(parts),
+                },
+            ];
+            let completion = self
+                .chat_messages(messages, None, max_tokens, 1, escalation)
+                .await?;
+
+            let indices: Vec<usize> = parse::parse_array(&completion.content)?;
+            check_positions(len, &indices)?;
+            Ok(indices)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_images_sends_image_parts() {
+        let backend = Arc::new(MockBackend::new().respond_with("[1, 0]"));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let images = [
+            ImageInput::url("https://example.com/a.png"),
+            ImageInput::base64("image/png", "iVBORw0KGgo="),
+        ];
+        let order = sorter
+            .sort_images(&images, "from most to least cluttered")
+            .await
+            .unwrap();
+        assert_eq!(order, vec![1, 0]);
+
+        let body = &backend.requests()[0].body;
+        let system = body["messages"][0]["content"].as_str().unwrap();
+        assert!(system.contains("criterion: from most to least cluttered"));
+        let parts = &body["messages"][1]["content"];
+        assert_eq!(
+            parts[1],
+            serde_json::json!({"type": "text", "text": "Image 0:"})
+        );
+        assert_eq!(
+            parts[2],
+            serde_json::json!({"type": "image_url", "image_url": {"url": "https://example.com/a.png"}})
+        );
+        assert_eq!(
+            parts[4]["image_url"]["url"],
+            "data:image/png;base64,iVBORw0KGgo="
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sort_images_rejects_missing_index() {
+        let backend = MockBackend::new()
+            .respond_with("[0, 0]")
+            .respond_with("[0, 0]")
+            .respond_with("[0, 0]");
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend);
+
+        let images = [ImageInput::url("a"), ImageInput::url("b")];
+        let err = sorter.sort_images(&images, "by hue").await.unwrap_err();
+        assert!(matches!(err, VibesortError::VerificationFailed(_)));
+    }
+}
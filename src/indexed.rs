@@ -0,0 +1,225 @@
+//! Sorting by index permutation.
+//!
+//! Instead of echoing the elements back, the model replies with the indices of
+//! the elements in sorted order. The caller rebuilds the sorted array from its
+//! own copies, so an element can never come back altered, which matters for
+//! long or whitespace-sensitive elements such as code. The indices are always
+//! checked to be a permutation of the input positions.
+//!
+//! The same payload serves operations that ask the model to describe every
+//! element instead, such as scoring it, with one object per index in the
+//! reply (see [`Vibesort::describe_indexed`]).
+
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError, parse, verify};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// An element of an index-permutation payload.
+#[derive(Serialize)]
+pub(crate) struct Indexed<'i, T: ?Sized> {
+    pub(crate) index: usize,
+    pub(crate) item: &'i T,
+}
+
+/// An object of a reply to [`Vibesort::describe_indexed`], describing the
+/// element at `index`.
+pub(crate) trait ElementReply: DeserializeOwned {
+    /// Returns the index of the element the object describes.
+    fn index(&self) -> usize;
+}
+
+/// Serializes the items as a payload of [`Indexed`] elements.
+pub(crate) fn indexed_payload<T: Serialize>(items: &[T]) -> Result<String, VibesortError> {
+    let payload: Vec<Indexed<'_, T>> = items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| Indexed { index, item })
+        .collect();
+    Ok(serde_json::to_string(&payload)?)
+}
+
+/// Returns the sentence of a system prompt that introduces an indexed
+/// payload, calling the items `element`s, e.g. "task".
+pub(crate) fn describe_payload(element: &str) -> String {
+    format!(
+        "The following JSON array contains objects of the form {{\"index\": <number>, \"item\": <{}>}}.",
+        element
+    )
+}
+
+/// Checks that `indices` lists every position of `len` elements exactly
+/// once.
+pub(crate) fn check_positions(len: usize, indices: &[usize]) -> Result<(), VibesortError> {
+    let positions: Vec<usize> = (0..len).collect();
+    verify::check_permutation(&positions, indices)
+}
+
+impl<'a> Vibesort<'a> {
+    /// Asks the model to sort the items and returns their positions in sorted
+    /// order.
+    ///
+    /// `instruction` completes "Sort the following elements ...", e.g. "with
+    /// ascending order".
+    pub(crate) async fn sort_indexed<T>(
+        &self,
+        operation: Operation,
+        items: &[T],
+        instruction: &str,
+    ) -> Result<Vec<usize>, VibesortError>
+    where
+        T: Serialize,
+    {
+        self.sort_indexed_checked(operation, items, instruction, |_| Ok(()))
+            .await
+    }
+
+    /// Like [`sort_indexed`](Self::sort_indexed), but also runs `check` on
+    /// every reply; an error from it counts as an invalid reply and is
+    /// retried.
+    pub(crate) async fn sort_indexed_checked<T>(
+        &self,
+        operation: Operation,
+        items: &[T],
+        instruction: &str,
+        check: impl Fn(&[usize]) -> Result<(), VibesortError>,
+    ) -> Result<Vec<usize>, VibesortError>
+    where
+        T: Serialize,
+    {
+        let json_array = indexed_payload(items)?;
+        // The reply is a list of numbers, a few bytes per element
+        let max_tokens = self.max_tokens_for(8 * items.len());
+
+        let (system_prompt, user_content) = self.render_prompt(operation, json_array, || {
+            format!(
+                "You are a helpful assistant that sorts arrays. {} Sort the elements {}. Return ONLY a JSON array of the indices of the elements in sorted order, with every index exactly once.",
+                describe_payload("element"),
+                instruction
+            )
+        })?;
+        let len = items.len();
+        let (system_prompt, user_content, check) = (&system_prompt, &user_content, &check);
+        self.retrying(|escalation| async move {
+            let completion = self
+                .chat(system_prompt, user_content, max_tokens, escalation)
+                .await?;
+
+            // A repaired reply is checked like any other
+            let (indices, _): (Vec<usize>, bool) =
+                parse::parse_array_repaired(&completion.content)?;
+            check_positions(len, &indices)?;
+            check(&indices)?;
+            Ok(indices)
+        })
+        .await
+    }
+
+    /// Sends the items as an indexed payload and asks for one object
+    /// describing every element, returning the result of `check` on the
+    /// objects ordered by index.
+    ///
+    /// `system_prompt` builds the default system prompt of `operation`, and
+    /// `reply_bytes` estimates the size of the reply. A reply that does not
+    /// describe every index exactly once, or that `check` rejects, counts as
+    /// an invalid reply and is retried.
+    pub(crate) async fn describe_indexed<T, R, U>(
+        &self,
+        operation: Operation,
+        items: &[T],
+        reply_bytes: usize,
+        system_prompt: impl FnOnce() -> String,
+        check: impl Fn(Vec<R>) -> Result<U, VibesortError>,
+    ) -> Result<U, VibesortError>
+    where
+        T: Serialize,
+        R: ElementReply,
+    {
+        let json_array = indexed_payload(items)?;
+        let max_tokens = self.max_tokens_for(reply_bytes);
+        let (system_prompt, user_content) =
+            self.render_prompt(operation, json_array, system_prompt)?;
+        let len = items.len();
+        let (system_prompt, user_content, check) = (&system_prompt, &user_content, &check);
+        self.retrying(|escalation| async move {
+            let completion = self
+                .chat(system_prompt, user_content, max_tokens, escalation)
+                .await?;
+
+            let (mut described, _): (Vec<R>, bool) =
+                parse::parse_array_repaired(&completion.content)?;
+            described.sort_by_key(ElementReply::index);
+            let indices: Vec<usize> = described.iter().map(ElementReply::index).collect();
+            check_positions(len, &indices)?;
+            check(described)
+        })
+        .await
+    }
+}
+
+/// Returns a sorter whose model answers with `replies` in order, and its
+/// backend.
+#[cfg(test)]
+pub(crate) fn replying(
+    replies: &[&str],
+) -> (
+    Vibesort<'static>,
+    std::sync::Arc<crate::testing::MockBackend>,
+) {
+    let backend = replies
+        .iter()
+        .fold(crate::testing::MockBackend::new(), |backend, reply| {
+            backend.respond_with(*reply)
+        });
+    let backend = std::sync::Arc::new(backend);
+    let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+    (sorter, backend)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_indexed() {
+        let backend = Arc::new(MockBackend::new().respond_with("[2, 0, 1]"));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let indices = sorter
+            .sort_indexed(Operation::Code, &["b", "c", "a"], "with ascending order")
+            .await
+            .unwrap();
+        assert_eq!(indices, vec![2, 0, 1]);
+
+        let user = backend.requests()[0].body["messages"][1]["content"].clone();
+        assert_eq!(
+            user,
+            r#"[{"index":0,"item":"b"},{"index":1,"item":"c"},{"index":2,"item":"a"}]"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sort_indexed_rejects_invalid_indices() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with("[0, 0, 5]"));
+
+        let result = sorter
+            .sort_indexed(Operation::Code, &["b", "c", "a"], "with ascending order")
+            .await;
+        assert!(matches!(result, Err(VibesortError::VerificationFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_sort_indexed_repairs_reply() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with("The order is [2, 0, 1,"));
+
+        let indices = sorter
+            .sort_indexed(Operation::Code, &["b", "c", "a"], "with ascending order")
+            .await
+            .unwrap();
+        assert_eq!(indices, vec![2, 0, 1]);
+    }
+}
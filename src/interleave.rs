@@ -0,0 +1,178 @@
+//! Ordering items to satisfy a balancing constraint instead of a total order.
+
+use crate::indexed::{check_positions, describe_payload, indexed_payload};
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError, parse};
+use serde::{Deserialize, Serialize};
+
+/// A place where an interleaved order could not satisfy the constraint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Violation {
+    /// The position in [`Interleaved::items`] where the constraint is broken.
+    pub position: usize,
+
+    /// Why the constraint could not be satisfied there.
+    pub reason: String,
+}
+
+/// The result of [`Vibesort::interleave`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interleaved<T> {
+    /// The items, in the balanced order.
+    pub items: Vec<T>,
+
+    /// The places where the model reported the constraint as broken, in
+    /// order of position. Empty if it was satisfied throughout.
+    pub violations: Vec<Violation>,
+}
+
+/// The reply of the model.
+#[derive(Debug, Deserialize)]
+struct Reply {
+    order: Vec<usize>,
+    #[serde(default)]
+    violations: Vec<Violation>,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Orders the items to satisfy a balancing constraint, such as "alternate
+    /// between technical and non-technical talks", rather than sorting them.
+    ///
+    /// The model only replies with the positions of the items in the new
+    /// order, which is checked to be a permutation of the input, and with the
+    /// places where the constraint cannot be satisfied (for example because
+    /// there are too few non-technical talks to alternate all the way). The
+    /// configured order and criterion are not used.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the reply does not
+    /// name every item exactly once or reports a violation at a position that
+    /// does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-4o-mini",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let talks = [
+    ///     "Zero-copy parsing in Rust",
+    ///     "Burnout and how to avoid it",
+    ///     "Lock-free queues",
+    ///     "Writing a conference proposal",
+    /// ];
+    /// let schedule = sorter
+    ///     .interleave(&talks, "alternate between technical and non-technical talks")
+    ///     .await?;
+    /// for violation in &schedule.violations {
+    ///     println!("slot {}: {}", violation.position + 1, violation.reason);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn interleave<T>(
+        &self,
+        items: &[T],
+        constraint: &str,
+    ) -> Result<Interleaved<T>, VibesortError>
+    where
+        T: Serialize + Clone,
+    {
+        let json_array = indexed_payload(items)?;
+        // The order is a few bytes per element; leave room for violations
+        let max_tokens = self.max_tokens_for(8 * items.len() + 256);
+
+        let (system_prompt, user_content) =
+            self.render_prompt(Operation::Interleave, json_array, || {
+                format!(
+                    "You are a helpful assistant that arranges arrays. {} Arrange the elements in an order that satisfies this constraint as well as possible: {}. This is not a sort; balance the sequence as the constraint asks. Return ONLY a JSON object of the form {{\"order\": [<index>, ...], \"violations\": [{{\"position\": <number>, \"reason\": \"...\"}}]}}, where \"order\" lists every index exactly once and \"violations\" lists the zero-based positions in the new order where the constraint could not be satisfied, or is empty.",
+                    describe_payload("element"),
+                    constraint
+                )
+            })?;
+        let len = items.len();
+        let (system_prompt, user_content) = (&system_prompt, &user_content);
+        let mut reply = self
+            .retrying(|escalation| async move {
+                let completion = self
+                    .chat(system_prompt, user_content, max_tokens, escalation)
+                    .await?;
+
+                let reply: Reply = parse::parse_json(&completion.content, "object")?;
+                check_positions(len, &reply.order)?;
+                if let Some(violation) = reply
+                    .violations
+                    .iter()
+                    .find(|violation| violation.position >= len)
+                {
+                    return Err(VibesortError::VerificationFailed(format!(
+                        "violation at position {} of {} elements",
+                        violation.position, len
+                    )));
+                }
+                Ok(reply)
+            })
+            .await?;
+
+        reply.violations.sort_by_key(|violation| violation.position);
+        Ok(Interleaved {
+            items: reply
+                .order
+                .into_iter()
+                .map(|index| items[index].clone())
+                .collect(),
+            violations: reply.violations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_interleave_reports_violations() {
+        let reply = r#"{"order": [0, 2, 1, 3], "violations": [{"position": 3, "reason": "no non-technical talk left"}]}"#;
+        let backend = Arc::new(MockBackend::new().respond_with(reply));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let talks = ["Rust", "Async", "Burnout", "Lock-free"].map(String::from);
+        let schedule = sorter
+            .interleave(
+                &talks,
+                "alternate between technical and non-technical talks",
+            )
+            .await
+            .unwrap();
+        assert_eq!(schedule.items, ["Rust", "Burnout", "Async", "Lock-free"]);
+        assert_eq!(schedule.violations[0].position, 3);
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        assert!(system.as_str().unwrap().contains(
+            "satisfies this constraint as well as possible: alternate between technical and non-technical talks."
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_interleave_rejects_non_permutation() {
+        let reply = r#"{"order": [0, 0]}"#;
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(
+            MockBackend::new()
+                .respond_with(reply)
+                .respond_with(reply)
+                .respond_with(reply),
+        );
+
+        let err = sorter.interleave(&[1, 2], "alternate").await.unwrap_err();
+        assert!(matches!(err, VibesortError::VerificationFailed(_)));
+    }
+}
@@ -0,0 +1,252 @@
+//! Control over the JSON that elements are sent as.
+
+use crate::VibesortError;
+use serde::Serialize;
+use serde_json::ser::{CompactFormatter, Formatter, Serializer};
+use std::io;
+
+/// How elements are serialized into prompts, set with
+/// [`Vibesort::json_format`](crate::Vibesort::json_format).
+///
+/// The default matches `serde_json::to_string`: floats at full precision,
+/// object keys in declaration order, and non-ASCII characters as is.
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::JsonFormat;
+///
+/// #[derive(serde::Serialize)]
+/// struct City {
+///     name: &'static str,
+///     area: f64,
+/// }
+///
+/// let format = JsonFormat::new()
+///     .float_precision(3)
+///     .sort_keys(true)
+///     .ascii_only(true);
+/// let city = City { name: "Zürich", area: 87.88 };
+/// assert_eq!(
+///     format.to_string(&city).unwrap(),
+///     r#"{"area":87.9,"name":"Z\u00fcrich"}"#
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct JsonFormat {
+    float_precision: Option<u32>,
+    sort_keys: bool,
+    ascii_only: bool,
+}
+
+impl JsonFormat {
+    /// Creates the default format.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rounds floats to `digits` significant digits, between 1 and 17.
+    ///
+    /// Models tend to echo long floats such as `0.30000000000000004`
+    /// shortened, which fails [verification](crate::Vibesort::verify); with
+    /// a precision, elements are compared after the same rounding.
+    pub fn float_precision(mut self, digits: u32) -> Self {
+        self.float_precision = Some(digits.clamp(1, 17));
+        self
+    }
+
+    /// Sorts the keys of every object alphabetically instead of keeping the
+    /// declaration order of struct fields.
+    pub fn sort_keys(mut self, sort: bool) -> Self {
+        self.sort_keys = sort;
+        self
+    }
+
+    /// Escapes every non-ASCII character as `\uXXXX`.
+    pub fn ascii_only(mut self, ascii_only: bool) -> Self {
+        self.ascii_only = ascii_only;
+        self
+    }
+
+    /// Serializes `value` in this format.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::JsonError`] if the value cannot be serialized.
+    pub fn to_string<T: Serialize + ?Sized>(&self, value: &T) -> Result<String, VibesortError> {
+        let mut out = Vec::new();
+        let mut serializer = Serializer::with_formatter(&mut out, PromptFormatter(*self));
+        if self.sort_keys {
+            // Values keep their keys sorted
+            serde_json::to_value(value)?.serialize(&mut serializer)?;
+        } else {
+            value.serialize(&mut serializer)?;
+        }
+        // The formatter only ever writes UTF-8
+        Ok(String::from_utf8(out).expect("JSON is not UTF-8"))
+    }
+
+    /// Returns the format elements are compared in: with sorted keys and
+    /// this format's precision.
+    pub(crate) fn canonical(&self) -> Self {
+        Self {
+            float_precision: self.float_precision,
+            sort_keys: true,
+            ascii_only: false,
+        }
+    }
+}
+
+/// A compact formatter applying the float and escaping options of a
+/// [`JsonFormat`].
+struct PromptFormatter(JsonFormat);
+
+impl Formatter for PromptFormatter {
+    fn write_f32<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f32) -> io::Result<()> {
+        match self.0.float_precision {
+            Some(digits) => CompactFormatter.write_f64(writer, round(f64::from(value), digits)),
+            None => CompactFormatter.write_f32(writer, value),
+        }
+    }
+
+    fn write_f64<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f64) -> io::Result<()> {
+        match self.0.float_precision {
+            Some(digits) => CompactFormatter.write_f64(writer, round(value, digits)),
+            None => CompactFormatter.write_f64(writer, value),
+        }
+    }
+
+    fn write_string_fragment<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        fragment: &str,
+    ) -> io::Result<()> {
+        if !self.0.ascii_only {
+            return writer.write_all(fragment.as_bytes());
+        }
+        for c in fragment.chars() {
+            if c.is_ascii() {
+                writer.write_all(&[c as u8])?;
+            } else {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    write!(writer, "\\u{:04x}", unit)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rounds `value` to `digits` significant digits.
+fn round(value: f64, digits: u32) -> f64 {
+    let precision = digits.saturating_sub(1) as usize;
+    format!("{:.*e}", precision, value).parse().unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vibesort;
+    use crate::testing::MockBackend;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_default_matches_serde_json() {
+        let value = serde_json::json!({"b": [0.1, 1e-7, "é"], "a": null});
+        assert_eq!(
+            JsonFormat::new().to_string(&value).unwrap(),
+            serde_json::to_string(&value).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_float_precision() {
+        let format = JsonFormat::new().float_precision(2);
+        assert_eq!(
+            format.to_string(&[0.1 + 0.2, 123456.0, 1.5e-9]).unwrap(),
+            "[0.3,120000.0,1.5e-9]"
+        );
+        assert_eq!(format.to_string(&[f64::NAN]).unwrap(), "[null]");
+    }
+
+    #[test]
+    fn test_ascii_only_escapes_keys_and_surrogates() {
+        let map = BTreeMap::from([("ключ", "😀")]);
+        assert_eq!(
+            JsonFormat::new().ascii_only(true).to_string(&map).unwrap(),
+            r#"{"\u043a\u043b\u044e\u0447":"\ud83d\ude00"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verification_at_prompt_precision() {
+        #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+        struct Reading {
+            sensor: String,
+            value: f64,
+        }
+
+        let backend = Arc::new(
+            MockBackend::new()
+                .respond_with(r#"[{"value": 0.3, "sensor": "b"}, {"sensor": "a", "value": 1.67}]"#),
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .verify(true)
+            .json_format(JsonFormat::new().float_precision(3).sort_keys(true));
+
+        let readings = [
+            Reading {
+                sensor: "a".into(),
+                value: 5.0 / 3.0,
+            },
+            Reading {
+                sensor: "b".into(),
+                value: 0.1 + 0.2,
+            },
+        ];
+        let sorted = sorter.sort_with_report(&readings).await.unwrap().items;
+        assert_eq!(sorted[0].sensor, "b");
+
+        let user = backend.requests()[0].body["messages"][1]["content"].clone();
+        assert_eq!(
+            user,
+            r#"[{"sensor":"a","value":1.67},{"sensor":"b","value":0.3}]"#
+        );
+
+        // Without the precision, the rounded floats are rejected
+        let sorter =
+            Vibesort::new("key", "model", "http://mock")
+                .backend(MockBackend::new().respond_with(
+                    r#"[{"sensor": "b", "value": 0.3}, {"sensor": "a", "value": 1.67}]"#,
+                ))
+                .verify(true);
+        assert!(sorter.sort_with_report(&readings).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_format_applies_to_explained_and_annotated_sorts() {
+        let backend = Arc::new(
+            MockBackend::new()
+                .respond_with(r#"{"sorted": [0.3, 1.67], "explanation": "Smaller first."}"#)
+                .respond_with(
+                    r#"[{"item": 0.3, "confidence": 0.9}, {"item": 1.67, "confidence": 0.8}]"#,
+                ),
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .verify(true)
+            .json_format(JsonFormat::new().float_precision(3));
+
+        let values = [5.0 / 3.0, 0.1 + 0.2];
+        let explained = sorter.sort_with_explanation(&values).await.unwrap();
+        assert_eq!(explained.items, [0.3, 1.67]);
+        let scored = sorter.sort_with_confidence(&values).await.unwrap();
+        assert_eq!(scored, [(0.3, 0.9), (1.67, 0.8)]);
+
+        for request in backend.requests() {
+            assert_eq!(request.body["messages"][1]["content"], "[1.67,0.3]");
+        }
+    }
+}
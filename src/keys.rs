@@ -0,0 +1,113 @@
+//! Reordering the keys of maps for people to read.
+
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+
+/// A key and its value, as shown to the model.
+#[derive(Serialize)]
+struct Entry<'e, K, V> {
+    key: &'e K,
+    value: &'e V,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Returns the keys of a map in a human-friendly order described by
+    /// `criterion`, for formatters of configuration files and similar
+    /// documents.
+    ///
+    /// The model sees every key with its value and replies with the order of
+    /// the keys only, so the returned keys are exactly the given ones. Any
+    /// iterator of key and value references works: a `BTreeMap` or `HashMap`
+    /// by reference, or a list of pairs mapped to references. The configured
+    /// criterion and order are not used.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the model does not
+    /// return every key exactly once.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::collections::BTreeMap;
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let config = BTreeMap::from([
+    ///     ("db_password", "hunter2"),
+    ///     ("log_level", "info"),
+    ///     ("db_host", "localhost"),
+    /// ]);
+    /// let keys = sorter
+    ///     .reorder_keys(&config, "group related config keys together")
+    ///     .await?;
+    ///
+    /// let pairs = vec![("port", "8080"), ("host", "localhost")];
+    /// let keys = sorter
+    ///     .reorder_keys(pairs.iter().map(|(key, value)| (key, value)), "host before port")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn reorder_keys<'m, K, V>(
+        &self,
+        entries: impl IntoIterator<Item = (&'m K, &'m V)>,
+        criterion: &str,
+    ) -> Result<Vec<K>, VibesortError>
+    where
+        K: Serialize + Clone + 'm,
+        V: Serialize + 'm,
+    {
+        let entries: Vec<Entry<'_, K, V>> = entries
+            .into_iter()
+            .map(|(key, value)| Entry { key, value })
+            .collect();
+        let instruction = format!(
+            "(the keys of a map, each shown with its value) into the order in which the keys should appear to a person reading the map, according to this criterion: {}",
+            criterion
+        );
+        let indices = self
+            .sort_indexed(Operation::Keys, &entries, &instruction)
+            .await?;
+        Ok(indices
+            .into_iter()
+            .map(|index| entries[index].key.clone())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_reorder_keys() {
+        let backend = Arc::new(MockBackend::new().respond_with("[1, 2, 0]"));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let config = BTreeMap::from([("db_host", "a"), ("db_port", "b"), ("log_level", "c")]);
+        let keys = sorter
+            .reorder_keys(&config, "group related keys")
+            .await
+            .unwrap();
+        assert_eq!(keys, ["db_port", "log_level", "db_host"]);
+
+        let user = backend.requests()[0].body["messages"][1]["content"].clone();
+        assert!(
+            user.as_str()
+                .unwrap()
+                .starts_with(r#"[{"index":0,"item":{"key":"db_host","value":"a"}}"#)
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Accounting of token usage by label, for billing sorts back internally.
+
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The `usage` object of a chat completion response.
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+pub(crate) struct TokenUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+/// The usage accumulated under one label, returned by
+/// [`Vibesort::usage_by_label`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LabelUsage {
+    /// The number of successful requests.
+    pub requests: u64,
+
+    /// The prompt tokens reported by the provider.
+    pub prompt_tokens: u64,
+
+    /// The completion tokens reported by the provider.
+    pub completion_tokens: u64,
+
+    /// The cost of the requests made with [`pricing`](Vibesort::pricing)
+    /// configured, each at the pricing of the client that made it.
+    ///
+    /// `None` if no request was priced.
+    pub cost: Option<f64>,
+}
+
+/// The usage of a client and its clones, by label name and value.
+pub(crate) type UsageLedger = Arc<Mutex<BTreeMap<(String, String), LabelUsage>>>;
+
+impl<'a> Vibesort<'a> {
+    /// Returns a clone of this client whose requests are accounted under
+    /// `labels`, such as `[("tenant", "acme")]`, in addition to any labels
+    /// of this client. A label that is already set takes the new value.
+    ///
+    /// Every successful request made through the clone adds its tokens, as
+    /// reported in the provider's `usage` object, to each of its labels.
+    /// Usage is shared by the client and all its clones, and read with
+    /// [`usage_by_label`](Self::usage_by_label).
+    pub fn labeled(&self, labels: &[(&str, &str)]) -> Self {
+        let mut sorter = self.clone();
+        sorter.labels.extend(
+            labels
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string())),
+        );
+        sorter
+    }
+
+    /// Sorts the items as [`sort_with_report`](Self::sort_with_report) does,
+    /// accounting every request under `labels`.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{Pricing, Vibesort};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-4o-mini",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .pricing(Pricing::new(0.15, 0.60));
+    ///
+    /// sorter
+    ///     .sort_with_labels(&[3, 1, 2], &[("tenant", "acme"), ("feature", "search")])
+    ///     .await?;
+    /// let acme = sorter.usage_by_label()[&("tenant".to_string(), "acme".to_string())];
+    /// println!("acme used {} tokens", acme.prompt_tokens + acme.completion_tokens);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_with_labels<T>(
+        &self,
+        items: &[T],
+        labels: &[(&str, &str)],
+    ) -> Result<Vec<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        Ok(self.labeled(labels).sort_with_report(items).await?.items)
+    }
+
+    /// Returns the usage accumulated under every label, keyed by label name
+    /// and value.
+    ///
+    /// Requests made without labels are not accounted.
+    pub fn usage_by_label(&self) -> BTreeMap<(String, String), LabelUsage> {
+        self.lock_usage().clone()
+    }
+
+    /// Clears the accumulated usage, such as at the end of a billing period.
+    pub fn reset_usage(&self) {
+        self.lock_usage().clear();
+    }
+
+    /// Adds the usage of a successful request to each label of this client.
+    pub(crate) fn account(&self, usage: TokenUsage) {
+        if self.labels.is_empty() {
+            return;
+        }
+        let mut ledger = self.lock_usage();
+        for (name, value) in &self.labels {
+            let entry = ledger.entry((name.clone(), value.clone())).or_default();
+            entry.requests += 1;
+            entry.prompt_tokens += usage.prompt_tokens;
+            entry.completion_tokens += usage.completion_tokens;
+            if let Some(pricing) = self.pricing {
+                let cost = pricing.cost(usage.prompt_tokens, usage.completion_tokens);
+                entry.cost = Some(entry.cost.unwrap_or_default() + cost);
+            }
+        }
+    }
+
+    fn lock_usage(&self) -> MutexGuard<'_, BTreeMap<(String, String), LabelUsage>> {
+        self.usage
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pricing;
+    use crate::testing::MockBackend;
+
+    #[tokio::test]
+    async fn test_usage_by_label() {
+        let reply = r#"{"choices":[{"message":{"content":"[1,2,3]"}}],"usage":{"prompt_tokens":100,"completion_tokens":10}}"#;
+        let backend = MockBackend::new()
+            .respond_with_status(200, reply)
+            .respond_with_status(200, reply)
+            .respond_with_status(200, reply)
+            .respond_with_status(200, reply);
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend)
+            .pricing(Pricing::new(1.0, 2.0));
+
+        let acme = &[("tenant", "acme"), ("feature", "search")];
+        sorter.sort_with_labels(&[3, 1, 2], acme).await.unwrap();
+        sorter.sort_with_labels(&[3, 1, 2], acme).await.unwrap();
+        sorter
+            .labeled(&[("tenant", "initech")])
+            .sort_with_report(&[3, 1, 2])
+            .await
+            .unwrap();
+        sorter.sort_with_report(&[3, 1, 2]).await.unwrap();
+
+        let label = |name: &str, value: &str| (name.to_string(), value.to_string());
+        let usage = sorter.usage_by_label();
+        assert_eq!(usage.len(), 3);
+        assert_eq!(
+            usage[&label("tenant", "acme")],
+            LabelUsage {
+                requests: 2,
+                prompt_tokens: 200,
+                completion_tokens: 20,
+                cost: Some(0.00024),
+            }
+        );
+        assert_eq!(usage[&label("feature", "search")].requests, 2);
+        assert_eq!(usage[&label("tenant", "initech")].prompt_tokens, 100);
+
+        sorter.reset_usage();
+        assert!(sorter.usage_by_label().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_usage_cost_at_the_pricing_of_each_clone() {
+        let reply = r#"{"choices":[{"message":{"content":"[1,2]"}}],"usage":{"prompt_tokens":1000,"completion_tokens":0}}"#;
+        let backend = MockBackend::new()
+            .respond_with_status(200, reply)
+            .respond_with_status(200, reply)
+            .respond_with_status(200, reply);
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend);
+        let cheap = sorter.clone().pricing(Pricing::new(1.0, 1.0));
+        let dear = sorter.clone().pricing(Pricing::new(3.0, 3.0));
+
+        let acme = &[("tenant", "acme")];
+        cheap.sort_with_labels(&[2, 1], acme).await.unwrap();
+        dear.sort_with_labels(&[2, 1], acme).await.unwrap();
+        sorter.sort_with_labels(&[2, 1], acme).await.unwrap();
+
+        let usage = sorter.usage_by_label()[&("tenant".to_string(), "acme".to_string())];
+        assert_eq!(usage.requests, 3);
+        assert_eq!(usage.prompt_tokens, 3000);
+        assert!((usage.cost.unwrap() - 0.004).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,209 @@
+//! Rendering sort results as Markdown, HTML, or CSV leaderboards.
+
+use crate::SortResult;
+use std::fmt::{Display, Write};
+
+/// The output format of a [`Leaderboard`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LeaderboardFormat {
+    /// A Markdown table with a rank column.
+    Markdown,
+
+    /// An HTML ordered list.
+    Html,
+
+    /// CSV with a header row and a rank column.
+    Csv,
+}
+
+/// A ranking ready to be rendered, created by [`SortResult::leaderboard`].
+#[derive(Debug, Clone, Copy)]
+pub struct Leaderboard<'r, T> {
+    items: &'r [T],
+    scores: Option<&'r [f64]>,
+    explanation: Option<&'r str>,
+}
+
+impl<T> SortResult<T> {
+    /// Returns a leaderboard of the sorted items, for pasting into reports.
+    ///
+    /// The [explanation](SortResult::explanation) is included if there is
+    /// one; see [`Leaderboard::explanation`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vibesort_rs::{LeaderboardFormat, SortReport, SortResult};
+    ///
+    /// let result = SortResult {
+    ///     items: vec!["crash", "typo"],
+    ///     report: SortReport::default(),
+    ///     explanation: None,
+    /// };
+    /// let table = result
+    ///     .leaderboard()
+    ///     .scores(&[9.5, 2.0])
+    ///     .render(LeaderboardFormat::Markdown);
+    /// assert_eq!(
+    ///     table,
+    ///     "| Rank | Item | Score |\n| ---: | --- | ---: |\n| 1 | crash | 9.5 |\n| 2 | typo | 2 |\n"
+    /// );
+    /// ```
+    pub fn leaderboard(&self) -> Leaderboard<'_, T> {
+        Leaderboard {
+            items: &self.items,
+            scores: None,
+            explanation: self.explanation.as_deref(),
+        }
+    }
+}
+
+impl<'r, T: Display> Leaderboard<'r, T> {
+    /// Adds a score column, with one score per item in sorted order.
+    ///
+    /// Items without a score get an empty cell.
+    pub fn scores(mut self, scores: &'r [f64]) -> Self {
+        self.scores = Some(scores);
+        self
+    }
+
+    /// Controls whether the explanation of the sort is included (the
+    /// default if there is one).
+    ///
+    /// It is rendered as a paragraph after the table or list; CSV output
+    /// never includes it.
+    pub fn explanation(mut self, include: bool) -> Self {
+        if !include {
+            self.explanation = None;
+        }
+        self
+    }
+
+    /// Renders the leaderboard in the given format.
+    pub fn render(&self, format: LeaderboardFormat) -> String {
+        match format {
+            LeaderboardFormat::Markdown => self.to_markdown(),
+            LeaderboardFormat::Html => self.to_html(),
+            LeaderboardFormat::Csv => self.to_csv(),
+        }
+    }
+
+    /// Renders the leaderboard as a Markdown table.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        if self.scores.is_some() {
+            out.push_str("| Rank | Item | Score |\n| ---: | --- | ---: |\n");
+        } else {
+            out.push_str("| Rank | Item |\n| ---: | --- |\n");
+        }
+        for (rank, item) in self.items.iter().enumerate() {
+            let item = item.to_string().replace('|', "\\|").replace('\n', " ");
+            let _ = write!(out, "| {} | {} |", rank + 1, item);
+            if self.scores.is_some() {
+                let _ = write!(out, " {} |", self.score(rank));
+            }
+            out.push('\n');
+        }
+        if let Some(explanation) = self.explanation {
+            let _ = write!(out, "\n{}\n", explanation);
+        }
+        out
+    }
+
+    /// Renders the leaderboard as an HTML ordered list.
+    pub fn to_html(&self) -> String {
+        let mut out = String::from("<ol>\n");
+        for (rank, item) in self.items.iter().enumerate() {
+            let _ = write!(out, "  <li>{}", escape_html(&item.to_string()));
+            if let Some(score) = self.scores.and_then(|scores| scores.get(rank)) {
+                let _ = write!(out, " <span class=\"score\">({})</span>", score);
+            }
+            out.push_str("</li>\n");
+        }
+        out.push_str("</ol>\n");
+        if let Some(explanation) = self.explanation {
+            let _ = writeln!(out, "<p>{}</p>", escape_html(explanation));
+        }
+        out
+    }
+
+    /// Renders the leaderboard as CSV.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        if self.scores.is_some() {
+            out.push_str("rank,item,score\n");
+        } else {
+            out.push_str("rank,item\n");
+        }
+        for (rank, item) in self.items.iter().enumerate() {
+            let _ = write!(out, "{},{}", rank + 1, escape_csv(&item.to_string()));
+            if self.scores.is_some() {
+                let _ = write!(out, ",{}", self.score(rank));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Returns the formatted score at `rank`, or an empty string.
+    fn score(&self, rank: usize) -> String {
+        self.scores
+            .and_then(|scores| scores.get(rank))
+            .map(ToString::to_string)
+            .unwrap_or_default()
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_csv(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SortReport;
+
+    fn result() -> SortResult<String> {
+        SortResult {
+            items: vec!["<crash>".to_string(), "typo, \"minor\"".to_string()],
+            report: SortReport::default(),
+            explanation: Some("Crashes lose data.".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_leaderboard_formats() {
+        let result = result();
+
+        assert_eq!(
+            result.leaderboard().to_markdown(),
+            "| Rank | Item |\n| ---: | --- |\n| 1 | <crash> |\n| 2 | typo, \"minor\" |\n\nCrashes lose data.\n"
+        );
+        assert_eq!(
+            result.leaderboard().scores(&[0.9]).to_html(),
+            "<ol>\n  <li>&lt;crash&gt; <span class=\"score\">(0.9)</span></li>\n  <li>typo, &quot;minor&quot;</li>\n</ol>\n<p>Crashes lose data.</p>\n"
+        );
+        assert_eq!(
+            result.leaderboard().scores(&[0.9, 0.1]).to_csv(),
+            "rank,item,score\n1,<crash>,0.9\n2,\"typo, \"\"minor\"\"\",0.1\n"
+        );
+        assert!(
+            !result
+                .leaderboard()
+                .explanation(false)
+                .render(LeaderboardFormat::Markdown)
+                .contains("Crashes")
+        );
+    }
+}
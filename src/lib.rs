@@ -12,6 +12,7 @@
 //! - Support for any LLM API compatible with OpenAI's chat completion format
 //! - Comprehensive error handling with detailed error messages
 //! - Async/await support using Tokio
+//! - Runs inside Cloudflare Workers with the `worker` feature
 //!
 //! ## Example
 //!
@@ -32,9 +33,152 @@
 //! # }
 //! ```
 
+// TLS is up to the JavaScript runtime on wasm32
+#[cfg(all(
+    target_arch = "wasm32",
+    any(not(feature = "worker"), feature = "__tls")
+))]
+compile_error!("on wasm32, vibesort-rs needs `default-features = false` and the `worker` feature");
+
+mod annotate;
+mod array;
+mod audit;
+pub mod backend;
+mod by_example;
+mod bytes;
+pub mod cache;
+mod chunk;
+mod code;
+mod colors;
+mod confidence;
+mod constrained;
+mod digest;
+mod display;
+pub mod engine;
+pub mod ensemble;
+mod estimate;
+pub mod eval;
+mod exact;
+mod explain;
+mod graph;
+mod health;
+mod heap;
+mod ids;
+mod images;
+mod indexed;
+mod interleave;
+mod json_format;
+mod keys;
+mod labels;
+mod leaderboard;
+mod limit;
+mod models;
+mod nulls;
+mod ord;
+mod pairs;
+pub mod parse;
+mod partial;
+#[cfg(not(target_arch = "wasm32"))]
+mod paths;
+mod plan;
+mod playlist;
+mod pointer;
+pub mod presets;
+pub mod prompt;
+mod provider;
+mod proximity;
+#[cfg(not(all(feature = "worker", target_arch = "wasm32")))]
+pub mod queue;
+mod reflect;
+mod report;
+pub mod retry;
+mod rng;
+mod rt;
+mod schedule;
+mod script;
+mod sentiment;
+mod session;
+mod shutdown;
+mod sortable;
+pub mod strategy;
+#[cfg(feature = "stream")]
+mod stream;
+mod tagged;
+mod tasks;
+mod temporal;
+mod tenant;
+pub mod testing;
+mod toposort;
+pub mod tournament;
+#[cfg(feature = "unicode")]
+pub mod unicode;
+pub mod verify;
+mod vibe;
+mod weighted;
+mod words;
+
+pub use audit::{AuditFlag, Audited};
+#[cfg(not(target_arch = "wasm32"))]
+use backend::HttpBackend;
+use backend::{Backend, BackendRequest, BackendResponse, RequestSigner, SortTask};
+pub use bytes::BytesEncoding;
+use cache::{ChunkCheckpoint, SortCache};
+pub use code::CodeCriterion;
+pub use colors::Hsl;
+pub use constrained::Precedence;
+use engine::Engine;
+pub use estimate::{Estimate, Pricing};
+pub use exact::ExactNumber;
+pub use health::HealthReport;
+pub use heap::VibeHeap;
+pub use images::ImageInput;
+pub use interleave::{Interleaved, Violation};
+pub use json_format::JsonFormat;
+pub use labels::LabelUsage;
+pub use leaderboard::{Leaderboard, LeaderboardFormat};
+pub use limit::AdaptiveScheduler;
+use limit::RequestLimiter;
+pub use models::ModelLimits;
+pub use nulls::NullsPolicy;
+pub use ord::{ComparisonOracle, VibeOrd};
+pub use pairs::PairKey;
+pub use partial::PartialSort;
+pub use plan::{PlanStage, SortPlan, StageKind};
+pub use playlist::EnergyCurve;
+use prompt::{Operation, PromptValues, TemplateRegistry};
+pub use prompt::{Order, PromptTemplate};
+pub use provider::ProviderError;
+pub use report::{SortMetadata, SortReport, SortResult};
+#[cfg(feature = "__tls")]
+pub use reqwest::Certificate;
+use retry::{Escalation, NoRetry, RetryPolicy};
+use rng::SplitMix64;
+use rt::Instant;
+pub use schedule::{ScheduledEvent, StartSource};
+pub use script::ScriptConvention;
+pub use secrecy::{ExposeSecret, SecretString};
+pub use sentiment::Sentiment;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
+pub use session::SortSession;
+pub use sortable::Vibesortable;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fmt::Display;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+pub use tasks::TaskMetadata;
+pub use temporal::Temporal;
 use thiserror::Error;
+pub use toposort::TopoSort;
+use verify::Sampling;
+pub use vibe::{VibeAxis, VibeRange};
+#[cfg(feature = "derive")]
+pub use vibesort_rs_derive::Vibesortable;
+pub use weighted::WeightedItem;
+pub use words::WordCriterion;
 
 #[cfg(test)]
 mod tests {
@@ -43,11 +187,43 @@ mod tests {
     #[test]
     fn test_vibesort_config() {
         let sorter = Vibesort::new("key", "model", "url");
-        assert_eq!(sorter.api_key, "key");
+        assert_eq!(sorter.api_key.expose_secret(), "key");
         assert_eq!(sorter.model, "model");
         assert_eq!(sorter.base_url, "url");
     }
 
+    #[test]
+    #[cfg(feature = "__tls")]
+    fn test_tls_options() {
+        let sorter = Vibesort::new("key", "model", "url")
+            .tls_built_in_root_certs(false)
+            .danger_accept_invalid_certs(true);
+        assert!(!sorter.built_in_root_certs);
+        assert!(sorter.accept_invalid_certs);
+        assert!(sorter.http_client_builder().build().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_http_client_is_shared_by_clones() {
+        let sorter = Vibesort::new("key", "model", "http://localhost:1/v1");
+        let tenant = sorter.for_tenant("acme");
+        tenant.http_client().await.unwrap();
+        assert!(sorter.http_client.get().is_some());
+
+        // New transport options get a client of their own
+        let resolved = sorter.resolve("api.internal", "10.0.0.5:443".parse().unwrap());
+        assert!(resolved.http_client.get().is_none());
+        assert!(tenant.http_client.get().is_some());
+    }
+
+    #[test]
+    fn test_api_key_redacted_in_debug() {
+        let sorter = Vibesort::new("sk-very-secret", "model", "url");
+        let debug = format!("{:?}", sorter);
+        assert!(!debug.contains("sk-very-secret"));
+        assert!(debug.contains("REDACTED"));
+    }
+
     #[tokio::test]
     async fn test_vibesort_with_mock() {
         use wiremock::matchers::{method, path};
@@ -105,7 +281,7 @@ mod tests {
 
         assert!(result.is_err());
         match result.unwrap_err() {
-            VibesortError::ApiError(_) => {}
+            VibesortError::ApiError { .. } => {}
             _ => panic!("Expected ApiError"),
         }
     }
@@ -180,6 +356,587 @@ mod tests {
         let sorted = result.unwrap();
         assert_eq!(sorted, vec!["apple", "banana", "cherry"]);
     }
+
+    #[tokio::test]
+    async fn test_deny_remote_rejects_public_endpoint() {
+        let sorter = Vibesort::new("test-api-key", "test-model", "http://8.8.8.8/v1").deny_remote();
+
+        let result = sorter.sort(&[3, 1, 2]).await;
+
+        match result.unwrap_err() {
+            VibesortError::RemoteDenied(msg) => assert!(msg.contains("8.8.8.8")),
+            _ => panic!("Expected RemoteDenied"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deny_remote_allows_local_endpoint() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        let base_url = mock_server.uri();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "message": {
+                        "content": "[1,2,3]"
+                    }
+                }]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let sorter = Vibesort::new("test-api-key", "test-model", base_url.as_str()).deny_remote();
+
+        let sorted = sorter.sort(&[3, 1, 2]).await.unwrap();
+        assert_eq!(sorted, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_overrides_dns() {
+        use wiremock::matchers::{header, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        let host = format!("api.internal:{}", mock_server.address().port());
+        Mock::given(method("POST"))
+            .and(header("host", host.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"content": "[1,2,3]"}}]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        // Resolving to a remote address is denied, the fixed local one is
+        // allowed
+        let base_url = format!("http://{}", host);
+        let remote = Vibesort::new("key", "model", &base_url)
+            .resolve("api.internal", "8.8.8.8:0".parse().unwrap())
+            .deny_remote();
+        assert!(matches!(
+            remote.sort(&[3, 1, 2]).await.unwrap_err(),
+            VibesortError::RemoteDenied(_)
+        ));
+
+        let sorter = remote.resolve("api.internal", *mock_server.address());
+        assert_eq!(sorter.sort(&[3, 1, 2]).await.unwrap(), vec![1, 2, 3]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket_transport() {
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+        let dir = std::env::temp_dir().join(format!("vibesort-uds-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("llm.sock");
+        let _ = std::fs::remove_file(&socket);
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            let mut request_line = String::new();
+            stream.read_line(&mut request_line).await.unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                stream.read_line(&mut header).await.unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            stream.read_exact(&mut body).await.unwrap();
+
+            let reply = r#"{"choices":[{"message":{"content":"[1,2,3]"}}]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                reply.len(),
+                reply
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            request_line
+        });
+
+        let base_url = format!("unix://{}:/v1", socket.display());
+        let sorter = Vibesort::new("key", "model", &base_url).deny_remote();
+        let sorted = sorter.sort(&[3, 1, 2]).await.unwrap();
+        assert_eq!(sorted, vec![1, 2, 3]);
+
+        let request_line = server.await.unwrap();
+        assert!(request_line.starts_with("POST /v1/chat/completions "));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_seed_and_system_fingerprint() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        let base_url = mock_server.uri();
+
+        // Only respond if the seed is passed through
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({ "seed": 42 })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "system_fingerprint": "fp_44709d6fcb",
+                "choices": [{
+                    "message": {
+                        "content": "[1,2,3]"
+                    }
+                }]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let sorter = Vibesort::new("test-api-key", "test-model", base_url.as_str()).seed(42);
+
+        let result = sorter.sort_with_report(&[3, 1, 2]).await.unwrap();
+        assert_eq!(result.items, vec![1, 2, 3]);
+        assert_eq!(result.report.seed, Some(42));
+        assert_eq!(
+            result.report.system_fingerprint.as_deref(),
+            Some("fp_44709d6fcb")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sort_metadata() {
+        use testing::MockBackend;
+
+        let body = serde_json::json!({
+            "id": "chatcmpl-123",
+            "model": "test-model-2024-08-06",
+            "system_fingerprint": "fp_44709d6fcb",
+            "choices": [{ "message": { "content": "[1,2,3]" } }]
+        });
+        let backend = MockBackend::new().respond_with_status(200, body.to_string());
+        let sorter = Vibesort::new("key", "test-model", "http://mock").backend(backend);
+
+        let result = sorter.sort_with_report(&[3, 1, 2]).await.unwrap();
+        assert_eq!(
+            result.report.metadata,
+            SortMetadata {
+                system_fingerprint: Some("fp_44709d6fcb".to_string()),
+                response_id: Some("chatcmpl-123".to_string()),
+                model: Some("test-model-2024-08-06".to_string()),
+                headers: BTreeMap::new(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_capture_headers() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-request-id", "req_123")
+                    .insert_header("openai-processing-ms", "412")
+                    .insert_header("cf-ray", "8a1b")
+                    .set_body_json(serde_json::json!({
+                        "choices": [{ "message": { "content": "[1,2,3]" } }]
+                    })),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let base_url = mock_server.uri();
+        let sorter = Vibesort::new("key", "model", base_url.as_str());
+        let metadata = sorter
+            .sort_with_report(&[3, 1, 2])
+            .await
+            .unwrap()
+            .report
+            .metadata;
+        assert_eq!(metadata.request_id(), Some("req_123"));
+        assert_eq!(metadata.headers["openai-processing-ms"], "412");
+        assert!(!metadata.headers.contains_key("cf-ray"));
+
+        let sorter = sorter.capture_headers(["CF-Ray"]);
+        let metadata = sorter
+            .sort_with_report(&[3, 1, 2])
+            .await
+            .unwrap()
+            .report
+            .metadata;
+        assert_eq!(
+            metadata.headers,
+            BTreeMap::from([("cf-ray".to_string(), "8a1b".to_string())])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_dropped_elements() {
+        use testing::MockBackend;
+
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with("[1,2,3]"))
+            .verify(true);
+
+        match sorter.sort(&[3, 1, 2, 1]).await.unwrap_err() {
+            VibesortError::VerificationFailed(msg) => assert!(msg.contains("missing [1]")),
+            _ => panic!("Expected VerificationFailed"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repairs_almost_valid_json() {
+        use testing::MockBackend;
+
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with("Sorted: [1, 2, 3,\n"));
+        let result = sorter.sort_with_report(&[3, 1, 2]).await.unwrap();
+        assert_eq!(result.items, vec![1, 2, 3]);
+        assert!(result.report.json_repaired);
+
+        // A reply cut off before the last element is caught even without
+        // verification
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with("[1, 2"));
+        let err = sorter.sort_with_report(&[3, 1, 2]).await.unwrap_err();
+        assert!(matches!(err, VibesortError::VerificationFailed(_)));
+
+        // Replies to tagged elements are repaired too
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with(
+                r#"[{"id": 1, "value": 1}, {"id": 2, "value": 2}, {"id": 0, "value": 3},"#,
+            ))
+            .tag_ids(true);
+        let result = sorter.sort_with_report(&[3, 1, 2]).await.unwrap();
+        assert_eq!(result.items, vec![1, 2, 3]);
+        assert!(result.report.json_repaired);
+    }
+
+    #[tokio::test]
+    async fn test_restore_duplicates() {
+        use testing::MockBackend;
+
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with("[1,2,3]"))
+            .verify(true)
+            .restore_duplicates(true);
+        let result = sorter.sort_with_report(&[3, 1, 2, 1, 3]).await.unwrap();
+        assert_eq!(result.items, vec![1, 1, 2, 3, 3]);
+        assert_eq!(result.report.duplicates_restored, 2);
+    }
+
+    #[tokio::test]
+    async fn test_verify_tolerance() {
+        use testing::MockBackend;
+
+        let reply = "[0.1, 0.20000000001, 0.3]";
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with(reply))
+            .verify(true);
+        let err = sorter.sort_with_report(&[0.3, 0.1, 0.2]).await.unwrap_err();
+        assert!(matches!(err, VibesortError::VerificationFailed(_)));
+
+        let sorter = sorter
+            .backend(MockBackend::new().respond_with(reply))
+            .verify_tolerance(1e-9);
+        let result = sorter.sort_with_report(&[0.3, 0.1, 0.2]).await.unwrap();
+        assert_eq!(result.items, vec![0.1, 0.2, 0.3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "verify tolerance must be finite")]
+    fn test_verify_tolerance_rejects_infinity() {
+        let _ = Vibesort::new("key", "model", "http://mock").verify_tolerance(f64::INFINITY);
+    }
+
+    #[tokio::test]
+    async fn test_verify_sampled_checks_boundaries() {
+        use testing::MockBackend;
+
+        let items: Vec<u32> = (0..100).rev().collect();
+        let mut reply: Vec<u32> = (0..100).collect();
+        reply[99] = 1000;
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(
+                MockBackend::new()
+                    .respond_with(serde_json::to_string(&reply).unwrap())
+                    .respond_with(serde_json::to_string(&reply).unwrap()),
+            )
+            .seed(1)
+            .verify_sampled(Sampling::new(10).boundary(2));
+
+        match sorter.sort(&items).await.unwrap_err() {
+            VibesortError::VerificationFailed(msg) => {
+                assert!(msg.contains("element 1000 at position 99 is not in the input"))
+            }
+            _ => panic!("Expected VerificationFailed"),
+        }
+        // Without the corruption the sample passes, as does a full check
+        // after switching back
+        let sorter = sorter.backend(MockBackend::new());
+        assert!(sorter.sort(&items).await.is_ok());
+        let sorter = sorter.verify(true);
+        assert!(sorter.sort(&items).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_few_shot_examples_are_sent() {
+        use testing::MockBackend;
+
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .example(&["b", "a"], &["a", "b"])
+            .unwrap();
+
+        sorter.sort_str(&["d", "c"]).await.unwrap();
+
+        let body = &backend.requests()[0].body;
+        let messages = body["messages"].as_array().unwrap();
+        let roles: Vec<&str> = messages
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, vec!["system", "user", "assistant", "user"]);
+        assert_eq!(messages[1]["content"], "[\"b\",\"a\"]");
+        assert_eq!(messages[2]["content"], "[\"a\",\"b\"]");
+        assert_eq!(messages[3]["content"], "[\"d\",\"c\"]");
+    }
+
+    #[tokio::test]
+    async fn test_prompt_template_and_order() {
+        use testing::MockBackend;
+
+        let template =
+            PromptTemplate::new("Sort {order} by {criterion}.", "Items: {array}").unwrap();
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .order(Order::Descending)
+            .criterion("size")
+            .prompt_template(template);
+
+        // The mock sorts from the structured task, so it honours the order
+        let sorted = sorter.sort(&[1, 3, 2]).await.unwrap();
+        assert_eq!(sorted, vec![3, 2, 1]);
+
+        let messages = &backend.requests()[0].body["messages"];
+        assert_eq!(messages[0]["content"], "Sort descending by size.");
+        assert_eq!(messages[1]["content"], "Items: [1,3,2]");
+    }
+
+    #[tokio::test]
+    async fn test_named_template_selected_per_call() {
+        use prompt::{Operation, TemplateRegistry};
+        use testing::MockBackend;
+
+        let registry = TemplateRegistry::new()
+            .register(
+                Operation::Sort,
+                "terse",
+                1,
+                PromptTemplate::new("v1", "{array}").unwrap(),
+            )
+            .register(
+                Operation::Sort,
+                "terse",
+                2,
+                PromptTemplate::new("v2", "{array}").unwrap(),
+            );
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .templates(registry);
+
+        sorter.with_template("terse").sort(&[2, 1]).await.unwrap();
+        sorter.with_template("terse@1").sort(&[2, 1]).await.unwrap();
+        sorter.sort(&[2, 1]).await.unwrap();
+
+        let requests = backend.requests();
+        assert_eq!(requests[0].body["messages"][0]["content"], "v2");
+        assert_eq!(requests[1].body["messages"][0]["content"], "v1");
+        assert_ne!(requests[2].body["messages"][0]["content"], "v1");
+
+        let result = sorter.with_template("missing").sort(&[2, 1]).await;
+        assert!(matches!(result, Err(VibesortError::InvalidTemplate(_))));
+    }
+
+    #[tokio::test]
+    async fn test_prompt_language() {
+        use testing::MockBackend;
+
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .prompt_language("ja");
+        assert_eq!(sorter.sort(&[2, 1]).await.unwrap(), vec![1, 2]);
+
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        assert!(system.as_str().unwrap().contains("昇順"));
+    }
+
+    #[tokio::test]
+    async fn test_max_tokens_sizing() {
+        use testing::MockBackend;
+
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+        let items: Vec<u32> = (0..1000).collect();
+        sorter.sort(&items).await.unwrap();
+
+        // The limit must leave room for the whole sorted array
+        let json_len = serde_json::to_string(&items).unwrap().len() as u64;
+        let limit = backend.requests()[0].body["max_tokens"].as_u64().unwrap();
+        assert!(limit > json_len / 3);
+
+        let sorter = sorter.max_tokens(MaxTokens::ProviderDefault);
+        sorter.sort(&items).await.unwrap();
+        assert!(backend.requests()[1].body.get("max_tokens").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_n_best_selects_first_valid_candidate() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({ "n": 3 })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [
+                    { "message": { "content": "[1,3]" } },
+                    { "message": { "content": "not json" } },
+                    { "message": { "content": "[1,2,3]" } }
+                ]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let base_url = mock_server.uri();
+        let sorter = Vibesort::new("key", "model", base_url.as_str()).n_best(3);
+        let result = sorter.sort_with_report(&[3, 1, 2]).await.unwrap();
+        assert_eq!(result.items, vec![1, 2, 3]);
+        assert_eq!(result.report.candidate, 2);
+    }
+
+    #[tokio::test]
+    async fn test_logit_bias_and_stop() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "logit_bias": { "58": 10, "60": 10 },
+                "stop": ["]"]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "message": { "content": "[1,2,3" },
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let base_url = mock_server.uri();
+        let sorter = Vibesort::new("key", "model", base_url.as_str())
+            .logit_bias([(58, 10), (60, 10)])
+            .stop(["]"]);
+        assert_eq!(sorter.sort(&[3, 1, 2]).await.unwrap(), vec![1, 2, 3]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hedge_delay() {
+        use backend::BoxFuture;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use testing::MockBackend;
+
+        /// Answers the first request after a minute, and later ones at once
+        /// with the number of the request.
+        #[derive(Debug, Default)]
+        struct SlowFirst(AtomicUsize);
+
+        impl Backend for SlowFirst {
+            fn send(
+                &self,
+                _request: BackendRequest,
+            ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>> {
+                let call = self.0.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move {
+                    if call == 0 {
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                    }
+                    let body = serde_json::json!({
+                        "choices": [{ "message": { "content": format!("[{}]", call) } }]
+                    });
+                    Ok(BackendResponse::new(
+                        reqwest::StatusCode::OK,
+                        body.to_string(),
+                    ))
+                })
+            }
+        }
+
+        let backend = Arc::new(SlowFirst::default());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .hedge_delay(Duration::from_secs(2));
+        let start = tokio::time::Instant::now();
+        assert_eq!(sorter.sort(&[0]).await.unwrap(), vec![1]);
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+        assert_eq!(backend.0.load(Ordering::SeqCst), 2);
+
+        // Requests answered in time are not duplicated
+        let backend = Arc::new(MockBackend::new());
+        let sorter = sorter.backend(backend.clone());
+        sorter.sort(&[2, 1]).await.unwrap();
+        assert_eq!(backend.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_extra_body() {
+        use testing::MockBackend;
+
+        let backend = Arc::new(MockBackend::new());
+        let mut extra = serde_json::Map::new();
+        extra.insert("top_k".to_string(), 40.into());
+        extra.insert("temperature".to_string(), 0.3.into());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .extra_body(extra);
+        assert_eq!(sorter.sort(&[3, 1, 2]).await.unwrap(), vec![1, 2, 3]);
+
+        let body = &backend.requests()[0].body;
+        assert_eq!(body["top_k"], 40);
+        assert_eq!(body["temperature"], 0.3);
+        assert_eq!(body["model"], "model");
+    }
+
+    #[test]
+    fn test_is_local_address() {
+        assert!(is_local_address("127.0.0.1".parse().unwrap()));
+        assert!(is_local_address("10.1.2.3".parse().unwrap()));
+        assert!(is_local_address("192.168.0.10".parse().unwrap()));
+        assert!(is_local_address("::1".parse().unwrap()));
+        assert!(is_local_address("fd00::1".parse().unwrap()));
+        assert!(is_local_address("::ffff:172.16.0.1".parse().unwrap()));
+        assert!(!is_local_address("8.8.8.8".parse().unwrap()));
+        assert!(!is_local_address("2606:4700::1111".parse().unwrap()));
+    }
 }
 
 /// Error types for vibesort operations.
@@ -196,11 +953,54 @@ pub enum VibesortError {
     #[error("JSON parsing failed: {0}")]
     JsonError(#[from] serde_json::Error),
 
-    /// The LLM API returned an error status code.
+    /// The LLM API returned an error status code, or the request could not be
+    /// sent through the Workers fetch API.
+    ///
+    /// `message` includes the HTTP status code and the server's response body.
+    /// Well-known failures are reported with the more specific variants below
+    /// instead, so `provider`, the parsed error envelope of the response, is
+    /// only set for statuses that are not otherwise classified; it is `None`
+    /// when no response was received.
+    #[error("LLM API error: {message}")]
+    ApiError {
+        message: String,
+        provider: Option<Box<ProviderError>>,
+    },
+
+    /// The LLM API is rate limiting requests (`429 Too Many Requests`).
+    ///
+    /// `retry_after` is the delay requested by the provider in its
+    /// `Retry-After` (or `retry-after-ms`) header, if any.
+    #[error("Rate limited by LLM API{}", .retry_after.map(|d| format!(" (retry after {:?})", d)).unwrap_or_default())]
+    RateLimited { retry_after: Option<Duration> },
+
+    /// The LLM API rejected the API key (`401` or `403`).
     ///
-    /// This error includes the HTTP status code and the server's response body.
-    #[error("LLM API error: {0}")]
-    ApiError(String),
+    /// This error includes the provider's error message.
+    #[error("Authentication with LLM API failed: {0}")]
+    AuthFailed(String),
+
+    /// The request does not fit in the model's context window.
+    ///
+    /// `limit` is the model's maximum and `got` the size of the request, in
+    /// tokens, when the provider reports them.
+    #[error("Request exceeds the model's context length (limit: {limit:?}, got: {got:?})")]
+    ContextLengthExceeded {
+        limit: Option<u32>,
+        got: Option<u32>,
+    },
+
+    /// The configured model does not exist or is not available to the API key.
+    ///
+    /// This error includes the provider's error message.
+    #[error("Model not found: {0}")]
+    ModelNotFound(String),
+
+    /// The LLM API is temporarily overloaded (`503` or `529`).
+    ///
+    /// This error includes the provider's error message.
+    #[error("LLM API is overloaded: {0}")]
+    Overloaded(String),
 
     /// The LLM API response is missing required fields or has an invalid structure.
     ///
@@ -215,6 +1015,105 @@ pub enum VibesortError {
     /// returned by the LLM, which helps diagnose why the parsing failed.
     #[error("Failed to parse LLM response as sorted array. LLM returned: {0}")]
     ParseError(String),
+
+    /// The LLM's output is not a permutation of the input.
+    ///
+    /// Only returned when verification is enabled with [`Vibesort::verify`].
+    /// This error lists the elements that were dropped and the elements that
+    /// were not in the input.
+    #[error("LLM output is not a permutation of the input: {0}")]
+    VerificationFailed(String),
+
+    /// The configured endpoint is not local and [`Vibesort::deny_remote`] is enabled.
+    ///
+    /// This error includes the base URL and the reason it was rejected. No request
+    /// is sent when this error is returned.
+    #[error("Refusing to contact non-local endpoint: {0}")]
+    RemoteDenied(String),
+
+    /// The request did not complete in time.
+    #[error("Request to LLM timed out")]
+    Timeout,
+
+    /// An error occurred while reading or writing local files (e.g., cassettes).
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// A prompt template is invalid.
+    ///
+    /// This error describes the missing or unknown placeholder.
+    #[error("Invalid prompt template: {0}")]
+    InvalidTemplate(String),
+
+    /// A job submitted to a [`VibesortQueue`](queue::VibesortQueue) was cancelled
+    /// before it finished.
+    #[error("Sort job was cancelled")]
+    JobCancelled,
+
+    /// No request slot became free within the
+    /// [queue timeout](Vibesort::queue_timeout).
+    ///
+    /// This error includes the timeout. No request is sent when this error is
+    /// returned.
+    #[error("Timed out after {0:?} waiting for a free request slot")]
+    QueueTimeout(Duration),
+
+    /// A sort did not finish within its [time budget](Vibesort::time_budget).
+    ///
+    /// This error includes the budget. It is not retried, since no time is
+    /// left for another attempt.
+    #[error("Sort did not finish within its time budget of {0:?}")]
+    BudgetExhausted(Duration),
+
+    /// A sort key does not select a value of the elements, as checked by
+    /// [`Vibesort::sort_by_pointers`].
+    ///
+    /// This error includes the key and the reason it was rejected. No request
+    /// is sent when this error is returned.
+    #[error("Invalid sort key: {0}")]
+    InvalidKey(String),
+
+    /// An element or argument is not of the form an operation requires, such
+    /// as the identifiers passed to [`Vibesort::sort_ids_by_time`] or the ends
+    /// of a [`VibeRange`].
+    ///
+    /// This error includes the offending value and the form it lacks. No
+    /// request is sent when this error is returned.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// The constraints passed to [`Vibesort::sort_constrained`] contradict
+    /// each other.
+    ///
+    /// This error includes the cycle of elements that no order can satisfy.
+    /// No request is sent when this error is returned.
+    #[error("Constraints cannot be satisfied: {0}")]
+    InvalidConstraints(String),
+
+    /// The client is [shutting down](Vibesort::shutdown).
+    ///
+    /// Returned for sorts started after the shutdown, before any request is
+    /// sent, and for in-flight sorts cancelled when its timeout ran out.
+    #[error("Client is shutting down")]
+    ShuttingDown,
+}
+
+/// How the `max_tokens` limit sent with each request is chosen.
+///
+/// Leaving the limit to the provider risks a default that is smaller than the
+/// sorted output, which silently truncates it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaxTokens {
+    /// Sized from the length of the expected output plus a margin, capped at
+    /// the model's [maximum output](crate::ModelLimits) (the default).
+    #[default]
+    Auto,
+
+    /// The same fixed limit for every request.
+    Fixed(u32),
+
+    /// No limit is sent, leaving it to the provider's default.
+    ProviderDefault,
 }
 
 /// OpenAI API request/response structures
@@ -223,29 +1122,110 @@ struct ChatRequest<'a> {
     model: &'a str,
     messages: Vec<ChatMessage<'a>>,
     temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n: Option<u32>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    logit_bias: &'a BTreeMap<u32, i32>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    stop: &'a [String],
 }
 
 #[derive(Debug, Serialize)]
-struct ChatMessage<'a> {
-    role: &'a str,
-    content: &'a str,
+pub(crate) struct ChatMessage<'a> {
+    pub(crate) role: &'a str,
+    pub(crate) content: MessageContent<'a>,
 }
 
-#[derive(Debug, Deserialize)]
-struct ChatMessageResponse {
-    content: String,
+/// The content of a chat message: plain text, or a list of parts for
+/// multi-part messages such as text interleaved with images.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub(crate) enum MessageContent<'a> {
+    Text(&'a str),
+    Parts(Vec<ContentPart<'a>>),
 }
 
-#[derive(Debug, Deserialize)]
-struct ChatResponse {
-    choices: Vec<Choice>,
+/// One part of a multi-part message.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ContentPart<'a> {
+    Text { text: &'a str },
+    ImageUrl { image_url: ImageUrl },
+}
+
+/// The image of an image part, as a URL or a `data:` URL.
+#[derive(Debug, Serialize)]
+pub(crate) struct ImageUrl {
+    pub(crate) url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessageResponse {
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    system_fingerprint: Option<String>,
+    #[serde(default)]
+    usage: Option<labels::TokenUsage>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Choice {
     message: ChatMessageResponse,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+/// The reply to a single chat completion request.
+#[derive(Debug, Clone)]
+pub(crate) struct Completion {
+    /// The content of the selected choice (the first one unless another
+    /// candidate was selected).
+    pub(crate) content: String,
+
+    /// The content of every choice, in the order returned.
+    pub(crate) candidates: Vec<String>,
+
+    /// The index of the selected choice.
+    pub(crate) selected: usize,
+
+    /// Whether the selected choice had to be repaired to parse as a JSON
+    /// array.
+    pub(crate) repaired: bool,
+
+    /// The number of collapsed duplicates restored in the selected choice.
+    pub(crate) duplicates_restored: usize,
+
+    /// The response id, model version and `system_fingerprint` returned by
+    /// the provider.
+    pub(crate) metadata: SortMetadata,
 }
 
+/// The response headers recorded in reports unless
+/// [`Vibesort::capture_headers`] is used.
+const DEFAULT_CAPTURED_HEADERS: [&str; 8] = [
+    "x-request-id",
+    "request-id",
+    "openai-processing-ms",
+    "x-ratelimit-limit-requests",
+    "x-ratelimit-remaining-requests",
+    "x-ratelimit-remaining-tokens",
+    "x-ratelimit-reset-requests",
+    "x-ratelimit-reset-tokens",
+];
+
 /// Client for sorting arrays using LLM APIs.
 ///
 /// This struct holds the configuration needed to communicate with an LLM API
@@ -265,41 +1245,1266 @@ struct Choice {
 #[derive(Debug, Clone)]
 pub struct Vibesort<'a> {
     /// The API key for authenticating with the LLM service.
-    pub api_key: &'a str,
+    ///
+    /// The key is kept in a [`SecretString`], which is zeroized when dropped,
+    /// redacted in `Debug` output, and cannot be serialized. Use
+    /// [`ExposeSecret::expose_secret`] to read it.
+    pub api_key: SecretString,
 
     /// The model identifier to use (e.g., "gpt-3.5-turbo", "gpt-4").
     pub model: &'a str,
 
-    /// The base URL of the LLM API endpoint (e.g., "https://api.openai.com/v1").
-    pub base_url: &'a str,
-}
+    /// The base URL of the LLM API endpoint (e.g., `"https://api.openai.com/v1"`
+    /// or `"unix:///var/run/llama.sock"`).
+    pub base_url: &'a str,
+
+    /// Whether requests are restricted to localhost and private network ranges.
+    local_only: bool,
+
+    /// Fixed addresses for host names, used instead of DNS.
+    dns_overrides: BTreeMap<String, Vec<SocketAddr>>,
+
+    /// Additional root certificates trusted when connecting to the endpoint.
+    #[cfg(feature = "__tls")]
+    root_certificates: Vec<Certificate>,
+
+    /// Whether the platform's built-in root certificates are trusted.
+    #[cfg(feature = "__tls")]
+    built_in_root_certs: bool,
+
+    /// Whether invalid TLS certificates are accepted (development only).
+    #[cfg(feature = "__tls")]
+    accept_invalid_certs: bool,
+
+    /// The HTTP client shared by this client and its clones, created on the
+    /// first request together with the Unix domain socket it connects to, if
+    /// any. Changing the transport options replaces it.
+    #[cfg(not(target_arch = "wasm32"))]
+    http_client: Arc<std::sync::OnceLock<(Option<String>, reqwest::Client)>>,
+
+    /// A custom transport used instead of the built-in HTTP client.
+    backend: Option<Arc<dyn Backend>>,
+
+    /// The signer adding authentication headers to every HTTP request.
+    request_signer: Option<Arc<dyn RequestSigner>>,
+
+    /// The function selecting the API key of each tenant.
+    key_selector: Option<tenant::KeySelector>,
+
+    /// The tenant requests are made for, if any.
+    tenant: Option<String>,
+
+    /// The labels requests are accounted under, by name.
+    labels: BTreeMap<String, String>,
+
+    /// The usage of this client and its clones, by label.
+    usage: labels::UsageLedger,
+
+    /// The engine performing the sort.
+    engine: Engine,
+
+    /// The sampling seed sent with each request, if any.
+    seed: Option<u64>,
+
+    /// How the `max_tokens` limit of each request is chosen.
+    max_tokens: MaxTokens,
+
+    /// Whether the output is checked to be a permutation of the input.
+    verify: bool,
+
+    /// Whether elements are sent wrapped with ids and restored by id.
+    tag_ids: bool,
+
+    /// How elements are serialized into sort prompts.
+    json_format: JsonFormat,
+
+    /// The sampling of the check of sorted outputs, if they are not checked
+    /// in full.
+    verify_sampling: Option<Sampling>,
+
+    /// Whether duplicates collapsed by the model are restored from the input.
+    restore_duplicates: bool,
+
+    /// The relative tolerance of numbers when checking sorted outputs.
+    verify_tolerance: f64,
+
+    /// Decides whether failed attempts are retried.
+    retry_policy: Arc<dyn RetryPolicy>,
+
+    /// How retries after malformed output are varied, if at all.
+    escalation: Option<Escalation>,
+
+    /// Whether failures that the provider may have billed are retried.
+    retry_ambiguous: bool,
+
+    /// The cache consulted before sorting, if any.
+    cache: Option<SortCache>,
+
+    /// The time-to-live of the cache entries stored by this sorter, overriding
+    /// the cache's default.
+    cache_ttl: Option<Duration>,
+
+    /// The checkpoint recording the requests of chunked sorts.
+    checkpoint: Option<ChunkCheckpoint>,
+
+    /// The number of completions requested per sort request.
+    candidates: u32,
+
+    /// Token biases sent with each request, keyed by token ID.
+    logit_bias: BTreeMap<u32, i32>,
+
+    /// Stop sequences sent with each request.
+    stop: Vec<String>,
+
+    /// Provider-specific fields merged into each request body.
+    extra_body: serde_json::Map<String, serde_json::Value>,
+
+    /// The lowercase names of the response headers recorded in reports.
+    captured_headers: Vec<String>,
+
+    /// The Unicode normalization applied to strings before sorting, if any.
+    #[cfg(feature = "unicode")]
+    normalization: Option<unicode::Normalization>,
+
+    /// Whether strings are sorted by their transliterated form.
+    #[cfg(feature = "unicode")]
+    transliterate: bool,
+
+    /// The chunk size used when the input exceeds the context length, if
+    /// chunking is enabled.
+    chunk_size: Option<usize>,
+
+    /// The estimated token budget of each chunk of streamed input.
+    #[cfg(feature = "stream")]
+    chunk_tokens: usize,
+
+    /// The maximum number of self-verification passes (0 disables them).
+    reflect_passes: usize,
+
+    /// Few-shot (input, output) demonstrations as serialized JSON arrays.
+    examples: Vec<(String, String)>,
+
+    /// The direction of the sort.
+    order: Order,
+
+    /// A natural-language criterion to sort by, if any.
+    criterion: Option<String>,
+
+    /// The language of the built-in sort prompt, if not English.
+    prompt_language: Option<String>,
+
+    /// A custom prompt template replacing the built-in sort prompt.
+    prompt_template: Option<PromptTemplate>,
+
+    /// Named, versioned prompt templates per operation.
+    templates: TemplateRegistry,
+
+    /// The selector of the named template to use, if any.
+    template_selector: Option<String>,
+
+    /// The limit on concurrent requests, shared by all clones of this client.
+    limiter: Option<RequestLimiter>,
+
+    /// The adaptive limit on requests in flight, shared by every sorter
+    /// given the same scheduler.
+    scheduler: Option<AdaptiveScheduler>,
+
+    /// How long a request waits for a free slot before failing.
+    queue_timeout: Option<Duration>,
+
+    /// The price of the model, used for cost estimates.
+    pricing: Option<Pricing>,
+
+    /// The limits of the model, overriding the built-in ones.
+    model_limits: Option<ModelLimits>,
+
+    /// The timeout of each request of a chunked sort.
+    chunk_timeout: Option<Duration>,
+
+    /// The timeout of each request, set from `chunk_timeout` while sorting
+    /// in chunks.
+    request_timeout: Option<Duration>,
+
+    /// How long a request may go unanswered before a duplicate is sent.
+    hedge_delay: Option<Duration>,
+
+    /// The wall-clock budget of a whole sort.
+    time_budget: Option<Duration>,
+
+    /// When the time budget of the running sort runs out.
+    deadline: Option<Instant>,
+
+    /// Whether a sort that runs out of time returns what it sorted so far.
+    partial_on_budget: bool,
+
+    /// The in-flight sorts of this client and its clones.
+    lifecycle: Arc<shutdown::Lifecycle>,
+
+    /// Whether this clone runs a sort that was already admitted.
+    admitted: bool,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Creates a new `Vibesort` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key` - The API key for authenticating with the LLM service
+    /// * `model` - The model identifier to use (e.g., "gpt-3.5-turbo", "gpt-4")
+    /// * `base_url` - The base URL of the LLM API endpoint
+    ///
+    /// On Unix, `base_url` may also name a Unix domain socket, as in
+    /// `unix:///var/run/llama.sock`, for local inference servers that do not
+    /// listen on TCP. Requests are then sent to `/chat/completions` over the
+    /// socket; append an API path after a colon, as in
+    /// `unix:///var/run/llama.sock:/v1`, to send them to
+    /// `/v1/chat/completions` instead.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new(
+    ///     "sk-1234567890abcdef",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    /// ```
+    pub fn new(api_key: &'a str, model: &'a str, base_url: &'a str) -> Self {
+        Self {
+            api_key: SecretString::from(api_key),
+            model,
+            base_url,
+            local_only: false,
+            dns_overrides: BTreeMap::new(),
+            #[cfg(feature = "__tls")]
+            root_certificates: Vec::new(),
+            #[cfg(feature = "__tls")]
+            built_in_root_certs: true,
+            #[cfg(feature = "__tls")]
+            accept_invalid_certs: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            http_client: Arc::default(),
+            backend: None,
+            request_signer: None,
+            key_selector: None,
+            tenant: None,
+            labels: BTreeMap::new(),
+            usage: Arc::default(),
+            engine: Engine::Llm,
+            seed: None,
+            max_tokens: MaxTokens::Auto,
+            verify: false,
+            tag_ids: false,
+            json_format: JsonFormat::default(),
+            verify_sampling: None,
+            restore_duplicates: false,
+            verify_tolerance: 0.0,
+            retry_policy: Arc::new(NoRetry),
+            escalation: None,
+            retry_ambiguous: true,
+            cache: None,
+            cache_ttl: None,
+            checkpoint: None,
+            candidates: 1,
+            logit_bias: BTreeMap::new(),
+            extra_body: serde_json::Map::new(),
+            captured_headers: DEFAULT_CAPTURED_HEADERS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            stop: Vec::new(),
+            #[cfg(feature = "unicode")]
+            normalization: None,
+            #[cfg(feature = "unicode")]
+            transliterate: false,
+            chunk_size: None,
+            #[cfg(feature = "stream")]
+            chunk_tokens: 1024,
+            reflect_passes: 0,
+            examples: Vec::new(),
+            order: Order::Ascending,
+            criterion: None,
+            prompt_language: None,
+            prompt_template: None,
+            templates: TemplateRegistry::new(),
+            template_selector: None,
+            limiter: None,
+            scheduler: None,
+            queue_timeout: None,
+            pricing: None,
+            model_limits: None,
+            chunk_timeout: None,
+            request_timeout: None,
+            hedge_delay: None,
+            time_budget: None,
+            deadline: None,
+            partial_on_budget: false,
+            lifecycle: Arc::default(),
+            admitted: false,
+        }
+    }
+
+    /// Restricts this client to endpoints on localhost or private networks.
+    ///
+    /// When enabled, the host of `base_url` is resolved before every request and
+    /// the request is rejected with [`VibesortError::RemoteDenied`] unless every
+    /// resolved address is a loopback, private (RFC 1918 / unique local), or
+    /// link-local address. The HTTP client is then pinned to the checked
+    /// addresses, so a later DNS answer cannot redirect the request elsewhere.
+    ///
+    /// This guarantees that the data being sorted never leaves the machine or the
+    /// private network, even if `base_url` is misconfigured.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new(
+    ///     "unused",
+    ///     "llama3",
+    ///     "http://localhost:11434/v1",
+    /// )
+    /// .deny_remote();
+    /// ```
+    pub fn deny_remote(mut self) -> Self {
+        self.local_only = true;
+        self
+    }
+
+    /// Resolves `domain` to a fixed address instead of using DNS, as
+    /// [`reqwest::ClientBuilder::resolve`] does.
+    ///
+    /// This helps in split-horizon DNS environments where the endpoint's name
+    /// does not resolve, or does not resolve to the right address, from the
+    /// machine sending requests. A port in the URL is always used instead of
+    /// the port of `addr`; set the port to `0` to use the scheme's default.
+    /// With [`deny_remote`](Self::deny_remote), the fixed addresses are
+    /// the ones checked. Calling this again for the same domain replaces its
+    /// address. Overrides are not applied with the `worker` feature, since
+    /// the Workers fetch API resolves host names itself.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.internal/v1",
+    /// )
+    /// .resolve("api.internal", "10.0.0.5:443".parse().unwrap());
+    /// ```
+    pub fn resolve(self, domain: &str, addr: SocketAddr) -> Self {
+        self.resolve_to_addrs(domain, &[addr])
+    }
+
+    /// Resolves `domain` to several fixed addresses instead of using DNS, as
+    /// [`reqwest::ClientBuilder::resolve_to_addrs`] does. See
+    /// [`resolve`](Self::resolve).
+    pub fn resolve_to_addrs(mut self, domain: &str, addrs: &[SocketAddr]) -> Self {
+        self.dns_overrides
+            .insert(domain.to_ascii_lowercase(), addrs.to_vec());
+        self.reset_http_client();
+        self
+    }
+
+    /// Limits how many requests this client sends at once.
+    ///
+    /// The limit is shared by all clones of the client made after this call,
+    /// so a web server can clone one configured client per handler and still
+    /// send at most `max` requests to the provider. Further requests wait in a
+    /// fair FIFO queue; see [`queue_timeout`](Self::queue_timeout) to bound the
+    /// wait. A limit of zero is treated as one.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .max_concurrent_requests(8)
+    /// .queue_timeout(Duration::from_secs(30));
+    /// ```
+    pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+        self.limiter = Some(RequestLimiter::new(max));
+        self
+    }
+
+    /// Schedules the requests of this client through a shared
+    /// [`AdaptiveScheduler`], which slows down every sorter using it when the
+    /// provider starts rate limiting.
+    ///
+    /// The scheduler only limits when requests are sent; combine it with a
+    /// [retry policy](Self::retry_policy) that retries
+    /// [`VibesortError::RateLimited`] so rate-limited sorts still succeed.
+    /// Requests wait for the scheduler after any
+    /// [`max_concurrent_requests`](Self::max_concurrent_requests) slot, and
+    /// [`queue_timeout`](Self::queue_timeout) bounds each wait.
+    pub fn scheduler(mut self, scheduler: AdaptiveScheduler) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// Sets the price of the model, used by [`estimate`](Self::estimate) to
+    /// estimate the cost of a sort.
+    pub fn pricing(mut self, pricing: Pricing) -> Self {
+        self.pricing = Some(pricing);
+        self
+    }
+
+    /// Sets the context window and maximum output of the model, overriding
+    /// the [built-in limits](ModelLimits::for_model).
+    ///
+    /// The limits cap the [automatic](MaxTokens::Auto) `max_tokens` of each
+    /// request, and size the chunks of the fallback on
+    /// [`VibesortError::ContextLengthExceeded`] when no
+    /// [`chunk_size`](Self::chunk_size) is set.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{ModelLimits, Vibesort};
+    ///
+    /// let sorter = Vibesort::new("your-api-key", "my-fine-tune", "https://api.openai.com/v1")
+    ///     .model_limits(ModelLimits::new(32_768, 8_192));
+    /// ```
+    pub fn model_limits(mut self, limits: ModelLimits) -> Self {
+        self.model_limits = Some(limits);
+        self
+    }
+
+    /// Sets how long a request waits for a free slot under
+    /// [`max_concurrent_requests`](Self::max_concurrent_requests) before
+    /// failing with [`VibesortError::QueueTimeout`].
+    ///
+    /// By default requests wait indefinitely.
+    pub fn queue_timeout(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a timeout for every request of a sort in chunks (see
+    /// [`chunk_size`](Self::chunk_size)): each chunk and each merge request.
+    ///
+    /// A request that takes longer fails with [`VibesortError::Timeout`],
+    /// which the [retry policy](Self::retry_policy) can retry without
+    /// restarting the whole sort.
+    pub fn chunk_timeout(mut self, timeout: Duration) -> Self {
+        self.chunk_timeout = Some(timeout);
+        self
+    }
+
+    /// Sends a duplicate of every request that is not answered within
+    /// `delay`, and uses whichever reply arrives first.
+    ///
+    /// Hedging cuts the latency of the occasional slow request at the cost
+    /// of paying for the duplicates. The request still pending when the
+    /// other one succeeds is cancelled. If one fails, the other is awaited;
+    /// a request that fails before `delay` is not hedged but left to the
+    /// [retry policy](Self::retry_policy). A hedged pair takes a single slot
+    /// of the [request limit](Self::max_concurrent_requests) and counts
+    /// against the same timeouts.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new("your-api-key", "gpt-3.5-turbo", "https://api.openai.com/v1")
+    ///     .hedge_delay(Duration::from_secs(5));
+    /// ```
+    pub fn hedge_delay(mut self, delay: Duration) -> Self {
+        self.hedge_delay = Some(delay);
+        self
+    }
+
+    /// Sets a wall-clock budget for a whole sort, including every chunk,
+    /// merge, retry, and reflection pass.
+    ///
+    /// A sort that runs out of time fails with
+    /// [`VibesortError::BudgetExhausted`], unless
+    /// [`partial_on_budget`](Self::partial_on_budget) is enabled. Waits
+    /// between retries, including those a provider asks for with
+    /// `Retry-After`, are cut short at the end of the budget.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .chunk_size(200)
+    /// .chunk_timeout(Duration::from_secs(30))
+    /// .time_budget(Duration::from_secs(300))
+    /// .partial_on_budget(true);
+    /// ```
+    pub fn time_budget(mut self, budget: Duration) -> Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    /// Controls whether a sort that exhausts its
+    /// [time budget](Self::time_budget) returns the items sorted so far
+    /// instead of failing (disabled by default).
+    ///
+    /// The returned items start with the elements that were sorted, followed
+    /// by the rest in input order; [`SortReport::sorted_prefix_len`] tells how
+    /// many are sorted.
+    pub fn partial_on_budget(mut self, enabled: bool) -> Self {
+        self.partial_on_budget = enabled;
+        self
+    }
+
+    /// Routes requests through a custom [`Backend`] instead of HTTP.
+    ///
+    /// The backend receives the prepared chat completion request, so prompting,
+    /// response parsing, and error handling behave exactly as with the built-in
+    /// transport. TLS options and [`deny_remote`](Self::deny_remote) only apply
+    /// to the built-in HTTP transport.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vibesort_rs::Vibesort;
+    /// use vibesort_rs::testing::MockBackend;
+    ///
+    /// let sorter = Vibesort::new("unused", "mock", "http://mock")
+    ///     .backend(MockBackend::new().respond_with("[1,2,3]"));
+    /// ```
+    pub fn backend(mut self, backend: impl Backend + 'static) -> Self {
+        self.backend = Some(Arc::new(backend));
+        self
+    }
+
+    /// Signs every HTTP request with the given signer, for gateways that
+    /// require signed requests.
+    ///
+    /// The signer sees the exact body sent; see
+    /// [`HmacSigner`](backend::HmacSigner) for the built-in HMAC-SHA256
+    /// scheme. Requests sent through a custom [`backend`](Self::backend) are
+    /// not signed.
+    pub fn request_signer(mut self, signer: impl RequestSigner + 'static) -> Self {
+        self.request_signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Selects the engine that performs the sort.
+    ///
+    /// [`Engine::Local`] sorts with a deterministic comparator and never sends a
+    /// request, which makes it suitable for CI. It answers the operations
+    /// that ask for the sorted array itself, listed in the [`engine`] module.
+    /// The default is [`Engine::Llm`]. See [`Engine::from_env`] to flip the
+    /// engine with an environment variable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vibesort_rs::Vibesort;
+    /// use vibesort_rs::engine::Engine;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new("unused", "gpt-3.5-turbo", "https://api.openai.com/v1")
+    ///     .engine(Engine::local());
+    ///
+    /// assert_eq!(sorter.sort(&[3, 1, 2]).await?, vec![1, 2, 3]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn engine(mut self, engine: Engine) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Sets the `seed` sent with each request.
+    ///
+    /// Providers that support it (such as OpenAI) make a best effort to return
+    /// the same output for the same seed and request. The seed and the
+    /// provider's `system_fingerprint` are recorded in the [`SortReport`]
+    /// returned by [`sort_with_report`](Self::sort_with_report); a changed
+    /// fingerprint means the backend configuration changed and results may
+    /// differ despite the seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets how the `max_tokens` limit of each request is chosen (defaults to
+    /// [`MaxTokens::Auto`]).
+    ///
+    /// The automatic limit is estimated from the size of the serialized input
+    /// with a generous margin, so that large sorted outputs are not cut off.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{MaxTokens, Vibesort};
+    ///
+    /// let sorter = Vibesort::new("your-api-key", "gpt-3.5-turbo", "https://api.openai.com/v1")
+    ///     .max_tokens(MaxTokens::Fixed(4096));
+    /// ```
+    pub fn max_tokens(mut self, max_tokens: MaxTokens) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Enables checking that the sorted output is a permutation of the input.
+    ///
+    /// When enabled, a result in which the LLM dropped, duplicated, or invented
+    /// elements is rejected with [`VibesortError::VerificationFailed`] instead
+    /// of being returned. Elements are compared by their JSON serialization.
+    /// See the [`verify`] module for details.
+    pub fn verify(mut self, enabled: bool) -> Self {
+        self.verify = enabled;
+        self.verify_sampling = None;
+        self
+    }
+
+    /// Sends every element wrapped as `{"id": ..., "value": ...}` and
+    /// restores the sorted elements from the input by id.
+    ///
+    /// This protects against models that slightly rewrite values, such as
+    /// `1.0` becoming `1` or strings losing their trailing whitespace: the
+    /// returned elements are deserialized from the JSON of the original
+    /// ones. The ids of the reply are always checked to be a permutation of
+    /// the input. The prompt and reply are somewhat longer.
+    pub fn tag_ids(mut self, enabled: bool) -> Self {
+        self.tag_ids = enabled;
+        self
+    }
+
+    /// Sets how elements are serialized into sort prompts.
+    ///
+    /// Verification compares elements with sorted keys and at the float
+    /// precision of the format, so a model echoing the rounded floats it was
+    /// shown passes. The returned elements are then parsed from the reply
+    /// and hold the rounded floats; combine with [`tag_ids`](Self::tag_ids)
+    /// to get the original elements back.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{JsonFormat, Vibesort};
+    ///
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .json_format(JsonFormat::new().float_precision(6).ascii_only(true));
+    /// ```
+    pub fn json_format(mut self, format: JsonFormat) -> Self {
+        self.json_format = format;
+        self
+    }
+
+    /// Enables a sampled check that the sorted output is a permutation of the
+    /// input, for outputs too large to check in full.
+    ///
+    /// Only the boundaries of the output and a random sample of the rest are
+    /// checked, as described in [`verify::check_sampled`]; a larger sampling
+    /// catches more problems at a higher cost. Positions are drawn with the
+    /// configured [`seed`](Self::seed) if there is one. Calling
+    /// [`verify`](Self::verify) afterwards switches back to the full check.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    /// use vibesort_rs::verify::Sampling;
+    ///
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .verify_sampled(Sampling::with_confidence(0.99, 0.01));
+    /// ```
+    pub fn verify_sampled(mut self, sampling: Sampling) -> Self {
+        self.verify = true;
+        self.verify_sampling = Some(sampling);
+        self
+    }
+
+    /// Restores duplicates that the model collapsed instead of rejecting the
+    /// output, such as `[1, 2, 3]` returned for `[1, 1, 2, 3]`.
+    ///
+    /// Every element of such an output is repeated as often as it occurs in
+    /// the input, as described in [`verify::restore_duplicates`], and
+    /// [`SortReport::duplicates_restored`] records how many were added.
+    /// Elements are matched to the input within the
+    /// [tolerance](Self::verify_tolerance) of verification, and restored as
+    /// they were in the input. Outputs that dropped or invented distinct
+    /// elements are still rejected when [verifying](Self::verify). Defaults
+    /// to `false`, so that verification fails on any difference.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .verify(true)
+    /// .restore_duplicates(true);
+    /// ```
+    pub fn restore_duplicates(mut self, enabled: bool) -> Self {
+        self.restore_duplicates = enabled;
+        self
+    }
+
+    /// Accepts numbers that differ by at most `tolerance`, relative to the
+    /// larger of them, when [verifying](Self::verify) the output.
+    ///
+    /// Models sometimes echo a float with a different last digit, such as
+    /// `0.1` as `0.10000000000000001`, which fails the exact comparison. Only
+    /// floats get the tolerance; integers such as ids must match exactly.
+    /// See [`verify::check_permutation_within`] for details. Elements that
+    /// only matched within the tolerance are returned as they were in the
+    /// input, not as the model wrote them. Defaults to `0.0`, comparing
+    /// exactly.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tolerance` is negative or not finite.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .verify(true)
+    /// .verify_tolerance(1e-9);
+    /// ```
+    pub fn verify_tolerance(mut self, tolerance: f64) -> Self {
+        assert!(
+            tolerance.is_finite() && tolerance >= 0.0,
+            "verify tolerance must be finite and non-negative, got {}",
+            tolerance
+        );
+        self.verify_tolerance = tolerance;
+        self
+    }
+
+    /// Enables "reflect" mode with up to `max_passes` self-verification rounds.
+    ///
+    /// After the initial sort, the model is shown the original array together
+    /// with its own output and asked to either confirm the ordering or return a
+    /// corrected one. Review rounds continue until the model confirms or
+    /// `max_passes` rounds have run; the last ordering is returned either way.
+    /// Corrections that are not a permutation of the input are rejected, and
+    /// a review that fails leaves the last ordering in place.
+    /// [`SortReport::reflection_passes`] and [`SortReport::confirmed`] record
+    /// the outcome. Each pass costs one additional request. Defaults to `0`
+    /// (disabled).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .reflect(2);
+    /// ```
+    pub fn reflect(mut self, max_passes: usize) -> Self {
+        self.reflect_passes = max_passes;
+        self
+    }
+
+    /// Adds a few-shot example of the expected ordering.
+    ///
+    /// Examples are sent before the array to sort as demonstration turns: the
+    /// example input as a user message, followed by the example output as the
+    /// assistant's reply. A handful of examples measurably improves unusual
+    /// orderings that are hard to describe in words. Can be called multiple
+    /// times; examples are sent in the order they were added.
+    ///
+    /// Examples are used by [`sort`](Self::sort) and
+    /// [`sort_with_report`](Self::sort_with_report).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::JsonError`] if the example cannot be
+    /// serialized to JSON.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .example(
+    ///     &["The door creaked open.", "Lunch was nice."],
+    ///     &["Lunch was nice.", "The door creaked open."],
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn example<T: Serialize>(
+        mut self,
+        input: &[T],
+        output: &[T],
+    ) -> Result<Self, VibesortError> {
+        let example = (
+            serde_json::to_string(input)?,
+            serde_json::to_string(output)?,
+        );
+        self.examples.push(example);
+        Ok(self)
+    }
+
+    /// Sets the policy deciding whether failed attempts are retried.
+    ///
+    /// An attempt covers sending the request, parsing the response, and
+    /// verifying it when [`verify`](Self::verify) is enabled. By default
+    /// nothing is retried; see [`retry`] for the built-in policies.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    /// use vibesort_rs::retry::DecorrelatedJitter;
+    ///
+    /// let sorter = Vibesort::new("your-api-key", "gpt-3.5-turbo", "https://api.openai.com/v1")
+    ///     .retry_policy(DecorrelatedJitter::new(3));
+    /// ```
+    pub fn retry_policy(mut self, policy: impl RetryPolicy + 'static) -> Self {
+        self.retry_policy = Arc::new(policy);
+        self
+    }
+
+    /// Controls whether failures after which the provider may have accepted
+    /// the request are retried (the default).
+    ///
+    /// These are timeouts once the request was sent, as described in
+    /// [`retry::is_ambiguous`]. Disable this for providers that bill every
+    /// accepted request, so a slow reply is not paid for twice; connection
+    /// failures are still retried per the [retry policy](Self::retry_policy).
+    pub fn retry_ambiguous(mut self, enabled: bool) -> Self {
+        self.retry_ambiguous = enabled;
+        self
+    }
+
+    /// Varies retried requests after malformed output.
+    ///
+    /// By default a retry repeats the identical request. See [`Escalation`]
+    /// for how the schedule is applied.
+    pub fn escalation(mut self, escalation: Escalation) -> Self {
+        self.escalation = Some(escalation);
+        self
+    }
+
+    /// Answers repeated sorts from a cache.
+    ///
+    /// Results of [`sort`](Self::sort) and
+    /// [`sort_with_report`](Self::sort_with_report) are stored in the cache and
+    /// reused for the same items, model, criterion, order, prompt template,
+    /// and few-shot examples. See [`cache`] for expiry and invalidation.
+    pub fn cache(mut self, cache: SortCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Sets the time-to-live of the cache entries stored by this sorter,
+    /// overriding the default of the [`cache`](Self::cache).
+    pub fn cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Records the requests of chunked sorts in a checkpoint file, so that a
+    /// sort interrupted by a crash can be resumed.
+    ///
+    /// Applies to [chunked](Self::chunk_size) sorts only. See
+    /// [`ChunkCheckpoint`] for how sorts are resumed.
+    pub fn checkpoint(mut self, checkpoint: ChunkCheckpoint) -> Self {
+        self.checkpoint = Some(checkpoint);
+        self
+    }
+
+    /// Requests `n` completions per sort request and uses the first valid one.
+    ///
+    /// Each candidate is parsed and checked to be a permutation of the input,
+    /// whether or not [`verify`](Self::verify) is enabled, and the first one
+    /// that passes is returned. This is cheaper than sequential retries for
+    /// flaky models. Candidates are sampled at a temperature of 0.7 so that
+    /// they differ. The index of the selected candidate is recorded in
+    /// [`SortReport::candidate`]. Values below 1 are treated as 1 (the
+    /// default).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new("your-api-key", "gpt-3.5-turbo", "https://api.openai.com/v1")
+    ///     .n_best(3);
+    /// ```
+    pub fn n_best(mut self, n: u32) -> Self {
+        self.candidates = n.max(1);
+        self
+    }
+
+    /// Sets the `logit_bias` sent with each request.
+    ///
+    /// Maps token IDs of the model's tokenizer to a bias between -100 and 100.
+    /// Biasing toward `[`, `]`, `,`, and digits makes well-formed arrays more
+    /// likely. Token IDs are model specific; look them up with the provider's
+    /// tokenizer.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// // `[` and `]` in the cl100k_base tokenizer
+    /// let sorter = Vibesort::new("your-api-key", "gpt-4", "https://api.openai.com/v1")
+    ///     .logit_bias([(58, 10), (60, 10)]);
+    /// ```
+    pub fn logit_bias(mut self, bias: impl IntoIterator<Item = (u32, i32)>) -> Self {
+        self.logit_bias = bias.into_iter().collect();
+        self
+    }
+
+    /// Sets the stop sequences sent with each request.
+    ///
+    /// Stopping after the closing bracket saves the tokens of any commentary
+    /// the model would add after the array. Providers cut the matched stop
+    /// sequence from the reply; if the reply is only valid JSON with it, the
+    /// stop sequence is put back before parsing.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new("your-api-key", "gpt-4", "https://api.openai.com/v1")
+    ///     .stop(["]"]);
+    /// ```
+    pub fn stop<S: Into<String>>(mut self, sequences: impl IntoIterator<Item = S>) -> Self {
+        self.stop = sequences.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets fields merged into the body of each chat request, for
+    /// provider-specific parameters without dedicated support such as
+    /// `reasoning_effort` or `top_k`.
+    ///
+    /// The fields are added at the top level of the request and take
+    /// precedence over the fields vibesort sets, including `temperature` and
+    /// `max_tokens`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let mut extra = serde_json::Map::new();
+    /// extra.insert("reasoning_effort".to_string(), "low".into());
+    /// let sorter = Vibesort::new("your-api-key", "o3-mini", "https://api.openai.com/v1")
+    ///     .extra_body(extra);
+    /// ```
+    pub fn extra_body(mut self, fields: serde_json::Map<String, serde_json::Value>) -> Self {
+        self.extra_body = fields;
+        self
+    }
+
+    /// Sets the response headers recorded in
+    /// [`SortMetadata::headers`], replacing the defaults.
+    ///
+    /// By default, the request id and processing time headers and the rate
+    /// limit counters of OpenAI-compatible providers are recorded, so a
+    /// support ticket can quote [`SortMetadata::request_id`]. Names are case
+    /// insensitive.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new("your-api-key", "gpt-4", "https://api.openai.com/v1")
+    ///     .capture_headers(["x-request-id", "cf-ray"]);
+    /// ```
+    pub fn capture_headers<S: AsRef<str>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.captured_headers = names
+            .into_iter()
+            .map(|name| name.as_ref().to_ascii_lowercase())
+            .collect();
+        self
+    }
+
+    /// Normalizes every string in the input before sorting.
+    ///
+    /// The normalization is applied locally to all strings in the serialized
+    /// items, and the normalized items are returned. Requires the `unicode`
+    /// feature.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    /// use vibesort_rs::unicode::Normalization;
+    ///
+    /// let sorter = Vibesort::new("your-api-key", "gpt-3.5-turbo", "https://api.openai.com/v1")
+    ///     .normalize(Normalization::Nfc);
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn normalize(mut self, form: unicode::Normalization) -> Self {
+        self.normalization = Some(form);
+        self
+    }
+
+    /// Sorts strings by their ASCII transliteration (`é` ≈ `e`).
+    ///
+    /// Only the transliterated forms are sent to the model; the original (or
+    /// [normalized](Self::normalize)) items are returned in the sorted order.
+    /// The model's output is always checked to be a permutation of the
+    /// transliterated input. Requires the `unicode` feature.
+    #[cfg(feature = "unicode")]
+    pub fn transliterate(mut self, enabled: bool) -> Self {
+        self.transliterate = enabled;
+        self
+    }
+
+    /// Enables sorting in chunks when the input is too large for the model.
+    ///
+    /// If a sort fails with [`VibesortError::ContextLengthExceeded`], it is
+    /// re-run by sorting chunks of at most `size` elements and merging the
+    /// sorted chunks, again in requests of at most `size` elements. This takes
+    /// many more requests than a single sort, and every chunk is checked to be
+    /// a permutation of its input. The fallback is recorded in
+    /// [`SortReport::chunked_fallback`]. Sizes below 2 are treated as 2.
+    ///
+    /// Without a chunk size, the fallback still applies to models whose
+    /// [limits](Self::model_limits) are known, with chunks sized to fit them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new("your-api-key", "gpt-3.5-turbo", "https://api.openai.com/v1")
+    ///     .chunk_size(200);
+    /// ```
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = Some(size.max(2));
+        self
+    }
+
+    /// Sets the estimated token budget of each chunk of
+    /// [streamed input](Self::sort_stream_input) (defaults to 1024).
+    ///
+    /// Requires the `stream` feature.
+    #[cfg(feature = "stream")]
+    pub fn chunk_tokens(mut self, tokens: usize) -> Self {
+        self.chunk_tokens = tokens;
+        self
+    }
+
+    /// Sets the direction of the sort (defaults to [`Order::Ascending`]).
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Sorts by a natural-language criterion instead of the natural order.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{Order, Vibesort};
+    ///
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .criterion("by how ominous the sentence sounds")
+    /// .order(Order::Descending);
+    /// ```
+    pub fn criterion(mut self, criterion: impl Into<String>) -> Self {
+        self.criterion = Some(criterion.into());
+        self
+    }
+
+    /// Issues the built-in sort prompt in another language.
+    ///
+    /// Smaller regional models often follow instructions far more reliably in
+    /// their native language. The language is an ISO 639-1 code such as
+    /// `"ja"` (region subtags like `"ja-JP"` are accepted); see
+    /// [`prompt::PROMPT_LANGUAGES`] for the built-in translations. Languages
+    /// without a translation fall back to English. The criterion is inserted
+    /// as given, so write it in the same language.
+    ///
+    /// Custom templates are used as written and are not affected.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new("your-api-key", "qwen2.5", "http://localhost:11434/v1")
+    ///     .prompt_language("ja")
+    ///     .criterion("辛さ");
+    /// ```
+    pub fn prompt_language(mut self, language: impl Into<String>) -> Self {
+        self.prompt_language = Some(language.into());
+        self
+    }
+
+    /// Replaces the built-in sort prompt with a custom template.
+    ///
+    /// The template is rendered with the array, order, and criterion; see
+    /// [`PromptTemplate`] for the available placeholders. It is used by
+    /// [`sort`](Self::sort) and [`sort_with_report`](Self::sort_with_report).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vibesort_rs::{PromptTemplate, Vibesort};
+    ///
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let template = PromptTemplate::new(
+    ///     "Sort the user's items in {order} order by {criterion}. Reply with a JSON array only.",
+    ///     "{array}",
+    /// )?;
+    /// let sorter = Vibesort::new("your-api-key", "gpt-3.5-turbo", "https://api.openai.com/v1")
+    ///     .prompt_template(template);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prompt_template(mut self, template: PromptTemplate) -> Self {
+        self.prompt_template = Some(template);
+        self
+    }
+
+    /// Sets the registry of named prompt templates.
+    ///
+    /// Templates from the registry are only used once selected with
+    /// [`with_template`](Self::with_template).
+    pub fn templates(mut self, registry: TemplateRegistry) -> Self {
+        self.templates = registry;
+        self
+    }
 
-impl<'a> Vibesort<'a> {
-    /// Creates a new `Vibesort` instance.
+    /// Returns a copy of this client that uses the named template.
     ///
-    /// # Arguments
+    /// The selector is `"name"` for the latest version or `"name@version"` for
+    /// a specific one. For each [`Operation`], the selected template replaces
+    /// the built-in prompt (and any [`prompt_template`](Self::prompt_template));
+    /// operations without a template under that name keep their default prompt.
     ///
-    /// * `api_key` - The API key for authenticating with the LLM service
-    /// * `model` - The model identifier to use (e.g., "gpt-3.5-turbo", "gpt-4")
-    /// * `base_url` - The base URL of the LLM API endpoint
+    /// # Errors
+    ///
+    /// Sorting with the returned client fails with
+    /// [`VibesortError::InvalidTemplate`] if the selector matches no template
+    /// for any operation.
     ///
     /// # Example
     ///
     /// ```no_run
     /// use vibesort_rs::Vibesort;
     ///
+    /// # async fn example(sorter: Vibesort<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorted = sorter.with_template("experimental@2").sort(&[3, 1, 2]).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_template(&self, selector: impl Into<String>) -> Self {
+        let mut sorter = self.clone();
+        sorter.template_selector = Some(selector.into());
+        sorter
+    }
+
+    /// Adds a custom root certificate to trust when connecting to the endpoint.
+    ///
+    /// Use this when the LLM endpoint is served behind an internal certificate
+    /// authority. Can be called multiple times to trust several certificates.
+    ///
+    /// Requires the `rustls` or `native-tls` feature.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{Certificate, Vibesort};
+    ///
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let pem = std::fs::read("internal-ca.pem")?;
     /// let sorter = Vibesort::new(
-    ///     "sk-1234567890abcdef",
+    ///     "your-api-key",
     ///     "gpt-3.5-turbo",
-    ///     "https://api.openai.com/v1",
-    /// );
+    ///     "https://llm.internal.example/v1",
+    /// )
+    /// .add_root_certificate(Certificate::from_pem(&pem)?);
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn new(api_key: &'a str, model: &'a str, base_url: &'a str) -> Self {
-        Self {
-            api_key,
-            model,
-            base_url,
-        }
+    #[cfg(feature = "__tls")]
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self.reset_http_client();
+        self
+    }
+
+    /// Controls whether the platform's built-in root certificates are trusted.
+    ///
+    /// Defaults to `true`. Disabling the built-in roots while adding a custom
+    /// certificate with [`add_root_certificate`](Self::add_root_certificate)
+    /// pins the connection to that certificate authority only.
+    ///
+    /// Requires the `rustls` or `native-tls` feature.
+    #[cfg(feature = "__tls")]
+    pub fn tls_built_in_root_certs(mut self, enabled: bool) -> Self {
+        self.built_in_root_certs = enabled;
+        self.reset_http_client();
+        self
+    }
+
+    /// Accepts invalid TLS certificates, including self-signed or expired ones.
+    ///
+    /// # Warning
+    ///
+    /// This disables certificate validation entirely and makes the connection
+    /// vulnerable to man-in-the-middle attacks. Only use it against development
+    /// endpoints.
+    ///
+    /// Requires the `rustls` or `native-tls` feature.
+    #[cfg(feature = "__tls")]
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self.reset_http_client();
+        self
     }
 
     /// Sorts an array using an LLM.
@@ -325,8 +2530,13 @@ impl<'a> Vibesort<'a> {
     /// This method can return various errors:
     /// - [`VibesortError::HttpError`] - Network or HTTP request errors
     /// - [`VibesortError::ApiError`] - API returned an error status code
+    /// - [`VibesortError::RateLimited`], [`VibesortError::AuthFailed`],
+    ///   [`VibesortError::ContextLengthExceeded`], [`VibesortError::ModelNotFound`],
+    ///   [`VibesortError::Overloaded`] - API reported a well-known failure
     /// - [`VibesortError::InvalidResponse`] - Response format is invalid
     /// - [`VibesortError::ParseError`] - LLM response cannot be parsed as a JSON array
+    /// - [`VibesortError::VerificationFailed`] - The result is not a permutation of the
+    ///   input (only when [`verify`](Self::verify) is enabled)
     /// - [`VibesortError::JsonError`] - JSON serialization/deserialization errors
     ///
     /// # Examples
@@ -385,7 +2595,8 @@ impl<'a> Vibesort<'a> {
     ///
     /// match sorter.sort(&vec![1, 2, 3]).await {
     ///     Ok(sorted) => println!("Sorted: {:?}", sorted),
-    ///     Err(VibesortError::ApiError(msg)) => eprintln!("API error: {}", msg),
+    ///     Err(VibesortError::RateLimited { retry_after }) => eprintln!("Slow down: {:?}", retry_after),
+    ///     Err(VibesortError::ApiError { message, .. }) => eprintln!("API error: {}", message),
     ///     Err(e) => eprintln!("Other error: {}", e),
     /// }
     /// # Ok(())
@@ -395,88 +2606,155 @@ impl<'a> Vibesort<'a> {
     where
         T: Display + Serialize + DeserializeOwned,
     {
-        // Serialize the input array to JSON
-        let json_array = serde_json::to_string(items)?;
-
-        // Build the API URL
-        let url = format!("{}/chat/completions", self.base_url);
-
-        // Create the HTTP client
-        let client = reqwest::Client::new();
+        Ok(self.sort_with_report(items).await?.items)
+    }
 
-        // Prepare the request with system prompt and user prompt
-        let system_prompt = "You are a helpful assistant that sorts arrays. Sort the following JSON array with ascending order and return ONLY the sorted JSON array, nothing else.";
-        let request = ChatRequest {
-            model: self.model,
-            messages: vec![
-                ChatMessage {
-                    role: "system",
-                    content: system_prompt,
-                },
-                ChatMessage {
-                    role: "user",
-                    content: &json_array,
-                },
-            ],
-            temperature: 0.0, // Use 0.0 for deterministic sorting
+    /// Sorts an array using an LLM and reports details about the request.
+    ///
+    /// This behaves exactly like [`sort`](Self::sort), but also returns a
+    /// [`SortReport`] describing how the sort was performed, such as the seed
+    /// that was sent and the `system_fingerprint` returned by the provider.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .seed(42);
+    ///
+    /// let result = sorter.sort_with_report(&[3, 1, 2]).await?;
+    /// println!("{:?} (fingerprint: {:?})", result.items, result.report.system_fingerprint);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_with_report<T>(&self, items: &[T]) -> Result<SortResult<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let Some(cache) = &self.cache else {
+            return self.sort_uncached(items).await;
         };
 
-        // Send the request
-        let response = client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
+        let values = items
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        let key = cache.key(self, &values)?;
+        if let Some(sorted) = cache
+            .get(&key)
+            .and_then(|sorted| cache.restore(values, &sorted))
+        {
+            let sorted = sorted
+                .into_iter()
+                .map(serde_json::from_value)
+                .collect::<Result<_, _>>()?;
+            let report = SortReport {
+                seed: self.seed,
+                cache_hits: 1,
+                ..SortReport::default()
+            };
+            return Ok(SortResult::new(sorted, report));
+        }
 
-        // Check if the request was successful
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(VibesortError::ApiError(format!(
-                "API returned status {}\nServer response: {}",
-                status, error_text
-            )));
+        let mut result = self.sort_uncached(items).await?;
+        let sorted = result
+            .items
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        cache.insert(key, cache.project(&sorted), self.cache_ttl);
+        result.report.cache_misses = 1;
+        Ok(result)
+    }
+
+    /// Sorts the items as [`sort_with_report`](Self::sort_with_report) does,
+    /// without consulting the cache.
+    async fn sort_uncached<T>(&self, items: &[T]) -> Result<SortResult<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        #[cfg(feature = "unicode")]
+        if self.uses_unicode_options() {
+            return self.sort_with_unicode(items).await;
         }
 
-        // Parse the response
-        let chat_response: ChatResponse = response.json().await?;
+        self.sort_with_report_inner(items, self.verify).await
+    }
+
+    /// Sorts the items as [`sort_with_report`](Self::sort_with_report) does,
+    /// without applying the Unicode options.
+    pub(crate) async fn sort_with_report_inner<T>(
+        &self,
+        items: &[T],
+        verify: bool,
+    ) -> Result<SortResult<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        if let Some((sorter, guard)) = self.admit()? {
+            return guard
+                .run(Box::pin(sorter.sort_with_report_inner(items, verify)))
+                .await;
+        }
+        if let Some(sorter) = self.start_budget() {
+            return Box::pin(sorter.sort_with_report_inner(items, verify)).await;
+        }
 
-        // Extract the sorted array from the LLM's response
-        let mut sorted_json = chat_response
-            .choices
-            .first()
-            .ok_or(VibesortError::InvalidResponse)?
-            .message
-            .content
-            .trim()
-            .to_string();
-
-        // Strip markdown code blocks if present (e.g., ```json ... ```)
-        if sorted_json.starts_with("```") {
-            // Remove the opening ``` and optional language identifier
-            if let Some(start_idx) = sorted_json.find('\n') {
-                sorted_json = sorted_json[start_idx + 1..].to_string();
-            } else {
-                // No newline, just remove the ```
-                sorted_json = sorted_json[3..].to_string();
+        let (completion, sorted) = match self.sort_pass(items, verify).await {
+            Ok(pass) => pass,
+            // Fall back to sorting in chunks if the input does not fit
+            Err(VibesortError::ContextLengthExceeded { .. })
+                if self.chunk_size.is_some() || self.limits().is_some() =>
+            {
+                let progress = self.sort_chunks(items).await?;
+                let mut report = SortReport {
+                    seed: self.seed,
+                    chunked_fallback: true,
+                    json_repaired: progress.repaired,
+                    ..SortReport::default()
+                };
+                let sorted = match progress.error {
+                    None => progress.sorted,
+                    Some(VibesortError::BudgetExhausted(_)) if self.partial_on_budget => {
+                        report.sorted_prefix_len = Some(progress.sorted.len());
+                        let mut sorted = progress.sorted;
+                        sorted.extend(progress.remainder);
+                        sorted
+                    }
+                    Some(e) => return Err(e),
+                };
+                return Ok(SortResult::new(sorted, report));
             }
-            // Remove the closing ```
-            if sorted_json.ends_with("```") {
-                sorted_json = sorted_json[..sorted_json.len() - 3].trim().to_string();
+            Err(VibesortError::BudgetExhausted(_)) if self.partial_on_budget => {
+                let report = SortReport {
+                    seed: self.seed,
+                    sorted_prefix_len: Some(0),
+                    ..SortReport::default()
+                };
+                let unsorted = items
+                    .iter()
+                    .map(|item| Ok(serde_json::from_value(serde_json::to_value(item)?)?))
+                    .collect::<Result<_, VibesortError>>()?;
+                return Ok(SortResult::new(unsorted, report));
             }
-        }
+            Err(e) => return Err(e),
+        };
 
-        // Parse the JSON array back to Vec<T>
-        let sorted: Vec<T> = serde_json::from_str(&sorted_json).map_err(|e| {
-            VibesortError::ParseError(format!(
-                "Failed to parse as JSON array: {}\nLLM returned: {}",
-                e, sorted_json
-            ))
-        })?;
+        // Let the model double-check its own output in reflect mode
+        let mut report = self.report(&completion);
+        let sorted = self.run_reflection(items, sorted, &mut report).await?;
 
-        Ok(sorted)
+        Ok(SortResult::new(sorted, report))
     }
 
     /// Sorts an array of strings using an LLM.
@@ -525,4 +2803,797 @@ impl<'a> Vibesort<'a> {
         let string_vec: Vec<String> = items.iter().map(|s| s.to_string()).collect();
         self.sort(&string_vec).await
     }
+
+    /// Sorts the items with a single request, retried per the retry policy.
+    ///
+    /// The output is checked to be a permutation of the input if `verify` is
+    /// set.
+    pub(crate) async fn sort_pass<T>(
+        &self,
+        items: &[T],
+        verify: bool,
+    ) -> Result<(Completion, Vec<T>), VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        // Serialize the input array to JSON, and ask the LLM to sort it
+        let (output_len, system_prompt, user_content) = if self.tag_ids {
+            self.tagged_sort_prompt(items)?
+        } else {
+            let json_array = self.json_format.to_string(items)?;
+            let len = json_array.len();
+            let (system_prompt, user_content) = self.sort_prompt(json_array)?;
+            (len, system_prompt, user_content)
+        };
+        let max_tokens = self.max_tokens_for(output_len);
+        let task = SortTask {
+            items: items
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<_, _>>()?,
+            order: self.order,
+        };
+
+        // With several candidates, the first permutation of the input wins
+        let verify = verify || self.candidates > 1;
+
+        let (system_prompt, user_content, task) = (&system_prompt, &user_content, &task);
+        self.retrying(|escalation| async move {
+            let mut completion = self
+                .chat_with_examples(
+                    system_prompt,
+                    user_content,
+                    task.clone(),
+                    max_tokens,
+                    escalation,
+                )
+                .await?;
+
+            let mut first_error = None;
+            for (index, content) in completion.candidates.iter().enumerate() {
+                // Parse the JSON array back to Vec<T>, and check that the LLM
+                // neither dropped nor invented elements. A repaired array may
+                // have lost elements, so it is always checked
+                let parsed = if self.tag_ids {
+                    self.parse_tagged(&task.items, content)
+                } else {
+                    parse::parse_array_repaired(content)
+                };
+                let candidate = parsed.and_then(|(mut sorted, repaired): (Vec<T>, bool)| {
+                    let restored = self.accept_sorted(items, &mut sorted, verify || repaired)?;
+                    Ok((sorted, repaired, restored))
+                });
+                match candidate {
+                    Ok((sorted, repaired, restored)) => {
+                        completion.content = content.clone();
+                        completion.selected = index;
+                        completion.repaired = repaired;
+                        completion.duplicates_restored = restored;
+                        return Ok((completion, sorted));
+                    }
+                    Err(e) => {
+                        first_error.get_or_insert(e);
+                    }
+                }
+            }
+            Err(first_error.unwrap_or(VibesortError::InvalidResponse))
+        })
+        .await
+    }
+
+    /// Accepts `sorted` as the model's ordering of `items`.
+    ///
+    /// Restores the duplicates the model collapsed if
+    /// [`restore_duplicates`](Self::restore_duplicates) is set, then, if
+    /// `verify` is set, checks the result with
+    /// [`verify_sorted`](Self::verify_sorted). Returns the number of restored
+    /// duplicates.
+    pub(crate) fn accept_sorted<T>(
+        &self,
+        items: &[T],
+        sorted: &mut Vec<T>,
+        verify: bool,
+    ) -> Result<usize, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let mut restored = 0;
+        if self.restore_duplicates
+            && let Some(expanded) = verify::restore_duplicates_in(
+                items,
+                sorted,
+                &self.json_format,
+                self.verify_tolerance,
+            )?
+        {
+            restored = expanded.len() - sorted.len();
+            *sorted = expanded;
+        }
+        if verify {
+            self.verify_sorted(items, sorted)?;
+        }
+        Ok(restored)
+    }
+
+    /// Checks that `sorted` is a permutation of `items` as configured, and
+    /// replaces the elements that only matched within the
+    /// [tolerance](Self::verify_tolerance) by their input elements.
+    pub(crate) fn verify_sorted<T>(
+        &self,
+        items: &[T],
+        sorted: &mut [T],
+    ) -> Result<(), VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        for (position, value) in self.check_sorted(items, sorted)? {
+            sorted[position] = serde_json::from_value(value)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that `sorted` is a permutation of `items`, in full or sampled,
+    /// returning the elements to restore from the input because they only
+    /// matched within the [tolerance](Self::verify_tolerance).
+    fn check_sorted<T: Serialize>(
+        &self,
+        items: &[T],
+        sorted: &[T],
+    ) -> Result<Vec<verify::Substitution>, VibesortError> {
+        match &self.verify_sampling {
+            Some(sampling) => {
+                let seed = self
+                    .seed
+                    .unwrap_or_else(|| SplitMix64::from_time().next_u64());
+                verify::check_sampled_in(
+                    items,
+                    sorted,
+                    sampling,
+                    seed,
+                    &self.json_format,
+                    self.verify_tolerance,
+                )
+            }
+            None => verify::check_permutation_in(
+                items,
+                sorted,
+                &self.json_format,
+                self.verify_tolerance,
+            ),
+        }
+    }
+
+    /// Runs an attempt until it succeeds or the retry policy gives up.
+    ///
+    /// The attempt is called with its escalation level: the number of earlier
+    /// attempts that failed with malformed output, to be passed on to
+    /// [`chat`](Self::chat).
+    pub(crate) async fn retrying<R, F, Fut>(&self, mut attempt: F) -> Result<R, VibesortError>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Result<R, VibesortError>>,
+    {
+        if let Some((sorter, guard)) = self.admit()? {
+            return guard.run(Box::pin(sorter.retrying(attempt))).await;
+        }
+
+        let mut number = 1;
+        let mut escalation = 0;
+        let mut previous = None;
+        loop {
+            match attempt(escalation).await {
+                Ok(result) => return Ok(result),
+                Err(error) => {
+                    let decision = if !self.retry_ambiguous && retry::is_ambiguous(&error) {
+                        None
+                    } else {
+                        self.retry_policy.decide_after(number, previous, &error)
+                    };
+                    match decision {
+                        Some(delay) => {
+                            previous = Some(delay);
+                            if retry::is_malformed_output(&error) {
+                                escalation += 1;
+                            }
+                            // Never wait past the time budget
+                            let delay = match self.deadline {
+                                Some(deadline) => {
+                                    let left = deadline.saturating_duration_since(Instant::now());
+                                    if left.is_zero() {
+                                        return Err(self.budget_exhausted());
+                                    }
+                                    delay.min(left)
+                                }
+                                None => delay,
+                            };
+                            rt::sleep(delay).await
+                        }
+                        None => return Err(error),
+                    }
+                }
+            }
+            number += 1;
+        }
+    }
+
+    /// Returns the system prompt for the given escalation level, with the
+    /// configured nudge appended once escalated.
+    pub(crate) fn escalated_prompt<'p>(
+        &self,
+        system_prompt: &'p str,
+        escalation: u32,
+    ) -> Cow<'p, str> {
+        match self
+            .escalation
+            .as_ref()
+            .and_then(|schedule| schedule.nudge_for(escalation))
+        {
+            Some(nudge) => Cow::Owned(format!("{}\n\n{}", system_prompt, nudge)),
+            None => Cow::Borrowed(system_prompt),
+        }
+    }
+
+    /// Sends a system prompt and user message to the LLM and returns its reply.
+    ///
+    /// Non-success status codes are turned into [`VibesortError::ApiError`] (or
+    /// a more specific variant) and the content of the first choice is
+    /// extracted. `escalation` is the level passed by
+    /// [`retrying`](Self::retrying), or 0 outside of it.
+    pub(crate) async fn chat(
+        &self,
+        system_prompt: &str,
+        user_content: &str,
+        max_tokens: Option<u32>,
+        escalation: u32,
+    ) -> Result<Completion, VibesortError> {
+        let system_prompt = self.escalated_prompt(system_prompt, escalation);
+        let messages = vec![
+            ChatMessage {
+                role: "system",
+                content: MessageContent::Text(&system_prompt),
+            },
+            ChatMessage {
+                role: "user",
+                content: MessageContent::Text(user_content),
+            },
+        ];
+        self.chat_messages(messages, None, max_tokens, 1, escalation)
+            .await
+    }
+
+    /// Sends a sorting prompt to the LLM, preceded by the few-shot examples.
+    ///
+    /// Each example is sent as a user message with the example input followed
+    /// by an assistant message with the expected output. The configured number
+    /// of candidates is requested.
+    pub(crate) async fn chat_with_examples(
+        &self,
+        system_prompt: &str,
+        user_content: &str,
+        task: SortTask,
+        max_tokens: Option<u32>,
+        escalation: u32,
+    ) -> Result<Completion, VibesortError> {
+        let system_prompt = self.escalated_prompt(system_prompt, escalation);
+        let mut messages = vec![ChatMessage {
+            role: "system",
+            content: MessageContent::Text(&system_prompt),
+        }];
+        for (input, output) in &self.examples {
+            messages.push(ChatMessage {
+                role: "user",
+                content: MessageContent::Text(input),
+            });
+            messages.push(ChatMessage {
+                role: "assistant",
+                content: MessageContent::Text(output),
+            });
+        }
+        messages.push(ChatMessage {
+            role: "user",
+            content: MessageContent::Text(user_content),
+        });
+
+        self.chat_messages(
+            messages,
+            Some(task),
+            max_tokens,
+            self.candidates,
+            escalation,
+        )
+        .await
+    }
+
+    /// Sends a conversation to the LLM and returns its reply.
+    pub(crate) async fn chat_messages(
+        &self,
+        messages: Vec<ChatMessage<'_>>,
+        task: Option<SortTask>,
+        max_tokens: Option<u32>,
+        candidates: u32,
+        escalation: u32,
+    ) -> Result<Completion, VibesortError> {
+        // Use 0.0 for deterministic sorting, unless distinct candidates are wanted
+        let mut temperature: f32 = if candidates > 1 { 0.7 } else { 0.0 };
+        if let Some(escalated) = self
+            .escalation
+            .as_ref()
+            .and_then(|schedule| schedule.temperature(escalation))
+        {
+            temperature = temperature.max(escalated);
+        }
+
+        let request = ChatRequest {
+            model: self.model,
+            messages,
+            temperature,
+            seed: self.seed,
+            max_tokens,
+            n: (candidates > 1).then_some(candidates),
+            logit_bias: &self.logit_bias,
+            stop: &self.stop,
+        };
+
+        let mut body = serde_json::to_value(&request)?;
+        if let Some(fields) = body.as_object_mut() {
+            fields.extend(self.extra_body.clone());
+        }
+
+        // Send the request
+        let response = self.send(body, task).await?;
+
+        // Check if the request was successful
+        if !response.status.is_success() {
+            return Err(provider::classify(&response));
+        }
+
+        // Parse the response and extract the content of every choice
+        let chat_response: ChatResponse = serde_json::from_str(&response.body)?;
+        let candidates: Vec<String> = chat_response
+            .choices
+            .into_iter()
+            .map(|choice| match choice.finish_reason.as_deref() {
+                Some("stop") if !self.stop.is_empty() => {
+                    parse::restore_stop_sequence(choice.message.content, &self.stop)
+                }
+                _ => choice.message.content,
+            })
+            .collect();
+        let content = candidates
+            .first()
+            .cloned()
+            .ok_or(VibesortError::InvalidResponse)?;
+        self.account(chat_response.usage.unwrap_or_default());
+
+        Ok(Completion {
+            content,
+            candidates,
+            selected: 0,
+            repaired: false,
+            duplicates_restored: 0,
+            metadata: SortMetadata {
+                system_fingerprint: chat_response.system_fingerprint,
+                response_id: chat_response.id,
+                model: chat_response.model,
+                headers: self.captured(&response.headers),
+            },
+        })
+    }
+
+    /// Returns the captured headers present in `headers`.
+    fn captured(&self, headers: &reqwest::header::HeaderMap) -> BTreeMap<String, String> {
+        self.captured_headers
+            .iter()
+            .filter_map(|name| {
+                let value = headers.get(name.as_str())?.to_str().ok()?;
+                Some((name.clone(), value.to_string()))
+            })
+            .collect()
+    }
+
+    /// Renders the system prompt and user message for sorting a JSON array.
+    pub(crate) fn sort_prompt(
+        &self,
+        json_array: String,
+    ) -> Result<(String, String), VibesortError> {
+        self.render_prompt(Operation::Sort, json_array, || {
+            let localized = self.prompt_language.as_deref().and_then(|language| {
+                prompt::localized_sort_prompt(language, self.order, self.criterion.as_deref())
+            });
+            localized.unwrap_or_else(|| {
+                format!(
+                    "You are a helpful assistant that sorts arrays. Sort the following JSON array {} and return ONLY the sorted JSON array, nothing else.",
+                    self.sort_instruction()
+                )
+            })
+        })
+    }
+
+    /// Returns the `max_tokens` limit for a reply of roughly `output_len` bytes
+    /// of JSON.
+    ///
+    /// JSON averages well over three bytes per token, so the automatic limit
+    /// assumes three and adds a quarter plus a fixed margin on top.
+    pub(crate) fn max_tokens_for(&self, output_len: usize) -> Option<u32> {
+        match self.max_tokens {
+            MaxTokens::Auto => {
+                let tokens = output_len.div_ceil(3);
+                let limit = u32::try_from(tokens + tokens / 4 + 64).unwrap_or(u32::MAX);
+                // Providers reject limits above the model's maximum output
+                match self.limits() {
+                    Some(limits) => Some(limit.min(limits.max_output)),
+                    None => Some(limit),
+                }
+            }
+            MaxTokens::Fixed(limit) => Some(limit),
+            MaxTokens::ProviderDefault => None,
+        }
+    }
+
+    /// Describes the requested order for use in prompts, e.g. "with ascending
+    /// order" or "with descending order according to this criterion: ...".
+    pub(crate) fn sort_instruction(&self) -> String {
+        self.sort_instruction_for(self.criterion.as_deref())
+    }
+
+    /// Describes the requested order with the given criterion for use in
+    /// prompts.
+    pub(crate) fn sort_instruction_for(&self, criterion: Option<&str>) -> String {
+        match criterion {
+            Some(criterion) => format!(
+                "with {} order according to this criterion: {}",
+                self.order.as_str(),
+                criterion
+            ),
+            None => format!("with {} order", self.order.as_str()),
+        }
+    }
+
+    /// Describes a target shape of a quantity over the sorted sequence for
+    /// use in prompts, for orders that are not monotonic such as "rise, then
+    /// fall".
+    pub(crate) fn shape_instruction(quantity: &str, shape: &str) -> String {
+        format!(
+            "so that {} follows this shape from the first element to the last: {}",
+            quantity, shape
+        )
+    }
+
+    /// Renders the system prompt and user message for an operation.
+    ///
+    /// Uses the selected named template if there is one for the operation,
+    /// then the custom [`PromptTemplate`] (for sorting only), and otherwise the
+    /// default system prompt with the payload as the user message.
+    pub(crate) fn render_prompt(
+        &self,
+        operation: Operation,
+        payload: String,
+        default_system_prompt: impl FnOnce() -> String,
+    ) -> Result<(String, String), VibesortError> {
+        let mut template = None;
+        if let Some(selector) = &self.template_selector {
+            if !self.templates.contains(selector) {
+                return Err(VibesortError::InvalidTemplate(format!(
+                    "no template matches {:?}",
+                    selector
+                )));
+            }
+            template = self.templates.get(operation, selector);
+        }
+        if operation == Operation::Sort {
+            template = template.or(self.prompt_template.as_ref());
+        }
+
+        Ok(match template {
+            Some(template) => template.render(PromptValues {
+                array: &payload,
+                order: self.order,
+                criterion: self.criterion.as_deref(),
+            }),
+            None => (default_system_prompt(), payload),
+        })
+    }
+
+    /// Builds the report for a sort answered by the given completion.
+    pub(crate) fn report(&self, completion: &Completion) -> SortReport {
+        SortReport {
+            seed: self.seed,
+            system_fingerprint: completion.metadata.system_fingerprint.clone(),
+            metadata: completion.metadata.clone(),
+            candidate: completion.selected,
+            json_repaired: completion.repaired,
+            duplicates_restored: completion.duplicates_restored,
+            ..SortReport::default()
+        }
+    }
+
+    /// Sends a chat completion request body through the configured backend.
+    async fn send(
+        &self,
+        body: serde_json::Value,
+        task: Option<SortTask>,
+    ) -> Result<BackendResponse, VibesortError> {
+        let request = BackendRequest {
+            url: self.endpoint_url("chat/completions"),
+            api_key: self.request_key()?,
+            body,
+            task,
+        };
+
+        if let Some(deadline) = self.deadline
+            && Instant::now() >= deadline
+        {
+            return Err(self.budget_exhausted());
+        }
+        let _permit = match &self.limiter {
+            Some(limiter) => Some(limiter.acquire(self.queue_timeout).await?),
+            None => None,
+        };
+        let scheduled = match &self.scheduler {
+            Some(scheduler) => Some(scheduler.acquire(self.queue_timeout).await?),
+            None => None,
+        };
+
+        // Stop the request at its own timeout or at the end of the budget,
+        // whichever comes first
+        let budget_left = self
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        let limit = match (self.request_timeout, budget_left) {
+            (Some(timeout), Some(left)) => Some(timeout.min(left)),
+            (timeout, left) => timeout.or(left),
+        };
+        let response = match limit {
+            Some(limit) => match rt::timeout(limit, self.dispatch_hedged(request)).await {
+                Ok(response) => response,
+                Err(_) if budget_left == Some(limit) => Err(self.budget_exhausted()),
+                Err(_) => Err(VibesortError::Timeout),
+            },
+            None => self.dispatch_hedged(request).await,
+        };
+        if let Some(permit) = scheduled
+            && let Ok(response) = &response
+        {
+            permit.record(response);
+        }
+        response
+    }
+
+    /// Returns a clone of this client with the clock of the time budget
+    /// started, unless there is no budget or an enclosing sort already
+    /// started it.
+    pub(crate) fn start_budget(&self) -> Option<Self> {
+        let budget = self.time_budget.filter(|_| self.deadline.is_none())?;
+        let mut sorter = self.clone();
+        sorter.deadline = Some(Instant::now() + budget);
+        Some(sorter)
+    }
+
+    /// Returns the error for a sort that ran out of its time budget.
+    fn budget_exhausted(&self) -> VibesortError {
+        VibesortError::BudgetExhausted(self.time_budget.unwrap_or_default())
+    }
+
+    /// Sends a request, hedged with a duplicate after the
+    /// [hedge delay](Self::hedge_delay) if there is one.
+    async fn dispatch_hedged(
+        &self,
+        request: BackendRequest,
+    ) -> Result<BackendResponse, VibesortError> {
+        let Some(delay) = self.hedge_delay else {
+            return self.dispatch(request).await;
+        };
+        let mut first = Box::pin(self.dispatch(request.clone()));
+        if let Ok(response) = rt::timeout(delay, first.as_mut()).await {
+            return response;
+        }
+
+        // Race the duplicate against the first request; the other one is
+        // dropped, and so cancelled, on return
+        let mut pending = vec![first, Box::pin(self.dispatch(request))];
+        let mut first_error = None;
+        std::future::poll_fn(|cx| {
+            let mut i = 0;
+            while i < pending.len() {
+                match pending[i].as_mut().poll(cx) {
+                    Poll::Ready(Ok(response)) => return Poll::Ready(Ok(response)),
+                    Poll::Ready(Err(error)) => {
+                        drop(pending.remove(i));
+                        first_error.get_or_insert(error);
+                    }
+                    Poll::Pending => i += 1,
+                }
+            }
+            if pending.is_empty() {
+                Poll::Ready(Err(first_error
+                    .take()
+                    .unwrap_or(VibesortError::InvalidResponse)))
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Sends a request through the configured engine, backend, or HTTP.
+    async fn dispatch(&self, request: BackendRequest) -> Result<BackendResponse, VibesortError> {
+        if let Engine::Local(local) = &self.engine {
+            return local.send(request).await;
+        }
+        if let Some(backend) = &self.backend {
+            return backend.send(request).await;
+        }
+        self.send_http(request).await
+    }
+
+    /// Returns the URL of an API endpoint, such as `chat/completions`.
+    pub(crate) fn endpoint_url(&self, endpoint: &str) -> String {
+        #[cfg(unix)]
+        let unix_socket = self.unix_socket();
+        #[cfg(not(unix))]
+        let unix_socket: Option<(&str, &str)> = None;
+
+        match unix_socket {
+            Some((_, api_path)) => format!("http://localhost{}/{}", api_path, endpoint),
+            None => format!("{}/{}", self.base_url, endpoint),
+        }
+    }
+
+    /// Sends a request with the built-in HTTP client.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn send_http(&self, request: BackendRequest) -> Result<BackendResponse, VibesortError> {
+        let client = self.http_client().await?;
+        self.http_backend(client).send(request).await
+    }
+
+    /// Returns the HTTP client.
+    ///
+    /// The client is created once and shared by every clone, so requests
+    /// share its connection pool. In local-only mode a client pinned to the
+    /// checked addresses is created for every request instead, since the
+    /// addresses are resolved again each time. A Unix domain socket is always
+    /// local.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) async fn http_client(&self) -> Result<reqwest::Client, VibesortError> {
+        #[cfg(unix)]
+        let socket = self.unix_socket().map(|(socket, _)| socket);
+        #[cfg(not(unix))]
+        let socket: Option<&str> = None;
+
+        if self.local_only && socket.is_none() {
+            let (host, addrs) = self.resolve_local_endpoint().await?;
+            return Ok(self
+                .http_client_builder()
+                .resolve_to_addrs(&host, &addrs)
+                .build()?);
+        }
+        if let Some((shared, client)) = self.http_client.get()
+            && shared.as_deref() == socket
+        {
+            return Ok(client.clone());
+        }
+        let builder = self.http_client_builder();
+        #[cfg(unix)]
+        let builder = match socket {
+            Some(socket) => builder.unix_socket(socket),
+            None => builder,
+        };
+        let client = builder.build()?;
+        // A client for another socket, after `base_url` was changed, is not
+        // shared
+        let _ = self
+            .http_client
+            .set((socket.map(String::from), client.clone()));
+        Ok(client)
+    }
+
+    /// Drops the shared HTTP client of this client, so that the next request
+    /// creates one with the current transport options.
+    fn reset_http_client(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.http_client = Arc::default();
+        }
+    }
+
+    /// Sends a request through the fetch API of Cloudflare Workers.
+    #[cfg(all(feature = "worker", target_arch = "wasm32"))]
+    async fn send_http(&self, request: BackendRequest) -> Result<BackendResponse, VibesortError> {
+        // Fetch resolves host names itself, so only addresses can be checked
+        if self.local_only {
+            self.resolve_local_endpoint().await?;
+        }
+        backend::FetchBackend::new()
+            .with_signer(self.request_signer.clone())
+            .send(request)
+            .await
+    }
+
+    /// Creates the HTTP backend sending requests with the given client.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn http_backend(&self, client: reqwest::Client) -> HttpBackend {
+        HttpBackend::new(client).with_signer(self.request_signer.clone())
+    }
+
+    /// Splits a `unix://` base URL into the socket path and the API path.
+    #[cfg(unix)]
+    fn unix_socket(&self) -> Option<(&'a str, &'a str)> {
+        let target = self.base_url.strip_prefix("unix://")?;
+        Some(match target.split_once(':') {
+            Some((socket, api_path)) => (socket, api_path.trim_end_matches('/')),
+            None => (target, ""),
+        })
+    }
+
+    /// Creates an HTTP client builder with the configured TLS options applied.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn http_client_builder(&self) -> reqwest::ClientBuilder {
+        let mut builder = reqwest::Client::builder();
+        for (domain, addrs) in &self.dns_overrides {
+            builder = builder.resolve_to_addrs(domain, addrs);
+        }
+        #[cfg(feature = "__tls")]
+        let builder = {
+            let mut builder = builder
+                .tls_built_in_root_certs(self.built_in_root_certs)
+                .danger_accept_invalid_certs(self.accept_invalid_certs);
+            for certificate in &self.root_certificates {
+                builder = builder.add_root_certificate(certificate.clone());
+            }
+            builder
+        };
+        builder
+    }
+
+    /// Resolves the host of `base_url` and checks that every address is local.
+    ///
+    /// Returns the host name together with the resolved addresses so the HTTP
+    /// client can be pinned to them.
+    async fn resolve_local_endpoint(&self) -> Result<(String, Vec<SocketAddr>), VibesortError> {
+        let denied =
+            |reason: &str| VibesortError::RemoteDenied(format!("{} ({})", self.base_url, reason));
+
+        let url = reqwest::Url::parse(self.base_url).map_err(|_| denied("invalid URL"))?;
+        let host = url.host_str().ok_or_else(|| denied("missing host"))?;
+        let port = url.port_or_known_default().unwrap_or(80);
+
+        // IPv6 literals are reported with surrounding brackets
+        let bare_host = host.trim_start_matches('[').trim_end_matches(']');
+        #[cfg(not(target_arch = "wasm32"))]
+        let addrs: Vec<SocketAddr> = match self.dns_overrides.get(bare_host) {
+            Some(addrs) => addrs
+                .iter()
+                .map(|addr| SocketAddr::new(addr.ip(), port))
+                .collect(),
+            None => tokio::net::lookup_host((bare_host, port))
+                .await
+                .map_err(|_| denied("host could not be resolved"))?
+                .collect(),
+        };
+        #[cfg(target_arch = "wasm32")]
+        let addrs: Vec<SocketAddr> = match bare_host.parse::<IpAddr>() {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => return Err(denied("host names cannot be resolved on wasm32")),
+        };
+
+        if addrs.is_empty() {
+            return Err(denied("host could not be resolved"));
+        }
+        if let Some(addr) = addrs.iter().find(|addr| !is_local_address(addr.ip())) {
+            return Err(denied(&format!("{} is not a local address", addr.ip())));
+        }
+
+        Ok((host.to_string(), addrs))
+    }
+}
+
+/// Returns `true` if the address is a loopback, private, or link-local address.
+fn is_local_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => v4.is_loopback() || v4.is_private() || v4.is_link_local(),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_local_address(IpAddr::V4(v4)),
+            None => v6.is_loopback() || v6.is_unique_local() || v6.is_unicast_link_local(),
+        },
+    }
 }
@@ -0,0 +1,376 @@
+//! Client-level limits on concurrent requests.
+
+use crate::VibesortError;
+use crate::backend::BackendResponse;
+use crate::provider;
+use crate::rt::{self, Instant};
+use reqwest::StatusCode;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+
+/// Limits how many requests a client and its clones send at once.
+///
+/// Waiting callers are served in FIFO order, since the semaphore is fair.
+#[derive(Debug, Clone)]
+pub(crate) struct RequestLimiter {
+    slots: Arc<Semaphore>,
+}
+
+impl RequestLimiter {
+    /// Creates a limiter allowing `max_concurrent` requests at once.
+    pub(crate) fn new(max_concurrent: usize) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(max_concurrent.max(1))),
+        }
+    }
+
+    /// Waits for a free request slot, giving up after `timeout` if one is
+    /// set. The slot is released when the returned permit is dropped.
+    pub(crate) async fn acquire(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Result<OwnedSemaphorePermit, VibesortError> {
+        let permit = self.slots.clone().acquire_owned();
+        let permit = match timeout {
+            Some(timeout) => rt::timeout(timeout, permit)
+                .await
+                .map_err(|_| VibesortError::QueueTimeout(timeout))?,
+            None => permit.await,
+        };
+        // The semaphore is never closed
+        Ok(permit.expect("request semaphore closed"))
+    }
+}
+
+/// A concurrency limit shared by many sorters that adapts to rate limiting.
+///
+/// The scheduler allows up to a window of requests in flight. Every
+/// `429 Too Many Requests` response halves the window and pauses all new
+/// requests for the provider's `Retry-After`, if it sent one; every
+/// successful response grows the window again by about one request per
+/// window of successes, up to the maximum. Responses to requests sent before
+/// the last decrease do not shrink the window again, so one burst of `429`s
+/// counts as one signal.
+///
+/// Clones share the same window, so one scheduler given to every sorter of a
+/// process slows down submission globally instead of letting each sort back
+/// off on its own and collide again. Unlike
+/// [`max_concurrent_requests`](crate::Vibesort::max_concurrent_requests),
+/// waiting requests are not served in a strict order.
+///
+/// # Example
+///
+/// ```no_run
+/// use vibesort_rs::{AdaptiveScheduler, Vibesort};
+/// use vibesort_rs::retry::ExponentialBackoff;
+///
+/// let scheduler = AdaptiveScheduler::new(32);
+/// let sorter = Vibesort::new(
+///     "your-api-key",
+///     "gpt-3.5-turbo",
+///     "https://api.openai.com/v1",
+/// )
+/// .scheduler(scheduler.clone())
+/// .retry_policy(ExponentialBackoff::new(5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdaptiveScheduler {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    window: Mutex<Window>,
+    changed: Notify,
+}
+
+#[derive(Debug)]
+struct Window {
+    /// The number of requests allowed in flight, at least 1.
+    limit: f64,
+    max: usize,
+    in_flight: usize,
+    /// No request is started before this instant.
+    paused_until: Option<Instant>,
+    /// Incremented on every decrease of the limit.
+    epoch: u64,
+}
+
+impl AdaptiveScheduler {
+    /// Creates a scheduler allowing at most `max_concurrent` requests in
+    /// flight, starting at the maximum. A maximum of zero is treated as one.
+    pub fn new(max_concurrent: usize) -> Self {
+        let max = max_concurrent.max(1);
+        Self {
+            shared: Arc::new(Shared {
+                window: Mutex::new(Window {
+                    limit: max as f64,
+                    max,
+                    in_flight: 0,
+                    paused_until: None,
+                    epoch: 0,
+                }),
+                changed: Notify::new(),
+            }),
+        }
+    }
+
+    /// Returns the number of requests currently allowed in flight.
+    pub fn current_limit(&self) -> usize {
+        self.window().limit as usize
+    }
+
+    /// Returns the number of requests currently in flight.
+    pub fn in_flight(&self) -> usize {
+        self.window().in_flight
+    }
+
+    fn window(&self) -> std::sync::MutexGuard<'_, Window> {
+        self.shared.window.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Waits until the window has room and no pause is in effect, giving up
+    /// after `timeout` if one is set.
+    pub(crate) async fn acquire(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Result<SchedulerPermit, VibesortError> {
+        match timeout {
+            Some(timeout) => rt::timeout(timeout, self.wait())
+                .await
+                .map_err(|_| VibesortError::QueueTimeout(timeout)),
+            None => Ok(self.wait().await),
+        }
+    }
+
+    async fn wait(&self) -> SchedulerPermit {
+        loop {
+            // Registered before checking, so a release in between is not
+            // missed
+            let changed = self.shared.changed.notified();
+            let paused_until = {
+                let mut window = self.window();
+                match window.paused_until {
+                    Some(until) if until > Instant::now() => Some(until),
+                    _ if window.in_flight < window.limit as usize => {
+                        window.in_flight += 1;
+                        return SchedulerPermit {
+                            scheduler: self.clone(),
+                            epoch: window.epoch,
+                        };
+                    }
+                    _ => None,
+                }
+            };
+            match paused_until {
+                Some(until) => rt::sleep_until(until).await,
+                None => changed.await,
+            }
+        }
+    }
+}
+
+/// A slot in the window of an [`AdaptiveScheduler`], released on drop.
+#[derive(Debug)]
+pub(crate) struct SchedulerPermit {
+    scheduler: AdaptiveScheduler,
+    /// The epoch of the window when the request was started.
+    epoch: u64,
+}
+
+impl SchedulerPermit {
+    /// Adapts the window to the response of the request.
+    pub(crate) fn record(&self, response: &BackendResponse) {
+        let mut window = self.scheduler.window();
+        if response.status == StatusCode::TOO_MANY_REQUESTS {
+            if self.epoch == window.epoch {
+                window.limit = (window.limit / 2.0).max(1.0);
+                window.epoch += 1;
+            }
+            if let Some(retry_after) = provider::retry_after(&response.headers)
+                && let Some(until) = Instant::now().checked_add(retry_after)
+            {
+                window.paused_until = Some(window.paused_until.map_or(until, |u| u.max(until)));
+            }
+        } else if response.status.is_success() {
+            window.limit = (window.limit + 1.0 / window.limit).min(window.max as f64);
+        }
+    }
+}
+
+impl Drop for SchedulerPermit {
+    fn drop(&mut self) {
+        self.scheduler.window().in_flight -= 1;
+        self.scheduler.shared.changed.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{Backend, BackendRequest, BackendResponse, BoxFuture};
+    use crate::{AdaptiveScheduler, Vibesort, VibesortError};
+    use reqwest::StatusCode;
+    use reqwest::header::{HeaderValue, RETRY_AFTER};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    /// A backend that takes a second to reply and records the peak number of
+    /// requests in flight.
+    #[derive(Debug, Default)]
+    struct SlowBackend {
+        in_flight: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl Backend for SlowBackend {
+        fn send(
+            &self,
+            _request: BackendRequest,
+        ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>> {
+            Box::pin(async move {
+                let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(in_flight, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+                let body = r#"{"choices":[{"message":{"content":"[1,2]"}}]}"#;
+                Ok(BackendResponse::new(StatusCode::OK, body))
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_max_concurrent_requests_shared_by_clones() {
+        let backend = Arc::new(SlowBackend::default());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .max_concurrent_requests(2);
+
+        let mut sorts = tokio::task::JoinSet::new();
+        for _ in 0..5 {
+            let sorter = sorter.clone();
+            sorts.spawn(async move { sorter.sort(&[2, 1]).await });
+        }
+        while let Some(result) = sorts.join_next().await {
+            assert_eq!(result.unwrap().unwrap(), vec![1, 2]);
+        }
+        assert_eq!(backend.peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_queue_timeout() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(SlowBackend::default())
+            .max_concurrent_requests(1)
+            .queue_timeout(Duration::from_millis(500));
+
+        let (first, second) = tokio::join!(sorter.sort(&[2, 1]), sorter.sort(&[2, 1]));
+        assert_eq!(first.unwrap(), vec![1, 2]);
+        assert!(matches!(
+            second.unwrap_err(),
+            VibesortError::QueueTimeout(timeout) if timeout == Duration::from_millis(500)
+        ));
+    }
+
+    /// A backend that rate limits its first request with a `Retry-After`
+    /// header and records when every request arrived.
+    #[derive(Debug)]
+    struct RateLimitedBackend {
+        retry_after: &'static str,
+        arrivals: Mutex<Vec<Instant>>,
+    }
+
+    impl RateLimitedBackend {
+        fn new(retry_after: &'static str) -> Self {
+            Self {
+                retry_after,
+                arrivals: Mutex::default(),
+            }
+        }
+    }
+
+    impl Backend for RateLimitedBackend {
+        fn send(
+            &self,
+            _request: BackendRequest,
+        ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>> {
+            Box::pin(async move {
+                let mut arrivals = self.arrivals.lock().unwrap();
+                arrivals.push(Instant::now());
+                if arrivals.len() == 1 {
+                    let mut response = BackendResponse::new(StatusCode::TOO_MANY_REQUESTS, "");
+                    response
+                        .headers
+                        .insert(RETRY_AFTER, HeaderValue::from_static(self.retry_after));
+                    return Ok(response);
+                }
+                let body = r#"{"choices":[{"message":{"content":"[1,2]"}}]}"#;
+                Ok(BackendResponse::new(StatusCode::OK, body))
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_pauses_every_sorter() {
+        let backend = Arc::new(RateLimitedBackend::new("2"));
+        let scheduler = AdaptiveScheduler::new(4);
+        let first = Vibesort::new("key", "first", "http://mock")
+            .backend(backend.clone())
+            .scheduler(scheduler.clone());
+        let second = Vibesort::new("key", "second", "http://mock")
+            .backend(backend.clone())
+            .scheduler(scheduler.clone());
+
+        let start = Instant::now();
+        assert!(matches!(
+            first.sort(&[2, 1]).await.unwrap_err(),
+            VibesortError::RateLimited { .. }
+        ));
+        assert_eq!(scheduler.current_limit(), 2);
+        assert_eq!(second.sort(&[2, 1]).await.unwrap(), vec![1, 2]);
+
+        let arrivals = backend.arrivals.lock().unwrap();
+        assert_eq!(arrivals[1] - start, Duration::from_secs(2));
+        assert_eq!(scheduler.in_flight(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_caps_long_retry_after() {
+        let backend = Arc::new(RateLimitedBackend::new("1e20"));
+        let scheduler = AdaptiveScheduler::new(4);
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .scheduler(scheduler);
+
+        let start = Instant::now();
+        assert!(sorter.sort(&[2, 1]).await.is_err());
+        assert_eq!(sorter.sort(&[2, 1]).await.unwrap(), vec![1, 2]);
+        let arrivals = backend.arrivals.lock().unwrap();
+        assert_eq!(arrivals[1] - start, crate::provider::MAX_RETRY_AFTER);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_decreases_once_per_burst() {
+        let scheduler = AdaptiveScheduler::new(8);
+        let burst = [
+            scheduler.acquire(None).await.unwrap(),
+            scheduler.acquire(None).await.unwrap(),
+        ];
+        let rate_limited = BackendResponse::new(StatusCode::TOO_MANY_REQUESTS, "");
+        for permit in &burst {
+            permit.record(&rate_limited);
+        }
+        assert_eq!(scheduler.current_limit(), 4);
+
+        // Growing back takes about one window of successes per request
+        let ok = BackendResponse::new(StatusCode::OK, "");
+        for _ in 0..5 {
+            scheduler.acquire(None).await.unwrap().record(&ok);
+        }
+        assert_eq!(scheduler.current_limit(), 5);
+        drop(burst);
+        assert_eq!(scheduler.in_flight(), 0);
+    }
+}
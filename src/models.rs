@@ -0,0 +1,352 @@
+//! The context window and output limits of models.
+
+use crate::Vibesort;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{VibesortError, backend::BackendResponse, engine::Engine, provider, rt};
+#[cfg(not(target_arch = "wasm32"))]
+use serde::Deserialize;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+
+/// The context window and maximum output of a model, in tokens.
+///
+/// Limits of common models are built in (see [`for_model`](Self::for_model));
+/// others can be set with [`Vibesort::model_limits`] or learned from the
+/// provider with [`Vibesort::detect_model`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelLimits {
+    /// The number of tokens the prompt and the reply share.
+    pub context_window: u32,
+
+    /// The largest number of tokens the model can generate in one reply.
+    pub max_output: u32,
+}
+
+/// The built-in limits, by model name prefix. The longest matching prefix
+/// applies, so dated snapshots such as `gpt-4o-2024-08-06` match their model.
+const BUILT_IN: &[(&str, ModelLimits)] = &[
+    ("gpt-3.5-turbo", ModelLimits::new(16_385, 4_096)),
+    ("gpt-4", ModelLimits::new(8_192, 8_192)),
+    ("gpt-4-turbo", ModelLimits::new(128_000, 4_096)),
+    ("gpt-4o", ModelLimits::new(128_000, 16_384)),
+    ("gpt-4o-mini", ModelLimits::new(128_000, 16_384)),
+    ("gpt-4.1", ModelLimits::new(1_047_576, 32_768)),
+    ("o1", ModelLimits::new(200_000, 100_000)),
+    ("o1-mini", ModelLimits::new(128_000, 65_536)),
+    ("o3", ModelLimits::new(200_000, 100_000)),
+    ("o3-mini", ModelLimits::new(200_000, 100_000)),
+    ("o4-mini", ModelLimits::new(200_000, 100_000)),
+    ("claude-3-haiku", ModelLimits::new(200_000, 4_096)),
+    ("claude-3-opus", ModelLimits::new(200_000, 4_096)),
+    ("claude-3-5-haiku", ModelLimits::new(200_000, 8_192)),
+    ("claude-3-5-sonnet", ModelLimits::new(200_000, 8_192)),
+    ("claude-3-7-sonnet", ModelLimits::new(200_000, 64_000)),
+    ("claude-sonnet-4", ModelLimits::new(200_000, 64_000)),
+    ("claude-opus-4", ModelLimits::new(200_000, 32_000)),
+    ("gemini-1.5-flash", ModelLimits::new(1_048_576, 8_192)),
+    ("gemini-1.5-pro", ModelLimits::new(2_097_152, 8_192)),
+    ("gemini-2.0-flash", ModelLimits::new(1_048_576, 8_192)),
+];
+
+impl ModelLimits {
+    /// Creates limits from the context window and the maximum output, in
+    /// tokens.
+    pub const fn new(context_window: u32, max_output: u32) -> Self {
+        Self {
+            context_window,
+            max_output,
+        }
+    }
+
+    /// Returns the built-in limits of `model`, or `None` if it is not known.
+    ///
+    /// Names are matched by their longest known prefix, and a routing prefix
+    /// such as `openai/` is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vibesort_rs::ModelLimits;
+    ///
+    /// let limits = ModelLimits::for_model("gpt-4o-2024-08-06").unwrap();
+    /// assert_eq!(limits.max_output, 16_384);
+    /// assert_eq!(ModelLimits::for_model("my-fine-tune"), None);
+    /// ```
+    pub fn for_model(model: &str) -> Option<Self> {
+        let name = model.rsplit('/').next().unwrap_or(model);
+        BUILT_IN
+            .iter()
+            .filter(|(prefix, _)| name.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|&(_, limits)| limits)
+    }
+}
+
+/// A model listed by the provider's `/models` endpoint.
+///
+/// Only the id is standard; gateways such as OpenRouter add the limits.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Deserialize)]
+struct ListedModel {
+    id: String,
+    #[serde(alias = "context_window")]
+    context_length: Option<u32>,
+    #[serde(alias = "max_output_tokens")]
+    max_completion_tokens: Option<u32>,
+    top_provider: Option<TopProvider>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Deserialize)]
+struct TopProvider {
+    max_completion_tokens: Option<u32>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<ListedModel>,
+}
+
+/// How long [`Vibesort::detect_model`] waits for the listing if no
+/// [time budget](Vibesort::time_budget) is set.
+#[cfg(not(target_arch = "wasm32"))]
+const DETECT_MODEL_TIMEOUT: Duration = Duration::from_secs(30);
+
+impl<'a> Vibesort<'a> {
+    /// Checks that the configured model is listed by the provider's
+    /// `/models` endpoint, and learns its limits from the listing if the
+    /// provider reports them.
+    ///
+    /// Call this once after building the sorter to fail at startup rather
+    /// than with a 404 in the middle of a sort. Reported limits are used
+    /// as if set with [`model_limits`](Self::model_limits), unless limits
+    /// were set explicitly. Nothing is queried when a custom
+    /// [`backend`](Self::backend) or a local [`engine`](Self::engine) is
+    /// configured, since those do not serve `/models`. The listing must
+    /// arrive within the [time budget](Self::time_budget), or within 30
+    /// seconds if none is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::ModelNotFound`] if the model is not listed,
+    /// [`VibesortError::HttpError`] if the request fails,
+    /// [`VibesortError::Timeout`] if the listing does not arrive in time, the
+    /// errors of
+    /// [`sort`](Self::sort) for error responses, and
+    /// [`VibesortError::InvalidResponse`] if the listing cannot be parsed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .detect_model()
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn detect_model(mut self) -> Result<Self, VibesortError> {
+        use secrecy::ExposeSecret;
+
+        if self.backend.is_some() || matches!(self.engine, Engine::Local(_)) {
+            return Ok(self);
+        }
+        let url = self.endpoint_url("models");
+        let listing = async {
+            let mut builder = self
+                .http_client()
+                .await?
+                .get(&url)
+                .bearer_auth(self.request_key()?.expose_secret());
+            if let Some(signer) = &self.request_signer {
+                builder = builder.headers(signer.sign(&url, &[])?);
+            }
+            let response = builder.send().await?;
+            let status = response.status();
+            if !status.is_success() {
+                let headers = response.headers().clone();
+                let body = response.text().await?;
+                return Err(provider::classify(&BackendResponse {
+                    status,
+                    headers,
+                    body,
+                }));
+            }
+            response
+                .json::<ModelList>()
+                .await
+                .map_err(|_| VibesortError::InvalidResponse)
+        };
+        let timeout = self.time_budget.unwrap_or(DETECT_MODEL_TIMEOUT);
+        let list = rt::timeout(timeout, listing)
+            .await
+            .map_err(|_| VibesortError::Timeout)??;
+        let listed = list
+            .data
+            .into_iter()
+            .find(|listed| listed.id == self.model)
+            .ok_or_else(|| {
+                VibesortError::ModelNotFound(format!("{} is not listed by {}", self.model, url))
+            })?;
+
+        if self.model_limits.is_none()
+            && let Some(context_window) = listed.context_length
+        {
+            let max_output = listed
+                .max_completion_tokens
+                .or(listed
+                    .top_provider
+                    .and_then(|top| top.max_completion_tokens))
+                .or(self.limits().map(|limits| limits.max_output))
+                .unwrap_or(context_window);
+            self.model_limits = Some(ModelLimits::new(context_window, max_output));
+        }
+        Ok(self)
+    }
+
+    /// Returns the limits of the configured model: those set with
+    /// [`model_limits`](Self::model_limits), or else the built-in ones.
+    pub(crate) fn limits(&self) -> Option<ModelLimits> {
+        self.model_limits
+            .or_else(|| ModelLimits::for_model(self.model))
+    }
+
+    /// Returns the number of elements of each chunk: the configured
+    /// [`chunk_size`](Self::chunk_size), or else the largest chunk whose
+    /// prompt and reply fit the model's limits, given the serialized size of
+    /// the whole input.
+    ///
+    /// Returns `None` if no chunk size is configured and the model's limits
+    /// are not known.
+    pub(crate) fn chunk_size_for(&self, elements: usize, array_bytes: usize) -> Option<usize> {
+        if let Some(size) = self.chunk_size {
+            return Some(size);
+        }
+        let limits = self.limits()?;
+        // The chunk is sent and repeated in the reply, with a quarter of the
+        // budget left for the prompt and the margin of the output limit
+        let budget_tokens = (limits.context_window / 2).min(limits.max_output) as usize / 4 * 3;
+        let element_bytes = array_bytes.div_ceil(elements.max(1)).max(1);
+        Some((budget_tokens * 3 / element_bytes).max(2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_model() {
+        assert_eq!(
+            ModelLimits::for_model("gpt-4o-mini-2024-07-18"),
+            Some(ModelLimits::new(128_000, 16_384))
+        );
+        assert_eq!(
+            ModelLimits::for_model("gpt-4-0613"),
+            Some(ModelLimits::new(8_192, 8_192))
+        );
+        assert_eq!(
+            ModelLimits::for_model("anthropic/claude-3-5-sonnet-20241022"),
+            Some(ModelLimits::new(200_000, 8_192))
+        );
+        assert_eq!(ModelLimits::for_model("model"), None);
+    }
+
+    #[test]
+    fn test_max_tokens_capped_at_max_output() {
+        let sorter = Vibesort::new("key", "gpt-4", "http://mock");
+        assert_eq!(sorter.max_tokens_for(300), Some(189));
+        assert_eq!(sorter.max_tokens_for(1_000_000), Some(8_192));
+
+        let sorter = Vibesort::new("key", "model", "http://mock");
+        assert_eq!(sorter.max_tokens_for(1_000_000), Some(416_731));
+    }
+
+    #[test]
+    fn test_chunk_size_for() {
+        // Unknown models are not chunked unless a size is set
+        let sorter = Vibesort::new("key", "model", "http://mock");
+        assert_eq!(sorter.chunk_size_for(1000, 10_000), None);
+        assert_eq!(sorter.chunk_size(50).chunk_size_for(1000, 10_000), Some(50));
+
+        // 4096 output tokens leave 3072, or about 9216 bytes of 10-byte elements
+        let sorter = Vibesort::new("key", "gpt-3.5-turbo", "http://mock");
+        assert_eq!(sorter.chunk_size_for(1000, 10_000), Some(921));
+
+        let sorter =
+            Vibesort::new("key", "model", "http://mock").model_limits(ModelLimits::new(1000, 100));
+        assert_eq!(sorter.chunk_size_for(1000, 100_000), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_detect_model() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .and(header("authorization", "Bearer key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [
+                    { "id": "gpt-3.5-turbo", "object": "model" },
+                    {
+                        "id": "meta-llama/llama-3-70b",
+                        "context_length": 8192,
+                        "top_provider": { "max_completion_tokens": 2048 }
+                    }
+                ]
+            })))
+            .mount(&mock_server)
+            .await;
+        let base_url = mock_server.uri();
+
+        let sorter = Vibesort::new("key", "gpt-3.5-turbo", base_url.as_str())
+            .detect_model()
+            .await
+            .unwrap();
+        assert_eq!(sorter.limits(), Some(ModelLimits::new(16_385, 4_096)));
+
+        let sorter = Vibesort::new("key", "meta-llama/llama-3-70b", base_url.as_str())
+            .detect_model()
+            .await
+            .unwrap();
+        assert_eq!(sorter.limits(), Some(ModelLimits::new(8192, 2048)));
+
+        let err = Vibesort::new("key", "gpt-9", base_url.as_str())
+            .detect_model()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::ModelNotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_detect_model_times_out() {
+        use std::time::Duration;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(10)))
+            .mount(&mock_server)
+            .await;
+
+        let err = Vibesort::new("key", "gpt-3.5-turbo", mock_server.uri().as_str())
+            .time_budget(Duration::from_millis(100))
+            .detect_model()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::Timeout));
+    }
+}
@@ -0,0 +1,133 @@
+//! Sorting optional values with a null-handling policy.
+
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Where [`Vibesort::sort_options`] places `None` values.
+///
+/// The policy is applied locally: `None` values are never sent to the model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum NullsPolicy {
+    /// Place all `None` values before the sorted values.
+    First,
+
+    /// Place all `None` values after the sorted values.
+    #[default]
+    Last,
+
+    /// Remove all `None` values from the result.
+    Drop,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts a slice of optional values, placing `None` values according to
+    /// `nulls`.
+    ///
+    /// Only the `Some` values are sent to the model. The `None` values are
+    /// extracted beforehand and reinserted afterwards, so the model never has
+    /// to guess where a null belongs. If every value is `None`, no request is
+    /// made.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{NullsPolicy, Vibesort};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let scores = vec![Some(3), None, Some(1), Some(2)];
+    /// let sorted = sorter.sort_options(&scores, NullsPolicy::First).await?;
+    /// println!("{:?}", sorted); // [None, Some(1), Some(2), Some(3)]
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_options<T>(
+        &self,
+        items: &[Option<T>],
+        nulls: NullsPolicy,
+    ) -> Result<Vec<Option<T>>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let values = items
+            .iter()
+            .flatten()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        let none_count = items.len() - values.len();
+
+        let sorted = if values.is_empty() {
+            Vec::new()
+        } else {
+            self.sort_with_report(&values).await?.items
+        };
+        let sorted = sorted
+            .into_iter()
+            .map(|value| serde_json::from_value(value).map(Some))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let nones = std::iter::repeat_with(|| None);
+        Ok(match nulls {
+            NullsPolicy::First => nones.take(none_count).chain(sorted).collect(),
+            NullsPolicy::Last => sorted.into_iter().chain(nones.take(none_count)).collect(),
+            NullsPolicy::Drop => sorted,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_options_places_nones_locally() {
+        let items = vec![Some(3), None, Some(1), None, Some(2)];
+
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+        let sorted = sorter
+            .sort_options(&items, NullsPolicy::First)
+            .await
+            .unwrap();
+        assert_eq!(sorted, vec![None, None, Some(1), Some(2), Some(3)]);
+        let user = backend.requests()[0].body["messages"][1]["content"].clone();
+        assert_eq!(user.as_str().unwrap(), "[3,1,2]");
+
+        let sorted = sorter
+            .sort_options(&items, NullsPolicy::Last)
+            .await
+            .unwrap();
+        assert_eq!(sorted, vec![Some(1), Some(2), Some(3), None, None]);
+
+        let sorted = sorter
+            .sort_options(&items, NullsPolicy::Drop)
+            .await
+            .unwrap();
+        assert_eq!(sorted, vec![Some(1), Some(2), Some(3)]);
+    }
+
+    #[tokio::test]
+    async fn test_sort_options_all_none_skips_request() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let sorted = sorter
+            .sort_options::<i32>(&[None, None], NullsPolicy::Last)
+            .await
+            .unwrap();
+        assert_eq!(sorted, vec![None, None]);
+        assert!(backend.requests().is_empty());
+    }
+}
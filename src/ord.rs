@@ -0,0 +1,341 @@
+//! An [`Ord`] adapter for LLM-ordered elements.
+
+use crate::cache::ComparisonCache;
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+/// A ranking of elements, learned from pairwise LLM comparisons, that
+/// [`VibeOrd`] consults synchronously.
+///
+/// [`Ord`] cannot wait for a request, so every comparison has to be known in
+/// advance: [`prefetch`](Self::prefetch) ranks the elements asynchronously,
+/// and [`wrap`](Self::wrap) then produces [`VibeOrd`] values that compare by
+/// their rank. Because the ranking is a single list, the resulting order is
+/// always a total order, even if the model's individual answers are not
+/// transitive, so it is safe to use with `slice::sort`, `BinaryHeap`, and
+/// `BTreeMap`.
+///
+/// Elements that were not prefetched compare after all ranked elements, and
+/// among themselves by their serialized JSON. Each [`VibeOrd`] keeps the
+/// ranking as it was when it was wrapped, so prefetching more elements later
+/// never changes how existing values compare; wrap elements again to use the
+/// extended ranking.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::collections::BTreeMap;
+/// use vibesort_rs::{ComparisonOracle, Vibesort};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let sorter = Vibesort::new(
+///     "your-api-key",
+///     "gpt-3.5-turbo",
+///     "https://api.openai.com/v1",
+/// )
+/// .criterion("by how spicy the dish is");
+///
+/// let dishes = ["korma", "vindaloo", "tikka masala"].map(String::from);
+/// let oracle = ComparisonOracle::new();
+/// oracle.prefetch(&sorter, &dishes).await?;
+///
+/// let mut menu = BTreeMap::new();
+/// for dish in dishes {
+///     menu.insert(oracle.wrap(dish)?, "on the menu");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ComparisonOracle {
+    inner: Arc<OracleInner>,
+}
+
+#[derive(Debug, Default)]
+struct OracleInner {
+    comparisons: ComparisonCache,
+    /// The ranked elements in order.
+    ordered: Mutex<Vec<Value>>,
+    /// The current ranking, shared with the values wrapped since it was made.
+    ranking: Mutex<Arc<Ranking>>,
+}
+
+/// The position of every ranked element, keyed by its serialized form.
+#[derive(Debug, Default)]
+struct Ranking {
+    ranks: HashMap<String, usize>,
+}
+
+impl ComparisonOracle {
+    /// Creates an oracle without any ranked elements.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses `comparisons` to look up and store the answers to comparisons,
+    /// for example a cache [loaded](ComparisonCache::load) from disk.
+    ///
+    /// Set the cache before the first [`prefetch`](Self::prefetch); clones of
+    /// the oracle made earlier keep using the previous cache.
+    pub fn comparisons(self, comparisons: ComparisonCache) -> Self {
+        let ranking = lock(&self.inner.ranking).clone();
+        let ordered = lock(&self.inner.ordered).clone();
+        Self {
+            inner: Arc::new(OracleInner {
+                comparisons,
+                ordered: Mutex::new(ordered),
+                ranking: Mutex::new(ranking),
+            }),
+        }
+    }
+
+    /// Ranks the items, asking `sorter` for every comparison that is needed.
+    ///
+    /// Each item is inserted into the ranking by binary search, so ranking `n`
+    /// elements takes about `n log2 n` comparisons, and comparisons already
+    /// made by this oracle are reused. The relative order of elements that
+    /// were already ranked never changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::JsonError`] if an item cannot be serialized,
+    /// or the error of a comparison (see [`Vibesort::sort`]). Items inserted
+    /// before the error stay ranked.
+    pub async fn prefetch<T: Serialize>(
+        &self,
+        sorter: &Vibesort<'_>,
+        items: &[T],
+    ) -> Result<(), VibesortError> {
+        if let Some((sorter, guard)) = sorter.admit()? {
+            return guard.run(Box::pin(self.prefetch(&sorter, items))).await;
+        }
+
+        for item in items {
+            let value = serde_json::to_value(item)?;
+            if self.ranking().ranks.contains_key(&key(&value)) {
+                continue;
+            }
+
+            // Search a snapshot, since comparisons are awaited
+            let ordered = lock(&self.inner.ordered).clone();
+            let (mut low, mut high) = (0, ordered.len());
+            while low < high {
+                let middle = (low + high) / 2;
+                let precedes = self
+                    .inner
+                    .comparisons
+                    .precedes(sorter, &ordered[middle], &value)
+                    .await?;
+                if precedes {
+                    low = middle + 1;
+                } else {
+                    high = middle;
+                }
+            }
+
+            // Clones of the oracle may have ranked other items meanwhile. They
+            // only insert, so the item goes right before the element that
+            // followed it in the snapshot, located under the lock it is
+            // inserted under
+            let mut current = lock(&self.inner.ordered);
+            if current.contains(&value) {
+                continue;
+            }
+            let index = ordered
+                .get(low)
+                .and_then(|next| current.iter().position(|ranked| ranked == next))
+                .unwrap_or(current.len());
+            current.insert(index, value);
+            let ranks = current
+                .iter()
+                .enumerate()
+                .map(|(rank, value)| (key(value), rank))
+                .collect();
+            *lock(&self.inner.ranking) = Arc::new(Ranking { ranks });
+        }
+        Ok(())
+    }
+
+    /// Wraps an item so that it is ordered by this oracle.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::JsonError`] if the item cannot be serialized.
+    pub fn wrap<T: Serialize>(&self, item: T) -> Result<VibeOrd<T>, VibesortError> {
+        let key = key(&serde_json::to_value(&item)?);
+        Ok(VibeOrd {
+            item,
+            key,
+            ranking: self.ranking(),
+        })
+    }
+
+    /// Returns the current ranking.
+    fn ranking(&self) -> Arc<Ranking> {
+        lock(&self.inner.ranking).clone()
+    }
+}
+
+/// Returns the key of an element in a [`Ranking`]: its JSON with sorted
+/// object keys, whatever the field order of its type.
+fn key(value: &Value) -> String {
+    value.to_string()
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// An element ordered by a [`ComparisonOracle`].
+///
+/// Two `VibeOrd` values are equal if their elements serialize to the same
+/// JSON. Values should only be compared with values wrapped from the same
+/// ranking; otherwise the ranking of the left-hand side is consulted.
+#[derive(Clone)]
+pub struct VibeOrd<T> {
+    item: T,
+    /// The serialized element.
+    key: String,
+    ranking: Arc<Ranking>,
+}
+
+impl<T> VibeOrd<T> {
+    /// Returns the wrapped element.
+    pub fn into_inner(self) -> T {
+        self.item
+    }
+}
+
+impl<T> Deref for VibeOrd<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.item
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for VibeOrd<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VibeOrd").field(&self.item).finish()
+    }
+}
+
+impl<T> Ord for VibeOrd<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.key == other.key {
+            return Ordering::Equal;
+        }
+        let ranks = &self.ranking.ranks;
+        match (ranks.get(&self.key), ranks.get(&other.key)) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => self.key.cmp(&other.key),
+        }
+    }
+}
+
+impl<T> PartialOrd for VibeOrd<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> PartialEq for VibeOrd<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<T> Eq for VibeOrd<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Order;
+    use crate::testing::MockBackend;
+    use std::collections::{BTreeSet, BinaryHeap};
+
+    #[tokio::test]
+    async fn test_vibe_ord_with_std_collections() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .order(Order::Descending);
+
+        let items = [4, 9, 1, 7, 3];
+        let oracle = ComparisonOracle::new();
+        oracle.prefetch(&sorter, &items).await.unwrap();
+        let requests = backend.requests().len();
+
+        let mut wrapped: Vec<_> = items.iter().map(|&i| oracle.wrap(i).unwrap()).collect();
+        wrapped.sort();
+        let sorted: Vec<i32> = wrapped.iter().map(|item| **item).collect();
+        assert_eq!(sorted, vec![9, 7, 4, 3, 1]);
+
+        let set: BTreeSet<_> = items.iter().map(|&i| oracle.wrap(i).unwrap()).collect();
+        assert_eq!(set.first().map(|item| **item), Some(9));
+
+        // A max-heap pops the element that sorts last
+        let mut heap: BinaryHeap<_> = items.iter().map(|&i| oracle.wrap(i).unwrap()).collect();
+        assert_eq!(heap.pop().map(VibeOrd::into_inner), Some(1));
+
+        // Ordering never requests anything after the prefetch
+        assert_eq!(backend.requests().len(), requests);
+    }
+
+    #[tokio::test]
+    async fn test_vibe_ord_with_struct_elements() {
+        // Serializes its fields in declaration order, unlike `Value`
+        #[derive(Debug, Clone, Copy, Serialize)]
+        struct Task {
+            priority: u32,
+            name: &'static str,
+        }
+
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+        let tasks = [
+            Task {
+                priority: 1,
+                name: "c",
+            },
+            Task {
+                priority: 3,
+                name: "a",
+            },
+            Task {
+                priority: 2,
+                name: "b",
+            },
+        ];
+        let oracle = ComparisonOracle::new();
+        oracle.prefetch(&sorter, &tasks).await.unwrap();
+        oracle.prefetch(&sorter, &tasks).await.unwrap();
+
+        let mut wrapped: Vec<_> = tasks.map(|task| oracle.wrap(task).unwrap()).into();
+        wrapped.sort();
+        let names: Vec<&str> = wrapped.iter().map(|task| task.name).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_unranked_elements_sort_last() {
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(MockBackend::new());
+        let oracle = ComparisonOracle::new();
+        oracle.prefetch(&sorter, &[5, 2]).await.unwrap();
+
+        let mut wrapped: Vec<_> = [9, 5, 0, 2].map(|i| oracle.wrap(i).unwrap()).into();
+        wrapped.sort();
+        let sorted: Vec<i32> = wrapped.into_iter().map(VibeOrd::into_inner).collect();
+        assert_eq!(sorted, vec![2, 5, 0, 9]);
+    }
+}
@@ -0,0 +1,133 @@
+//! Sorting pairs by one of their elements or by both.
+
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Which part of a pair [`Vibesort::sort_pairs`] sorts by.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PairKey {
+    /// Sort by the first element of each pair.
+    First,
+
+    /// Sort by the second element of each pair.
+    Second,
+
+    /// Sort by a criterion over both elements, e.g. "by how well the wine
+    /// pairs with the dish".
+    Both(String),
+}
+
+impl PairKey {
+    /// Creates a key sorting by a criterion over both elements.
+    pub fn both(criterion: impl Into<String>) -> Self {
+        PairKey::Both(criterion.into())
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts pairs by their first element, their second element, or a
+    /// criterion over both, returning the pairs intact.
+    ///
+    /// Pairs are sent as two-element JSON arrays. With [`PairKey::First`] or
+    /// [`PairKey::Second`], the configured criterion, if any, is applied to
+    /// the selected element; with [`PairKey::Both`] it is replaced. The
+    /// configured [`order`](Self::order) applies.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{PairKey, Vibesort};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .criterion("by how spicy the dish is");
+    ///
+    /// let menu = vec![
+    ///     ("vindaloo".to_string(), 14.5),
+    ///     ("korma".to_string(), 12.0),
+    /// ];
+    /// let mildest_first = sorter.sort_pairs(&menu, PairKey::First).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_pairs<K, V>(
+        &self,
+        items: &[(K, V)],
+        key: PairKey,
+    ) -> Result<Vec<(K, V)>, VibesortError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let selected = |element: &str| match &self.criterion {
+            Some(criterion) => format!(
+                "by the {} element of each [first, second] pair, {}",
+                element, criterion
+            ),
+            None => format!("by the {} element of each [first, second] pair", element),
+        };
+        let criterion = match key {
+            PairKey::First => selected("first"),
+            PairKey::Second => selected("second"),
+            PairKey::Both(criterion) => format!(
+                "by both elements of each [first, second] pair, {}",
+                criterion
+            ),
+        };
+        let sorter = self.clone().criterion(criterion);
+        Ok(sorter.sort_with_report(items).await?.items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    fn system_prompt(backend: &MockBackend) -> String {
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        system.as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_sort_pairs_by_element() {
+        let backend = Arc::new(MockBackend::new().respond_with(r#"[["b", 1], ["a", 2]]"#));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .criterion("numerically");
+
+        let pairs = vec![("a".to_string(), 2), ("b".to_string(), 1)];
+        let sorted = sorter.sort_pairs(&pairs, PairKey::Second).await.unwrap();
+        assert_eq!(sorted, vec![("b".to_string(), 1), ("a".to_string(), 2)]);
+        assert!(system_prompt(&backend).contains(
+            "criterion: by the second element of each [first, second] pair, numerically"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sort_pairs_by_both() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .criterion("ignored");
+
+        let pairs = vec![(2, 1), (1, 2)];
+        sorter
+            .sort_pairs(&pairs, PairKey::both("by their sum"))
+            .await
+            .unwrap();
+        let system = system_prompt(&backend);
+        assert!(system.contains("by both elements of each [first, second] pair, by their sum"));
+        assert!(!system.contains("ignored"));
+    }
+}
@@ -0,0 +1,234 @@
+//! Parsing of the LLM's response content.
+
+use crate::VibesortError;
+use serde::de::DeserializeOwned;
+
+/// Parses the content of an LLM response as a JSON array.
+///
+/// Surrounding whitespace and markdown code fences (e.g. ```` ```json ````) are
+/// stripped before parsing.
+///
+/// # Errors
+///
+/// Returns [`VibesortError::ParseError`] with the content that was returned if
+/// it cannot be parsed as an array of `T`.
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::parse::parse_array;
+///
+/// let sorted: Vec<u32> = parse_array("```json\n[1, 2, 3]\n```").unwrap();
+/// assert_eq!(sorted, vec![1, 2, 3]);
+/// ```
+pub fn parse_array<T: DeserializeOwned>(content: &str) -> Result<Vec<T>, VibesortError> {
+    parse_json(content, "array")
+}
+
+/// Parses the content of an LLM response as a JSON array like
+/// [`parse_array`], falling back to [repairing](repair_array) content that is
+/// almost an array.
+///
+/// Returns the array and whether it had to be repaired. A repaired array may
+/// have lost the elements cut off a truncated reply, so it should always be
+/// checked against the input.
+///
+/// # Errors
+///
+/// Returns the error of [`parse_array`] if the repaired content cannot be
+/// parsed either.
+pub(crate) fn parse_array_repaired<T: DeserializeOwned>(
+    content: &str,
+) -> Result<(Vec<T>, bool), VibesortError> {
+    let error = match parse_array(content) {
+        Ok(items) => return Ok((items, false)),
+        Err(e) => e,
+    };
+    repair_array(content)
+        .and_then(|repaired| serde_json::from_str(&repaired).ok())
+        .map(|items| (items, true))
+        .ok_or(error)
+}
+
+/// Turns content that is almost a JSON array into one: text before the first
+/// `[` and after the array is removed, trailing commas are dropped, and an
+/// unterminated string and unclosed brackets at the end are closed.
+///
+/// Returns `None` if the content has no `[`. The result is not guaranteed to
+/// be valid JSON.
+pub(crate) fn repair_array(content: &str) -> Option<String> {
+    let start = content.find('[')?;
+    let mut repaired = String::with_capacity(content.len() - start + 8);
+    let mut closers = Vec::new();
+    let (mut in_string, mut escaped) = (false, false);
+    for c in content[start..].chars() {
+        if in_string {
+            repaired.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '[' => closers.push(']'),
+            '{' => closers.push('}'),
+            ']' | '}' => {
+                // A bracket that closes nothing ends the array
+                if closers.last() != Some(&c) {
+                    break;
+                }
+                closers.pop();
+                trim_trailing_comma(&mut repaired);
+                repaired.push(c);
+                if closers.is_empty() {
+                    return Some(repaired);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        repaired.push(c);
+    }
+
+    if in_string {
+        if escaped {
+            repaired.pop();
+        }
+        repaired.push('"');
+    }
+    while let Some(closer) = closers.pop() {
+        trim_trailing_comma(&mut repaired);
+        repaired.push(closer);
+    }
+    Some(repaired)
+}
+
+/// Removes trailing whitespace and a trailing comma.
+fn trim_trailing_comma(json: &mut String) {
+    json.truncate(json.trim_end().len());
+    if json.ends_with(',') {
+        json.pop();
+    }
+}
+
+/// Parses the content of an LLM response as JSON of the given shape.
+///
+/// `kind` names the expected shape (e.g. `"array"`) in error messages.
+pub(crate) fn parse_json<U: DeserializeOwned>(
+    content: &str,
+    kind: &str,
+) -> Result<U, VibesortError> {
+    let json = strip_code_fence(content.trim());
+
+    serde_json::from_str(json).map_err(|e| {
+        VibesortError::ParseError(format!(
+            "Failed to parse as JSON {}: {}\nLLM returned: {}",
+            kind, e, json
+        ))
+    })
+}
+
+/// Re-appends a stop sequence that the provider cut off the end of a reply.
+///
+/// Providers omit the matched stop sequence from the content, so stopping on
+/// `"]"` returns `[1, 2, 3`. If the content is not valid JSON but becomes valid
+/// with one of the stop sequences appended, that version is returned.
+pub(crate) fn restore_stop_sequence(content: String, stop: &[String]) -> String {
+    let is_json = |content: &str| parse_json::<serde_json::Value>(content, "value").is_ok();
+    if is_json(&content) {
+        return content;
+    }
+    stop.iter()
+        .map(|sequence| format!("{}{}", content.trim_end(), sequence))
+        .find(|restored| is_json(restored))
+        .unwrap_or(content)
+}
+
+/// Strips markdown code blocks if present (e.g., ```json ... ```).
+fn strip_code_fence(content: &str) -> &str {
+    let Some(mut inner) = content.strip_prefix("```") else {
+        return content;
+    };
+
+    // Remove the optional language identifier up to the first newline
+    if let Some(start_idx) = inner.find('\n') {
+        inner = &inner[start_idx + 1..];
+    }
+    // Remove the closing ```
+    if let Some(stripped) = inner.trim_end().strip_suffix("```") {
+        inner = stripped;
+    }
+    inner.trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_stop_sequence() {
+        let stop = vec!["\n\n".to_string(), "]".to_string()];
+        assert_eq!(restore_stop_sequence("[1, 2".to_string(), &stop), "[1, 2]");
+        assert_eq!(restore_stop_sequence("[1]".to_string(), &stop), "[1]");
+        assert_eq!(restore_stop_sequence("nope".to_string(), &stop), "nope");
+    }
+
+    #[test]
+    fn test_parse_array_strips_code_fences() {
+        let plain: Vec<i32> = parse_array(" [3, 2] ").unwrap();
+        assert_eq!(plain, vec![3, 2]);
+
+        let fenced: Vec<i32> = parse_array("```json\n[1,2]\n```").unwrap();
+        assert_eq!(fenced, vec![1, 2]);
+
+        let bare_fence: Vec<i32> = parse_array("```[1,2]```").unwrap();
+        assert_eq!(bare_fence, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_repair_array() {
+        for (content, repaired) in [
+            ("Sure! Here it is: [1, 2, 3]\nHope this helps.", "[1, 2, 3]"),
+            ("[1, 2, 3,]", "[1, 2, 3]"),
+            ("[1, 2, 3", "[1, 2, 3]"),
+            (r#"["a", "b"#, r#"["a", "b"]"#),
+            (r#"["a", "b\"#, r#"["a", "b"]"#),
+            (
+                r#"[{"id": 1}, {"id": [2, "]"], "#,
+                r#"[{"id": 1}, {"id": [2, "]"]}]"#,
+            ),
+            ("[1, 2]]", "[1, 2]"),
+            ("[1, 2}", "[1, 2]"),
+        ] {
+            assert_eq!(repair_array(content).unwrap(), repaired, "{}", content);
+        }
+        assert_eq!(repair_array("no array"), None);
+    }
+
+    #[test]
+    fn test_parse_array_repaired() {
+        let (items, repaired): (Vec<i32>, bool) = parse_array_repaired("[3, 1]").unwrap();
+        assert_eq!((items, repaired), (vec![3, 1], false));
+
+        let (items, repaired): (Vec<i32>, bool) =
+            parse_array_repaired("The sorted array is [1, 3,").unwrap();
+        assert_eq!((items, repaired), (vec![1, 3], true));
+
+        assert!(matches!(
+            parse_array_repaired::<i32>(r#"["a", "b"]"#),
+            Err(VibesortError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_array_reports_content() {
+        match parse_array::<i32>("not an array").unwrap_err() {
+            VibesortError::ParseError(msg) => assert!(msg.contains("not an array")),
+            _ => panic!("Expected ParseError"),
+        }
+    }
+}
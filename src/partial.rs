@@ -0,0 +1,155 @@
+//! Sorting that keeps completed work when a request fails.
+
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// The outcome of [`Vibesort::sort_partial`]: the elements that were sorted,
+/// and the rest if the sort did not finish.
+#[derive(Debug)]
+pub struct PartialSort<T> {
+    /// The elements that were sorted, in order. All of them if the sort
+    /// finished.
+    pub sorted_prefix: Vec<T>,
+
+    /// The elements that were not sorted relative to `sorted_prefix`. Empty
+    /// if the sort finished.
+    pub unsorted_remainder: Vec<T>,
+
+    /// The error that stopped the sort, if it did not finish.
+    pub error: Option<VibesortError>,
+}
+
+impl<T> PartialSort<T> {
+    /// Returns whether the sort finished.
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts the items, returning what was sorted so far instead of an error
+    /// if a request fails after exhausting its retries.
+    ///
+    /// Inputs with more elements than the [chunk size](Self::chunk_size) are
+    /// sorted in chunks right away. When a chunk fails, the chunks sorted
+    /// before it are still merged into
+    /// [`sorted_prefix`](PartialSort::sorted_prefix); if a merge fails, only
+    /// the first sorted chunk is kept there. Every other element is returned
+    /// in [`unsorted_remainder`](PartialSort::unsorted_remainder), so a long
+    /// job can resume with just those. Smaller inputs are sorted with one
+    /// request, so if it fails nothing is sorted.
+    ///
+    /// A [time budget](Self::time_budget) that runs out stops the sort like a
+    /// failed request.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::JsonError`] if the items cannot be serialized
+    /// or deserialized. Every other error is returned in
+    /// [`PartialSort::error`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .chunk_size(100);
+    ///
+    /// let items: Vec<u32> = (0..1000).rev().collect();
+    /// let partial = sorter.sort_partial(&items).await?;
+    /// if let Some(error) = &partial.error {
+    ///     eprintln!(
+    ///         "sorted {} of {} items before failing: {}",
+    ///         partial.sorted_prefix.len(),
+    ///         items.len(),
+    ///         error
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_partial<T>(&self, items: &[T]) -> Result<PartialSort<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        if let Some((sorter, guard)) = self.admit()? {
+            return guard.run(Box::pin(sorter.sort_partial(items))).await;
+        }
+        if let Some(sorter) = self.start_budget() {
+            return Box::pin(sorter.sort_partial(items)).await;
+        }
+
+        if let Some(chunk_size) = self.chunk_size
+            && items.len() > chunk_size
+        {
+            let progress = self.sort_chunks(items).await?;
+            return Ok(PartialSort {
+                sorted_prefix: progress.sorted,
+                unsorted_remainder: progress.remainder,
+                error: progress.error,
+            });
+        }
+
+        match self.sort_with_report(items).await {
+            Ok(result) => Ok(PartialSort {
+                sorted_prefix: result.items,
+                unsorted_remainder: Vec::new(),
+                error: None,
+            }),
+            Err(error) => Ok(PartialSort {
+                sorted_prefix: Vec::new(),
+                unsorted_remainder: items
+                    .iter()
+                    .map(|item| Ok(serde_json::from_value(serde_json::to_value(item)?)?))
+                    .collect::<Result<_, VibesortError>>()?,
+                error: Some(error),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+
+    #[tokio::test]
+    async fn test_sort_partial_keeps_completed_chunks() {
+        // The third chunk fails, the first two are still merged
+        let backend = MockBackend::new()
+            .respond_with("[4, 5, 6]")
+            .respond_with("[1, 2, 3]")
+            .respond_with_status(401, "invalid api key");
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend)
+            .chunk_size(3);
+
+        let partial = sorter
+            .sort_partial(&[6, 5, 4, 3, 2, 1, 9, 8, 7])
+            .await
+            .unwrap();
+        assert!(!partial.is_complete());
+        assert!(matches!(partial.error, Some(VibesortError::AuthFailed(_))));
+        assert_eq!(partial.sorted_prefix, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(partial.unsorted_remainder, vec![9, 8, 7]);
+    }
+
+    #[tokio::test]
+    async fn test_sort_partial_complete() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new())
+            .chunk_size(2);
+
+        let partial = sorter.sort_partial(&[3, 1, 2]).await.unwrap();
+        assert!(partial.is_complete());
+        assert_eq!(partial.sorted_prefix, vec![1, 2, 3]);
+        assert!(partial.unsorted_remainder.is_empty());
+    }
+}
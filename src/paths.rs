@@ -0,0 +1,153 @@
+//! Sorting file paths by their metadata and a prose criterion.
+
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The facts about a file sent to the model.
+#[derive(Serialize)]
+struct FileFacts {
+    path: String,
+    kind: &'static str,
+    size_bytes: u64,
+    days_since_modified: Option<f64>,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts file paths by a criterion over their metadata, such as "old large
+    /// log files first".
+    ///
+    /// The size, kind (file, directory, or symlink), and age of every path is
+    /// read locally and sent alongside it, so the model can combine these
+    /// facts with what the names suggest in one request. Ages are sent in days
+    /// since the last modification, rounded to a tenth of a day; they are left
+    /// out where the platform does not record them. Symlinks are not
+    /// followed. The paths are returned exactly as given. The configured
+    /// [`order`](Self::order) applies; the configured criterion is replaced by
+    /// `criterion`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::IoError`] if the metadata of a path cannot be
+    /// read. Otherwise this method can return the same errors as
+    /// [`sort`](Self::sort); [`VibesortError::VerificationFailed`] is returned
+    /// if the reply does not name every path exactly once.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let paths = ["/var/log/syslog", "/var/log/app/debug.log", "/var/log/auth.log"];
+    /// let cleanup_first = sorter
+    ///     .sort_paths(&paths, "old large log files first")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_paths<P>(
+        &self,
+        paths: &[P],
+        criterion: &str,
+    ) -> Result<Vec<PathBuf>, VibesortError>
+    where
+        P: AsRef<Path>,
+    {
+        let now = SystemTime::now();
+        let mut facts = Vec::with_capacity(paths.len());
+        for path in paths {
+            let path = path.as_ref();
+            let metadata = tokio::fs::symlink_metadata(path).await?;
+            let kind = if metadata.is_symlink() {
+                "symlink"
+            } else if metadata.is_dir() {
+                "directory"
+            } else {
+                "file"
+            };
+            let days_since_modified = metadata.modified().ok().map(|modified| {
+                let age = now.duration_since(modified).unwrap_or_default();
+                (age.as_secs_f64() / 8640.0).round() / 10.0
+            });
+            facts.push(FileFacts {
+                path: path.display().to_string(),
+                kind,
+                size_bytes: metadata.len(),
+                days_since_modified,
+            });
+        }
+
+        let instruction = format!(
+            "(files, described by their path, kind, size in bytes, and days since they were last modified) {}",
+            self.sort_instruction_for(Some(criterion))
+        );
+        let indices = self
+            .sort_indexed(Operation::Paths, &facts, &instruction)
+            .await?;
+        Ok(indices
+            .into_iter()
+            .map(|index| paths[index].as_ref().to_path_buf())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_sort_paths_sends_metadata() {
+        let dir = std::env::temp_dir().join(format!("vibesort-paths-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let old = dir.join("old.log");
+        let new = dir.join("new.log");
+        std::fs::write(&old, vec![b'x'; 2048]).unwrap();
+        std::fs::write(&new, b"x").unwrap();
+        let ten_days = Duration::from_secs(10 * 86400);
+        std::fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(SystemTime::now() - ten_days)
+            .unwrap();
+
+        let backend = Arc::new(MockBackend::new().respond_with("[1, 0]"));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+        let sorted = sorter
+            .sort_paths(&[&new, &old], "old large log files first")
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(sorted, vec![old.clone(), new]);
+
+        let user = backend.requests()[0].body["messages"][1]["content"].clone();
+        let payload: serde_json::Value = serde_json::from_str(user.as_str().unwrap()).unwrap();
+        assert_eq!(payload[1]["item"]["size_bytes"], 2048);
+        assert_eq!(payload[1]["item"]["kind"], "file");
+        assert_eq!(payload[1]["item"]["days_since_modified"], 10.0);
+        assert_eq!(payload[1]["item"]["path"], old.display().to_string());
+    }
+
+    #[tokio::test]
+    async fn test_sort_paths_missing_file() {
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(MockBackend::new());
+
+        let err = sorter
+            .sort_paths(&["/nonexistent/vibesort/file"], "by size")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::IoError(_)));
+    }
+}
@@ -0,0 +1,188 @@
+//! Previewing the requests a sort would make.
+
+use crate::Vibesort;
+use crate::engine::Engine;
+use crate::estimate::merge_frontiers;
+use std::ops::Range;
+
+/// What the requests of a [`PlanStage`] do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum StageKind {
+    /// Sorting the input, or chunks of it, one request per chunk.
+    Sort,
+
+    /// Merging sorted runs in rounds of requests.
+    Merge,
+
+    /// Reviewing the sorted result in reflect mode.
+    Reflect,
+}
+
+/// One stage of a [`SortPlan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanStage {
+    /// What the requests of this stage do.
+    pub kind: StageKind,
+
+    /// The model the requests of this stage are sent to.
+    pub model: String,
+
+    /// The ranges of input indices sorted by each request of a
+    /// [`Sort`](StageKind::Sort) stage. Empty for other stages.
+    pub chunks: Vec<Range<usize>>,
+
+    /// The number of requests in this stage. For merge and reflect stages
+    /// this is an estimate or an upper bound, since it depends on the model's
+    /// replies.
+    pub requests: usize,
+
+    /// Whether the stage only runs if the single request of the sort is
+    /// rejected for exceeding the model's context window, as part of the
+    /// [fallback to chunks](Vibesort::chunk_size).
+    pub fallback: bool,
+}
+
+/// The stages of requests a sort would make, returned by
+/// [`Vibesort::plan`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SortPlan {
+    /// The stages, in the order they run.
+    pub stages: Vec<PlanStage>,
+}
+
+impl SortPlan {
+    /// Returns the number of API calls if the single request of the sort
+    /// fits the model's context window.
+    pub fn api_calls(&self) -> usize {
+        self.stages
+            .iter()
+            .filter(|stage| !stage.fallback)
+            .map(|stage| stage.requests)
+            .sum()
+    }
+
+    /// Returns the number of API calls if the single request is rejected and
+    /// the input is sorted in chunks, counting the rejected request.
+    pub fn fallback_api_calls(&self) -> Option<usize> {
+        self.is_chunked().then(|| {
+            self.stages
+                .iter()
+                .filter(|stage| stage.fallback || stage.kind == StageKind::Sort)
+                .map(|stage| stage.requests)
+                .sum()
+        })
+    }
+
+    /// Returns whether the input is sorted in chunks if the single request
+    /// is rejected.
+    pub fn is_chunked(&self) -> bool {
+        self.stages.iter().any(|stage| stage.fallback)
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Returns the plan of requests that sorting the items would make,
+    /// without calling the API.
+    ///
+    /// Every sort starts with a single request. For inputs with more elements
+    /// than the [chunk size](Self::chunk_size), it is followed by the
+    /// [`fallback`](PlanStage::fallback) stages that run if that request is
+    /// rejected for exceeding the context window: sorting in chunks, then one
+    /// merge stage per merge round. The number of merge requests is
+    /// estimated as in [`estimate`](Self::estimate); reflect stages count the
+    /// [maximum number of passes](Self::reflect), and only run after a
+    /// single request that succeeds. Retries are not included.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-4o-mini",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .chunk_size(100);
+    ///
+    /// let items: Vec<u32> = (0..1000).collect();
+    /// let plan = sorter.plan(&items);
+    /// for stage in &plan.stages {
+    ///     println!("{:?} on {}: {} requests", stage.kind, stage.model, stage.requests);
+    /// }
+    /// println!("{} API calls if the input fits", plan.api_calls());
+    /// println!("{:?} API calls if it does not", plan.fallback_api_calls());
+    /// ```
+    pub fn plan<T>(&self, items: &[T]) -> SortPlan {
+        let stage = |kind, chunks, requests, fallback| PlanStage {
+            kind,
+            model: self.model.to_string(),
+            chunks,
+            requests,
+            fallback,
+        };
+
+        let whole = std::iter::once(0..items.len()).collect();
+        let mut stages = vec![stage(StageKind::Sort, whole, 1, false)];
+        if self.reflect_passes > 0 && !matches!(self.engine, Engine::Local(_)) {
+            stages.push(stage(
+                StageKind::Reflect,
+                Vec::new(),
+                self.reflect_passes,
+                false,
+            ));
+        }
+        if let Some(chunk_size) = self.chunk_size
+            && items.len() > chunk_size
+        {
+            let chunks: Vec<Range<usize>> = (0..items.len())
+                .step_by(chunk_size)
+                .map(|start| start..(start + chunk_size).min(items.len()))
+                .collect();
+            let runs: Vec<usize> = chunks.iter().map(ExactSizeIterator::len).collect();
+            stages.push(stage(StageKind::Sort, chunks, runs.len(), true));
+            for round in merge_frontiers(&runs, chunk_size) {
+                stages.push(stage(StageKind::Merge, Vec::new(), round.len(), true));
+            }
+        }
+        SortPlan { stages }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_single_request() {
+        let sorter = Vibesort::new("key", "model", "http://mock").reflect(2);
+
+        let plan = sorter.plan(&[3, 1, 2]);
+        assert!(!plan.is_chunked());
+        assert_eq!(plan.api_calls(), 3);
+        assert_eq!(plan.fallback_api_calls(), None);
+        assert_eq!(plan.stages[0].chunks.len(), 1);
+        assert_eq!(plan.stages[0].chunks[0], 0..3);
+        assert_eq!(plan.stages[1].kind, StageKind::Reflect);
+    }
+
+    #[test]
+    fn test_plan_chunked() {
+        let sorter = Vibesort::new("key", "model", "http://mock").chunk_size(4);
+        let items: Vec<u32> = (0..10).collect();
+
+        let plan = sorter.plan(&items);
+        assert!(plan.is_chunked());
+        assert_eq!(plan.stages[0].chunks, vec![0..10]);
+        assert!(!plan.stages[0].fallback);
+        assert_eq!(plan.stages[1].kind, StageKind::Sort);
+        assert_eq!(plan.stages[1].chunks, vec![0..4, 4..8, 8..10]);
+        assert_eq!(plan.stages[1].requests, 3);
+        assert!(plan.stages[2..].iter().all(|s| s.kind == StageKind::Merge));
+        assert!(plan.stages[1..].iter().all(|s| s.fallback));
+        assert!(plan.stages.iter().all(|s| s.model == "model"));
+        assert_eq!(plan.api_calls(), 1);
+        assert!(plan.fallback_api_calls().unwrap() > 4);
+    }
+}
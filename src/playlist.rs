@@ -0,0 +1,142 @@
+//! Ordering songs to follow an energy curve.
+
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+
+/// The shape of the energy of a playlist over time, for
+/// [`Vibesort::sort_playlist`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnergyCurve {
+    /// Start calm and build steadily to the most energetic songs at the end.
+    BuildUp,
+
+    /// Start with the most energetic songs and wind down to the calmest.
+    WindDown,
+
+    /// Start calm, peak about two thirds of the way through, then come down
+    /// for the last few songs.
+    Peak,
+
+    /// Alternate between rising and falling energy in several waves.
+    Wave,
+
+    /// Keep the energy even, avoiding jumps between neighbouring songs.
+    Steady,
+}
+
+impl EnergyCurve {
+    /// Returns the shape as used in the prompt.
+    fn describe(&self) -> &'static str {
+        match self {
+            EnergyCurve::BuildUp => {
+                "start with the calmest songs and build steadily to the most energetic songs at the end"
+            }
+            EnergyCurve::WindDown => {
+                "start with the most energetic songs and wind down steadily to the calmest songs at the end"
+            }
+            EnergyCurve::Peak => {
+                "start calm, rise to the most energetic songs about two thirds of the way through, then come down again for the last few songs"
+            }
+            EnergyCurve::Wave => {
+                "rise and fall in several waves, so that energetic and calm stretches take turns"
+            }
+            EnergyCurve::Steady => {
+                "stay as even as possible, so that neighbouring songs have similar energy and there are no sudden jumps"
+            }
+        }
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Orders songs so that the energy of the playlist follows a curve, such
+    /// as building up to the end or peaking in the middle.
+    ///
+    /// Tracks can be titles, descriptions, or structs with fields such as
+    /// artist and genre. The model only replies with the positions of the
+    /// tracks, which are returned from the input. The curve replaces the
+    /// configured [`order`](Self::order) and criterion.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the reply does not
+    /// name every track exactly once.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{EnergyCurve, Vibesort};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let tracks = [
+    ///     "Daft Punk - One More Time",
+    ///     "Erik Satie - Gymnopédie No. 1",
+    ///     "The Prodigy - Firestarter",
+    /// ];
+    /// let warm_up = sorter.sort_playlist(&tracks, EnergyCurve::BuildUp).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_playlist<T>(
+        &self,
+        tracks: &[T],
+        curve: EnergyCurve,
+    ) -> Result<Vec<T>, VibesortError>
+    where
+        T: Serialize + Clone,
+    {
+        let instruction = format!(
+            "(songs, each described by its title or a description) {}",
+            Self::shape_instruction(
+                "the musical energy of the playlist (tempo, loudness, and intensity)",
+                curve.describe()
+            )
+        );
+        let indices = self
+            .sort_indexed(Operation::Playlist, tracks, &instruction)
+            .await?;
+        Ok(indices
+            .into_iter()
+            .map(|index| tracks[index].clone())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_playlist_follows_curve() {
+        let backend = Arc::new(MockBackend::new().respond_with("[1, 0, 2]"));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .criterion("ignored");
+
+        let tracks = ["Firestarter", "Gymnopédie No. 1", "Clair de Lune"];
+        let sorted = sorter
+            .sort_playlist(&tracks, EnergyCurve::Peak)
+            .await
+            .unwrap();
+        assert_eq!(sorted, ["Gymnopédie No. 1", "Firestarter", "Clair de Lune"]);
+
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        let system = system.as_str().unwrap();
+        assert!(
+            system.contains(
+                "follows this shape from the first element to the last: start calm, rise"
+            )
+        );
+        assert!(!system.contains("ignored"));
+        assert!(!system.contains("ascending"));
+    }
+}
@@ -0,0 +1,172 @@
+//! Sorting nested structs by fields selected with JSON pointers.
+
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+impl<'a> Vibesort<'a> {
+    /// Sorts the items by nested fields selected with
+    /// [JSON pointers](https://www.rfc-editor.org/rfc/rfc6901), such as
+    /// `/address/city`, without flattening them first. The empty pointer `""`
+    /// selects the whole element.
+    ///
+    /// Later pointers break ties of earlier ones. The configured criterion,
+    /// if any, is applied to the selected values, and the configured
+    /// [`order`](Self::order) applies. Every pointer is checked against the
+    /// first element, serialized, before anything is sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::InvalidKey`] if no pointer is given, or if a
+    /// pointer is malformed or selects nothing in the first element.
+    /// Otherwise this method can return the same errors as
+    /// [`sort`](Self::sort).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use serde::{Deserialize, Serialize};
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Address {
+    ///     city: String,
+    /// }
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Customer {
+    ///     name: String,
+    ///     address: Address,
+    /// }
+    ///
+    /// # async fn example(customers: Vec<Customer>) -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .criterion("from north to south");
+    ///
+    /// let sorted = sorter
+    ///     .sort_by_pointers(&customers, &["/address/city", "/name"])
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_by_pointers<T>(
+        &self,
+        items: &[T],
+        pointers: &[&str],
+    ) -> Result<Vec<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        if pointers.is_empty() {
+            return Err(VibesortError::InvalidKey(String::from(
+                "no JSON pointer given",
+            )));
+        }
+        let Some(sample) = items.first() else {
+            return Ok(Vec::new());
+        };
+        let sample = serde_json::to_value(sample)?;
+        for pointer in pointers {
+            if !pointer.is_empty() && !pointer.starts_with('/') {
+                return Err(VibesortError::InvalidKey(format!(
+                    "{:?} is not a JSON pointer (it must be empty or start with '/')",
+                    pointer
+                )));
+            }
+            if sample.pointer(pointer).is_none() {
+                return Err(VibesortError::InvalidKey(format!(
+                    "{:?} selects nothing in the first element",
+                    pointer
+                )));
+            }
+        }
+
+        let fields = pointers
+            .iter()
+            .map(|pointer| match *pointer {
+                "" => "\"\" (the whole element)",
+                pointer => pointer,
+            })
+            .collect::<Vec<_>>()
+            .join(", then by ");
+        let criterion = match &self.criterion {
+            Some(criterion) => format!(
+                "by the value at the JSON pointer {} of each element, {}",
+                fields, criterion
+            ),
+            None => format!(
+                "by the value at the JSON pointer {} of each element",
+                fields
+            ),
+        };
+        let sorter = self.clone().criterion(criterion);
+        Ok(sorter.sort_with_report(items).await?.items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use serde_json::{Value, json};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_by_pointers() {
+        let items = vec![
+            json!({"name": "b", "address": {"city": "Oslo"}}),
+            json!({"name": "a", "address": {"city": "Bergen"}}),
+        ];
+        let backend =
+            Arc::new(MockBackend::new().respond_with(json!([items[1], items[0]]).to_string()));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let sorted = sorter
+            .sort_by_pointers(&items, &["/address/city", "/name"])
+            .await
+            .unwrap();
+        assert_eq!(sorted[0]["name"], "a");
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        assert!(system.as_str().unwrap().contains(
+            "by the value at the JSON pointer /address/city, then by /name of each element"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sort_by_pointers_validates_before_sending() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+        let items: Vec<Value> = vec![json!({"address": {"city": "Oslo"}})];
+
+        for pointer in ["/address/zip", "address/city"] {
+            let err = sorter
+                .sort_by_pointers(&items, &[pointer])
+                .await
+                .unwrap_err();
+            assert!(matches!(err, VibesortError::InvalidKey(_)));
+        }
+        assert!(backend.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sort_by_empty_pointer() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let sorted = sorter
+            .sort_by_pointers(&[json!("b"), json!("a")], &[""])
+            .await
+            .unwrap();
+        assert_eq!(sorted, [json!("a"), json!("b")]);
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        assert!(
+            system.as_str().unwrap().contains(
+                "by the value at the JSON pointer \"\" (the whole element) of each element"
+            )
+        );
+    }
+}
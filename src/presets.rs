@@ -0,0 +1,355 @@
+//! Ready-made prompts for common sorting tasks.
+//!
+//! A [`Preset`] bundles a tuned prompt with the fields the model reports for
+//! every element, so everyday tasks such as triaging an inbox do not need a
+//! prompt of their own. Sort with a preset using [`Vibesort::sort_preset`].
+
+use crate::indexed::{check_positions, describe_payload, indexed_payload};
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError, parse};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// A tuned prompt together with the fields the model reports for every
+/// element.
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::presets::Preset;
+///
+/// let preset = Preset::new(
+///     "bug_severity",
+///     "The elements are bug reports. Order them from the most to the least severe.",
+/// )
+/// .field("severity", "one of \"critical\", \"major\", \"minor\"");
+/// assert_eq!(preset.name(), "bug_severity");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preset {
+    name: String,
+    instructions: String,
+    fields: Vec<(String, String)>,
+    rank: Option<(String, Vec<String>)>,
+}
+
+impl Preset {
+    /// Creates a preset from a name and instructions describing the elements
+    /// and the order to sort them in.
+    ///
+    /// The instructions are followed by a description of the payload and the
+    /// reply, so they only need to describe the task.
+    pub fn new(name: impl Into<String>, instructions: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            instructions: instructions.into(),
+            fields: Vec::new(),
+            rank: None,
+        }
+    }
+
+    /// Adds a field the model reports for every element, with a description
+    /// of its values.
+    pub fn field(mut self, name: impl Into<String>, description: impl Into<String>) -> Self {
+        self.fields.push((name.into(), description.into()));
+        self
+    }
+
+    /// Guarantees that elements are ranked by the values of a field, in the
+    /// given order, no matter how the model ordered them.
+    ///
+    /// After the reply, the elements are sorted locally by the position of
+    /// their value of `field` in `values`, keeping the model's order among
+    /// equal values. Elements with any other value go last.
+    pub fn rank_by<S: Into<String>>(
+        mut self,
+        field: impl Into<String>,
+        values: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.rank = Some((field.into(), values.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Returns the name of the preset.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the names of the fields the model reports for every element.
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the system prompt, with an additional criterion if any.
+    fn system_prompt(&self, criterion: Option<&str>) -> String {
+        let mut shape = String::from("{\"index\": <number>");
+        let mut descriptions = String::new();
+        for (name, description) in &self.fields {
+            shape.push_str(&format!(", \"{}\": <{}>", name, name));
+            descriptions.push_str(&format!(" \"{}\" is {}.", name, description));
+        }
+        shape.push('}');
+
+        let mut prompt = format!(
+            "You are a helpful assistant that sorts arrays. {} {}",
+            describe_payload("element"),
+            self.instructions
+        );
+        if let Some(criterion) = criterion {
+            prompt.push_str(&format!(" Also take this into account: {}.", criterion));
+        }
+        prompt.push_str(&format!(
+            " Return ONLY a JSON array of objects of the form {}, in sorted order, with every index exactly once.{}",
+            shape, descriptions
+        ));
+        prompt
+    }
+}
+
+/// An element sorted by [`Vibesort::sort_preset`], with the fields the model
+/// reported for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresetItem<T> {
+    /// The element.
+    pub item: T,
+
+    /// The fields of the [`Preset`], as reported by the model.
+    pub fields: Map<String, Value>,
+}
+
+/// Orders email subject lines, optionally with a snippet of the body, so that
+/// the email needing attention first comes first.
+///
+/// Every email gets an `urgency` and an `importance` from 1 (lowest) to 5
+/// (highest), and a short `reason`.
+///
+/// # Example
+///
+/// ```no_run
+/// use vibesort_rs::{Vibesort, presets};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let sorter = Vibesort::new(
+///     "your-api-key",
+///     "gpt-4o-mini",
+///     "https://api.openai.com/v1",
+/// );
+///
+/// let inbox = [
+///     "Weekly newsletter: 10 tips for better sleep",
+///     "URGENT: production database is down",
+///     "Lunch on Friday?",
+/// ];
+/// for email in sorter.sort_preset(&inbox, &presets::email_priority()).await? {
+///     println!("{} (urgency {})", email.item, email.fields["urgency"]);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn email_priority() -> Preset {
+    Preset::new(
+        "email_priority",
+        "Each element is an email: a subject line, optionally followed by a snippet of its body. You are triaging the inbox of a busy professional. Order the emails from the one that needs attention first to the one that can wait the longest. Urgency is how soon an email needs action: outages, security alerts, deadlines today, and people waiting on a reply are urgent. Importance is how much it matters: direct requests from colleagues, customers, or managers, money, and legal matters are important. Rank by urgency first and importance second. Newsletters, marketing, social media, and automated notifications that need no action go last.",
+    )
+    .field("urgency", "an integer from 1 (can wait) to 5 (needs action now)")
+    .field("importance", "an integer from 1 (trivial) to 5 (critical)")
+    .field("reason", "a short phrase explaining the placement")
+}
+
+/// Orders commit messages or changelog entries by their impact on users:
+/// breaking changes first, then features, fixes, and chores.
+///
+/// Every entry gets a `category`, one of `"breaking"`, `"feature"`, `"fix"`,
+/// and `"chore"`. Entries are always grouped by category in that order; within
+/// a category, the entries that matter most to users come first.
+///
+/// # Example
+///
+/// ```no_run
+/// use vibesort_rs::{Vibesort, presets};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let sorter = Vibesort::new(
+///     "your-api-key",
+///     "gpt-4o-mini",
+///     "https://api.openai.com/v1",
+/// );
+///
+/// let commits = [
+///     "Fix off-by-one in pagination",
+///     "Remove the deprecated v1 API",
+///     "Bump CI image",
+///     "Add dark mode",
+/// ];
+/// for entry in sorter.sort_preset(&commits, &presets::changelog()).await? {
+///     println!("[{}] {}", entry.fields["category"], entry.item);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn changelog() -> Preset {
+    Preset::new(
+        "changelog",
+        "Each element is a commit message or changelog entry. You are preparing release notes. Order the entries by their impact on users: breaking changes (removed or renamed APIs, changed defaults, anything that requires users to change their code or configuration) first, then new features, then bug fixes, then chores (refactoring, tests, CI, dependency bumps, documentation). Within a category, put the entries most users will notice first. Judge an entry by what it changes, not by its prefix alone.",
+    )
+    .field(
+        "category",
+        "one of \"breaking\", \"feature\", \"fix\", \"chore\"",
+    )
+    .rank_by("category", ["breaking", "feature", "fix", "chore"])
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts the items with a [`Preset`], returning them in sorted order with
+    /// the fields the model reported for each.
+    ///
+    /// The preset decides the order, including any
+    /// [ranking](Preset::rank_by) it guarantees, so the configured
+    /// [`order`](Self::order) is not used; the configured criterion, if any,
+    /// is passed on as additional guidance. The model only replies with the
+    /// positions of the elements, which are returned from the input.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the reply does not
+    /// name every element exactly once, and [`VibesortError::ParseError`] if a
+    /// field of the preset is missing.
+    pub async fn sort_preset<T>(
+        &self,
+        items: &[T],
+        preset: &Preset,
+    ) -> Result<Vec<PresetItem<T>>, VibesortError>
+    where
+        T: Serialize + Clone,
+    {
+        let json_array = indexed_payload(items)?;
+        // Every element gets an index and a small object of fields
+        let max_tokens = self.max_tokens_for(items.len() * (16 + 24 * preset.fields.len()));
+
+        let (system_prompt, user_content) =
+            self.render_prompt(Operation::Preset, json_array, || {
+                preset.system_prompt(self.criterion.as_deref())
+            })?;
+        let len = items.len();
+        let (system_prompt, user_content) = (&system_prompt, &user_content);
+        let reported = self
+            .retrying(|escalation| async move {
+                let completion = self
+                    .chat(system_prompt, user_content, max_tokens, escalation)
+                    .await?;
+
+                let reported: Vec<Map<String, Value>> = parse::parse_array(&completion.content)?;
+                let mut sorted = Vec::with_capacity(reported.len());
+                let mut indices = Vec::with_capacity(reported.len());
+                for mut fields in reported {
+                    let index = fields
+                        .remove("index")
+                        .and_then(|index| index.as_u64())
+                        .ok_or_else(|| {
+                            VibesortError::ParseError(format!(
+                                "element without an index: {}",
+                                Value::Object(fields.clone())
+                            ))
+                        })?;
+                    if let Some(name) = preset.fields().find(|name| !fields.contains_key(*name)) {
+                        return Err(VibesortError::ParseError(format!(
+                            "missing field {:?} for index {}",
+                            name, index
+                        )));
+                    }
+                    fields.retain(|name, _| preset.fields().any(|field| field == name));
+                    indices.push(index as usize);
+                    sorted.push((index as usize, fields));
+                }
+                check_positions(len, &indices)?;
+                Ok(sorted)
+            })
+            .await?;
+
+        let mut sorted: Vec<PresetItem<T>> = reported
+            .into_iter()
+            .map(|(index, fields)| PresetItem {
+                item: items[index].clone(),
+                fields,
+            })
+            .collect();
+        if let Some((field, values)) = &preset.rank {
+            sorted.sort_by_key(|element| {
+                let value = element.fields.get(field).and_then(Value::as_str);
+                values
+                    .iter()
+                    .position(|candidate| Some(candidate.as_str()) == value)
+                    .unwrap_or(values.len())
+            });
+        }
+        Ok(sorted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_preset_email_priority() {
+        let backend = Arc::new(MockBackend::new().respond_with(
+            r#"[
+                {"index": 1, "urgency": 5, "importance": 5, "reason": "outage"},
+                {"index": 0, "urgency": 1, "importance": 1, "reason": "newsletter", "mood": "sad"}
+            ]"#,
+        ));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .criterion("I am on call this week");
+
+        let inbox = ["Weekly newsletter", "Production is down"].map(String::from);
+        let sorted = sorter.sort_preset(&inbox, &email_priority()).await.unwrap();
+        assert_eq!(sorted[0].item, "Production is down");
+        assert_eq!(sorted[0].fields["urgency"], 5);
+        assert_eq!(sorted[1].fields["reason"], "newsletter");
+        assert!(!sorted[1].fields.contains_key("mood"));
+
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        let system = system.as_str().unwrap();
+        assert!(system.contains("triaging the inbox"));
+        assert!(system.contains("Also take this into account: I am on call this week."));
+        assert!(system.contains(
+            r#"{"index": <number>, "urgency": <urgency>, "importance": <importance>, "reason": <reason>}"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sort_preset_rejects_missing_fields() {
+        let reply = r#"[{"index": 0, "urgency": 3}]"#;
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with(reply));
+
+        let err = sorter
+            .sort_preset(&["Lunch?"], &email_priority())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::ParseError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_sort_preset_changelog_ranks_categories() {
+        // The model puts a fix before a breaking change; the ranking wins
+        let reply = r#"[
+            {"index": 0, "category": "fix"},
+            {"index": 2, "category": "chore"},
+            {"index": 1, "category": "breaking"},
+            {"index": 3, "category": "fix"}
+        ]"#;
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with(reply));
+
+        let commits = ["Fix crash", "Remove v1 API", "Bump CI", "Fix typo"].map(String::from);
+        let sorted = sorter.sort_preset(&commits, &changelog()).await.unwrap();
+        let order: Vec<&str> = sorted.iter().map(|entry| entry.item.as_str()).collect();
+        assert_eq!(order, ["Remove v1 API", "Fix crash", "Fix typo", "Bump CI"]);
+        assert_eq!(sorted[0].fields["category"], "breaking");
+    }
+}
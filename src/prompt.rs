@@ -0,0 +1,560 @@
+//! Prompt templates used to build requests.
+//!
+//! Templates are plain strings with `{placeholder}` markers. The supported
+//! placeholders are:
+//!
+//! - `{array}` - the JSON array to sort (required)
+//! - `{order}` - `ascending` or `descending`
+//! - `{criterion}` - the configured criterion, or `their natural order`
+//!
+//! Literal braces are written as `{{` and `}}`.
+//!
+//! A [`TemplateRegistry`] holds named, versioned templates for each
+//! [`Operation`], so prompts can be iterated on without code changes and
+//! selected per call with [`Vibesort::with_template`](crate::Vibesort::with_template).
+
+use crate::VibesortError;
+use std::collections::BTreeMap;
+
+/// The direction of the sort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Order {
+    /// Smallest (or first, by the criterion) elements first.
+    #[default]
+    Ascending,
+
+    /// Largest (or last, by the criterion) elements first.
+    Descending,
+}
+
+impl Order {
+    /// Returns the lowercase name used in prompts.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Order::Ascending => "ascending",
+            Order::Descending => "descending",
+        }
+    }
+}
+
+/// The placeholders that may appear in a template.
+const PLACEHOLDERS: [&str; 3] = ["array", "order", "criterion"];
+
+/// A segment of a parsed template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Placeholder(&'static str),
+}
+
+/// A validated pair of system prompt and user message templates.
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::PromptTemplate;
+///
+/// let template = PromptTemplate::new(
+///     "Order the user's list in {order} order by {criterion}. Reply with a JSON array only.",
+///     "List: {array}",
+/// )
+/// .unwrap();
+///
+/// // `{array}` is required
+/// assert!(PromptTemplate::new("Sort this.", "no placeholder").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptTemplate {
+    system: Vec<Segment>,
+    user: Vec<Segment>,
+}
+
+/// The values substituted into a template.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PromptValues<'v> {
+    pub(crate) array: &'v str,
+    pub(crate) order: Order,
+    pub(crate) criterion: Option<&'v str>,
+}
+
+impl PromptTemplate {
+    /// Creates a template from a system prompt and a user message template.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::InvalidTemplate`] if neither template contains
+    /// `{array}`, if a template uses an unknown placeholder, or if a brace is
+    /// not closed or escaped.
+    pub fn new(system: &str, user: &str) -> Result<Self, VibesortError> {
+        let template = Self {
+            system: parse(system)?,
+            user: parse(user)?,
+        };
+
+        let has_array = template
+            .system
+            .iter()
+            .chain(&template.user)
+            .any(|segment| *segment == Segment::Placeholder("array"));
+        if !has_array {
+            return Err(VibesortError::InvalidTemplate(
+                "template must contain the {array} placeholder".to_string(),
+            ));
+        }
+
+        Ok(template)
+    }
+
+    /// Renders the system prompt and user message.
+    pub(crate) fn render(&self, values: PromptValues<'_>) -> (String, String) {
+        (render(&self.system, values), render(&self.user, values))
+    }
+}
+
+fn parse(template: &str) -> Result<Vec<Segment>, VibesortError> {
+    let invalid = |msg: String| VibesortError::InvalidTemplate(msg);
+
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => {
+                            return Err(invalid(format!("unterminated placeholder {{{}", name)));
+                        }
+                    }
+                }
+                let placeholder = PLACEHOLDERS
+                    .iter()
+                    .find(|&&known| known == name)
+                    .ok_or_else(|| invalid(format!("unknown placeholder {{{}}}", name)))?;
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(Segment::Placeholder(placeholder));
+            }
+            '}' => return Err(invalid("unescaped '}' (use '}}')".to_string())),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+
+    Ok(segments)
+}
+
+fn render(segments: &[Segment], values: PromptValues<'_>) -> String {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => text.as_str(),
+            Segment::Placeholder("array") => values.array,
+            Segment::Placeholder("order") => values.order.as_str(),
+            Segment::Placeholder(_) => values.criterion.unwrap_or("their natural order"),
+        })
+        .collect()
+}
+
+/// The languages with a built-in translation of the sort prompt, as ISO 639-1
+/// codes.
+pub const PROMPT_LANGUAGES: [&str; 7] = ["en", "ja", "zh", "ko", "es", "fr", "de"];
+
+/// Returns the built-in sort prompt translated into a language.
+///
+/// The language is matched on its primary subtag, case-insensitively, so
+/// `ja-JP` selects Japanese. Returns `None` for English and for languages
+/// without a translation.
+pub(crate) fn localized_sort_prompt(
+    language: &str,
+    order: Order,
+    criterion: Option<&str>,
+) -> Option<String> {
+    let primary = language.split(['-', '_']).next().unwrap_or_default();
+    let descending = order == Order::Descending;
+
+    let prompt = match primary.to_ascii_lowercase().as_str() {
+        "ja" => {
+            let order = if descending { "降順" } else { "昇順" };
+            let criterion = criterion.map_or(String::new(), |c| format!("「{}」という基準で", c));
+            format!(
+                "あなたは配列を並べ替えるアシスタントです。次のJSON配列を{}{}に並べ替え、並べ替えたJSON配列のみを返してください。それ以外は何も出力しないでください。",
+                criterion, order
+            )
+        }
+        "zh" => {
+            let order = if descending { "降序" } else { "升序" };
+            let criterion = criterion.map_or(String::new(), |c| format!("按照“{}”这一标准", c));
+            format!(
+                "你是一个对数组进行排序的助手。请将以下JSON数组{}按{}排序，只返回排序后的JSON数组，不要输出其他任何内容。",
+                criterion, order
+            )
+        }
+        "ko" => {
+            let order = if descending {
+                "내림차순"
+            } else {
+                "오름차순"
+            };
+            let criterion = criterion.map_or(String::new(), |c| format!("\"{}\" 기준에 따라 ", c));
+            format!(
+                "당신은 배열을 정렬하는 도우미입니다. 다음 JSON 배열을 {}{}으로 정렬하고, 정렬된 JSON 배열만 반환하세요. 다른 내용은 출력하지 마세요.",
+                criterion, order
+            )
+        }
+        "es" => {
+            let order = if descending {
+                "descendente"
+            } else {
+                "ascendente"
+            };
+            let criterion =
+                criterion.map_or(String::new(), |c| format!(" según este criterio: {}", c));
+            format!(
+                "Eres un asistente que ordena arreglos. Ordena el siguiente arreglo JSON en orden {}{} y devuelve SOLO el arreglo JSON ordenado, nada más.",
+                order, criterion
+            )
+        }
+        "fr" => {
+            let order = if descending {
+                "décroissant"
+            } else {
+                "croissant"
+            };
+            let criterion =
+                criterion.map_or(String::new(), |c| format!(" selon ce critère : {}", c));
+            format!(
+                "Tu es un assistant qui trie des tableaux. Trie le tableau JSON suivant par ordre {}{} et renvoie UNIQUEMENT le tableau JSON trié, rien d'autre.",
+                order, criterion
+            )
+        }
+        "de" => {
+            let order = if descending {
+                "absteigender"
+            } else {
+                "aufsteigender"
+            };
+            let criterion =
+                criterion.map_or(String::new(), |c| format!(" nach diesem Kriterium: {}", c));
+            format!(
+                "Du bist ein Assistent, der Arrays sortiert. Sortiere das folgende JSON-Array in {} Reihenfolge{} und gib NUR das sortierte JSON-Array zurück, sonst nichts.",
+                order, criterion
+            )
+        }
+        _ => return None,
+    };
+    Some(prompt)
+}
+
+/// An operation whose prompt can be customized with a [`TemplateRegistry`].
+///
+/// For operations other than [`Operation::Sort`], the `{array}` placeholder is
+/// replaced by the operation's JSON payload and the template must keep asking
+/// for the response format the operation expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Operation {
+    /// [`Vibesort::sort`](crate::Vibesort::sort) and
+    /// [`sort_with_report`](crate::Vibesort::sort_with_report), answered with
+    /// a JSON array.
+    Sort,
+
+    /// [`Vibesort::sort_with_explanation`](crate::Vibesort::sort_with_explanation),
+    /// answered with `{"sorted": [...], "explanation": "..."}`.
+    Explain,
+
+    /// [`Vibesort::explain_order`](crate::Vibesort::explain_order), whose
+    /// payload is `{"sorted": [...], "first": ..., "second": ...,
+    /// "explanation": "..."}` and which is answered with plain text.
+    ExplainOrder,
+
+    /// [`Vibesort::sort_with_confidence`](crate::Vibesort::sort_with_confidence),
+    /// answered with `[{"item": ..., "confidence": ...}]`.
+    Confidence,
+
+    /// Reflect-mode review passes, whose payload is `{"input": [...],
+    /// "proposed": [...]}` and which are answered with `{"confirmed": bool,
+    /// "sorted": [...]}`.
+    Reflect,
+
+    /// [`Vibesort::sort_colors`](crate::Vibesort::sort_colors), answered with
+    /// `[{"item": ..., "hsl": {"hue": ..., "saturation": ..., "lightness": ...}}]`.
+    Colors,
+
+    /// [`Vibesort::sort_by_proximity_with_distances`](crate::Vibesort::sort_by_proximity_with_distances),
+    /// answered with `[{"item": ..., "distance_km": ...}]`.
+    Proximity,
+
+    /// [`Vibesort::sort_tasks`](crate::Vibesort::sort_tasks), answered with
+    /// `[{"item": ..., "deadline": ..., "urgency": ...}]`.
+    Tasks,
+
+    /// [`Vibesort::sort_code`](crate::Vibesort::sort_code), whose payload is
+    /// `[{"index": ..., "item": ...}]` and which is answered with a JSON array
+    /// of indices.
+    Code,
+
+    /// [`Vibesort::sort_audited`](crate::Vibesort::sort_audited), whose
+    /// payload is `[{"index": ..., "item": ...}]` and which is answered with
+    /// `[{"index": ..., "reason": "..."}]`.
+    Audit,
+
+    /// [`Vibesort::sort_images`](crate::Vibesort::sort_images), whose payload
+    /// is a short text sent before the labelled images and which is answered
+    /// with a JSON array of indices.
+    Images,
+
+    /// [`Vibesort::sort_weighted`](crate::Vibesort::sort_weighted), whose
+    /// payload is `[{"index": ..., "item": ...}]` and which is answered with
+    /// `[{"index": ..., "scores": {<criterion>: ...}}]`.
+    Weighted,
+
+    /// [`Vibesort::sort_paths`](crate::Vibesort::sort_paths), whose payload
+    /// is `[{"index": ..., "item": {"path": ..., "size_bytes": ..., ...}}]`
+    /// and which is answered with a JSON array of indices.
+    Paths,
+
+    /// [`Vibesort::sort_preset`](crate::Vibesort::sort_preset), whose payload
+    /// is `[{"index": ..., "item": ...}]` and which is answered with
+    /// `[{"index": ..., <field>: ...}]` in sorted order.
+    Preset,
+
+    /// [`Vibesort::sort_playlist`](crate::Vibesort::sort_playlist), whose
+    /// payload is `[{"index": ..., "item": ...}]` and which is answered with a
+    /// JSON array of indices.
+    Playlist,
+
+    /// [`Vibesort::sort_by_sentiment`](crate::Vibesort::sort_by_sentiment),
+    /// whose payload is `[{"index": ..., "item": ...}]` and which is answered
+    /// with `[{"index": ..., "score": ...}]`.
+    Sentiment,
+
+    /// [`Vibesort::interleave`](crate::Vibesort::interleave), whose payload is
+    /// `[{"index": ..., "item": ...}]` and which is answered with
+    /// `{"order": [...], "violations": [{"position": ..., "reason": "..."}]}`.
+    Interleave,
+
+    /// [`Vibesort::sort_constrained`](crate::Vibesort::sort_constrained),
+    /// whose payload is `[{"index": ..., "item": ...}]` and which is answered
+    /// with a JSON array of indices.
+    Constrained,
+
+    /// [`Vibesort::toposort`](crate::Vibesort::toposort), whose payload is
+    /// `[{"index": ..., "item": ...}]` and which is answered with
+    /// `[{"index": ..., "depends_on": [...]}]`.
+    Toposort,
+
+    /// [`Vibesort::sort_schedule`](crate::Vibesort::sort_schedule), whose
+    /// payload is `{"now": ..., "events": [{"index": ..., "item": ...}]}` with
+    /// only the events whose start time could not be parsed, and which is
+    /// answered with `[{"index": ..., "start": "<RFC 3339 timestamp>"}]`.
+    Schedule,
+
+    /// [`Vibesort::sort_mixed_script`](crate::Vibesort::sort_mixed_script),
+    /// whose payload is `[{"index": ..., "item": ...}]` and which is answered
+    /// with a JSON array of indices.
+    MixedScript,
+
+    /// [`Vibesort::sort_words`](crate::Vibesort::sort_words), whose payload
+    /// is `[{"index": ..., "item": ...}]` and which is answered with a JSON
+    /// array of indices.
+    Words,
+
+    /// [`Vibesort::reorder_keys`](crate::Vibesort::reorder_keys), whose
+    /// payload is `[{"index": ..., "item": {"key": ..., "value": ...}}]` and
+    /// which is answered with a JSON array of indices.
+    Keys,
+
+    /// A plain sort with [`Vibesort::tag_ids`](crate::Vibesort::tag_ids)
+    /// enabled, whose payload is `[{"id": ..., "value": ...}]` and which is
+    /// answered with the same objects in sorted order.
+    TaggedSort,
+
+    /// [`Vibesort::sort_by_display`](crate::Vibesort::sort_by_display), whose
+    /// payload is `[{"index": ..., "item": ...}]` of strings and which is
+    /// answered with a JSON array of indices.
+    Display,
+
+    /// [`Vibesort::sort_exact`](crate::Vibesort::sort_exact), whose payload
+    /// is a JSON array of numbers written as strings and which is answered
+    /// with the same strings in sorted order.
+    Exact,
+
+    /// [`Vibesort::sort_ids_by_time`](crate::Vibesort::sort_ids_by_time),
+    /// whose payload is a JSON array of identifiers and which is answered
+    /// with `[{"item": ..., "timestamp_ms": ...}]` in sorted order.
+    TimeIds,
+}
+
+/// A collection of named, versioned prompt templates per [`Operation`].
+///
+/// Templates are selected with a selector string: `"name"` picks the highest
+/// registered version of `name`, and `"name@3"` picks version 3 exactly.
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::prompt::{Operation, TemplateRegistry};
+/// use vibesort_rs::{PromptTemplate, Vibesort};
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let registry = TemplateRegistry::new()
+///     .register(Operation::Sort, "terse", 1, PromptTemplate::new("Sort {order}. JSON only.", "{array}")?)
+///     .register(Operation::Sort, "terse", 2, PromptTemplate::new("Sort {order} by {criterion}. JSON array only.", "{array}")?);
+///
+/// let sorter = Vibesort::new("your-api-key", "gpt-3.5-turbo", "https://api.openai.com/v1")
+///     .templates(registry);
+///
+/// // Select a template for a single call
+/// let terse_v1 = sorter.with_template("terse@1");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateRegistry {
+    templates: BTreeMap<(Operation, String), BTreeMap<u32, PromptTemplate>>,
+}
+
+impl TemplateRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a template under a name and version for an operation.
+    ///
+    /// Registering the same operation, name, and version again replaces the
+    /// previous template.
+    pub fn register(
+        mut self,
+        operation: Operation,
+        name: impl Into<String>,
+        version: u32,
+        template: PromptTemplate,
+    ) -> Self {
+        self.templates
+            .entry((operation, name.into()))
+            .or_default()
+            .insert(version, template);
+        self
+    }
+
+    /// Looks up the template matching a selector for an operation.
+    ///
+    /// Returns `None` if the selector matches no template for that operation.
+    pub fn get(&self, operation: Operation, selector: &str) -> Option<&PromptTemplate> {
+        let (name, version) = parse_selector(selector);
+        let versions = self.templates.get(&(operation, name.to_string()))?;
+        match version {
+            Some(version) => versions.get(&version),
+            None => versions.values().next_back(),
+        }
+    }
+
+    /// Returns `true` if the selector matches a template for any operation.
+    pub fn contains(&self, selector: &str) -> bool {
+        self.templates
+            .keys()
+            .any(|(operation, _)| self.get(*operation, selector).is_some())
+    }
+}
+
+/// Splits a selector into its name and optional version.
+fn parse_selector(selector: &str) -> (&str, Option<u32>) {
+    match selector.rsplit_once('@') {
+        Some((name, version)) => match version.parse() {
+            Ok(version) => (name, Some(version)),
+            Err(_) => (selector, None),
+        },
+        None => (selector, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_renders_placeholders() {
+        let template =
+            PromptTemplate::new("Sort {order} by {criterion}. {{json}}", "Items: {array}").unwrap();
+
+        let (system, user) = template.render(PromptValues {
+            array: "[1,2]",
+            order: Order::Descending,
+            criterion: Some("size"),
+        });
+        assert_eq!(system, "Sort descending by size. {json}");
+        assert_eq!(user, "Items: [1,2]");
+    }
+
+    #[test]
+    fn test_template_rejects_unterminated_placeholder() {
+        let err = PromptTemplate::new("Sort them.", "Items: {array").unwrap_err();
+        assert!(matches!(
+            err,
+            VibesortError::InvalidTemplate(msg) if msg == "unterminated placeholder {array"
+        ));
+        assert!(PromptTemplate::new("Sort them {", "{array}").is_err());
+    }
+
+    #[test]
+    fn test_registry_selects_versions() {
+        let v1 = PromptTemplate::new("one", "{array}").unwrap();
+        let v2 = PromptTemplate::new("two", "{array}").unwrap();
+        let registry = TemplateRegistry::new()
+            .register(Operation::Sort, "base", 2, v2.clone())
+            .register(Operation::Sort, "base", 1, v1.clone());
+
+        assert_eq!(registry.get(Operation::Sort, "base"), Some(&v2));
+        assert_eq!(registry.get(Operation::Sort, "base@1"), Some(&v1));
+        assert_eq!(registry.get(Operation::Sort, "base@3"), None);
+        assert_eq!(registry.get(Operation::Explain, "base"), None);
+        assert!(registry.contains("base"));
+        assert!(!registry.contains("other"));
+    }
+
+    #[test]
+    fn test_localized_sort_prompt() {
+        let ja = localized_sort_prompt("ja-JP", Order::Descending, Some("大きさ")).unwrap();
+        assert!(ja.contains("「大きさ」という基準で降順"));
+
+        let de = localized_sort_prompt("DE", Order::Ascending, None).unwrap();
+        assert!(de.contains("aufsteigender Reihenfolge und"));
+
+        assert_eq!(localized_sort_prompt("en", Order::Ascending, None), None);
+        assert_eq!(localized_sort_prompt("tlh", Order::Ascending, None), None);
+    }
+
+    #[test]
+    fn test_template_validation() {
+        assert!(PromptTemplate::new("{array}", "").is_ok());
+        assert!(matches!(
+            PromptTemplate::new("Sort", "Items"),
+            Err(VibesortError::InvalidTemplate(_))
+        ));
+        assert!(matches!(
+            PromptTemplate::new("Sort {orderr}", "{array}"),
+            Err(VibesortError::InvalidTemplate(_))
+        ));
+        assert!(matches!(
+            PromptTemplate::new("Sort }", "{array}"),
+            Err(VibesortError::InvalidTemplate(_))
+        ));
+    }
+}
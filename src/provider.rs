@@ -0,0 +1,316 @@
+//! Classification of provider error responses.
+//!
+//! Providers report failures with a status code and a JSON error envelope
+//! such as `{"error": {"type": "...", "code": "...", "message": "..."}}`. The
+//! well-known failures are mapped to dedicated [`VibesortError`] variants so
+//! callers can branch on them; everything else becomes
+//! [`VibesortError::ApiError`], with the envelope parsed into a
+//! [`ProviderError`].
+
+use crate::VibesortError;
+use crate::backend::BackendResponse;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use serde_json::Value;
+use std::time::Duration;
+
+/// The error envelope of a provider's error response, attached to
+/// [`VibesortError::ApiError`].
+///
+/// The OpenAI (`{"error": {"type", "code", "param", "message"}}`), Anthropic
+/// (`{"type": "error", "error": {"type", "message"}}`), and Gemini
+/// (`{"error": {"code", "status", "message"}}`) envelopes are understood, as
+/// are flat envelopes without the `error` object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderError {
+    /// The HTTP status code of the response.
+    pub status: u16,
+
+    /// The error type, such as `invalid_request_error`, or Gemini's status,
+    /// such as `INVALID_ARGUMENT`.
+    pub kind: Option<String>,
+
+    /// The error code, such as `model_not_found` or Gemini's `400`.
+    pub code: Option<String>,
+
+    /// The request parameter the error refers to, if reported.
+    pub param: Option<String>,
+
+    /// The error message, or the whole response body if it holds no
+    /// envelope.
+    pub message: String,
+}
+
+impl ProviderError {
+    /// Parses the envelope in the body of an error response.
+    pub(crate) fn parse(status: StatusCode, body: &str) -> Self {
+        let mut error = Self {
+            status: status.as_u16(),
+            kind: None,
+            code: None,
+            param: None,
+            message: String::new(),
+        };
+        if let Ok(json) = serde_json::from_str::<Value>(body) {
+            let envelope = match &json["error"] {
+                Value::Object(_) => &json["error"],
+                _ => &json,
+            };
+            let field = |name: &str| match &envelope[name] {
+                Value::String(s) if !s.is_empty() => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            };
+            error.kind = field("type").or_else(|| field("status"));
+            error.code = field("code");
+            error.param = field("param");
+            error.message = field("message").unwrap_or_default();
+        }
+        if error.message.is_empty() {
+            error.message = body.to_string();
+        }
+        error
+    }
+
+    /// Returns `true` if the type or the code is `name`.
+    fn is(&self, name: &str) -> bool {
+        self.kind.as_deref() == Some(name) || self.code.as_deref() == Some(name)
+    }
+}
+
+/// Turns a non-success response into the most specific error.
+pub(crate) fn classify(response: &BackendResponse) -> VibesortError {
+    let envelope = ProviderError::parse(response.status, &response.body);
+    let message = envelope.message.clone();
+    let is = |name: &str| envelope.is(name);
+
+    match response.status {
+        StatusCode::TOO_MANY_REQUESTS => VibesortError::RateLimited {
+            retry_after: retry_after(&response.headers),
+        },
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => VibesortError::AuthFailed(message),
+        _ if is("context_length_exceeded") || is_context_length_message(&message) => {
+            let (limit, got) = context_lengths(&message);
+            VibesortError::ContextLengthExceeded { limit, got }
+        }
+        _ if is("model_not_found")
+            || (response.status == StatusCode::NOT_FOUND && message.contains("model")) =>
+        {
+            VibesortError::ModelNotFound(message)
+        }
+        status if matches!(status.as_u16(), 503 | 529) || is("overloaded_error") => {
+            VibesortError::Overloaded(message)
+        }
+        status => VibesortError::ApiError {
+            message: format!(
+                "API returned status {}\nServer response: {}",
+                status, response.body
+            ),
+            provider: Some(Box::new(envelope)),
+        },
+    }
+}
+
+/// The longest delay accepted from a `Retry-After` header. Longer delays are
+/// shortened to it.
+pub(crate) const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+
+/// Reads the delay from a `retry-after-ms` or `Retry-After` header.
+///
+/// Only delays in (milli)seconds are understood; HTTP dates and values that
+/// are not finite are ignored. Negative delays are read as zero, and delays
+/// longer than [`MAX_RETRY_AFTER`] are shortened to it.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        let secs = headers
+            .get(name)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse::<f64>()
+            .ok()?;
+        secs.is_finite().then_some(secs)
+    };
+    let secs = match header("retry-after-ms") {
+        Some(ms) => ms / 1000.0,
+        None => header(RETRY_AFTER.as_str())?,
+    };
+    let delay = Duration::try_from_secs_f64(secs.max(0.0)).unwrap_or(MAX_RETRY_AFTER);
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+fn is_context_length_message(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    message.contains("maximum context length") || message.contains("prompt is too long")
+}
+
+/// Extracts the token limit and the requested token count from messages like
+/// "This model's maximum context length is 4097 tokens. However, your messages
+/// resulted in 5000 tokens." or "prompt is too long: 5000 tokens > 4097
+/// maximum".
+fn context_lengths(message: &str) -> (Option<u32>, Option<u32>) {
+    let number_after = |marker: &str| {
+        let rest = &message[message.find(marker)? + marker.len()..];
+        let digits: String = rest
+            .trim_start()
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        digits.parse().ok()
+    };
+
+    if message.contains("prompt is too long") {
+        (number_after(" > "), number_after("prompt is too long:"))
+    } else {
+        (
+            number_after("maximum context length is"),
+            number_after("resulted in").or_else(|| number_after("you requested")),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn response(status: u16, body: &str) -> BackendResponse {
+        BackendResponse::new(StatusCode::from_u16(status).unwrap(), body)
+    }
+
+    #[test]
+    fn test_classify_provider_errors() {
+        let mut rate_limited = response(429, "slow down");
+        rate_limited
+            .headers
+            .insert(RETRY_AFTER, HeaderValue::from_static("2"));
+        assert!(matches!(
+            classify(&rate_limited),
+            VibesortError::RateLimited { retry_after: Some(d) } if d == Duration::from_secs(2)
+        ));
+
+        let auth = response(401, r#"{"error":{"message":"Incorrect API key provided"}}"#);
+        assert!(
+            matches!(classify(&auth), VibesortError::AuthFailed(m) if m == "Incorrect API key provided")
+        );
+
+        let model = response(
+            404,
+            r#"{"error":{"code":"model_not_found","message":"The model `gpt-9` does not exist"}}"#,
+        );
+        assert!(matches!(classify(&model), VibesortError::ModelNotFound(_)));
+
+        let overloaded = response(
+            529,
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+        );
+        assert!(matches!(
+            classify(&overloaded),
+            VibesortError::Overloaded(_)
+        ));
+
+        assert!(matches!(
+            classify(&response(500, "Internal Server Error")),
+            VibesortError::ApiError { .. }
+        ));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let retry_after = |name: &'static str, value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, HeaderValue::from_static(value));
+            super::retry_after(&headers)
+        };
+        assert_eq!(
+            retry_after("retry-after", "2"),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            retry_after("retry-after-ms", "250"),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(retry_after("retry-after", "-5"), Some(Duration::ZERO));
+        assert_eq!(retry_after("retry-after", "1e20"), Some(MAX_RETRY_AFTER));
+        assert_eq!(retry_after("retry-after-ms", "1e20"), Some(MAX_RETRY_AFTER));
+        assert_eq!(retry_after("retry-after", "inf"), None);
+        assert_eq!(retry_after("retry-after", "NaN"), None);
+        assert_eq!(
+            retry_after("retry-after", "Wed, 21 Oct 2015 07:28:00 GMT"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_provider_error_envelopes() {
+        let provider = |status: u16, body: &str| match classify(&response(status, body)) {
+            VibesortError::ApiError {
+                provider: Some(provider),
+                ..
+            } => *provider,
+            other => panic!("unexpected error: {:?}", other),
+        };
+
+        let openai = provider(
+            400,
+            r#"{"error":{"message":"Invalid value for 'temperature'","type":"invalid_request_error","param":"temperature","code":"invalid_value"}}"#,
+        );
+        assert_eq!(
+            openai,
+            ProviderError {
+                status: 400,
+                kind: Some("invalid_request_error".to_string()),
+                code: Some("invalid_value".to_string()),
+                param: Some("temperature".to_string()),
+                message: "Invalid value for 'temperature'".to_string(),
+            }
+        );
+
+        let anthropic = provider(
+            400,
+            r#"{"type":"error","error":{"type":"invalid_request_error","message":"max_tokens: too large"}}"#,
+        );
+        assert_eq!(anthropic.kind.as_deref(), Some("invalid_request_error"));
+        assert_eq!(anthropic.message, "max_tokens: too large");
+
+        let gemini = provider(
+            400,
+            r#"{"error":{"code":400,"message":"Invalid JSON payload","status":"INVALID_ARGUMENT"}}"#,
+        );
+        assert_eq!(gemini.kind.as_deref(), Some("INVALID_ARGUMENT"));
+        assert_eq!(gemini.code.as_deref(), Some("400"));
+
+        let raw = provider(502, "Bad Gateway");
+        assert_eq!(
+            (raw.status, raw.kind, raw.message.as_str()),
+            (502, None, "Bad Gateway")
+        );
+    }
+
+    #[test]
+    fn test_classify_context_length() {
+        let openai = response(
+            400,
+            r#"{"error":{"code":"context_length_exceeded","message":"This model's maximum context length is 4097 tokens. However, your messages resulted in 5000 tokens."}}"#,
+        );
+        assert!(matches!(
+            classify(&openai),
+            VibesortError::ContextLengthExceeded {
+                limit: Some(4097),
+                got: Some(5000)
+            }
+        ));
+
+        let anthropic = response(
+            400,
+            r#"{"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 210000 tokens > 200000 maximum"}}"#,
+        );
+        assert!(matches!(
+            classify(&anthropic),
+            VibesortError::ContextLengthExceeded {
+                limit: Some(200000),
+                got: Some(210000)
+            }
+        ));
+    }
+}
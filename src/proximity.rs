@@ -0,0 +1,159 @@
+//! Sorting places by distance to a location.
+
+use crate::annotate::Annotation;
+use crate::prompt::{Operation, Order};
+use crate::{Vibesort, VibesortError};
+use serde::Deserialize;
+
+/// The annotation requested by [`Vibesort::sort_by_proximity_with_distances`].
+#[derive(Debug, Deserialize)]
+struct Distance {
+    distance_km: f64,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts place names by their approximate geographic distance to `anchor`.
+    ///
+    /// With the default [`Order::Ascending`] the
+    /// nearest place comes first. The configured criterion is replaced for this
+    /// call.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let sorted = sorter
+    ///     .sort_by_proximity(&["Lisbon", "Osaka", "Denver"], "Berlin")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_by_proximity(
+        &self,
+        places: &[&str],
+        anchor: &str,
+    ) -> Result<Vec<String>, VibesortError> {
+        let places: Vec<String> = places.iter().map(|s| s.to_string()).collect();
+        let sorter = self
+            .clone()
+            .criterion(proximity_criterion(anchor, self.order));
+        Ok(sorter.sort_with_report(&places).await?.items)
+    }
+
+    /// Sorts place names by distance to `anchor` and returns the model's
+    /// distance estimates in kilometers.
+    ///
+    /// Use the estimates to sanity-check the ordering: a place the model
+    /// cannot locate tends to get an implausible distance.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::ParseError`] is returned if any place lacks a distance.
+    pub async fn sort_by_proximity_with_distances(
+        &self,
+        places: &[&str],
+        anchor: &str,
+    ) -> Result<Vec<(String, f64)>, VibesortError> {
+        let places: Vec<String> = places.iter().map(|s| s.to_string()).collect();
+        let instruction = format!(
+            "of place names {}",
+            self.sort_instruction_for(Some(&proximity_criterion(anchor, self.order)))
+        );
+        let annotation = Annotation {
+            fields: "\"distance_km\": <number>",
+            meaning: &format!(
+                "\"distance_km\" is your estimate of the great-circle distance from the place to {} in kilometers",
+                anchor
+            ),
+            size: 24,
+        };
+
+        let sorted: Vec<(String, Distance)> = self
+            .sort_annotated(Operation::Proximity, &places, &instruction, annotation)
+            .await?;
+        Ok(sorted
+            .into_iter()
+            .map(|(place, distance)| (place, distance.distance_km))
+            .collect())
+    }
+}
+
+/// Returns the criterion of sorting by distance to `anchor`, worded to agree
+/// with the configured order.
+fn proximity_criterion(anchor: &str, order: Order) -> String {
+    let first = match order {
+        Order::Ascending => "nearest",
+        Order::Descending => "farthest",
+    };
+    format!(
+        "by approximate geographic distance to {}, {} first",
+        anchor, first
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_by_proximity_with_distances() {
+        let backend = Arc::new(MockBackend::new().respond_with(
+            r#"[
+                {"item": "Lisbon", "distance_km": 2310},
+                {"item": "Denver", "distance_km": 8200},
+                {"item": "Osaka", "distance_km": 9150}
+            ]"#,
+        ));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let sorted = sorter
+            .sort_by_proximity_with_distances(&["Osaka", "Lisbon", "Denver"], "Berlin")
+            .await
+            .unwrap();
+        assert_eq!(
+            sorted,
+            vec![
+                ("Lisbon".to_string(), 2310.0),
+                ("Denver".to_string(), 8200.0),
+                ("Osaka".to_string(), 9150.0)
+            ]
+        );
+
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        assert!(system.as_str().unwrap().contains("distance to Berlin"));
+    }
+
+    #[tokio::test]
+    async fn test_sort_by_proximity_descending() {
+        let backend = Arc::new(MockBackend::new().respond_with(r#"["Osaka", "Lisbon"]"#));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .order(Order::Descending);
+
+        let sorted = sorter
+            .sort_by_proximity(&["Lisbon", "Osaka"], "Berlin")
+            .await
+            .unwrap();
+        assert_eq!(sorted, vec!["Osaka", "Lisbon"]);
+
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        let system = system.as_str().unwrap();
+        assert!(system.contains("farthest first"));
+        assert!(!system.contains("nearest first"));
+    }
+}
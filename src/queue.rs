@@ -0,0 +1,376 @@
+//! A background queue of sort jobs.
+//!
+//! Sorting can take far longer than a web request should. A
+//! [`VibesortQueue`] runs sorts in background tasks, at most a configured
+//! number at a time, and hands out a [`JobHandle`] for every submitted job
+//! that can be polled for its status or awaited for its result. [`JobHooks`]
+//! are notified as jobs move through the queue, for example to persist their
+//! state so that another process can report on them.
+//!
+//! Jobs are spawned on the current Tokio runtime, so the queue is not
+//! available with the `worker` feature.
+
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+
+/// The identifier of a submitted job, unique within its queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JobId(pub u64);
+
+impl fmt::Display for JobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "job-{}", self.0)
+    }
+}
+
+/// The status of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    /// The job is waiting for a free worker.
+    Queued,
+
+    /// The job is being sorted.
+    Running,
+
+    /// The job finished successfully.
+    Completed,
+
+    /// The job finished with an error.
+    Failed,
+
+    /// The job was cancelled with [`JobHandle::cancel`].
+    Cancelled,
+}
+
+/// Callbacks notified as jobs move through a [`VibesortQueue`].
+///
+/// Every method does nothing by default. The callbacks run on the worker
+/// tasks, so they should return quickly; hand long-running work such as
+/// database writes off to another task.
+pub trait JobHooks: fmt::Debug + Send + Sync {
+    /// Called when a job is submitted, with its serialized items.
+    fn on_submit(&self, _id: JobId, _items: &[Value]) {}
+
+    /// Called when a worker starts sorting a job.
+    fn on_start(&self, _id: JobId) {}
+
+    /// Called when a job finishes, with its serialized sorted items or error.
+    ///
+    /// A cancelled job finishes with [`VibesortError::JobCancelled`], called
+    /// from [`JobHandle::cancel`].
+    fn on_finish(&self, _id: JobId, _result: Result<&[Value], &VibesortError>) {}
+}
+
+/// Hooks that do nothing (the default).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoHooks;
+
+impl JobHooks for NoHooks {}
+
+/// A queue that sorts in background tasks.
+///
+/// # Example
+///
+/// ```no_run
+/// use vibesort_rs::Vibesort;
+/// use vibesort_rs::queue::{JobStatus, VibesortQueue};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let sorter = Vibesort::new(
+///     "your-api-key",
+///     "gpt-3.5-turbo",
+///     "https://api.openai.com/v1",
+/// );
+/// let queue = VibesortQueue::new(sorter).concurrency(2);
+///
+/// // In the request handler: submit and return the job ID right away
+/// let handle = queue.submit(vec![3, 1, 2])?;
+/// println!("submitted {}", handle.id());
+///
+/// // Later: poll, or wait for the result
+/// if handle.status() != JobStatus::Completed {
+///     println!("still working...");
+/// }
+/// let sorted = handle.wait().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct VibesortQueue {
+    sorter: Arc<Vibesort<'static>>,
+    workers: Arc<Semaphore>,
+    hooks: Arc<dyn JobHooks>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl VibesortQueue {
+    /// Creates a queue that sorts with `sorter`, running up to 4 jobs at a
+    /// time.
+    pub fn new(sorter: Vibesort<'static>) -> Self {
+        Self {
+            sorter: Arc::new(sorter),
+            workers: Arc::new(Semaphore::new(4)),
+            hooks: Arc::new(NoHooks),
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Sets the maximum number of jobs sorted at the same time (at least 1).
+    pub fn concurrency(mut self, workers: usize) -> Self {
+        self.workers = Arc::new(Semaphore::new(workers.max(1)));
+        self
+    }
+
+    /// Sets the hooks notified as jobs move through the queue.
+    pub fn hooks(mut self, hooks: impl JobHooks + 'static) -> Self {
+        self.hooks = Arc::new(hooks);
+        self
+    }
+
+    /// Submits a sort and returns a handle to the job.
+    ///
+    /// The job runs [`Vibesort::sort_with_report`] once a worker is free.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::JsonError`] if the items cannot be serialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub fn submit<T>(&self, items: Vec<T>) -> Result<JobHandle<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned + Send + 'static,
+    {
+        let id = JobId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let values: Vec<Value> = items
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<_, _>>()?;
+        self.hooks.on_submit(id, &values);
+
+        let status = Arc::new(Mutex::new(JobStatus::Queued));
+        let (sorter, workers, hooks) = (
+            self.sorter.clone(),
+            self.workers.clone(),
+            self.hooks.clone(),
+        );
+        let job_status = status.clone();
+        let task = tokio::spawn(async move {
+            // The semaphore is never closed
+            let _worker = workers.acquire_owned().await.ok();
+            if !advance(&job_status, JobStatus::Running) {
+                return Err(VibesortError::JobCancelled);
+            }
+            hooks.on_start(id);
+
+            let result = sorter
+                .sort_with_report(&values)
+                .await
+                .map(|result| result.items);
+            let finished = match result {
+                Ok(_) => JobStatus::Completed,
+                Err(_) => JobStatus::Failed,
+            };
+            // A job cancelled in the meantime has already been reported
+            if !advance(&job_status, finished) {
+                return Err(VibesortError::JobCancelled);
+            }
+            hooks.on_finish(id, result.as_deref());
+            result
+        });
+
+        Ok(JobHandle {
+            id,
+            status,
+            task,
+            hooks: self.hooks.clone(),
+            item: PhantomData,
+        })
+    }
+}
+
+/// Moves a job that has not finished yet to `new`, returning `false` if it
+/// had already finished.
+fn advance(status: &Mutex<JobStatus>, new: JobStatus) -> bool {
+    let mut status = status
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if matches!(*status, JobStatus::Queued | JobStatus::Running) {
+        *status = new;
+        true
+    } else {
+        false
+    }
+}
+
+/// A handle to a job submitted to a [`VibesortQueue`].
+///
+/// Dropping the handle does not cancel the job.
+#[derive(Debug)]
+pub struct JobHandle<T> {
+    id: JobId,
+    status: Arc<Mutex<JobStatus>>,
+    task: JoinHandle<Result<Vec<Value>, VibesortError>>,
+    hooks: Arc<dyn JobHooks>,
+    item: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> JobHandle<T> {
+    /// Returns the job's identifier.
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    /// Returns the job's current status without waiting.
+    pub fn status(&self) -> JobStatus {
+        *self
+            .status
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns `true` once the job has completed, failed, or been cancelled.
+    pub fn is_finished(&self) -> bool {
+        !matches!(self.status(), JobStatus::Queued | JobStatus::Running)
+    }
+
+    /// Cancels the job if it has not finished yet.
+    ///
+    /// The hooks are notified that the job finished with
+    /// [`VibesortError::JobCancelled`].
+    pub fn cancel(&self) {
+        if advance(&self.status, JobStatus::Cancelled) {
+            self.task.abort();
+            self.hooks
+                .on_finish(self.id, Err(&VibesortError::JobCancelled));
+        }
+    }
+
+    /// Waits for the job to finish and returns the sorted items.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the sort, or [`VibesortError::JobCancelled`] if
+    /// the job was cancelled.
+    pub async fn wait(self) -> Result<Vec<T>, VibesortError> {
+        let sorted = match self.task.await {
+            Ok(result) => result?,
+            Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+            Err(_) => return Err(VibesortError::JobCancelled),
+        };
+        Ok(sorted
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+
+    /// Records every hook call.
+    #[derive(Debug, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl JobHooks for Recorder {
+        fn on_submit(&self, id: JobId, items: &[Value]) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("submit {} {}", id, items.len()));
+        }
+
+        fn on_start(&self, id: JobId) {
+            self.0.lock().unwrap().push(format!("start {}", id));
+        }
+
+        fn on_finish(&self, id: JobId, result: Result<&[Value], &VibesortError>) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("finish {} {}", id, result.is_ok()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_runs_jobs_in_background() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(
+            MockBackend::new()
+                .respond_with("[1,2,3]")
+                .respond_with("not an array"),
+        );
+        let queue = VibesortQueue::new(sorter)
+            .concurrency(1)
+            .hooks(Recorder(log.clone()));
+
+        let first = queue.submit(vec![3, 1, 2]).unwrap();
+        let second = queue.submit(vec![2, 1]).unwrap();
+        assert_eq!(first.status(), JobStatus::Queued);
+        assert_ne!(first.id(), second.id());
+
+        assert_eq!(first.wait().await.unwrap(), vec![1, 2, 3]);
+        assert!(matches!(
+            second.wait().await,
+            Err(VibesortError::ParseError(_))
+        ));
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "submit job-1 3",
+                "submit job-2 2",
+                "start job-1",
+                "finish job-1 true",
+                "start job-2",
+                "finish job-2 false"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_job() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(MockBackend::new());
+        let queue = VibesortQueue::new(sorter).hooks(Recorder(log.clone()));
+
+        let handle = queue.submit(vec![2, 1]).unwrap();
+        handle.cancel();
+        handle.cancel();
+        assert_eq!(handle.status(), JobStatus::Cancelled);
+        assert!(matches!(
+            handle.wait().await,
+            Err(VibesortError::JobCancelled)
+        ));
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["submit job-1 2", "finish job-1 false"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_after_completion_keeps_the_result() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(MockBackend::new());
+        let queue = VibesortQueue::new(sorter).hooks(Recorder(log.clone()));
+
+        let handle = queue.submit(vec![2, 1]).unwrap();
+        while !handle.is_finished() {
+            tokio::task::yield_now().await;
+        }
+        handle.cancel();
+        assert_eq!(handle.status(), JobStatus::Completed);
+        assert_eq!(handle.wait().await.unwrap(), vec![1, 2]);
+        assert_eq!(log.lock().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,157 @@
+//! Self-verification ("reflect") passes over a proposed ordering.
+
+use crate::engine::Engine;
+use crate::prompt::Operation;
+use crate::{SortReport, Vibesort, VibesortError, parse};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// The model's verdict on a proposed ordering.
+#[derive(Debug, Deserialize)]
+struct Review<T> {
+    confirmed: bool,
+    sorted: Option<Vec<T>>,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Shows the model its own output and lets it confirm or fix it.
+    ///
+    /// Runs up to `self.reflect_passes` review rounds and stops as soon as the
+    /// model confirms an ordering. The number of passes and whether the final
+    /// ordering was confirmed are recorded in the report. Reflection is skipped
+    /// for [`Engine::Local`], whose output is exact.
+    ///
+    /// A correction is only accepted if it is a permutation of the input.
+    /// Reviews are retried like other requests; if one still fails, the last
+    /// accepted ordering is kept unconfirmed.
+    pub(crate) async fn run_reflection<T>(
+        &self,
+        items: &[T],
+        mut sorted: Vec<T>,
+        report: &mut SortReport,
+    ) -> Result<Vec<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        if self.reflect_passes == 0 || matches!(self.engine, Engine::Local(_)) {
+            return Ok(sorted);
+        }
+
+        report.confirmed = Some(false);
+        for _ in 0..self.reflect_passes {
+            report.reflection_passes += 1;
+
+            let review_request = serde_json::json!({ "input": items, "proposed": sorted });
+            // A correction is at most as long as the proposal
+            let max_tokens = self.max_tokens_for(review_request["proposed"].to_string().len() + 32);
+            let (system_prompt, user_content) =
+                self.render_prompt(Operation::Reflect, review_request.to_string(), || {
+                    format!(
+                        "You are a careful reviewer of sorted arrays. You will receive a JSON object with an original array (\"input\") and a proposed ordering of it (\"proposed\") that should be sorted {}. Check that the proposal contains exactly the input elements and is correctly sorted. Return ONLY a JSON object: {{\"confirmed\": true}} if the proposal is correct, or {{\"confirmed\": false, \"sorted\": [...]}} with the corrected sorted array otherwise.",
+                        self.sort_instruction()
+                    )
+                })?;
+            let (system_prompt, user_content) = (&system_prompt, &user_content);
+            let review = self
+                .retrying(|escalation| async move {
+                    let completion = self
+                        .chat(system_prompt, user_content, max_tokens, escalation)
+                        .await?;
+                    let mut review: Review<T> = parse::parse_json(&completion.content, "object")?;
+                    if !review.confirmed
+                        && let Some(corrected) = &mut review.sorted
+                    {
+                        self.accept_sorted(items, corrected, true)?;
+                    }
+                    Ok(review)
+                })
+                .await;
+            let Ok(review) = review else {
+                break;
+            };
+
+            if review.confirmed {
+                report.confirmed = Some(true);
+                break;
+            }
+            if let Some(corrected) = review.sorted {
+                sorted = corrected;
+            }
+        }
+
+        Ok(sorted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Vibesort;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_reflect_fixes_then_confirms() {
+        let backend = Arc::new(
+            MockBackend::new()
+                .respond_with("[1,3,2]")
+                .respond_with(r#"{"confirmed": false, "sorted": [1,2,3]}"#)
+                .respond_with(r#"{"confirmed": true}"#),
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .reflect(3);
+
+        let result = sorter.sort_with_report(&[3, 1, 2]).await.unwrap();
+        assert_eq!(result.items, vec![1, 2, 3]);
+        assert_eq!(result.report.reflection_passes, 2);
+        assert_eq!(result.report.confirmed, Some(true));
+        assert_eq!(backend.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_reflect_is_bounded() {
+        let backend = MockBackend::new()
+            .respond_with("[2,1]")
+            .respond_with(r#"{"confirmed": false}"#);
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend)
+            .reflect(1);
+
+        let result = sorter.sort_with_report(&[2, 1]).await.unwrap();
+        assert_eq!(result.items, vec![2, 1]);
+        assert_eq!(result.report.reflection_passes, 1);
+        assert_eq!(result.report.confirmed, Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_reflect_keeps_first_pass_on_bad_review() {
+        for review in ["not json", r#"{"confirmed": false, "sorted": [1, 2, 4]}"#] {
+            let backend = MockBackend::new()
+                .respond_with("[1,2,3]")
+                .respond_with(review);
+            let sorter = Vibesort::new("key", "model", "http://mock")
+                .backend(backend)
+                .reflect(2);
+
+            let result = sorter.sort_with_report(&[3, 1, 2]).await.unwrap();
+            assert_eq!(result.items, vec![1, 2, 3]);
+            assert_eq!(result.report.reflection_passes, 1);
+            assert_eq!(result.report.confirmed, Some(false));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reflect_correction_within_tolerance() {
+        let backend = MockBackend::new()
+            .respond_with("[0.2, 0.1]")
+            .respond_with(r#"{"confirmed": false, "sorted": [0.1, 0.20000000001]}"#);
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend)
+            .verify(true)
+            .verify_tolerance(1e-9)
+            .reflect(1);
+
+        let result = sorter.sort_with_report(&[0.2, 0.1]).await.unwrap();
+        assert_eq!(result.items, vec![0.1, 0.2]);
+    }
+}
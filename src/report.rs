@@ -0,0 +1,129 @@
+//! Reporting on how a sort was performed.
+
+use std::collections::BTreeMap;
+
+/// Details about how a sort was performed.
+///
+/// Returned as part of a [`SortResult`] by
+/// [`Vibesort::sort_with_report`](crate::Vibesort::sort_with_report).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SortReport {
+    /// The seed sent with the request, if one was configured.
+    pub seed: Option<u64>,
+
+    /// The `system_fingerprint` returned by the provider, if any; the same
+    /// as [`SortMetadata::system_fingerprint`].
+    ///
+    /// The fingerprint identifies the backend configuration that served the
+    /// request. Runs are only reproducible with the same seed when the
+    /// fingerprint is unchanged.
+    pub system_fingerprint: Option<String>,
+
+    /// What the provider reported about the completion that answered the
+    /// sort.
+    pub metadata: SortMetadata,
+
+    /// The number of self-verification passes run in reflect mode.
+    pub reflection_passes: usize,
+
+    /// Whether the model confirmed the final ordering in reflect mode.
+    ///
+    /// `None` if reflect mode is disabled or was skipped.
+    pub confirmed: Option<bool>,
+
+    /// The index of the completion that was used when several candidates were
+    /// requested with [`Vibesort::n_best`](crate::Vibesort::n_best).
+    pub candidate: usize,
+
+    /// Whether the input exceeded the model's context length and was sorted
+    /// in chunks instead (see [`Vibesort::chunk_size`](crate::Vibesort::chunk_size)).
+    pub chunked_fallback: bool,
+
+    /// The number of results answered from the
+    /// [cache](crate::Vibesort::cache).
+    pub cache_hits: usize,
+
+    /// The number of results looked up in the [cache](crate::Vibesort::cache)
+    /// without a valid entry.
+    pub cache_misses: usize,
+
+    /// The number of leading items that are sorted, if the sort ran out of
+    /// its [time budget](crate::Vibesort::time_budget) and
+    /// [returned partial results](crate::Vibesort::partial_on_budget). The
+    /// remaining items follow unsorted.
+    ///
+    /// `None` if the sort finished.
+    pub sorted_prefix_len: Option<usize>,
+
+    /// Whether a reply of the model, or of any chunk or merge request of a
+    /// [chunked fallback](Self::chunked_fallback), was not a valid JSON array
+    /// and had to be repaired, such as by closing an array cut off by the
+    /// output limit or removing text around it. Repaired replies are always
+    /// checked to be a permutation of the input.
+    pub json_repaired: bool,
+
+    /// The number of duplicates the model collapsed that were
+    /// [restored](crate::Vibesort::restore_duplicates) from the input.
+    pub duplicates_restored: usize,
+}
+
+/// What the provider reported about a completion.
+///
+/// Providers can update the model behind a name without notice. Recording
+/// these values alongside each result makes it possible to correlate a change
+/// in sort quality with such an update.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SortMetadata {
+    /// The `system_fingerprint` returned by the provider, if any.
+    pub system_fingerprint: Option<String>,
+
+    /// The id of the response, if the provider returned one.
+    pub response_id: Option<String>,
+
+    /// The model version that served the request, if the provider returned
+    /// it. This is often more specific than the configured model name, e.g.
+    /// `gpt-4o-2024-08-06` for `gpt-4o`.
+    pub model: Option<String>,
+
+    /// The [captured](crate::Vibesort::capture_headers) response headers
+    /// that were present, keyed by lowercase name.
+    pub headers: BTreeMap<String, String>,
+}
+
+impl SortMetadata {
+    /// Returns the provider's id of the HTTP request, from the
+    /// `x-request-id` or `request-id` header, for support tickets.
+    ///
+    /// This differs from the [`response_id`](Self::response_id) in the body.
+    pub fn request_id(&self) -> Option<&str> {
+        self.headers
+            .get("x-request-id")
+            .or_else(|| self.headers.get("request-id"))
+            .map(String::as_str)
+    }
+}
+
+/// The sorted items together with a [`SortReport`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortResult<T> {
+    /// The sorted items.
+    pub items: Vec<T>,
+
+    /// Details about how the sort was performed.
+    pub report: SortReport,
+
+    /// The model's explanation of the ordering, if one was requested with
+    /// [`Vibesort::sort_with_explanation`](crate::Vibesort::sort_with_explanation).
+    pub explanation: Option<String>,
+}
+
+impl<T> SortResult<T> {
+    /// Creates a result without an explanation.
+    pub(crate) fn new(items: Vec<T>, report: SortReport) -> Self {
+        Self {
+            items,
+            report,
+            explanation: None,
+        }
+    }
+}
@@ -0,0 +1,569 @@
+//! Retry policies for failed sort attempts.
+//!
+//! A [`RetryPolicy`] is consulted after every failed attempt and decides
+//! whether to try again and how long to wait first. An attempt covers the
+//! whole round trip: sending the request, parsing the response, and (when
+//! enabled) verifying it, so malformed model output can be retried just like
+//! a dropped connection.
+//!
+//! By default nothing is retried ([`NoRetry`]). [`ExponentialBackoff`] and
+//! [`DecorrelatedJitter`] cover the common cases; implement the trait to
+//! encode provider-specific rules.
+//!
+//! Requests are sent at temperature 0, so retrying after malformed output
+//! often reproduces the same bad reply. An [`Escalation`] schedule varies the
+//! retried requests instead.
+
+use crate::VibesortError;
+use crate::rng::SplitMix64;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Decides whether and when a failed attempt is retried.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use vibesort_rs::VibesortError;
+/// use vibesort_rs::retry::RetryPolicy;
+///
+/// /// Retries timeouts once, immediately.
+/// #[derive(Debug)]
+/// struct RetryTimeoutOnce;
+///
+/// impl RetryPolicy for RetryTimeoutOnce {
+///     fn decide(&self, attempt: u32, error: &VibesortError) -> Option<Duration> {
+///         match error {
+///             VibesortError::Timeout if attempt == 1 => Some(Duration::ZERO),
+///             _ => None,
+///         }
+///     }
+/// }
+/// ```
+pub trait RetryPolicy: fmt::Debug + Send + Sync {
+    /// Returns how long to wait before retrying, or `None` to give up.
+    ///
+    /// `attempt` is the number of the attempt that just failed, starting at 1.
+    fn decide(&self, attempt: u32, error: &VibesortError) -> Option<Duration>;
+
+    /// Like [`decide`](Self::decide), also given the delay this policy chose
+    /// before the attempt that just failed, or `None` after the first attempt.
+    ///
+    /// Every sort keeps its own previous delay, so policies whose delays grow
+    /// from the previous one, such as [`DecorrelatedJitter`], implement this
+    /// instead of keeping it themselves. The default ignores `previous`.
+    fn decide_after(
+        &self,
+        attempt: u32,
+        previous: Option<Duration>,
+        error: &VibesortError,
+    ) -> Option<Duration> {
+        let _ = previous;
+        self.decide(attempt, error)
+    }
+}
+
+/// Returns `true` for errors that may succeed when retried.
+///
+/// These are timeouts, connection failures, rate limiting, overloaded
+/// providers, other `408`, `409`, and `5xx` responses, and responses that could
+/// not be parsed or verified. Errors in the request itself, such as
+/// authentication failures, an exceeded context length, invalid templates, or
+/// a denied remote endpoint, are not retryable.
+pub fn is_retryable(error: &VibesortError) -> bool {
+    match error {
+        VibesortError::Timeout
+        | VibesortError::RateLimited { .. }
+        | VibesortError::Overloaded(_)
+        | VibesortError::InvalidResponse
+        | VibesortError::ParseError(_)
+        | VibesortError::VerificationFailed(_) => true,
+        #[cfg(not(target_arch = "wasm32"))]
+        VibesortError::HttpError(e) => e.is_timeout() || e.is_connect(),
+        #[cfg(target_arch = "wasm32")]
+        VibesortError::HttpError(e) => e.is_timeout(),
+        VibesortError::ApiError { message, provider } => {
+            let status = match provider {
+                Some(provider) => Some(provider.status),
+                None => api_error_status(message),
+            };
+            matches!(status, Some(408 | 409 | 429 | 500..=599))
+        }
+        _ => false,
+    }
+}
+
+/// Returns `true` for failures after which the provider may have accepted the
+/// request, and billed it, without the reply arriving: timeouts once the
+/// request was sent.
+///
+/// Connection failures and connect timeouts happen before the request is
+/// accepted and are not ambiguous, nor are error responses. Retrying an
+/// ambiguous failure can pay for the same request twice; see
+/// [`Vibesort::retry_ambiguous`](crate::Vibesort::retry_ambiguous).
+pub fn is_ambiguous(error: &VibesortError) -> bool {
+    match error {
+        VibesortError::Timeout => true,
+        #[cfg(not(target_arch = "wasm32"))]
+        VibesortError::HttpError(e) => e.is_timeout() && !e.is_connect(),
+        #[cfg(target_arch = "wasm32")]
+        VibesortError::HttpError(e) => e.is_timeout(),
+        _ => false,
+    }
+}
+
+/// Returns the delay the provider asked for, if any.
+fn requested_delay(error: &VibesortError) -> Duration {
+    match error {
+        VibesortError::RateLimited {
+            retry_after: Some(delay),
+        } => *delay,
+        _ => Duration::ZERO,
+    }
+}
+
+/// Returns `true` for errors caused by the model's reply rather than by the
+/// request or the connection: output that could not be parsed or verified.
+pub(crate) fn is_malformed_output(error: &VibesortError) -> bool {
+    matches!(
+        error,
+        VibesortError::InvalidResponse
+            | VibesortError::ParseError(_)
+            | VibesortError::VerificationFailed(_)
+    )
+}
+
+/// Extracts the status code from an [`VibesortError::ApiError`] message, for
+/// errors without provider details.
+fn api_error_status(message: &str) -> Option<u16> {
+    message
+        .strip_prefix("API returned status ")?
+        .get(..3)?
+        .parse()
+        .ok()
+}
+
+/// A policy that never retries (the default).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoRetry;
+
+impl RetryPolicy for NoRetry {
+    fn decide(&self, _attempt: u32, _error: &VibesortError) -> Option<Duration> {
+        None
+    }
+}
+
+/// Retries [retryable](is_retryable) errors with exponentially growing delays.
+///
+/// The delay after attempt `n` is `base * factor^(n - 1)`, capped at
+/// `max_delay`. A longer `Retry-After` delay requested by the provider takes
+/// precedence.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use vibesort_rs::Vibesort;
+/// use vibesort_rs::retry::ExponentialBackoff;
+///
+/// let sorter = Vibesort::new("your-api-key", "gpt-3.5-turbo", "https://api.openai.com/v1")
+///     .retry_policy(ExponentialBackoff::new(3).base(Duration::from_millis(500)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExponentialBackoff {
+    max_retries: u32,
+    base: Duration,
+    factor: f64,
+    max_delay: Duration,
+}
+
+impl ExponentialBackoff {
+    /// Retries up to `max_retries` times, starting at 100ms and doubling up to
+    /// 10s.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            base: Duration::from_millis(100),
+            factor: 2.0,
+            max_delay: Duration::from_secs(10),
+        }
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn base(mut self, base: Duration) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Sets the multiplier applied to the delay after each retry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is negative or not finite.
+    pub fn factor(mut self, factor: f64) -> Self {
+        assert!(
+            factor.is_finite() && factor >= 0.0,
+            "backoff factor must be finite and non-negative, got {}",
+            factor
+        );
+        self.factor = factor;
+        self
+    }
+
+    /// Sets the longest delay between two attempts.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+}
+
+impl RetryPolicy for ExponentialBackoff {
+    fn decide(&self, attempt: u32, error: &VibesortError) -> Option<Duration> {
+        if attempt > self.max_retries || !is_retryable(error) {
+            return None;
+        }
+        let delay = self.base.as_secs_f64() * self.factor.powi(attempt as i32 - 1);
+        let delay = Duration::from_secs_f64(delay.min(self.max_delay.as_secs_f64()));
+        Some(delay.max(requested_delay(error)))
+    }
+}
+
+/// Retries [retryable](is_retryable) errors with randomized, growing delays.
+///
+/// The delay after the first attempt is drawn uniformly between `base` and
+/// `base * 3`, and every later one between `base` and three times the
+/// previous delay of the same sort. Delays are capped at `cap`, and the
+/// `Retry-After` delay requested by the provider is used instead if that is
+/// longer. The randomness spreads out clients that failed at the same moment,
+/// so they do not retry in lockstep.
+#[derive(Debug)]
+pub struct DecorrelatedJitter {
+    max_retries: u32,
+    base: Duration,
+    cap: Duration,
+    /// The random generator, shared by the sorts using this policy.
+    rng: Mutex<SplitMix64>,
+}
+
+impl DecorrelatedJitter {
+    /// Retries up to `max_retries` times with a 100ms base and a 20s cap.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            base: Duration::from_millis(100),
+            cap: Duration::from_secs(20),
+            rng: Mutex::new(SplitMix64::from_time()),
+        }
+    }
+
+    /// Sets the shortest delay between two attempts.
+    pub fn base(mut self, base: Duration) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Sets the longest delay between two attempts.
+    pub fn cap(mut self, cap: Duration) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    /// Seeds the random generator to make the delays reproducible.
+    pub fn seed(self, seed: u64) -> Self {
+        *self.rng.lock().unwrap() = SplitMix64::new(seed);
+        self
+    }
+}
+
+impl RetryPolicy for DecorrelatedJitter {
+    fn decide(&self, attempt: u32, error: &VibesortError) -> Option<Duration> {
+        self.decide_after(attempt, None, error)
+    }
+
+    fn decide_after(
+        &self,
+        attempt: u32,
+        previous: Option<Duration>,
+        error: &VibesortError,
+    ) -> Option<Duration> {
+        if attempt > self.max_retries || !is_retryable(error) {
+            return None;
+        }
+        let previous = previous.unwrap_or(self.base).max(self.base);
+        let base = self.base.as_secs_f64();
+        let upper = previous.as_secs_f64() * 3.0;
+        let roll = self.rng.lock().unwrap().next_f64();
+        let delay = (base + (upper - base) * roll).min(self.cap.as_secs_f64());
+        Some(Duration::from_secs_f64(delay).max(requested_delay(error)))
+    }
+}
+
+/// A schedule for varying retried requests after malformed output.
+///
+/// Each retry that follows an attempt with malformed output (a reply that
+/// could not be parsed or verified) moves one step along the schedule: the
+/// `n`-th such retry is sent with the `n`-th temperature, and the last
+/// temperature is reused once the schedule runs out. An optional nudge is
+/// appended to the system prompt of escalated requests. Retries after other
+/// errors, such as timeouts, repeat the previous request unchanged.
+///
+/// Escalation only takes effect when the [`RetryPolicy`] retries.
+///
+/// # Example
+///
+/// ```no_run
+/// use vibesort_rs::Vibesort;
+/// use vibesort_rs::retry::{Escalation, ExponentialBackoff};
+///
+/// let sorter = Vibesort::new("your-api-key", "gpt-3.5-turbo", "https://api.openai.com/v1")
+///     .retry_policy(ExponentialBackoff::new(3))
+///     .escalation(
+///         Escalation::new([0.3, 0.6, 0.9])
+///             .nudge("Your previous reply was not a valid JSON array. Reply with the JSON array only."),
+///     );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Escalation {
+    temperatures: Vec<f32>,
+    nudge: Option<String>,
+}
+
+impl Escalation {
+    /// Creates a schedule with the given temperatures and no prompt nudge.
+    pub fn new(temperatures: impl IntoIterator<Item = f32>) -> Self {
+        Self {
+            temperatures: temperatures.into_iter().collect(),
+            nudge: None,
+        }
+    }
+
+    /// Appends `nudge` to the system prompt of every escalated request.
+    pub fn nudge(mut self, nudge: impl Into<String>) -> Self {
+        self.nudge = Some(nudge.into());
+        self
+    }
+
+    /// Returns the temperature for the given escalation level, if any.
+    ///
+    /// Level 0 is an unescalated request.
+    pub(crate) fn temperature(&self, level: u32) -> Option<f32> {
+        let step = (level as usize).checked_sub(1)?;
+        self.temperatures
+            .get(step)
+            .or(self.temperatures.last())
+            .copied()
+    }
+
+    /// Returns the prompt nudge for the given escalation level, if any.
+    pub(crate) fn nudge_for(&self, level: u32) -> Option<&str> {
+        self.nudge.as_deref().filter(|_| level > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vibesort;
+    use crate::testing::{ChaosBackend, MockBackend};
+    use std::sync::Arc;
+
+    fn server_error() -> VibesortError {
+        VibesortError::ApiError {
+            message: "API returned status 503 Service Unavailable\nServer response: busy"
+                .to_string(),
+            provider: None,
+        }
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(is_retryable(&VibesortError::Timeout));
+        assert!(is_retryable(&server_error()));
+        assert!(!is_retryable(&VibesortError::ApiError {
+            message: "API returned status 401 Unauthorized\nServer response: bad key".to_string(),
+            provider: None,
+        }));
+        assert!(!is_retryable(
+            &VibesortError::InvalidTemplate(String::new())
+        ));
+    }
+
+    #[test]
+    fn test_is_ambiguous() {
+        assert!(is_ambiguous(&VibesortError::Timeout));
+        assert!(!is_ambiguous(&server_error()));
+        assert!(!is_ambiguous(&VibesortError::ParseError(String::new())));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_ambiguous() {
+        let chaos = Arc::new(ChaosBackend::new(MockBackend::new()).timeout(1.0));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(chaos.clone())
+            .retry_policy(ExponentialBackoff::new(2));
+        assert!(matches!(
+            sorter.sort(&[2, 1]).await,
+            Err(VibesortError::Timeout)
+        ));
+        assert_eq!(chaos.injected().len(), 3);
+
+        let chaos = Arc::new(ChaosBackend::new(MockBackend::new()).timeout(1.0));
+        let sorter = sorter.backend(chaos.clone()).retry_ambiguous(false);
+        assert!(matches!(
+            sorter.sort(&[2, 1]).await,
+            Err(VibesortError::Timeout)
+        ));
+        assert_eq!(chaos.injected().len(), 1);
+    }
+
+    #[test]
+    fn test_exponential_backoff_delays() {
+        let policy = ExponentialBackoff::new(3)
+            .base(Duration::from_secs(1))
+            .max_delay(Duration::from_secs(3));
+
+        assert_eq!(
+            policy.decide(1, &server_error()),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            policy.decide(2, &server_error()),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            policy.decide(3, &server_error()),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(policy.decide(4, &server_error()), None);
+
+        let rate_limited = VibesortError::RateLimited {
+            retry_after: Some(Duration::from_secs(30)),
+        };
+        assert_eq!(
+            policy.decide(1, &rate_limited),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn test_decorrelated_jitter_bounds() {
+        let policy = DecorrelatedJitter::new(10)
+            .base(Duration::from_millis(10))
+            .cap(Duration::from_millis(200))
+            .seed(1);
+
+        for attempt in 1..=10 {
+            let delay = policy.decide(attempt, &VibesortError::Timeout).unwrap();
+            assert!(delay >= Duration::from_millis(10));
+            assert!(delay <= Duration::from_millis(200));
+        }
+        assert_eq!(policy.decide(11, &VibesortError::Timeout), None);
+    }
+
+    #[test]
+    fn test_decorrelated_jitter_grows_from_previous_delay() {
+        let policy = DecorrelatedJitter::new(10)
+            .base(Duration::from_millis(10))
+            .cap(Duration::from_secs(100))
+            .seed(7);
+
+        let mut previous = None;
+        for attempt in 1..=10 {
+            let delay = policy
+                .decide_after(attempt, previous, &VibesortError::Timeout)
+                .unwrap();
+            assert!(delay >= Duration::from_millis(10));
+            assert!(delay <= previous.unwrap_or(Duration::from_millis(10)) * 3);
+            previous = Some(delay);
+        }
+
+        // Without a previous delay, every sort starts from the base
+        let grown = Some(Duration::from_secs(50));
+        policy.decide_after(2, grown, &VibesortError::Timeout);
+        let delay = policy.decide(1, &VibesortError::Timeout).unwrap();
+        assert!(delay <= Duration::from_millis(30));
+
+        // A base above the cap is held to the cap
+        let policy = DecorrelatedJitter::new(3)
+            .base(Duration::from_secs(30))
+            .cap(Duration::from_secs(20));
+        for attempt in 1..=3 {
+            assert_eq!(
+                policy.decide(attempt, &VibesortError::Timeout),
+                Some(Duration::from_secs(20))
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "backoff factor must be finite")]
+    fn test_exponential_backoff_rejects_nan_factor() {
+        let _ = ExponentialBackoff::new(3).factor(f64::NAN);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_sort_retries_with_policy() {
+        let backend = Arc::new(MockBackend::new().respond_with_status(503, "busy"));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+        assert!(sorter.sort(&[2, 1]).await.is_err());
+
+        let backend = Arc::new(
+            MockBackend::new()
+                .respond_with_status(503, "busy")
+                .respond_with("not an array"),
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .retry_policy(ExponentialBackoff::new(2));
+        assert_eq!(sorter.sort(&[2, 1]).await.unwrap(), vec![1, 2]);
+        assert_eq!(backend.requests().len(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_delay_capped_by_time_budget() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with_status(503, "busy"))
+            .retry_policy(
+                ExponentialBackoff::new(3)
+                    .base(Duration::from_secs(60))
+                    .max_delay(Duration::from_secs(60)),
+            )
+            .time_budget(Duration::from_secs(5));
+
+        let start = tokio::time::Instant::now();
+        let err = sorter.sort(&[2, 1]).await.unwrap_err();
+        assert!(matches!(err, VibesortError::BudgetExhausted(_)));
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_escalation_after_malformed_output() {
+        let backend = Arc::new(
+            MockBackend::new()
+                .respond_with_status(503, "busy")
+                .respond_with("not an array")
+                .respond_with("still not an array"),
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .retry_policy(ExponentialBackoff::new(3))
+            .escalation(Escalation::new([0.5]).nudge("JSON only!"));
+        assert_eq!(sorter.sort(&[2, 1]).await.unwrap(), vec![1, 2]);
+
+        // The retry after the server error is unchanged; those after
+        // malformed output are escalated, reusing the last temperature
+        let requests = backend.requests();
+        let temperatures: Vec<f64> = requests
+            .iter()
+            .map(|request| request.body["temperature"].as_f64().unwrap())
+            .collect();
+        assert_eq!(temperatures, vec![0.0, 0.0, 0.5, 0.5]);
+
+        let system = requests[2].body["messages"][0]["content"].clone();
+        assert!(system.as_str().unwrap().ends_with("\n\nJSON only!"));
+        let system = requests[1].body["messages"][0]["content"].clone();
+        assert!(!system.as_str().unwrap().contains("JSON only!"));
+    }
+}
@@ -0,0 +1,145 @@
+//! Timers and clocks for the supported runtimes.
+//!
+//! Everything time-related goes through this module: on native targets it is
+//! backed by tokio (so tests can pause and advance time), and with the
+//! `worker` feature on wasm32 by JavaScript, since Cloudflare Workers provide
+//! neither tokio's timer driver nor `std::time::Instant`.
+
+#[cfg(not(all(feature = "worker", target_arch = "wasm32")))]
+pub(crate) use tokio::time::{Instant, sleep, sleep_until, timeout};
+
+#[cfg(all(feature = "worker", target_arch = "wasm32"))]
+pub(crate) use worker::{Instant, SendFuture, sleep, sleep_until, timeout};
+
+use std::time::Duration;
+
+/// Returns the current time as a duration since the Unix epoch, or zero if
+/// the clock is before it.
+pub(crate) fn unix_time() -> Duration {
+    #[cfg(not(all(feature = "worker", target_arch = "wasm32")))]
+    {
+        use std::time::{SystemTime, UNIX_EPOCH};
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+    #[cfg(all(feature = "worker", target_arch = "wasm32"))]
+    {
+        Duration::from_secs_f64(js_sys::Date::now().max(0.0) / 1000.0)
+    }
+}
+
+#[cfg(all(feature = "worker", target_arch = "wasm32"))]
+mod worker {
+    use std::future::Future;
+    use std::ops::{Add, Sub};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    /// A point in time, measured with `Date.now()`.
+    ///
+    /// Workers only advance the clock across I/O, which is when timers fire
+    /// anyway.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub(crate) struct Instant(Duration);
+
+    impl Instant {
+        pub(crate) fn now() -> Self {
+            Self(super::unix_time())
+        }
+
+        pub(crate) fn saturating_duration_since(&self, earlier: Self) -> Duration {
+            self.0.saturating_sub(earlier.0)
+        }
+
+        pub(crate) fn elapsed(&self) -> Duration {
+            Self::now().saturating_duration_since(*self)
+        }
+
+        pub(crate) fn checked_add(&self, duration: Duration) -> Option<Self> {
+            self.0.checked_add(duration).map(Self)
+        }
+    }
+
+    impl Add<Duration> for Instant {
+        type Output = Self;
+
+        fn add(self, duration: Duration) -> Self {
+            Self(self.0 + duration)
+        }
+    }
+
+    impl Sub for Instant {
+        type Output = Duration;
+
+        fn sub(self, earlier: Self) -> Duration {
+            self.saturating_duration_since(earlier)
+        }
+    }
+
+    /// A future holding JavaScript values, made usable where a `Send`
+    /// future is required.
+    pub(crate) struct SendFuture<F>(Pin<Box<F>>);
+
+    impl<F> SendFuture<F> {
+        pub(crate) fn new(future: F) -> Self {
+            Self(Box::pin(future))
+        }
+    }
+
+    // SAFETY: wasm32 Workers run a single thread, so the future is never
+    // actually sent to another thread.
+    unsafe impl<F> Send for SendFuture<F> {}
+
+    impl<F: Future> Future for SendFuture<F> {
+        type Output = F::Output;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+            self.0.as_mut().poll(cx)
+        }
+    }
+
+    /// Waits for `duration` with `setTimeout`.
+    pub(crate) async fn sleep(duration: Duration) {
+        let millis = i32::try_from(duration.as_millis()).unwrap_or(i32::MAX);
+        let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+            let scope: web_sys::WorkerGlobalScope = js_sys::global().unchecked_into();
+            // A timer that cannot be set resolves right away
+            if scope
+                .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis)
+                .is_err()
+            {
+                let _ = resolve.call0(&wasm_bindgen::JsValue::UNDEFINED);
+            }
+        });
+        let _ = SendFuture::new(JsFuture::from(promise)).await;
+    }
+
+    /// Waits until `deadline`.
+    pub(crate) async fn sleep_until(deadline: Instant) {
+        sleep(deadline.saturating_duration_since(Instant::now())).await
+    }
+
+    /// The error of a [`timeout`] that elapsed.
+    #[derive(Debug)]
+    pub(crate) struct Elapsed;
+
+    /// Runs `future`, giving up after `duration`.
+    pub(crate) async fn timeout<F: Future>(
+        duration: Duration,
+        future: F,
+    ) -> Result<F::Output, Elapsed> {
+        let mut future = Box::pin(future);
+        let mut timer = Box::pin(sleep(duration));
+        std::future::poll_fn(|cx| {
+            if let Poll::Ready(output) = future.as_mut().poll(cx) {
+                return Poll::Ready(Ok(output));
+            }
+            timer.as_mut().poll(cx).map(|()| Err(Elapsed))
+        })
+        .await
+    }
+}
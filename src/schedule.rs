@@ -0,0 +1,365 @@
+//! Ordering events by start time, parsed locally where possible.
+
+use crate::indexed::Indexed;
+use crate::prompt::Operation;
+use crate::{Order, Vibesort, VibesortError, parse};
+use serde::Deserialize;
+
+/// How the start time of a [`ScheduledEvent`] was determined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StartSource {
+    /// Parsed locally from a timestamp in the event's text.
+    Parsed,
+
+    /// Inferred by the model from a description such as "next Tuesday at
+    /// 3pm".
+    Inferred,
+}
+
+/// An event sorted by [`Vibesort::sort_schedule`], with its start time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledEvent<T> {
+    /// The event.
+    pub item: T,
+
+    /// The start time, in seconds since the Unix epoch.
+    pub start: i64,
+
+    /// Whether the start time was parsed or inferred by the model.
+    pub source: StartSource,
+}
+
+/// The start time the model inferred for one event.
+#[derive(Debug, Deserialize)]
+struct InferredStart {
+    index: usize,
+    start: String,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts events by their start time, parsing timestamps locally and only
+    /// asking the model about events whose start time is ambiguous.
+    ///
+    /// An event whose text contains a date and time in ISO 8601 / RFC 3339
+    /// form, such as `2024-05-01T09:30+02:00` or `2024-05-01 07:30Z`, is
+    /// scheduled at that time without a request; the offset is honored, and
+    /// times without one are taken as UTC. All other events ("standup next
+    /// Tuesday at 3pm CET") are sent to the model in one request, together
+    /// with the current time, and the model replies with a timestamp for each.
+    /// The events are then sorted by start time locally, earliest first unless
+    /// the configured [`order`](Self::order) is [`Order::Descending`]; ties
+    /// keep their input order. The configured criterion is not used.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the model does not
+    /// date every ambiguous event exactly once, and
+    /// [`VibesortError::ParseError`] if one of its timestamps is invalid.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{StartSource, Vibesort};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-4o-mini",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let events = [
+    ///     "Design review 2024-05-02T15:00+02:00",
+    ///     "Standup tomorrow at 9am Pacific",
+    ///     "Release 2024-05-02 09:00Z",
+    /// ];
+    /// for event in sorter.sort_schedule(&events).await? {
+    ///     let how = match event.source {
+    ///         StartSource::Parsed => "parsed",
+    ///         StartSource::Inferred => "inferred",
+    ///     };
+    ///     println!("{} ({}, {})", event.item, event.start, how);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_schedule<T>(
+        &self,
+        events: &[T],
+    ) -> Result<Vec<ScheduledEvent<T>>, VibesortError>
+    where
+        T: AsRef<str> + Clone,
+    {
+        let parsed: Vec<Option<i64>> = events
+            .iter()
+            .map(|event| find_timestamp(event.as_ref()))
+            .collect();
+        let ambiguous: Vec<usize> = (0..events.len())
+            .filter(|&index| parsed[index].is_none())
+            .collect();
+
+        let mut inferred = Vec::new();
+        if !ambiguous.is_empty() {
+            let payload: Vec<Indexed<'_, str>> = ambiguous
+                .iter()
+                .map(|&index| Indexed {
+                    index,
+                    item: events[index].as_ref(),
+                })
+                .collect();
+            let request = serde_json::json!({
+                "now": format_timestamp(now()),
+                "events": payload,
+            });
+            // Every event gets an index and a timestamp
+            let max_tokens = self.max_tokens_for(48 * ambiguous.len());
+
+            let (system_prompt, user_content) =
+                self.render_prompt(Operation::Schedule, request.to_string(), || {
+                    String::from(
+                        "You are a helpful assistant that schedules events. You will receive a JSON object with the current time in UTC (\"now\") and an array (\"events\") of objects of the form {\"index\": <number>, \"item\": <event>}. Work out when each event starts from its description, resolving relative dates such as \"tomorrow\" or \"next Tuesday\" against the current time and converting named time zones to UTC offsets. If an event gives no time zone, assume UTC. Return ONLY a JSON array of objects of the form {\"index\": <number>, \"start\": \"<RFC 3339 timestamp, e.g. 2024-05-01T09:30:00Z>\"}, with every index exactly once.",
+                    )
+                })?;
+            let (system_prompt, user_content, ambiguous) =
+                (&system_prompt, &user_content, &ambiguous);
+            inferred = self
+                .retrying(|escalation| async move {
+                    let completion = self
+                        .chat(system_prompt, user_content, max_tokens, escalation)
+                        .await?;
+
+                    let mut starts: Vec<InferredStart> = parse::parse_array(&completion.content)?;
+                    starts.sort_by_key(|start| start.index);
+                    let indices: Vec<usize> = starts.iter().map(|start| start.index).collect();
+                    if indices != *ambiguous {
+                        return Err(VibesortError::VerificationFailed(format!(
+                            "expected start times for indices {:?}, got {:?}",
+                            ambiguous, indices
+                        )));
+                    }
+                    starts
+                        .iter()
+                        .map(|start| {
+                            find_timestamp(&start.start).ok_or_else(|| {
+                                VibesortError::ParseError(format!(
+                                    "invalid start time {:?} for index {}",
+                                    start.start, start.index
+                                ))
+                            })
+                        })
+                        .collect::<Result<Vec<i64>, _>>()
+                })
+                .await?;
+        }
+
+        let mut inferred = inferred.into_iter();
+        let mut scheduled: Vec<ScheduledEvent<T>> = events
+            .iter()
+            .zip(parsed)
+            .map(|(event, start)| match start {
+                Some(start) => ScheduledEvent {
+                    item: event.clone(),
+                    start,
+                    source: StartSource::Parsed,
+                },
+                None => ScheduledEvent {
+                    item: event.clone(),
+                    start: inferred.next().unwrap_or_default(),
+                    source: StartSource::Inferred,
+                },
+            })
+            .collect();
+        scheduled.sort_by(|a, b| match self.order {
+            Order::Ascending => a.start.cmp(&b.start),
+            Order::Descending => b.start.cmp(&a.start),
+        });
+        Ok(scheduled)
+    }
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+fn now() -> i64 {
+    crate::rt::unix_time().as_secs() as i64
+}
+
+/// Finds the first ISO 8601 date and time in `text` and returns it in seconds
+/// since the Unix epoch.
+///
+/// Accepts `YYYY-MM-DD`, then `T` or a space, then `HH:MM`, optional seconds
+/// and fractional seconds, and an optional `Z` or `±HH:MM` / `±HHMM` offset.
+/// Times without an offset are taken as UTC.
+fn find_timestamp(text: &str) -> Option<i64> {
+    let bytes = text.as_bytes();
+    (0..bytes.len()).find_map(|start| parse_timestamp(&bytes[start..]))
+}
+
+/// Parses an ISO 8601 date and time at the start of `bytes`.
+fn parse_timestamp(bytes: &[u8]) -> Option<i64> {
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = bytes.get(range)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        std::str::from_utf8(digits).ok()?.parse().ok()
+    };
+    let at = |index: usize, expected: &[u8]| bytes.get(index).is_some_and(|b| expected.contains(b));
+
+    // YYYY-MM-DD[T ]HH:MM
+    if !(at(4, b"-") && at(7, b"-") && at(10, b"T ") && at(13, b":")) {
+        return None;
+    }
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute) = (number(11..13)?, number(14..16)?);
+    let mut next = 16;
+    let mut second = 0;
+    if at(next, b":") {
+        second = number(next + 1..next + 3)?;
+        next += 3;
+        if at(next, b".,") {
+            next += 1;
+            while bytes.get(next).is_some_and(u8::is_ascii_digit) {
+                next += 1;
+            }
+        }
+    }
+    if !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let offset = if at(next, b"Zz") {
+        0
+    } else if at(next, b"+-") {
+        let sign = if bytes[next] == b'-' { -1 } else { 1 };
+        let hours = number(next + 1..next + 3)?;
+        let minutes = if at(next + 3, b":") {
+            number(next + 4..next + 6)?
+        } else {
+            number(next + 3..next + 5)?
+        };
+        if hours > 23 || minutes > 59 {
+            return None;
+        }
+        sign * (hours * 3600 + minutes * 60)
+    } else {
+        0
+    };
+
+    Some(days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second - offset)
+}
+
+/// Formats seconds since the Unix epoch as an RFC 3339 UTC timestamp.
+fn format_timestamp(timestamp: i64) -> String {
+    let (days, seconds) = (timestamp.div_euclid(86400), timestamp.rem_euclid(86400));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of days from 1970-01-01 to the given date of the
+/// proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Returns the date of the proleptic Gregorian calendar that is the given
+/// number of days from 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_find_timestamp() {
+        assert_eq!(find_timestamp("1970-01-01T00:00Z"), Some(0));
+        assert_eq!(
+            find_timestamp("at 2024-05-01 09:30:15.5+02:00 sharp"),
+            Some(1714548615)
+        );
+        assert_eq!(find_timestamp("2024-05-01T07:30:15"), Some(1714548615));
+        assert_eq!(find_timestamp("2024-05-01T02:30:15-0500"), Some(1714548615));
+        assert_eq!(find_timestamp("2024-02-30T10:00Z"), None);
+        assert_eq!(find_timestamp("sometime 2024-05-01"), None);
+        assert_eq!(format_timestamp(1714548615), "2024-05-01T07:30:15Z");
+    }
+
+    #[tokio::test]
+    async fn test_sort_schedule_asks_only_about_ambiguous_events() {
+        let backend = Arc::new(
+            MockBackend::new().respond_with(r#"[{"index": 1, "start": "2024-05-01T08:00:00Z"}]"#),
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let events = [
+            "Release 2024-05-01T10:00+02:00",
+            "Standup tomorrow at 8 UTC",
+            "Retro 2024-05-01T07:00Z",
+        ];
+        let sorted = sorter.sort_schedule(&events).await.unwrap();
+        let items: Vec<&str> = sorted.iter().map(|event| event.item).collect();
+        assert_eq!(items, [events[2], events[0], events[1]]);
+        assert_eq!(sorted[0].source, StartSource::Parsed);
+        assert_eq!(sorted[2].source, StartSource::Inferred);
+
+        let user = backend.requests()[0].body["messages"][1]["content"].clone();
+        let request: serde_json::Value = serde_json::from_str(user.as_str().unwrap()).unwrap();
+        assert_eq!(request["events"].as_array().unwrap().len(), 1);
+        assert_eq!(request["events"][0]["index"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_sort_schedule_without_ambiguity_makes_no_request() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .order(Order::Descending);
+
+        let sorted = sorter
+            .sort_schedule(&["a 2024-01-01T00:00Z", "b 2024-06-01T00:00Z"])
+            .await
+            .unwrap();
+        assert_eq!(sorted[0].item, "b 2024-06-01T00:00Z");
+        assert!(backend.requests().is_empty());
+    }
+}
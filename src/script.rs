@@ -0,0 +1,132 @@
+//! Sorting lists that mix writing systems.
+
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+
+/// A convention for ordering elements written in different scripts, for
+/// [`Vibesort::sort_mixed_script`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScriptConvention {
+    /// One alphabetical sequence of the Latin transliterations: Hanyu Pinyin
+    /// for Chinese, Hepburn for Japanese, Revised Romanization for Korean,
+    /// and the standard romanization for Cyrillic, Greek, and other scripts.
+    Transliterated,
+
+    /// By the number of strokes of the first character, then of the
+    /// following ones, as in Chinese and Japanese dictionaries.
+    StrokeCount,
+
+    /// By pronunciation, in the dictionary order of the sounds: the gojūon
+    /// order of the kana reading for Japanese, Pinyin for Chinese, and the
+    /// native alphabet for other scripts.
+    Reading,
+
+    /// Grouped by script (Latin, Greek, Cyrillic, then other scripts, then
+    /// CJK), each group in its own dictionary order.
+    ByScript,
+}
+
+impl ScriptConvention {
+    /// Returns the convention as used in the prompt.
+    fn describe(&self) -> &'static str {
+        match self {
+            ScriptConvention::Transliterated => {
+                "alphabetically by the Latin transliteration of each element (Hanyu Pinyin for Chinese, Hepburn for Japanese by its reading, Revised Romanization for Korean, and the standard romanization for Cyrillic, Greek, and other scripts), so that all scripts form one alphabetical sequence"
+            }
+            ScriptConvention::StrokeCount => {
+                "by the number of strokes of the first character, as counted in Chinese and Japanese dictionaries (count Latin, Cyrillic, and other letters by how they are usually handwritten), then by the strokes of each following character"
+            }
+            ScriptConvention::Reading => {
+                "by the pronunciation of each element, in dictionary order of its sounds: Japanese by the gojūon order of its kana reading, Chinese by Pinyin, Korean by Hangul order, and other scripts by their own alphabet"
+            }
+            ScriptConvention::ByScript => {
+                "grouped by writing system (Latin first, then Greek, then Cyrillic, then other alphabets, then Chinese, Japanese, and Korean), with each group in the dictionary order of its own language"
+            }
+        }
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts elements that mix writing systems, such as Latin, CJK, and
+    /// Cyrillic names, by a chosen [`ScriptConvention`].
+    ///
+    /// Collation libraries order such lists by code point or script block,
+    /// which rarely matches what readers expect; the model applies the
+    /// convention instead. It only replies with the positions of the
+    /// elements, which are returned exactly as given, so no element comes
+    /// back romanized or converted. The configured [`order`](Self::order)
+    /// applies; the configured criterion is replaced by the convention.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the reply does not
+    /// name every element exactly once.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{ScriptConvention, Vibesort};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-4o",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let cities = ["東京", "Berlin", "Москва", "北京", "Amsterdam"];
+    /// let sorted = sorter
+    ///     .sort_mixed_script(&cities, ScriptConvention::Transliterated)
+    ///     .await?;
+    /// // ["Amsterdam", "北京", "Berlin", "Москва", "東京"]
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_mixed_script<T>(
+        &self,
+        items: &[T],
+        convention: ScriptConvention,
+    ) -> Result<Vec<T>, VibesortError>
+    where
+        T: Serialize + Clone,
+    {
+        let instruction = format!(
+            "(which may be written in several scripts) {}",
+            self.sort_instruction_for(Some(convention.describe()))
+        );
+        let indices = self
+            .sort_indexed(Operation::MixedScript, items, &instruction)
+            .await?;
+        Ok(indices
+            .into_iter()
+            .map(|index| items[index].clone())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_mixed_script_returns_originals() {
+        let backend = Arc::new(MockBackend::new().respond_with("[2, 0, 1]"));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let cities = ["Berlin", "Москва", "北京"];
+        let sorted = sorter
+            .sort_mixed_script(&cities, ScriptConvention::Transliterated)
+            .await
+            .unwrap();
+        assert_eq!(sorted, ["北京", "Berlin", "Москва"]);
+
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        assert!(system.as_str().unwrap().contains(
+            "with ascending order according to this criterion: alphabetically by the Latin transliteration"
+        ));
+    }
+}
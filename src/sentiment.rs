@@ -0,0 +1,132 @@
+//! Sorting free-text answers by sentiment.
+
+use crate::indexed::{ElementReply, describe_payload};
+use crate::prompt::Operation;
+use crate::{Order, Vibesort, VibesortError};
+use serde::{Deserialize, Serialize};
+
+/// An element sorted by [`Vibesort::sort_by_sentiment`], with its score.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sentiment<T> {
+    /// The element.
+    pub item: T,
+
+    /// The sentiment the model found in the element, from -1 (most negative)
+    /// to 1 (most positive). 0 is neutral; scores outside the range are
+    /// clamped.
+    pub score: f64,
+}
+
+/// The score of one element, as returned by the model.
+#[derive(Debug, Deserialize)]
+struct ElementScore {
+    index: usize,
+    score: f64,
+}
+
+impl ElementReply for ElementScore {
+    fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts free-text answers, such as survey responses or reviews, from the
+    /// most negative to the most positive, with a sentiment score for each.
+    ///
+    /// The model scores the intensity of the sentiment of every answer; the
+    /// answers are then sorted by score locally, so the order always agrees
+    /// with the scores. [`Order::Descending`] puts the most positive answers
+    /// first. Ties keep their input order. The configured criterion is not
+    /// used.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the model does not
+    /// score every answer exactly once.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-4o-mini",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let answers = [
+    ///     "Support never answered my ticket. Cancelling.",
+    ///     "Works fine.",
+    ///     "Absolutely love the new dashboard!",
+    /// ];
+    /// for answer in sorter.sort_by_sentiment(&answers).await? {
+    ///     println!("{:+.2} {}", answer.score, answer.item);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_by_sentiment<T>(
+        &self,
+        answers: &[T],
+    ) -> Result<Vec<Sentiment<T>>, VibesortError>
+    where
+        T: Serialize + Clone,
+    {
+        let prompt = || {
+            format!(
+                "You are a helpful assistant that analyzes sentiment. {} Score the sentiment of every text from -1 (most negative) through 0 (neutral) to 1 (most positive), taking its intensity into account: strong anger or delight scores close to -1 or 1, mild complaints or praise closer to 0. Return ONLY a JSON array of objects of the form {{\"index\": <number>, \"score\": <number>}}, with every index exactly once.",
+                describe_payload("text")
+            )
+        };
+        // Every element gets an index and a score
+        let scored = self
+            .describe_indexed(
+                Operation::Sentiment,
+                answers,
+                24 * answers.len(),
+                prompt,
+                |scored: Vec<ElementScore>| Ok(scored),
+            )
+            .await?;
+
+        let mut sorted: Vec<Sentiment<T>> = answers
+            .iter()
+            .zip(scored)
+            .map(|(item, element)| Sentiment {
+                item: item.clone(),
+                score: element.score.clamp(-1.0, 1.0),
+            })
+            .collect();
+        sorted.sort_by(|a, b| match self.order {
+            Order::Ascending => a.score.total_cmp(&b.score),
+            Order::Descending => b.score.total_cmp(&a.score),
+        });
+        Ok(sorted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::indexed::replying;
+
+    #[tokio::test]
+    async fn test_sort_by_sentiment() {
+        let (sorter, _) = replying(&[r#"[
+            {"index": 0, "score": 0.1},
+            {"index": 2, "score": -0.9},
+            {"index": 1, "score": 1.5}
+        ]"#]);
+
+        let answers = ["Works fine.", "Love it!", "Cancelling."].map(String::from);
+        let sorted = sorter.sort_by_sentiment(&answers).await.unwrap();
+        let items: Vec<&str> = sorted.iter().map(|answer| answer.item.as_str()).collect();
+        assert_eq!(items, ["Cancelling.", "Works fine.", "Love it!"]);
+        assert_eq!(sorted[0].score, -0.9);
+        // Out-of-range scores are clamped
+        assert_eq!(sorted[2].score, 1.0);
+    }
+}
@@ -0,0 +1,201 @@
+//! Iterative refinement of a sort with human corrections.
+
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// A sorted list that can be refined with corrections.
+///
+/// After the initial sort, every [`correct`](Self::correct) records that one
+/// element belongs before another. All corrections so far are sent with later
+/// requests as few-shot examples, and the region between the two elements is
+/// re-sorted. If the model still disagrees with the latest correction, it is
+/// applied locally by moving the element into place, so a correction always
+/// takes effect.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::cmp::Ordering;
+/// use vibesort_rs::Vibesort;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let sorter = Vibesort::new(
+///     "your-api-key",
+///     "gpt-3.5-turbo",
+///     "https://api.openai.com/v1",
+/// )
+/// .criterion("by how well the candidate fits the role");
+///
+/// let candidates = ["Ana", "Bo", "Cy", "Di"].map(String::from);
+/// let mut session = sorter.session(&candidates).await?;
+/// println!("{:?}", session.items());
+///
+/// // The reviewer thinks the fourth candidate belongs above the second
+/// session.correct(3, 1, Ordering::Less).await?;
+/// println!("{:?}", session.items());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SortSession<'a, T> {
+    sorter: Vibesort<'a>,
+    items: Vec<T>,
+    values: Vec<Value>,
+    /// Pairs of serialized elements where the first belongs before the second.
+    constraints: Vec<(Value, Value)>,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts the items and starts a [`SortSession`] for correcting the result.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    pub async fn session<T>(&self, items: &[T]) -> Result<SortSession<'a, T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let values: Vec<Value> = items
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<_, _>>()?;
+        let sorted = self.sort_with_report(&values).await?.items;
+        let mut session = SortSession {
+            sorter: self.clone(),
+            items: Vec::new(),
+            values: sorted,
+            constraints: Vec::new(),
+        };
+        session.items = session.deserialize()?;
+        Ok(session)
+    }
+}
+
+impl<'a, T> SortSession<'a, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Returns the current order.
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Consumes the session and returns the current order.
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Records how the elements at positions `a` and `b` of the current order
+    /// relate, and re-sorts the region between them.
+    ///
+    /// [`Ordering::Less`] means the element at `a` belongs before the one at
+    /// `b`, and [`Ordering::Greater`] means it belongs after it.
+    /// [`Ordering::Equal`] records nothing. Returns the new order.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Vibesort::sort). The
+    /// order is unchanged if the request fails, but the correction is kept for
+    /// later requests.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` or `b` is out of bounds.
+    pub async fn correct(
+        &mut self,
+        a: usize,
+        b: usize,
+        ordering: Ordering,
+    ) -> Result<&[T], VibesortError> {
+        let (first, second) = match ordering {
+            Ordering::Less => (a, b),
+            Ordering::Greater => (b, a),
+            Ordering::Equal => return Ok(&self.items),
+        };
+        let (first, second) = (self.values[first].clone(), self.values[second].clone());
+        self.constraints.push((first.clone(), second.clone()));
+
+        // Every correction so far is shown as a two-element example
+        let mut sorter = self.sorter.clone();
+        for (before, after) in &self.constraints {
+            sorter = sorter.example(&[after, before], &[before, after])?;
+        }
+
+        let (start, end) = (a.min(b), a.max(b));
+        let region = sorter
+            .sort_with_report_inner(&self.values[start..=end], true)
+            .await?
+            .items;
+        let mut values = self.values.clone();
+        values.splice(start..=end, region);
+
+        // Enforce the latest correction if the model ignored it
+        let position = |value: &Value, values: &[Value]| {
+            values[start..=end]
+                .iter()
+                .position(|v| v == value)
+                .map(|i| i + start)
+        };
+        if let (Some(i), Some(j)) = (position(&first, &values), position(&second, &values))
+            && i > j
+        {
+            let moved = values.remove(i);
+            values.insert(j, moved);
+        }
+
+        self.values = values;
+        self.items = self.deserialize()?;
+        Ok(&self.items)
+    }
+
+    fn deserialize(&self) -> Result<Vec<T>, VibesortError> {
+        Ok(self
+            .values
+            .iter()
+            .cloned()
+            .map(serde_json::from_value)
+            .collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_correction_resorts_region_with_examples() {
+        let backend = Arc::new(
+            MockBackend::new()
+                .respond_with("[1, 2, 3, 4]")
+                .respond_with("[4, 2, 3]"),
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let mut session = sorter.session(&[3, 1, 4, 2]).await.unwrap();
+        assert_eq!(session.items(), &[1, 2, 3, 4]);
+
+        let corrected = session.correct(3, 1, Ordering::Less).await.unwrap();
+        assert_eq!(corrected, &[1, 4, 2, 3]);
+
+        // The correction is sent as an example, and only the region is re-sorted
+        let request = &backend.requests()[1];
+        assert_eq!(request.body["messages"][1]["content"], "[2,4]");
+        assert_eq!(request.body["messages"][2]["content"], "[4,2]");
+        assert_eq!(request.task.as_ref().unwrap().items.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_correction_is_enforced() {
+        // Unscripted requests are answered in natural order, ignoring the example
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(MockBackend::new());
+
+        let mut session = sorter.session(&[1, 2, 3]).await.unwrap();
+        let corrected = session.correct(0, 2, Ordering::Greater).await.unwrap();
+        assert_eq!(corrected, &[3, 1, 2]);
+    }
+}
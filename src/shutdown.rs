@@ -0,0 +1,265 @@
+//! Draining in-flight sorts before the client is dropped.
+
+use crate::{Vibesort, VibesortError, rt};
+use std::future::{Future, poll_fn};
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// The in-flight sorts of a client and its clones, and whether it is shutting
+/// down.
+#[derive(Debug, Default)]
+pub(crate) struct Lifecycle {
+    state: Mutex<State>,
+    changed: Notify,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The number of sorts admitted and not yet finished.
+    in_flight: usize,
+    /// No new sort is admitted once set.
+    closed: bool,
+    /// The in-flight sorts are failed once set.
+    cancelled: bool,
+}
+
+impl Lifecycle {
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Waits until `done` holds for the state.
+    async fn wait_until(&self, done: impl Fn(&State) -> bool) {
+        loop {
+            // Registered before checking, so a change in between is not
+            // missed
+            let changed = self.changed.notified();
+            if done(&self.state()) {
+                return;
+            }
+            changed.await;
+        }
+    }
+}
+
+/// An admitted sort, counted as in flight until dropped.
+#[derive(Debug)]
+pub(crate) struct SortGuard {
+    lifecycle: Arc<Lifecycle>,
+}
+
+impl SortGuard {
+    /// Runs the sort, failing it with [`VibesortError::ShuttingDown`] if the
+    /// shutdown cancels it first.
+    pub(crate) async fn run<T>(
+        self,
+        sort: impl Future<Output = Result<T, VibesortError>>,
+    ) -> Result<T, VibesortError> {
+        let mut sort = pin!(sort);
+        let mut cancelled = pin!(self.lifecycle.wait_until(|state| state.cancelled));
+        poll_fn(|cx| {
+            if let Poll::Ready(result) = sort.as_mut().poll(cx) {
+                return Poll::Ready(result);
+            }
+            cancelled
+                .as_mut()
+                .poll(cx)
+                .map(|()| Err(VibesortError::ShuttingDown))
+        })
+        .await
+    }
+}
+
+impl Drop for SortGuard {
+    fn drop(&mut self) {
+        self.lifecycle.state().in_flight -= 1;
+        self.lifecycle.changed.notify_waiters();
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Stops accepting new sorts and waits for the in-flight ones to finish,
+    /// for at most `timeout`.
+    ///
+    /// The shutdown applies to this client and all its clones. Sorts started
+    /// afterwards fail with [`VibesortError::ShuttingDown`] before sending
+    /// any request, while sorts already running make all the requests they
+    /// need, such as the later chunks and merges of a
+    /// [chunked](Self::chunk_size) sort, the rounds of
+    /// [`sort_constrained`](Self::sort_constrained), or the comparisons of
+    /// [`sort_tournament`](Self::sort_tournament). Sorts still running after
+    /// the timeout are cancelled, failing with
+    /// [`VibesortError::ShuttingDown`].
+    ///
+    /// Returns the number of sorts that were cancelled. Cancelled sorts are
+    /// given another `timeout` to stop, so this returns after at most twice
+    /// the timeout even if a sort is never polled again.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example(sorter: Vibesort<'_>) {
+    /// // On SIGTERM
+    /// let cancelled = sorter.shutdown(Duration::from_secs(30)).await;
+    /// if cancelled > 0 {
+    ///     eprintln!("{} sorts did not finish before shutdown", cancelled);
+    /// }
+    /// # }
+    /// ```
+    pub async fn shutdown(&self, timeout: Duration) -> usize {
+        self.lifecycle.state().closed = true;
+        let drained = self.lifecycle.wait_until(|state| state.in_flight == 0);
+        if rt::timeout(timeout, drained).await.is_ok() {
+            return 0;
+        }
+
+        let cancelled = {
+            let mut state = self.lifecycle.state();
+            state.cancelled = true;
+            state.in_flight
+        };
+        self.lifecycle.changed.notify_waiters();
+        let stopped = self.lifecycle.wait_until(|state| state.in_flight == 0);
+        let _ = rt::timeout(timeout, stopped).await;
+        cancelled
+    }
+
+    /// Returns `true` once [`shutdown`](Self::shutdown) has been called on
+    /// this client or one of its clones.
+    pub fn is_shutting_down(&self) -> bool {
+        self.lifecycle.state().closed
+    }
+
+    /// Admits a sort, returning a clone of this client that runs it without
+    /// being admitted again, unless an enclosing sort was already admitted.
+    ///
+    /// Operations that make several requests admit themselves once at their
+    /// entry point and run on the returned clone, so that a shutdown starting
+    /// between their requests does not refuse the later ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::ShuttingDown`] once the client is shutting
+    /// down.
+    pub(crate) fn admit(&self) -> Result<Option<(Self, SortGuard)>, VibesortError> {
+        if self.admitted {
+            return Ok(None);
+        }
+        {
+            let mut state = self.lifecycle.state();
+            if state.closed {
+                return Err(VibesortError::ShuttingDown);
+            }
+            state.in_flight += 1;
+        }
+        let mut sorter = self.clone();
+        sorter.admitted = true;
+        let guard = SortGuard {
+            lifecycle: self.lifecycle.clone(),
+        };
+        Ok(Some((sorter, guard)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{Backend, BackendRequest, BackendResponse, BoxFuture};
+    use crate::testing::MockBackend;
+
+    /// Replies like the mock backend after a delay.
+    #[derive(Debug)]
+    struct Slow(Duration, MockBackend);
+
+    impl Backend for Slow {
+        fn send(
+            &self,
+            request: BackendRequest,
+        ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>> {
+            Box::pin(async move {
+                tokio::time::sleep(self.0).await;
+                self.1.send(request).await
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_drains_in_flight_sorts() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(Slow(Duration::from_secs(5), MockBackend::new()))
+            .chunk_size(2);
+
+        let running = sorter.clone();
+        let sort =
+            tokio::spawn(
+                async move { running.sort_with_report(&[3, 1, 2]).await.map(|r| r.items) },
+            );
+        tokio::task::yield_now().await;
+
+        assert_eq!(sorter.shutdown(Duration::from_secs(60)).await, 0);
+        assert!(sorter.is_shutting_down());
+        assert_eq!(sort.await.unwrap().unwrap(), vec![1, 2, 3]);
+
+        let err = sorter.sort_with_report(&[2, 1]).await.unwrap_err();
+        assert!(matches!(err, VibesortError::ShuttingDown));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_lets_later_requests_of_a_sort_through() {
+        let backend = MockBackend::new()
+            .respond_with("[0, 1, 2]")
+            .respond_with("[2, 0, 1]");
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(Slow(Duration::from_secs(5), backend));
+
+        // The first reply breaks the constraint, so a second round is needed
+        let running = sorter.clone();
+        let sort = tokio::spawn(async move {
+            let items = ["a", "b", "c"].map(String::from);
+            running
+                .sort_constrained(&items, &[crate::Precedence::new(2, 0)])
+                .await
+        });
+        tokio::task::yield_now().await;
+
+        assert_eq!(sorter.shutdown(Duration::from_secs(60)).await, 0);
+        assert_eq!(sort.await.unwrap().unwrap(), ["c", "a", "b"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_returns_if_a_cancelled_sort_is_not_polled() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(Slow(Duration::from_secs(60), MockBackend::new()));
+
+        let mut sort = Box::pin(sorter.sort_with_report(&[3, 1, 2]));
+        let started = tokio::time::timeout(Duration::from_millis(1), &mut sort).await;
+        assert!(started.is_err());
+
+        let start = tokio::time::Instant::now();
+        assert_eq!(sorter.shutdown(Duration::from_secs(1)).await, 1);
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_cancels_after_timeout() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(Slow(Duration::from_secs(60), MockBackend::new()));
+
+        let running = sorter.clone();
+        let sort =
+            tokio::spawn(
+                async move { running.sort_with_report(&[3, 1, 2]).await.map(|r| r.items) },
+            );
+        tokio::task::yield_now().await;
+
+        assert_eq!(sorter.shutdown(Duration::from_secs(1)).await, 1);
+        let err = sort.await.unwrap().unwrap_err();
+        assert!(matches!(err, VibesortError::ShuttingDown));
+    }
+}
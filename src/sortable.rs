@@ -0,0 +1,96 @@
+//! Types that describe their own sort criterion.
+
+use crate::{Order, Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// A type that knows how its values should be sorted.
+///
+/// Implement this by hand, or derive it with the `derive` feature and
+/// `#[derive(Vibesortable)]`. Field attributes mark the keys to sort by, in
+/// declaration order:
+///
+/// - `#[vibesort(key)]` sorts by the field in ascending order.
+/// - `#[vibesort(key, desc)]` sorts by the field in descending order.
+/// - `#[vibesort(criterion = "...")]` describes how to judge the field.
+///
+/// Alternatively, `#[vibesort(criterion = "...")]` on the type itself
+/// describes the ordering of the whole value, optionally with `desc`.
+/// Field names are taken from the Rust source, so they should match the
+/// serialized names.
+///
+/// # Example
+///
+#[cfg_attr(feature = "derive", doc = "```")]
+#[cfg_attr(not(feature = "derive"), doc = "```ignore")]
+/// use serde::{Deserialize, Serialize};
+/// use vibesort_rs::Vibesortable;
+///
+/// #[derive(Serialize, Deserialize, Vibesortable)]
+/// struct Song {
+///     #[vibesort(criterion = "by how catchy the title is", desc)]
+///     title: String,
+///     #[vibesort(key)]
+///     year: u16,
+/// }
+/// ```
+pub trait Vibesortable: Serialize + DeserializeOwned {
+    /// Returns the criterion used in the prompt for sorting values of this
+    /// type.
+    fn vibesort_criterion() -> &'static str;
+
+    /// Returns the order used for sorting values of this type.
+    fn vibesort_order() -> Order {
+        Order::Ascending
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts values using the criterion and order their type describes.
+    ///
+    /// The configured criterion and order are replaced by those of
+    /// [`Vibesortable`].
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use serde::{Deserialize, Serialize};
+    /// use vibesort_rs::{Vibesort, Vibesortable};
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Song {
+    ///     title: String,
+    /// }
+    ///
+    /// impl Vibesortable for Song {
+    ///     fn vibesort_criterion() -> &'static str {
+    ///         "by how catchy the title is"
+    ///     }
+    /// }
+    ///
+    /// # async fn example(songs: Vec<Song>) -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let sorted = sorter.sort_vibesortable(&songs).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_vibesortable<T>(&self, items: &[T]) -> Result<Vec<T>, VibesortError>
+    where
+        T: Vibesortable,
+    {
+        let sorter = self
+            .clone()
+            .order(T::vibesort_order())
+            .criterion(T::vibesort_criterion());
+        Ok(sorter.sort_with_report(items).await?.items)
+    }
+}
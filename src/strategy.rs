@@ -0,0 +1,270 @@
+//! Pluggable sorting algorithms.
+//!
+//! A [`SortStrategy`] decides how a sort is split into requests: one request
+//! for everything ([`SingleShot`]), sorted chunks merged together
+//! ([`ChunkedMerge`]), a merge sort of pairwise comparisons
+//! ([`PairwiseMergesort`]), an Elo [`Tournament`], or an [`Ensemble`] of
+//! models. Pick one per call with [`Vibesort::sort_with_strategy`], or
+//! implement the trait to build your own algorithm on top of the sorter's
+//! backend, prompts, and retry handling.
+
+use crate::backend::BoxFuture;
+use crate::ensemble::Ensemble;
+use crate::tournament::Tournament;
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt;
+
+/// An algorithm that sorts items with a [`Vibesort`].
+///
+/// The items are passed as JSON values and the strategy must return a
+/// permutation of them.
+///
+/// # Example
+///
+/// ```
+/// use serde_json::Value;
+/// use vibesort_rs::backend::BoxFuture;
+/// use vibesort_rs::strategy::SortStrategy;
+/// use vibesort_rs::{Vibesort, VibesortError};
+///
+/// /// Sends the items in reverse to counter a model's position bias.
+/// #[derive(Debug)]
+/// struct ReversedInput;
+///
+/// impl SortStrategy for ReversedInput {
+///     fn sort<'s>(
+///         &'s self,
+///         sorter: &'s Vibesort<'s>,
+///         items: &'s [Value],
+///     ) -> BoxFuture<'s, Result<Vec<Value>, VibesortError>> {
+///         Box::pin(async move {
+///             let reversed: Vec<Value> = items.iter().rev().cloned().collect();
+///             Ok(sorter.sort_with_report(&reversed).await?.items)
+///         })
+///     }
+/// }
+/// ```
+pub trait SortStrategy: fmt::Debug + Send + Sync {
+    /// Sorts the items with the given sorter.
+    fn sort<'s>(
+        &'s self,
+        sorter: &'s Vibesort<'s>,
+        items: &'s [Value],
+    ) -> BoxFuture<'s, Result<Vec<Value>, VibesortError>>;
+}
+
+/// Sorts everything with a single request, as [`Vibesort::sort`] does.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SingleShot;
+
+impl SortStrategy for SingleShot {
+    fn sort<'s>(
+        &'s self,
+        sorter: &'s Vibesort<'s>,
+        items: &'s [Value],
+    ) -> BoxFuture<'s, Result<Vec<Value>, VibesortError>> {
+        Box::pin(async move { Ok(sorter.sort_with_report(items).await?.items) })
+    }
+}
+
+/// Sorts chunks of at most `chunk_size` elements and merges the sorted chunks,
+/// without trying a single request first.
+///
+/// See [`Vibesort::chunk_size`] for the merge algorithm.
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkedMerge {
+    /// The maximum number of elements per request (at least 2).
+    pub chunk_size: usize,
+}
+
+impl SortStrategy for ChunkedMerge {
+    fn sort<'s>(
+        &'s self,
+        sorter: &'s Vibesort<'s>,
+        items: &'s [Value],
+    ) -> BoxFuture<'s, Result<Vec<Value>, VibesortError>> {
+        Box::pin(async move {
+            sorter
+                .clone()
+                .chunk_size(self.chunk_size)
+                .sort_chunked(items)
+                .await
+        })
+    }
+}
+
+/// A bottom-up merge sort in which every comparison is a request for two
+/// elements.
+///
+/// Needs about `n log2 n` requests, but each one is a small, easy question,
+/// which suits weak models and criteria that are hard to apply to a whole list
+/// at once.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PairwiseMergesort;
+
+impl SortStrategy for PairwiseMergesort {
+    fn sort<'s>(
+        &'s self,
+        sorter: &'s Vibesort<'s>,
+        items: &'s [Value],
+    ) -> BoxFuture<'s, Result<Vec<Value>, VibesortError>> {
+        Box::pin(async move {
+            let mut runs: Vec<Vec<Value>> = items.iter().map(|item| vec![item.clone()]).collect();
+            while runs.len() > 1 {
+                let mut merged_runs = Vec::with_capacity(runs.len().div_ceil(2));
+                let mut pairs = runs.into_iter();
+                while let Some(left) = pairs.next() {
+                    match pairs.next() {
+                        Some(right) => merged_runs.push(merge(sorter, left, right).await?),
+                        None => merged_runs.push(left),
+                    }
+                }
+                runs = merged_runs;
+            }
+            Ok(runs.pop().unwrap_or_default())
+        })
+    }
+}
+
+/// Merges two sorted runs, comparing their heads with the model.
+async fn merge(
+    sorter: &Vibesort<'_>,
+    left: Vec<Value>,
+    right: Vec<Value>,
+) -> Result<Vec<Value>, VibesortError> {
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        // Equal elements keep their input order
+        if sorter.compare(a, b).await? >= 0.5 {
+            merged.extend(left.next());
+        } else {
+            merged.extend(right.next());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+impl SortStrategy for Tournament {
+    fn sort<'s>(
+        &'s self,
+        sorter: &'s Vibesort<'s>,
+        items: &'s [Value],
+    ) -> BoxFuture<'s, Result<Vec<Value>, VibesortError>> {
+        Box::pin(async move {
+            let ranking = sorter.sort_tournament(items, self).await?;
+            Ok(ranking.into_iter().map(|ranked| ranked.item).collect())
+        })
+    }
+}
+
+/// Ignores the sorter passed in and sorts with the ensemble's own members.
+impl SortStrategy for Ensemble<'_> {
+    fn sort<'s>(
+        &'s self,
+        _sorter: &'s Vibesort<'s>,
+        items: &'s [Value],
+    ) -> BoxFuture<'s, Result<Vec<Value>, VibesortError>> {
+        Box::pin(Ensemble::sort(self, items))
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts an array with the given [`SortStrategy`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of the strategy, which for the built-in strategies
+    /// are the same as for [`sort`](Self::sort).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    /// use vibesort_rs::strategy::PairwiseMergesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let sorted = sorter
+    ///     .sort_with_strategy(&[3, 1, 2], &PairwiseMergesort)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_with_strategy<T>(
+        &self,
+        items: &[T],
+        strategy: &dyn SortStrategy,
+    ) -> Result<Vec<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        if let Some((sorter, guard)) = self.admit()? {
+            return guard
+                .run(Box::pin(sorter.sort_with_strategy(items, strategy)))
+                .await;
+        }
+
+        let values: Vec<Value> = items
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<_, _>>()?;
+        let sorted = strategy.sort(self, &values).await?;
+        Ok(sorted
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Order;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_pairwise_mergesort() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .order(Order::Descending);
+
+        let sorted = sorter
+            .sort_with_strategy(&[3, 7, 1, 5, 2], &PairwiseMergesort)
+            .await
+            .unwrap();
+        assert_eq!(sorted, vec![7, 5, 3, 2, 1]);
+        for request in backend.requests() {
+            assert_eq!(request.task.as_ref().unwrap().items.len(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_strategies_agree_on_a_local_backend() {
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(MockBackend::new());
+        let items = [5, 3, 8, 1, 9, 2];
+        let strategies: [&dyn SortStrategy; 3] = [
+            &SingleShot,
+            &ChunkedMerge { chunk_size: 2 },
+            &PairwiseMergesort,
+        ];
+
+        for strategy in strategies {
+            let sorted = sorter.sort_with_strategy(&items, strategy).await.unwrap();
+            assert_eq!(sorted, vec![1, 2, 3, 5, 8, 9], "{:?}", strategy);
+        }
+    }
+}
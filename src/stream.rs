@@ -0,0 +1,140 @@
+//! Sorting items as they arrive from a stream.
+//!
+//! Requires the `stream` feature.
+
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::VecDeque;
+use tokio_stream::{Stream, StreamExt};
+
+impl<'a> Vibesort<'a> {
+    /// Sorts the items of a stream without first collecting them into a
+    /// `Vec`.
+    ///
+    /// Items are buffered into chunks as they arrive, and each chunk is sorted
+    /// as soon as it is full: when adding the next item would exceed
+    /// [`chunk_tokens`](Self::chunk_tokens) estimated tokens, or when it holds
+    /// [`chunk_size`](Self::chunk_size) elements. Once the stream ends, the
+    /// sorted chunks are merged as in chunked sorting. Every request is
+    /// checked to return a permutation of its input.
+    ///
+    /// Requires the `stream` feature.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use tokio_stream::wrappers::ReceiverStream;
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .chunk_tokens(2000);
+    ///
+    /// let (tx, rx) = tokio::sync::mpsc::channel(64);
+    /// tokio::spawn(async move {
+    ///     for word in ["pear", "apple", "fig"] {
+    ///         tx.send(word.to_string()).await.ok();
+    ///     }
+    /// });
+    /// let sorted = sorter.sort_stream_input(ReceiverStream::new(rx)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_stream_input<T, S>(&self, stream: S) -> Result<Vec<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+        S: Stream<Item = T>,
+    {
+        if let Some((sorter, guard)) = self.admit()? {
+            return guard.run(Box::pin(sorter.sort_stream_input(stream))).await;
+        }
+
+        let max_elements = self.chunk_size.unwrap_or(usize::MAX);
+        // The same estimate of three bytes per token as for `max_tokens`
+        let max_bytes = self.chunk_tokens.saturating_mul(3);
+
+        let mut stream = std::pin::pin!(stream);
+        let mut runs = Vec::new();
+        let mut chunk: Vec<Value> = Vec::new();
+        let mut chunk_bytes = 0;
+        let mut largest_chunk = 2;
+        while let Some(item) = stream.next().await {
+            let value = serde_json::to_value(&item)?;
+            let bytes = value.to_string().len() + 1;
+            if !chunk.is_empty() && (chunk.len() >= max_elements || chunk_bytes + bytes > max_bytes)
+            {
+                largest_chunk = largest_chunk.max(chunk.len());
+                let (_, sorted) = self.sort_pass(&chunk, true).await?;
+                runs.push(VecDeque::from(sorted));
+                chunk.clear();
+                chunk_bytes = 0;
+            }
+            chunk.push(value);
+            chunk_bytes += bytes;
+        }
+        if !chunk.is_empty() {
+            largest_chunk = largest_chunk.max(chunk.len());
+            let (_, sorted) = self.sort_pass(&chunk, true).await?;
+            runs.push(VecDeque::from(sorted));
+        }
+
+        Ok(self
+            .merge_all(runs, largest_chunk, &mut false)
+            .await?
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_stream_input_in_chunks() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .chunk_size(3);
+
+        let items: Vec<i64> = (0..10).map(|i| (i * 7) % 10).collect();
+        let sorted: Vec<i64> = sorter
+            .sort_stream_input(tokio_stream::iter(items))
+            .await
+            .unwrap();
+        assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+        for request in backend.requests() {
+            assert!(request.task.as_ref().unwrap().items.len() <= 3);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sort_stream_input_token_budget() {
+        let backend = Arc::new(MockBackend::new());
+        // Two items of about 30 bytes each fit in 24 tokens
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .chunk_tokens(24);
+
+        let items = ["d", "b", "c", "a"].map(|letter| letter.repeat(28));
+        let sorted: Vec<String> = sorter
+            .sort_stream_input(tokio_stream::iter(items.clone()))
+            .await
+            .unwrap();
+        assert_eq!(sorted, ["a", "b", "c", "d"].map(|letter| letter.repeat(28)));
+        assert_eq!(backend.requests()[0].task.as_ref().unwrap().items.len(), 2);
+    }
+}
@@ -0,0 +1,129 @@
+//! Sorting elements wrapped with ids, for models that rewrite values.
+//!
+//! With [`Vibesort::tag_ids`], every element is sent as `{"id": ..., "value":
+//! ...}` and the model is asked to return the objects sorted. The sorted
+//! elements are then restored from the input by id, so a model that echoes
+//! `1.0` as `1` or normalizes whitespace cannot alter them.
+
+use crate::indexed::check_positions;
+use crate::prompt::Operation;
+use crate::{Vibesort, VibesortError, parse};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// An element of a tagged payload.
+#[derive(Serialize)]
+struct Tagged<'t, T> {
+    id: usize,
+    value: &'t T,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Returns the payload and prompt of a sort of the items wrapped with
+    /// ids.
+    pub(crate) fn tagged_sort_prompt<T: Serialize>(
+        &self,
+        items: &[T],
+    ) -> Result<(usize, String, String), VibesortError> {
+        let payload: Vec<Tagged<'_, T>> = items
+            .iter()
+            .enumerate()
+            .map(|(id, value)| Tagged { id, value })
+            .collect();
+        let json_array = self.json_format.to_string(&payload)?;
+        let len = json_array.len();
+        let (system_prompt, user_content) =
+            self.render_prompt(Operation::TaggedSort, json_array, || {
+                format!(
+                    "You are a helpful assistant that sorts arrays. The following JSON array contains objects of the form {{\"id\": <number>, \"value\": <element>}}. Sort the objects by their values {} and return ONLY the sorted JSON array of the objects, unchanged and with every id exactly once, nothing else.",
+                    self.sort_instruction()
+                )
+            })?;
+        Ok((len, system_prompt, user_content))
+    }
+
+    /// Parses a reply to a tagged sort and restores the elements by id from
+    /// their JSON `values`, returning whether the reply had to be repaired.
+    ///
+    /// A reply of plain elements, as sent by
+    /// [`Engine::Local`](crate::engine::Engine::Local) or a model that ignored
+    /// the ids, is parsed as is.
+    pub(crate) fn parse_tagged<T: DeserializeOwned>(
+        &self,
+        values: &[Value],
+        content: &str,
+    ) -> Result<(Vec<T>, bool), VibesortError> {
+        let (reply, repaired): (Vec<Value>, bool) = parse::parse_array_repaired(content)?;
+        let ids: Option<Vec<usize>> = reply.iter().map(tag_id).collect();
+        let Some(ids) = ids else {
+            return Ok((serde_json::from_value(Value::Array(reply))?, repaired));
+        };
+
+        check_positions(values.len(), &ids)?;
+        let sorted = ids
+            .into_iter()
+            .map(|id| Ok(serde_json::from_value(values[id].clone())?))
+            .collect::<Result<_, VibesortError>>()?;
+        Ok((sorted, repaired))
+    }
+}
+
+/// Returns the id of an element of a tagged reply: an object with exactly an
+/// `id` and a `value`.
+fn tag_id(element: &Value) -> Option<usize> {
+    let object = element.as_object()?;
+    if object.len() != 2 || !object.contains_key("value") {
+        return None;
+    }
+    usize::try_from(object.get("id")?.as_u64()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Engine;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_tag_ids_restores_originals() {
+        let backend = Arc::new(MockBackend::new().respond_with(
+            r#"[{"id": 1, "value": 1}, {"id": 2, "value": 2.5}, {"id": 0, "value": 3}]"#,
+        ));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .tag_ids(true);
+
+        let sorted = sorter.sort(&[3.0, 1.0, 2.5]).await.unwrap();
+        assert_eq!(sorted, vec![1.0, 2.5, 3.0]);
+
+        let user = backend.requests()[0].body["messages"][1]["content"].clone();
+        assert_eq!(
+            user,
+            r#"[{"id":0,"value":3.0},{"id":1,"value":1.0},{"id":2,"value":2.5}]"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tag_ids_rejects_missing_ids() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with(r#"[{"id": 1, "value": "a"}]"#))
+            .tag_ids(true);
+
+        let err = sorter
+            .sort(&["b", "a"].map(String::from))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::VerificationFailed(_)));
+    }
+
+    #[tokio::test]
+    async fn test_tag_ids_with_local_engine() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .engine(Engine::local())
+            .tag_ids(true);
+
+        assert_eq!(sorter.sort(&[3, 1, 2]).await.unwrap(), vec![1, 2, 3]);
+    }
+}
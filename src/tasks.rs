@@ -0,0 +1,136 @@
+//! Sorting todo items by the urgency implied by their wording.
+
+use crate::annotate::Annotation;
+use crate::prompt::Operation;
+use crate::{Order, Vibesort, VibesortError};
+use serde::{Deserialize, Serialize};
+
+/// The deadline and urgency the model read from a task's wording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskMetadata {
+    /// The deadline implied by the task, as the model understood it (for
+    /// example `"Friday"` or `"end of month"`), or `None` if there is none.
+    pub deadline: Option<String>,
+
+    /// The urgency from `0.0` (someday) to `1.0` (drop everything).
+    pub urgency: f32,
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts todo items by urgency, most urgent first.
+    ///
+    /// The model reads deadlines and urgency cues from the free text of each
+    /// task ("ship by Friday!!", "someday: clean garage") and returns every
+    /// task unchanged together with the [`TaskMetadata`] it extracted. Urgency
+    /// scores outside `0.0..=1.0` are clamped. The configured criterion and
+    /// order are replaced for this call.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::ParseError`] is returned if any task lacks metadata.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let tasks = sorter
+    ///     .sort_tasks(&["someday: clean garage", "ship by Friday!!", "reply to Sam"])
+    ///     .await?;
+    /// for (task, metadata) in tasks {
+    ///     println!("{} (due: {:?})", task, metadata.deadline);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_tasks(
+        &self,
+        tasks: &[&str],
+    ) -> Result<Vec<(String, TaskMetadata)>, VibesortError> {
+        let tasks: Vec<String> = tasks.iter().map(|s| s.to_string()).collect();
+        let sorter = self.clone().order(Order::Ascending);
+        let instruction = format!(
+            "of todo items {}",
+            sorter.sort_instruction_for(Some(
+                "by urgency implied by deadlines and wording, from the most urgent to the least urgent"
+            ))
+        );
+        let annotation = Annotation {
+            fields: "\"deadline\": <string or null>, \"urgency\": <number>",
+            meaning: "\"deadline\" is the deadline implied by the item in a few words, or null if there is none, and \"urgency\" is a number between 0 and 1 expressing how urgent the item is",
+            size: 48,
+        };
+
+        let sorted: Vec<(String, TaskMetadata)> = sorter
+            .sort_annotated(Operation::Tasks, &tasks, &instruction, annotation)
+            .await?;
+        Ok(sorted
+            .into_iter()
+            .map(|(task, metadata)| {
+                let urgency = metadata.urgency.clamp(0.0, 1.0);
+                (
+                    task,
+                    TaskMetadata {
+                        urgency,
+                        ..metadata
+                    },
+                )
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_tasks() {
+        let backend = Arc::new(MockBackend::new().respond_with(
+            r#"[
+                {"item": "ship by Friday!!", "deadline": "Friday", "urgency": 1.2},
+                {"item": "someday: clean garage", "deadline": null, "urgency": 0.1}
+            ]"#,
+        ));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .order(Order::Descending);
+
+        let sorted = sorter
+            .sort_tasks(&["someday: clean garage", "ship by Friday!!"])
+            .await
+            .unwrap();
+        assert_eq!(
+            sorted,
+            vec![
+                (
+                    "ship by Friday!!".to_string(),
+                    TaskMetadata {
+                        deadline: Some("Friday".to_string()),
+                        urgency: 1.0
+                    }
+                ),
+                (
+                    "someday: clean garage".to_string(),
+                    TaskMetadata {
+                        deadline: None,
+                        urgency: 0.1
+                    }
+                )
+            ]
+        );
+
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        assert!(system.as_str().unwrap().contains("ascending order"));
+    }
+}
@@ -0,0 +1,284 @@
+//! Sorting dates, times, and durations sent as ISO 8601 strings.
+
+use crate::{Vibesort, VibesortError};
+use std::time::Duration;
+
+/// A date, time, or duration that is sent to the model as an ISO 8601
+/// string, for [`Vibesort::sort_temporal`].
+///
+/// Implemented for [`Duration`] (as in `P1DT2H30M`), and with the `chrono`
+/// feature for `DateTime<Utc>`, `DateTime<FixedOffset>`, `NaiveDateTime`,
+/// and `NaiveDate`.
+pub trait Temporal: Sized {
+    /// Formats the value as ISO 8601.
+    fn to_iso(&self) -> String;
+
+    /// Parses a value formatted by [`to_iso`](Self::to_iso), or returns
+    /// `None` if `text` is not valid.
+    fn from_iso(text: &str) -> Option<Self>;
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts dates, times, or durations, sent to the model as ISO 8601
+    /// strings and parsed back from the reply.
+    ///
+    /// The configured criterion and order apply, so criteria such as "by
+    /// weekday" or "by season" work as well as plain chronological order.
+    /// [`verify`](Self::verify) compares the strings, so a model that
+    /// rewrites `Z` as `+00:00` fails verification.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::ParseError`] is returned if the reply contains a
+    /// string that cannot be parsed back.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let timeouts = [Duration::from_secs(90), Duration::from_millis(250)];
+    /// let sorted = sorter.sort_temporal(&timeouts).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_temporal<T: Temporal>(&self, items: &[T]) -> Result<Vec<T>, VibesortError> {
+        let encoded: Vec<String> = items.iter().map(Temporal::to_iso).collect();
+        let sorted = self.sort_with_report(&encoded).await?.items;
+        sorted
+            .iter()
+            .map(|text| {
+                T::from_iso(text).ok_or_else(|| {
+                    VibesortError::ParseError(format!("{:?} is not a valid ISO 8601 value", text))
+                })
+            })
+            .collect()
+    }
+}
+
+impl Temporal for Duration {
+    fn to_iso(&self) -> String {
+        let secs = self.as_secs();
+        let (days, hours, minutes, seconds) =
+            (secs / 86_400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+        let mut out = String::from("P");
+        if days > 0 {
+            out.push_str(&format!("{}D", days));
+        }
+        if hours + minutes + seconds > 0 || self.subsec_nanos() > 0 || days == 0 {
+            out.push('T');
+            if hours > 0 {
+                out.push_str(&format!("{}H", hours));
+            }
+            if minutes > 0 {
+                out.push_str(&format!("{}M", minutes));
+            }
+            if seconds > 0 || self.subsec_nanos() > 0 || hours + minutes == 0 {
+                out.push_str(&seconds.to_string());
+                if self.subsec_nanos() > 0 {
+                    let fraction = format!("{:09}", self.subsec_nanos());
+                    out.push('.');
+                    out.push_str(fraction.trim_end_matches('0'));
+                }
+                out.push('S');
+            }
+        }
+        out
+    }
+
+    /// Parses durations of days, hours, minutes, and seconds, such as
+    /// `P2D`, `PT1H30M`, or `PT0.25S`. Years, months, and weeks are rejected,
+    /// since their length is not fixed.
+    fn from_iso(text: &str) -> Option<Self> {
+        let rest = text.strip_prefix('P')?;
+        let (date, time) = match rest.split_once('T') {
+            Some((date, time)) => (date, Some(time)),
+            None => (rest, None),
+        };
+        if date.is_empty() && time.is_none_or(str::is_empty) {
+            return None;
+        }
+
+        let mut secs = 0u64;
+        if !date.is_empty() {
+            secs = digits(date.strip_suffix('D')?)?.checked_mul(86_400)?;
+        }
+        let mut nanos = 0;
+        if let Some(mut time) = time {
+            if time.is_empty() {
+                return None;
+            }
+            for (unit, scale) in [('H', 3600), ('M', 60)] {
+                if let Some((count, tail)) = time.split_once(unit) {
+                    secs = secs.checked_add(digits(count)?.checked_mul(scale)?)?;
+                    time = tail;
+                }
+            }
+            if !time.is_empty() {
+                let seconds = time.strip_suffix('S')?;
+                let (whole, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+                secs = secs.checked_add(digits(whole)?)?;
+                if !fraction.is_empty() {
+                    if fraction.len() > 9 {
+                        return None;
+                    }
+                    nanos = digits(&format!("{:0<9}", fraction))? as u32;
+                }
+            }
+        }
+        Some(Duration::new(secs, nanos))
+    }
+}
+
+/// Parses a non-empty string of ASCII digits.
+fn digits(text: &str) -> Option<u64> {
+    if text.is_empty() || !text.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use super::Temporal;
+    use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+
+    impl Temporal for DateTime<Utc> {
+        fn to_iso(&self) -> String {
+            self.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+        }
+
+        fn from_iso(text: &str) -> Option<Self> {
+            let time = DateTime::parse_from_rfc3339(text).ok()?;
+            Some(time.with_timezone(&Utc))
+        }
+    }
+
+    impl Temporal for DateTime<FixedOffset> {
+        fn to_iso(&self) -> String {
+            self.to_rfc3339_opts(SecondsFormat::AutoSi, false)
+        }
+
+        fn from_iso(text: &str) -> Option<Self> {
+            DateTime::parse_from_rfc3339(text).ok()
+        }
+    }
+
+    impl Temporal for NaiveDateTime {
+        fn to_iso(&self) -> String {
+            self.format("%Y-%m-%dT%H:%M:%S%.f").to_string()
+        }
+
+        fn from_iso(text: &str) -> Option<Self> {
+            text.parse().ok()
+        }
+    }
+
+    impl Temporal for NaiveDate {
+        fn to_iso(&self) -> String {
+            self.format("%Y-%m-%d").to_string()
+        }
+
+        fn from_iso(text: &str) -> Option<Self> {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_duration_iso() {
+        for (duration, text) in [
+            (Duration::ZERO, "PT0S"),
+            (Duration::from_millis(250), "PT0.25S"),
+            (Duration::from_secs(90), "PT1M30S"),
+            (Duration::from_secs(3600), "PT1H"),
+            (Duration::from_secs(2 * 86_400), "P2D"),
+            (Duration::new(86_400 + 5, 1), "P1DT5.000000001S"),
+        ] {
+            assert_eq!(duration.to_iso(), text);
+            assert_eq!(Duration::from_iso(text), Some(duration));
+        }
+        for invalid in [
+            "",
+            "P",
+            "PT",
+            "P1W",
+            "PT1M1H",
+            "PT+1S",
+            "PT1.0000000001S",
+            "1S",
+        ] {
+            assert_eq!(Duration::from_iso(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_iso() {
+        use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        assert_eq!(date.to_iso(), "2024-02-29");
+        assert_eq!(NaiveDate::from_iso("2024-02-29"), Some(date));
+        assert_eq!(NaiveDate::from_iso("2023-02-29"), None);
+
+        let time = date.and_hms_milli_opt(12, 30, 0, 500).unwrap();
+        assert_eq!(time.to_iso(), "2024-02-29T12:30:00.500");
+        assert_eq!(NaiveDateTime::from_iso(&time.to_iso()), Some(time));
+
+        let utc = time.and_utc();
+        assert_eq!(utc.to_iso(), "2024-02-29T12:30:00.500Z");
+        assert_eq!(
+            DateTime::<Utc>::from_iso("2024-02-29T13:30:00.5+01:00"),
+            Some(utc)
+        );
+
+        let offset = utc.with_timezone(&FixedOffset::east_opt(3600).unwrap());
+        assert_eq!(offset.to_iso(), "2024-02-29T13:30:00.500+01:00");
+        assert_eq!(
+            DateTime::<FixedOffset>::from_iso(&offset.to_iso()),
+            Some(offset)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sort_temporal() {
+        let backend = Arc::new(MockBackend::new().respond_with(r#"["PT0.25S", "PT1M30S"]"#));
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let sorted = sorter
+            .sort_temporal(&[Duration::from_secs(90), Duration::from_millis(250)])
+            .await
+            .unwrap();
+        assert_eq!(
+            sorted,
+            [Duration::from_millis(250), Duration::from_secs(90)]
+        );
+
+        let user = backend.requests()[0].body["messages"][1]["content"].clone();
+        assert_eq!(user, r#"["PT1M30S","PT0.25S"]"#);
+
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with(r#"["PT1M30S", "soon"]"#));
+        let err = sorter
+            .sort_temporal(&[Duration::from_secs(90), Duration::from_millis(250)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::ParseError(_)));
+    }
+}
@@ -0,0 +1,115 @@
+//! Routing requests through the API keys of tenants.
+
+use crate::{Vibesort, VibesortError};
+use secrecy::SecretString;
+use std::fmt;
+use std::sync::Arc;
+
+/// A function returning the API key of a tenant, or `None` if it has none.
+type SelectKey = dyn Fn(&str) -> Option<SecretString> + Send + Sync;
+
+/// The [`Vibesort::key_selector`] of a client.
+#[derive(Clone)]
+pub(crate) struct KeySelector(Arc<SelectKey>);
+
+impl fmt::Debug for KeySelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KeySelector")
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Selects the API key of each request by the tenant it is made for.
+    ///
+    /// Sorts made through a clone returned by [`for_tenant`](Self::for_tenant)
+    /// call `selector` with the tenant id before every request and
+    /// authenticate with the key it returns, so every tenant is billed on
+    /// its own credentials while sharing one client, its limits, and its
+    /// caches. Sorts made without a tenant use [`api_key`](Self::api_key).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::collections::HashMap;
+    /// use vibesort_rs::{SecretString, Vibesort};
+    ///
+    /// # async fn example(keys: HashMap<String, String>) -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new("fallback-key", "gpt-3.5-turbo", "https://api.openai.com/v1")
+    ///     .key_selector(move |tenant| keys.get(tenant).map(|key| SecretString::from(key.as_str())));
+    ///
+    /// let sorted = sorter.for_tenant("acme").sort(&[3, 1, 2]).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn key_selector(
+        mut self,
+        selector: impl Fn(&str) -> Option<SecretString> + Send + Sync + 'static,
+    ) -> Self {
+        self.key_selector = Some(KeySelector(Arc::new(selector)));
+        self
+    }
+
+    /// Returns a clone of this client whose requests are made for `tenant`,
+    /// authenticated with the key of the [`key_selector`](Self::key_selector).
+    ///
+    /// The clone is cheap to create for every sort.
+    pub fn for_tenant(&self, tenant: &str) -> Self {
+        let mut sorter = self.clone();
+        sorter.tenant = Some(tenant.to_string());
+        sorter
+    }
+
+    /// Returns the API key to authenticate the next request with.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::AuthFailed`] if the key selector has no key
+    /// for the tenant.
+    pub(crate) fn request_key(&self) -> Result<SecretString, VibesortError> {
+        match (&self.tenant, &self.key_selector) {
+            (Some(tenant), Some(selector)) => (selector.0)(tenant).ok_or_else(|| {
+                VibesortError::AuthFailed(format!("no API key for tenant {:?}", tenant))
+            }),
+            _ => Ok(self.api_key.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use secrecy::ExposeSecret;
+
+    #[tokio::test]
+    async fn test_key_selector() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("shared", "model", "http://mock")
+            .backend(backend.clone())
+            .key_selector(|tenant| match tenant {
+                "acme" => Some(SecretString::from("acme-key")),
+                _ => None,
+            });
+
+        sorter.sort_with_report(&[2, 1]).await.unwrap();
+        sorter
+            .for_tenant("acme")
+            .sort_with_report(&[2, 1])
+            .await
+            .unwrap();
+        let keys: Vec<String> = backend
+            .requests()
+            .iter()
+            .map(|request| request.api_key.expose_secret().to_string())
+            .collect();
+        assert_eq!(keys, ["shared", "acme-key"]);
+
+        let err = sorter
+            .for_tenant("initech")
+            .sort_with_report(&[2, 1])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::AuthFailed(_)));
+        assert_eq!(backend.requests().len(), 2);
+    }
+}
@@ -0,0 +1,76 @@
+//! Assertion helpers for checking sorted results.
+
+use crate::verify;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::fmt::Debug;
+
+/// Asserts that `output` contains exactly the elements of `input`.
+///
+/// Elements are compared by their JSON serialization, the same way
+/// [`Vibesort::verify`](crate::Vibesort::verify) compares them.
+///
+/// # Panics
+///
+/// Panics with the missing and unexpected elements if `output` is not a
+/// permutation of `input`.
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::testing::assert_is_permutation;
+///
+/// assert_is_permutation(&[3, 1, 2], &[1, 2, 3]);
+/// ```
+#[track_caller]
+pub fn assert_is_permutation<T: Serialize + Debug>(input: &[T], output: &[T]) {
+    let mismatch = verify::diff(input, output).expect("elements must serialize to JSON");
+    assert!(
+        mismatch.is_empty(),
+        "output is not a permutation of the input\n  input: {:?}\n output: {:?}\nmissing: {:?}\nunexpected: {:?}",
+        input,
+        output,
+        mismatch.missing,
+        mismatch.unexpected
+    );
+}
+
+/// Asserts that `output` equals `input` sorted with [`slice::sort`].
+///
+/// # Panics
+///
+/// Panics showing both orderings if they differ.
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::testing::assert_sorted_by_oracle;
+///
+/// assert_sorted_by_oracle(&[3, 1, 2], &[1, 2, 3]);
+/// ```
+#[track_caller]
+pub fn assert_sorted_by_oracle<T: Ord + Clone + Debug>(input: &[T], output: &[T]) {
+    assert_sorted_by_oracle_with(input, output, T::cmp);
+}
+
+/// Asserts that `output` equals `input` sorted with a custom comparator.
+///
+/// The oracle uses a stable sort, so equal elements must keep their input
+/// order for the assertion to pass.
+///
+/// # Panics
+///
+/// Panics showing both orderings if they differ.
+#[track_caller]
+pub fn assert_sorted_by_oracle_with<T, F>(input: &[T], output: &[T], compare: F)
+where
+    T: Clone + Debug + PartialEq,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut expected = input.to_vec();
+    expected.sort_by(compare);
+    assert_eq!(
+        output, expected,
+        "output does not match the oracle ordering"
+    );
+}
@@ -0,0 +1,216 @@
+//! VCR-style recording and replaying of backend responses.
+
+use crate::VibesortError;
+use crate::backend::{Backend, BackendRequest, BackendResponse, BoxFuture};
+use crate::cache::fnv1a;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Controls whether a [`Cassette`] records, replays, or both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CassetteMode {
+    /// Replays recorded responses and records any request not seen before.
+    #[default]
+    Auto,
+
+    /// Always forwards requests to the inner backend and overwrites recordings.
+    Record,
+
+    /// Only replays recorded responses; unknown requests fail.
+    ///
+    /// Use this in CI to guarantee that no request reaches the real API.
+    Replay,
+}
+
+/// A [`Backend`] that records responses to files and replays them later.
+///
+/// Each request is keyed by a hash of its URL and body (the API key is not
+/// part of the key), and its response is stored as `<hash>.json` in the
+/// cassette directory. The recorded files are plain JSON and can be committed
+/// alongside the tests that use them.
+///
+/// # Example
+///
+/// ```no_run
+/// use vibesort_rs::Vibesort;
+/// use vibesort_rs::backend::HttpBackend;
+/// use vibesort_rs::testing::{Cassette, CassetteMode};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mode = if std::env::var("CI").is_ok() {
+///     CassetteMode::Replay
+/// } else {
+///     CassetteMode::Auto
+/// };
+/// let cassette = Cassette::new("tests/cassettes", HttpBackend::default()).mode(mode);
+///
+/// let api_key = std::env::var("API_KEY").unwrap_or_default();
+/// let sorter = Vibesort::new(&api_key, "gpt-3.5-turbo", "https://api.openai.com/v1")
+///     .backend(cassette);
+/// let sorted = sorter.sort(&[3, 1, 2]).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Cassette<B> {
+    dir: PathBuf,
+    inner: B,
+    mode: CassetteMode,
+}
+
+/// The on-disk format of a recorded response.
+#[derive(Debug, Serialize, Deserialize)]
+struct Recording {
+    status: u16,
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+impl<B: Backend> Cassette<B> {
+    /// Creates a cassette stored in `dir` that forwards unrecorded requests to
+    /// `inner`.
+    ///
+    /// The directory is created on the first recording if it does not exist.
+    pub fn new(dir: impl Into<PathBuf>, inner: B) -> Self {
+        Self {
+            dir: dir.into(),
+            inner,
+            mode: CassetteMode::default(),
+        }
+    }
+
+    /// Sets the recording mode (defaults to [`CassetteMode::Auto`]).
+    pub fn mode(mut self, mode: CassetteMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Returns the path of the recording for the given request.
+    pub fn path_for(&self, request: &BackendRequest) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}.json", request_hash(request)))
+    }
+
+    async fn replay_or_record(
+        &self,
+        request: BackendRequest,
+    ) -> Result<BackendResponse, VibesortError> {
+        let path = self.path_for(&request);
+
+        if self.mode != CassetteMode::Record && path.exists() {
+            return Ok(load(&path)?);
+        }
+        if self.mode == CassetteMode::Replay {
+            return Err(VibesortError::IoError(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no recording for request at {}", path.display()),
+            )));
+        }
+
+        let response = self.inner.send(request).await?;
+        save(&self.dir, &path, &response)?;
+        Ok(response)
+    }
+}
+
+impl<B: Backend> Backend for Cassette<B> {
+    fn send(
+        &self,
+        request: BackendRequest,
+    ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>> {
+        Box::pin(self.replay_or_record(request))
+    }
+}
+
+/// Hashes the URL and body of a request with 64-bit FNV-1a, so that
+/// recordings stay valid across Rust versions.
+fn request_hash(request: &BackendRequest) -> u64 {
+    fnv1a(&format!("{}\n{}", request.url, request.body))
+}
+
+fn load(path: &Path) -> io::Result<BackendResponse> {
+    let recording: Recording = serde_json::from_slice(&std::fs::read(path)?)?;
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in recording.headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            headers.insert(name, value);
+        }
+    }
+
+    Ok(BackendResponse {
+        status: StatusCode::from_u16(recording.status)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        headers,
+        body: recording.body,
+    })
+}
+
+fn save(dir: &Path, path: &Path, response: &BackendResponse) -> io::Result<()> {
+    let recording = Recording {
+        status: response.status.as_u16(),
+        headers: response
+            .headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        body: response.body.clone(),
+    };
+
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(path, serde_json::to_vec_pretty(&recording)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vibesort;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vibesort-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_cassette_records_then_replays() {
+        let dir = temp_dir("cassette");
+
+        let recorder = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(Cassette::new(&dir, recorder.clone()));
+        assert_eq!(sorter.sort(&[3, 1, 2]).await.unwrap(), vec![1, 2, 3]);
+        assert_eq!(recorder.requests().len(), 1);
+
+        // The replaying backend would answer differently if it were consulted
+        let replayer = Arc::new(MockBackend::new().respond_with("[9,9,9]"));
+        let sorter = Vibesort::new("other-key", "model", "http://mock")
+            .backend(Cassette::new(&dir, replayer.clone()).mode(CassetteMode::Replay));
+        assert_eq!(sorter.sort(&[3, 1, 2]).await.unwrap(), vec![1, 2, 3]);
+        assert!(replayer.requests().is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_cassette_replay_missing_recording() {
+        let dir = temp_dir("cassette-missing");
+
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(Cassette::new(&dir, MockBackend::new()).mode(CassetteMode::Replay));
+
+        match sorter.sort(&[3, 1, 2]).await.unwrap_err() {
+            VibesortError::IoError(e) => assert_eq!(e.kind(), io::ErrorKind::NotFound),
+            _ => panic!("Expected IoError"),
+        }
+    }
+}
@@ -0,0 +1,336 @@
+//! Fault injection for exercising retry and fallback handling.
+
+use crate::VibesortError;
+use crate::backend::{Backend, BackendRequest, BackendResponse, BoxFuture};
+use crate::rng::SplitMix64;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderValue, RETRY_AFTER};
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A fault injected by [`ChaosBackend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// A `429 Too Many Requests` response with a `Retry-After` header.
+    RateLimited,
+
+    /// A [`VibesortError::Timeout`] after the configured delay.
+    Timeout,
+
+    /// A successful response whose content is cut off mid-array.
+    Truncated,
+
+    /// A successful response with an extra element that was not in the input.
+    ///
+    /// The element has the type of the element it is inserted next to where
+    /// possible: a number or string that is in no position of the reply, and
+    /// otherwise a marker object.
+    Hallucinated,
+}
+
+/// A [`Backend`] wrapper that randomly injects realistic LLM failures.
+///
+/// Each request rolls at most one fault using the configured probabilities;
+/// requests that roll no fault are forwarded to the inner backend untouched.
+/// The random generator is seeded, so a given seed always produces the same
+/// sequence of faults.
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::Vibesort;
+/// use vibesort_rs::testing::{ChaosBackend, MockBackend};
+///
+/// let chaos = ChaosBackend::new(MockBackend::new())
+///     .rate_limit(0.2)
+///     .timeout(0.1)
+///     .truncate(0.1)
+///     .hallucinate(0.1)
+///     .seed(7);
+/// let sorter = Vibesort::new("unused", "mock", "http://mock").backend(chaos);
+/// ```
+#[derive(Debug)]
+pub struct ChaosBackend<B> {
+    inner: B,
+    rate_limit: f64,
+    timeout: f64,
+    truncate: f64,
+    hallucinate: f64,
+    timeout_delay: Duration,
+    rng: Mutex<SplitMix64>,
+    injected: Mutex<Vec<Fault>>,
+}
+
+impl<B: Backend> ChaosBackend<B> {
+    /// Wraps `inner` with all fault probabilities set to zero.
+    pub fn new(inner: B) -> Self {
+        Self {
+            inner,
+            rate_limit: 0.0,
+            timeout: 0.0,
+            truncate: 0.0,
+            hallucinate: 0.0,
+            timeout_delay: Duration::ZERO,
+            rng: Mutex::new(SplitMix64::new(0x853c_49e6_748f_ea9b)),
+            injected: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Sets the probability of answering with `429 Too Many Requests`.
+    pub fn rate_limit(mut self, probability: f64) -> Self {
+        self.rate_limit = probability;
+        self
+    }
+
+    /// Sets the probability of failing with [`VibesortError::Timeout`].
+    pub fn timeout(mut self, probability: f64) -> Self {
+        self.timeout = probability;
+        self
+    }
+
+    /// Sets how long an injected timeout waits before failing (default: none).
+    pub fn timeout_delay(mut self, delay: Duration) -> Self {
+        self.timeout_delay = delay;
+        self
+    }
+
+    /// Sets the probability of truncating the returned JSON array.
+    pub fn truncate(mut self, probability: f64) -> Self {
+        self.truncate = probability;
+        self
+    }
+
+    /// Sets the probability of adding an element that was not in the input.
+    pub fn hallucinate(mut self, probability: f64) -> Self {
+        self.hallucinate = probability;
+        self
+    }
+
+    /// Seeds the random generator to make the fault sequence reproducible.
+    pub fn seed(self, seed: u64) -> Self {
+        *self.rng.lock().unwrap() = SplitMix64::new(seed);
+        self
+    }
+
+    /// Returns every fault injected so far, in order.
+    pub fn injected(&self) -> Vec<Fault> {
+        self.injected.lock().unwrap().clone()
+    }
+
+    /// Returns a uniformly distributed number in `[0, 1)`.
+    fn next_f64(&self) -> f64 {
+        self.rng.lock().unwrap().next_f64()
+    }
+
+    fn roll(&self) -> Option<Fault> {
+        let roll = self.next_f64();
+        let faults = [
+            (Fault::RateLimited, self.rate_limit),
+            (Fault::Timeout, self.timeout),
+            (Fault::Truncated, self.truncate),
+            (Fault::Hallucinated, self.hallucinate),
+        ];
+
+        let mut threshold = 0.0;
+        for (fault, probability) in faults {
+            threshold += probability;
+            if roll < threshold {
+                return Some(fault);
+            }
+        }
+        None
+    }
+
+    async fn send_with_chaos(
+        &self,
+        request: BackendRequest,
+    ) -> Result<BackendResponse, VibesortError> {
+        let fault = self.roll();
+        if let Some(fault) = fault {
+            self.injected.lock().unwrap().push(fault);
+        }
+
+        match fault {
+            Some(Fault::RateLimited) => {
+                let mut response = BackendResponse::new(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    r#"{"error":{"message":"Rate limit reached","type":"rate_limit_exceeded"}}"#,
+                );
+                response
+                    .headers
+                    .insert(RETRY_AFTER, HeaderValue::from_static("1"));
+                Ok(response)
+            }
+            Some(Fault::Timeout) => {
+                crate::rt::sleep(self.timeout_delay).await;
+                Err(VibesortError::Timeout)
+            }
+            Some(Fault::Truncated) => {
+                let response = self.inner.send(request).await?;
+                let cut = self.next_f64();
+                Ok(map_content(response, |content| {
+                    let keep = (content.len() as f64 * cut) as usize;
+                    let keep = keep.min(content.len().saturating_sub(1));
+                    let boundary = (0..=keep).rev().find(|&i| content.is_char_boundary(i));
+                    content[..boundary.unwrap_or(0)].to_string()
+                }))
+            }
+            Some(Fault::Hallucinated) => {
+                let response = self.inner.send(request).await?;
+                let position = self.next_f64();
+                Ok(map_content(
+                    response,
+                    |content| match serde_json::from_str::<Vec<Value>>(content) {
+                        Ok(mut values) => {
+                            let index = (values.len() as f64 * position) as usize;
+                            let extra = invented(&values, values.get(index));
+                            values.insert(index.min(values.len()), extra);
+                            Value::Array(values).to_string()
+                        }
+                        Err(_) => content.to_string(),
+                    },
+                ))
+            }
+            None => self.inner.send(request).await,
+        }
+    }
+}
+
+impl<B: Backend> Backend for ChaosBackend<B> {
+    fn send(
+        &self,
+        request: BackendRequest,
+    ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>> {
+        Box::pin(self.send_with_chaos(request))
+    }
+}
+
+/// Returns an element that is not in `values`, of the type of `like` if it
+/// is a number or a string.
+fn invented(values: &[Value], like: Option<&Value>) -> Value {
+    let fresh = |candidate: &Value| !values.contains(candidate);
+    match like {
+        Some(Value::Number(_)) => {
+            if values.iter().all(Value::is_i64) {
+                let max = values.iter().filter_map(Value::as_i64).max();
+                if let Some(next) = max.unwrap_or_default().checked_add(1) {
+                    return Value::from(next);
+                }
+            }
+            let max = values.iter().filter_map(Value::as_f64).fold(0.0, f64::max);
+            let next = Value::from(max + 1.0);
+            if fresh(&next) {
+                return next;
+            }
+        }
+        Some(Value::String(text)) => {
+            let mut text = format!("{} (invented)", text);
+            while !fresh(&Value::String(text.clone())) {
+                text.push('!');
+            }
+            return Value::String(text);
+        }
+        _ => {}
+    }
+    (0u64..)
+        .map(|marker| serde_json::json!({ "hallucinated": marker }))
+        .find(fresh)
+        .expect("a reply has fewer elements than markers")
+}
+
+/// Rewrites the message content of a successful chat completion response.
+fn map_content(mut response: BackendResponse, f: impl FnOnce(&str) -> String) -> BackendResponse {
+    if !response.status.is_success() {
+        return response;
+    }
+    let Ok(mut body) = serde_json::from_str::<Value>(&response.body) else {
+        return response;
+    };
+    if let Some(content) = body["choices"][0]["message"]["content"].as_str() {
+        body["choices"][0]["message"]["content"] = Value::String(f(content));
+        response.body = body.to_string();
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vibesort;
+    use crate::testing::MockBackend;
+
+    #[tokio::test]
+    async fn test_chaos_backend_rate_limit() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(ChaosBackend::new(MockBackend::new()).rate_limit(1.0));
+
+        let result = sorter.sort(&[2, 1]).await;
+        assert!(matches!(
+            result,
+            Err(VibesortError::RateLimited {
+                retry_after: Some(_)
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chaos_backend_timeout() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(ChaosBackend::new(MockBackend::new()).timeout(1.0));
+
+        let result = sorter.sort(&[2, 1]).await;
+        assert!(matches!(result, Err(VibesortError::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn test_chaos_backend_truncates_and_hallucinates() {
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(ChaosBackend::new(MockBackend::new()).truncate(1.0));
+        let result = sorter.sort(&[3, 2, 1]).await;
+        assert!(matches!(result, Err(VibesortError::ParseError(_))));
+
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(ChaosBackend::new(MockBackend::new()).hallucinate(1.0));
+        let sorted = sorter.sort(&[3, 2, 1]).await.unwrap();
+        assert_eq!(sorted.len(), 4);
+        assert!(sorted.contains(&4));
+
+        let err = sorter
+            .clone()
+            .verify(true)
+            .restore_duplicates(true)
+            .sort(&["b", "a", "a"].map(String::from))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, VibesortError::VerificationFailed(message) if message.contains("(invented)"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chaos_backend_is_reproducible() {
+        let run = |seed| {
+            let chaos = ChaosBackend::new(MockBackend::new())
+                .rate_limit(0.3)
+                .truncate(0.3)
+                .seed(seed);
+            async move {
+                for _ in 0..20 {
+                    let request = BackendRequest {
+                        url: "http://mock/chat/completions".to_string(),
+                        api_key: "key".into(),
+                        body: serde_json::json!({"messages": [{"role": "user", "content": "[1]"}]}),
+                        task: None,
+                    };
+                    let _ = chaos.send(request).await;
+                }
+                chaos.injected()
+            }
+        };
+
+        let first = run(42).await;
+        assert!(!first.is_empty());
+        assert_eq!(first, run(42).await);
+    }
+}
@@ -0,0 +1,124 @@
+//! A scripted, network-free [`Backend`].
+
+use crate::VibesortError;
+use crate::backend::{Backend, BackendRequest, BackendResponse, BoxFuture};
+use crate::engine::{LocalEngine, completion};
+use reqwest::StatusCode;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// A scripted response returned by [`MockBackend`].
+#[derive(Debug, Clone)]
+enum Scripted {
+    /// A successful completion whose message content is the given string.
+    Content(String),
+
+    /// A raw response with the given status code and body.
+    Raw(StatusCode, String),
+}
+
+/// A [`Backend`] that returns scripted responses without any network access.
+///
+/// Scripted responses are returned in the order they were added. Once the
+/// script is exhausted, the backend sorts the array from the request locally
+/// using the natural ordering of JSON values (numbers numerically, strings
+/// lexicographically), so an unscripted `MockBackend` behaves like a perfect
+/// model.
+///
+/// Every request received is recorded and can be inspected with
+/// [`requests`](Self::requests).
+#[derive(Debug, Default)]
+pub struct MockBackend {
+    script: Mutex<VecDeque<Scripted>>,
+    requests: Mutex<Vec<BackendRequest>>,
+}
+
+impl MockBackend {
+    /// Creates a backend that sorts every request locally.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a successful response whose message content is `content`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vibesort_rs::testing::MockBackend;
+    ///
+    /// let backend = MockBackend::new()
+    ///     .respond_with("Sure! Here is your array: [1, 2, 3]")
+    ///     .respond_with("[1,2,3]");
+    /// ```
+    pub fn respond_with(self, content: impl Into<String>) -> Self {
+        self.push(Scripted::Content(content.into()))
+    }
+
+    /// Queues a raw response with the given status code and body.
+    ///
+    /// Use this to simulate provider errors such as `429 Too Many Requests`.
+    pub fn respond_with_status(self, status: u16, body: impl Into<String>) -> Self {
+        let status = StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        self.push(Scripted::Raw(status, body.into()))
+    }
+
+    /// Returns every request received so far, in order.
+    pub fn requests(&self) -> Vec<BackendRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn push(self, scripted: Scripted) -> Self {
+        self.script.lock().unwrap().push_back(scripted);
+        self
+    }
+}
+
+impl Backend for MockBackend {
+    fn send(
+        &self,
+        request: BackendRequest,
+    ) -> BoxFuture<'_, Result<BackendResponse, VibesortError>> {
+        let scripted = self.script.lock().unwrap().pop_front();
+        let response = match scripted {
+            Some(Scripted::Content(content)) => completion(&content),
+            Some(Scripted::Raw(status, body)) => BackendResponse::new(status, body),
+            None => LocalEngine::default().respond(&request),
+        };
+        self.requests.lock().unwrap().push(request);
+        Box::pin(async move { Ok(response) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vibesort;
+
+    #[tokio::test]
+    async fn test_mock_backend_sorts_locally() {
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(MockBackend::new());
+
+        let sorted = sorter.sort(&[3.5, -1.0, 2.25]).await.unwrap();
+        assert_eq!(sorted, vec![-1.0, 2.25, 3.5]);
+    }
+
+    #[tokio::test]
+    async fn test_mock_backend_scripted_responses() {
+        let backend = std::sync::Arc::new(
+            MockBackend::new()
+                .respond_with_status(429, "Too Many Requests")
+                .respond_with("[\"a\",\"b\"]"),
+        );
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let first = sorter.sort_str(&["b", "a"]).await;
+        assert!(matches!(first, Err(VibesortError::RateLimited { .. })));
+
+        let second = sorter.sort_str(&["b", "a"]).await.unwrap();
+        assert_eq!(second, vec!["a", "b"]);
+
+        let requests = backend.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].url, "http://mock/chat/completions");
+    }
+}
@@ -0,0 +1,43 @@
+//! Test doubles for code that depends on vibesort.
+//!
+//! [`MockBackend`] lets downstream crates unit-test their sorting code without
+//! an API key, network access, or an HTTP mock server. [`Cassette`] records
+//! real API responses to disk and replays them, keeping integration tests
+//! deterministic and free after the first recording. [`ChaosBackend`] injects
+//! rate limits, timeouts, truncated JSON, and hallucinated elements to test
+//! failure handling.
+//!
+//! With the `test-util` feature enabled, this module also provides assertion
+//! helpers such as `assert_is_permutation` and `proptest` strategies in
+//! `strategies` for fuzzing the parsing and verification layers.
+//!
+//! # Example
+//!
+//! ```
+//! use vibesort_rs::Vibesort;
+//! use vibesort_rs::testing::MockBackend;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let sorter = Vibesort::new("unused", "mock", "http://mock").backend(MockBackend::new());
+//!
+//! let sorted = sorter.sort(&[3, 1, 2]).await?;
+//! assert_eq!(sorted, vec![1, 2, 3]);
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "test-util")]
+mod assertions;
+mod cassette;
+mod chaos;
+mod mock;
+#[cfg(feature = "test-util")]
+pub mod strategies;
+
+#[cfg(feature = "test-util")]
+pub use assertions::{
+    assert_is_permutation, assert_sorted_by_oracle, assert_sorted_by_oracle_with,
+};
+pub use cassette::{Cassette, CassetteMode};
+pub use chaos::{ChaosBackend, Fault};
+pub use mock::MockBackend;
@@ -0,0 +1,66 @@
+//! [`proptest`] strategies for fuzzing the parsing and verification layers.
+//!
+//! # Example
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use vibesort_rs::parse::parse_array;
+//! use vibesort_rs::testing::strategies::{json_array, llm_content};
+//!
+//! let cases = json_array(16).prop_flat_map(|v| (Just(v.clone()), llm_content(v)));
+//! proptest!(|((values, content) in cases)| {
+//!     let parsed: Vec<serde_json::Value> = parse_array(&content).unwrap();
+//!     prop_assert_eq!(parsed, values);
+//! });
+//! ```
+
+use proptest::prelude::*;
+use serde_json::Value;
+
+/// Generates JSON scalars of the kinds commonly sorted: integers, floats,
+/// strings (including non-ASCII), booleans, and `null`.
+pub fn json_scalar() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        any::<i64>().prop_map(Value::from),
+        (-1e9f64..1e9f64).prop_map(Value::from),
+        "\\PC{0,12}".prop_map(Value::from),
+        any::<bool>().prop_map(Value::from),
+        Just(Value::Null),
+    ]
+}
+
+/// Generates arrays of up to `max_len` JSON scalars.
+pub fn json_array(max_len: usize) -> impl Strategy<Value = Vec<Value>> {
+    prop::collection::vec(json_scalar(), 0..=max_len)
+}
+
+/// Generates a random permutation of `items`.
+pub fn permutation_of<T: Clone + std::fmt::Debug>(items: Vec<T>) -> impl Strategy<Value = Vec<T>> {
+    Just(items).prop_shuffle()
+}
+
+/// Generates LLM-style response content encoding `values`.
+///
+/// The array is rendered compactly or pretty-printed, optionally wrapped in a
+/// markdown code fence with or without a language tag, and padded with
+/// surrounding whitespace, mirroring how models format their answers.
+pub fn llm_content(values: Vec<Value>) -> impl Strategy<Value = String> {
+    let array = Value::Array(values);
+    let compact = array.to_string();
+    let pretty = serde_json::to_string_pretty(&array).unwrap_or_else(|_| compact.clone());
+
+    (
+        prop_oneof![Just(compact), Just(pretty)],
+        prop_oneof![
+            Just(("", "")),
+            Just(("```\n", "\n```")),
+            Just(("```json\n", "\n```")),
+            Just(("```JSON\n", "```")),
+        ],
+        "[ \n\t]{0,3}",
+        "[ \n\t]{0,3}",
+    )
+        .prop_map(|(json, (open, close), lead, trail)| {
+            format!("{}{}{}{}{}", lead, open, json, close, trail)
+        })
+}
@@ -0,0 +1,175 @@
+//! Ordering tasks by dependencies described in their text.
+
+use crate::indexed::{ElementReply, describe_payload};
+use crate::prompt::Operation;
+use crate::{Precedence, Vibesort, VibesortError, graph};
+use serde::{Deserialize, Serialize};
+
+/// The result of [`Vibesort::toposort`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopoSort<T> {
+    /// The tasks, each after every task it depends on.
+    pub items: Vec<T>,
+
+    /// The dependencies the model inferred, as positions in the input: every
+    /// [`before`](Precedence::before) task must be done before its
+    /// [`after`](Precedence::after) task. Sorted by `after`, then `before`.
+    pub edges: Vec<Precedence>,
+}
+
+/// The dependencies of one task, as returned by the model.
+#[derive(Debug, Deserialize)]
+struct Dependencies {
+    index: usize,
+    #[serde(default)]
+    depends_on: Vec<usize>,
+}
+
+impl ElementReply for Dependencies {
+    fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Orders tasks whose text describes their dependencies, such as "deploy
+    /// after the DB migration", so that every task comes after the tasks it
+    /// depends on.
+    ///
+    /// The model only extracts the dependency graph; the order is computed
+    /// locally, after checking that the graph has no cycles, so it always
+    /// respects every inferred dependency. Tasks that do not depend on each
+    /// other keep their input order. A graph with a cycle counts as an invalid
+    /// reply and is retried under the [retry policy](Self::retry_policy). The
+    /// configured order and criterion are not used.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the inferred
+    /// dependencies form a cycle or refer to tasks that do not exist.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-4o-mini",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let tasks = [
+    ///     "Deploy the new release, after the DB migration",
+    ///     "Run the DB migration once the backup is verified",
+    ///     "Back up the database",
+    /// ];
+    /// let plan = sorter.toposort(&tasks).await?;
+    /// for edge in &plan.edges {
+    ///     println!("{:?} before {:?}", tasks[edge.before], tasks[edge.after]);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn toposort<T>(&self, tasks: &[T]) -> Result<TopoSort<T>, VibesortError>
+    where
+        T: Serialize + Clone,
+    {
+        let prompt = || {
+            format!(
+                "You are a helpful assistant that plans work. {} The text of a task may say which other tasks it depends on, for example \"after the DB migration\" or \"once the backup is verified\". For every task, list the indices of the tasks that must be done before it, based only on what the texts say. Return ONLY a JSON array of objects of the form {{\"index\": <number>, \"depends_on\": [<index>, ...]}}, with every index exactly once.",
+                describe_payload("task")
+            )
+        };
+        let len = tasks.len();
+        // Every task gets an index and a short list of dependencies
+        let (order, edges) = self
+            .describe_indexed(
+                Operation::Toposort,
+                tasks,
+                32 * len,
+                prompt,
+                |graph: Vec<Dependencies>| {
+                    let mut edges: Vec<(usize, usize)> = Vec::new();
+                    for task in &graph {
+                        for &dependency in &task.depends_on {
+                            if dependency >= len {
+                                return Err(VibesortError::VerificationFailed(format!(
+                                    "task {} depends on task {}, which does not exist",
+                                    task.index, dependency
+                                )));
+                            }
+                            edges.push((dependency, task.index));
+                        }
+                    }
+                    edges.sort_by_key(|&(before, after)| (after, before));
+                    edges.dedup();
+
+                    match graph::topological_order(len, &edges) {
+                        Some(order) => Ok((order, edges)),
+                        None => {
+                            let cycle: Vec<String> = graph::find_cycle(len, &edges)
+                                .unwrap_or_default()
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect();
+                            Err(VibesortError::VerificationFailed(format!(
+                                "dependencies form a cycle: {}",
+                                cycle.join(" -> ")
+                            )))
+                        }
+                    }
+                },
+            )
+            .await?;
+
+        Ok(TopoSort {
+            items: order
+                .into_iter()
+                .map(|index| tasks[index].clone())
+                .collect(),
+            edges: edges
+                .into_iter()
+                .map(|(before, after)| Precedence::new(before, after))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexed::replying;
+
+    #[tokio::test]
+    async fn test_toposort() {
+        let reply = r#"[
+            {"index": 0, "depends_on": [1]},
+            {"index": 1, "depends_on": [2]},
+            {"index": 2, "depends_on": []},
+            {"index": 3}
+        ]"#;
+        let (sorter, _) = replying(&[reply]);
+
+        let tasks = ["Deploy", "Migrate", "Back up", "Write docs"].map(String::from);
+        let plan = sorter.toposort(&tasks).await.unwrap();
+        assert_eq!(plan.items, ["Back up", "Migrate", "Deploy", "Write docs"]);
+        assert_eq!(
+            plan.edges,
+            vec![Precedence::new(1, 0), Precedence::new(2, 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_toposort_rejects_cycles() {
+        let reply = r#"[{"index": 0, "depends_on": [1]}, {"index": 1, "depends_on": [0]}]"#;
+        let (sorter, _) = replying(&[reply]);
+
+        let err = sorter.toposort(&["a", "b"]).await.unwrap_err();
+        assert!(
+            matches!(err, VibesortError::VerificationFailed(message) if message.contains("0 -> 1 -> 0"))
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Ranking by an Elo tournament of pairwise comparisons.
+//!
+//! Single-shot sorting asks the model to order everything at once, which gets
+//! unreliable for long lists and subjective criteria. A tournament instead
+//! asks the model to compare two elements at a time and keeps an Elo rating
+//! for every element. Each round pairs elements with similar ratings (a Swiss
+//! system), so comparisons concentrate where the ordering is still uncertain.
+//! The final ratings come with a standard error derived from the
+//! Bradley-Terry model, so close calls can be told apart from clear wins.
+//!
+//! Every comparison is a request, so a tournament over `n` elements costs
+//! about `n / 2` requests per round.
+
+use crate::rng::SplitMix64;
+use crate::{Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// The rating every element starts with.
+const INITIAL_RATING: f64 = 1500.0;
+
+/// The configuration of a tournament.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tournament {
+    rounds: Option<usize>,
+    k_factor: f64,
+    seed: Option<u64>,
+}
+
+impl Tournament {
+    /// Creates a tournament with the default settings: `⌈log2 n⌉ + 1` rounds
+    /// for `n` elements and a K-factor of 32.
+    pub fn new() -> Self {
+        Self {
+            rounds: None,
+            k_factor: 32.0,
+            seed: None,
+        }
+    }
+
+    /// Sets the number of rounds. Every element plays at most one comparison
+    /// per round.
+    pub fn rounds(mut self, rounds: usize) -> Self {
+        self.rounds = Some(rounds);
+        self
+    }
+
+    /// Sets the K-factor, the maximum rating change per comparison.
+    pub fn k_factor(mut self, k_factor: f64) -> Self {
+        self.k_factor = k_factor;
+        self
+    }
+
+    /// Seeds the pairing of elements with equal ratings, for reproducible
+    /// schedules. Without a seed the pairing is randomized.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+impl Default for Tournament {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An element of a tournament ranking.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ranked<T> {
+    /// The element.
+    pub item: T,
+
+    /// The final Elo rating. Higher ratings come first in the ranking.
+    pub rating: f64,
+
+    /// The standard error of the rating, in rating points.
+    ///
+    /// Two elements whose ratings differ by less than about twice their
+    /// standard errors are not clearly separated. Infinite for an element that
+    /// played no comparisons.
+    pub std_error: f64,
+
+    /// The number of comparisons the element took part in.
+    pub matches: usize,
+}
+
+/// Returns the expected score of an element rated `a` against one rated `b`.
+fn expected_score(a: f64, b: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((b - a) / 400.0))
+}
+
+impl<'a> Vibesort<'a> {
+    /// Ranks the items with an Elo tournament of pairwise comparisons.
+    ///
+    /// Each comparison sorts two elements with the configured order and
+    /// criterion, and is always verified. The ranking is returned best first,
+    /// that is, in the configured order. See the [module
+    /// documentation](crate::tournament) for how elements are paired.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of any comparison; see [`sort`](Self::sort).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::Vibesort;
+    /// use vibesort_rs::tournament::Tournament;
+    ///
+    /// # async fn example(headlines: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .criterion("by how likely a reader is to click, most clickable first");
+    ///
+    /// let ranking = sorter
+    ///     .sort_tournament(&headlines, &Tournament::new().rounds(8))
+    ///     .await?;
+    /// for ranked in ranking.iter().take(10) {
+    ///     println!("{:.0} ± {:.0}  {}", ranked.rating, ranked.std_error, ranked.item);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_tournament<T>(
+        &self,
+        items: &[T],
+        tournament: &Tournament,
+    ) -> Result<Vec<Ranked<T>>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        if let Some((sorter, guard)) = self.admit()? {
+            return guard
+                .run(Box::pin(sorter.sort_tournament(items, tournament)))
+                .await;
+        }
+
+        let values: Vec<Value> = items
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<_, _>>()?;
+        let len = values.len();
+        let rounds = tournament
+            .rounds
+            .unwrap_or_else(|| len.next_power_of_two().trailing_zeros() as usize + 1);
+        let mut rng = tournament
+            .seed
+            .map_or_else(SplitMix64::from_time, SplitMix64::new);
+
+        let mut ratings = vec![INITIAL_RATING; len];
+        let mut matches = Vec::new();
+        for _ in 0..rounds {
+            // Shuffle, then stably sort by rating, so that elements with equal
+            // ratings are paired randomly
+            let mut standings: Vec<usize> = (0..len).collect();
+            for i in (1..len).rev() {
+                standings.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+            }
+            standings.sort_by(|&a, &b| ratings[b].total_cmp(&ratings[a]));
+
+            for pair in standings.chunks_exact(2) {
+                let (a, b) = (pair[0], pair[1]);
+                let score = self.compare(&values[a], &values[b]).await?;
+                let expected = expected_score(ratings[a], ratings[b]);
+                ratings[a] += tournament.k_factor * (score - expected);
+                ratings[b] -= tournament.k_factor * (score - expected);
+                matches.push((a, b));
+            }
+        }
+
+        // The Fisher information of each rating under the Bradley-Terry model
+        let mut information = vec![0.0; len];
+        let mut played = vec![0; len];
+        for &(a, b) in &matches {
+            let p = expected_score(ratings[a], ratings[b]);
+            for index in [a, b] {
+                information[index] += p * (1.0 - p);
+                played[index] += 1;
+            }
+        }
+        let scale = 400.0 / std::f64::consts::LN_10;
+
+        let mut ranking: Vec<usize> = (0..len).collect();
+        ranking.sort_by(|&a, &b| ratings[b].total_cmp(&ratings[a]));
+        ranking
+            .into_iter()
+            .map(|index| {
+                Ok(Ranked {
+                    item: serde_json::from_value(values[index].clone())?,
+                    rating: ratings[index],
+                    std_error: scale / f64::sqrt(information[index]),
+                    matches: played[index],
+                })
+            })
+            .collect()
+    }
+
+    /// Compares two elements, returning 1 if `a` comes first, 0 if `b` does,
+    /// and 0.5 if they are equal.
+    pub(crate) async fn compare(&self, a: &Value, b: &Value) -> Result<f64, VibesortError> {
+        if a == b {
+            return Ok(0.5);
+        }
+        let result = self
+            .sort_with_report_inner(&[a.clone(), b.clone()], true)
+            .await?;
+        Ok(if result.items[0] == *a { 1.0 } else { 0.0 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Order;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_tournament_ranking() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .order(Order::Descending);
+
+        let items = [3, 7, 1, 5, 2, 8, 4, 6];
+        let ranking = sorter
+            .sort_tournament(&items, &Tournament::new().rounds(6).seed(7))
+            .await
+            .unwrap();
+
+        let ranked: Vec<i32> = ranking.iter().map(|ranked| ranked.item).collect();
+        assert_eq!(ranked[0], 8);
+        assert_eq!(ranked[7], 1);
+        assert!(ranking.iter().all(|ranked| ranked.matches == 6));
+        assert!(ranking.iter().all(|ranked| ranked.std_error.is_finite()));
+
+        // Every request compares exactly two elements
+        assert_eq!(backend.requests().len(), 6 * 4);
+        for request in backend.requests() {
+            assert_eq!(request.task.as_ref().unwrap().items.len(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_equal_elements_draw_without_a_request() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(backend.clone());
+
+        let ranking = sorter
+            .sort_tournament(&[1, 1], &Tournament::new().rounds(3))
+            .await
+            .unwrap();
+        assert_eq!(ranking[0].rating, INITIAL_RATING);
+        assert_eq!(ranking[1].rating, INITIAL_RATING);
+        assert!(backend.requests().is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+//! Unicode normalization and transliteration of strings before sorting.
+//!
+//! Visually identical strings can be encoded differently (`é` as one code
+//! point or as `e` followed by a combining accent), and models order them
+//! inconsistently. Normalizing all strings locally before the request makes
+//! multilingual lists sort consistently. Transliteration goes further and
+//! sorts by an ASCII approximation of each string (`é` ≈ `e`, `Москва` ≈
+//! `Moskva`), while still returning the original strings.
+//!
+//! Requires the `unicode` feature.
+
+use crate::{SortResult, Vibesort, VibesortError, verify};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use unicode_normalization::UnicodeNormalization;
+
+/// A Unicode normalization form applied to strings before sorting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Normalization {
+    /// Canonical composition: equivalent encodings of the same characters
+    /// become identical.
+    Nfc,
+
+    /// Compatibility composition: additionally folds variants such as
+    /// full-width letters and ligatures (`ﬁ` becomes `fi`).
+    Nfkc,
+}
+
+impl Normalization {
+    fn apply(self, s: &str) -> String {
+        match self {
+            Normalization::Nfc => s.nfc().collect(),
+            Normalization::Nfkc => s.nfkc().collect(),
+        }
+    }
+}
+
+/// Applies `f` to every string in a JSON value.
+fn map_strings(value: Value, f: &impl Fn(&str) -> String) -> Value {
+    match value {
+        Value::String(s) => Value::String(f(&s)),
+        Value::Array(values) => {
+            Value::Array(values.into_iter().map(|v| map_strings(v, f)).collect())
+        }
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, v)| (key, map_strings(v, f)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Returns `true` if any Unicode option is enabled.
+    pub(crate) fn uses_unicode_options(&self) -> bool {
+        self.normalization.is_some() || self.transliterate
+    }
+
+    /// Sorts the items by their normalized (and possibly transliterated) form.
+    ///
+    /// Normalized items are returned. When transliterating, the model sorts
+    /// the transliterated keys and each key is mapped back to its item, so the
+    /// keys are always checked to be a permutation of the input.
+    pub(crate) async fn sort_with_unicode<T>(
+        &self,
+        items: &[T],
+    ) -> Result<SortResult<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let normalization = self.normalization;
+        let normalize = |s: &str| normalization.map_or_else(|| s.to_string(), |n| n.apply(s));
+        let values: Vec<Value> = items
+            .iter()
+            .map(|item| Ok(map_strings(serde_json::to_value(item)?, &normalize)))
+            .collect::<Result<_, VibesortError>>()?;
+
+        if !self.transliterate {
+            let result = self.sort_with_report_inner(&values, self.verify).await?;
+            let sorted = result
+                .items
+                .into_iter()
+                .map(serde_json::from_value)
+                .collect::<Result<_, _>>()?;
+            return Ok(SortResult::new(sorted, result.report));
+        }
+
+        let keys: Vec<Value> = values
+            .iter()
+            .map(|value| map_strings(value.clone(), &|s| deunicode::deunicode(s)))
+            .collect();
+        let result = self.sort_with_report_inner(&keys, true).await?;
+        verify::check_permutation(&keys, &result.items)?;
+
+        // Map each sorted key back to an unused item with that key
+        let mut used = vec![false; keys.len()];
+        let mut sorted = Vec::with_capacity(values.len());
+        for key in &result.items {
+            if let Some(index) = (0..keys.len()).find(|&i| !used[i] && keys[i] == *key) {
+                used[index] = true;
+                sorted.push(serde_json::from_value(values[index].clone())?);
+            }
+        }
+        Ok(SortResult::new(sorted, result.report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_normalization_before_sorting() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .normalize(Normalization::Nfc);
+
+        // "e" followed by a combining acute accent
+        let sorted = sorter.sort_str(&["e\u{301}clair", "apple"]).await.unwrap();
+        assert_eq!(sorted, vec!["apple", "\u{e9}clair"]);
+
+        let sent = backend.requests()[0].task.clone().unwrap().items;
+        assert!(sent.contains(&Value::String("\u{e9}clair".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_transliterated_sort_returns_originals() {
+        let backend = Arc::new(MockBackend::new());
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .transliterate(true);
+
+        // By code point "é" would sort after "f"
+        let sorted = sorter.sort_str(&["fig", "éclair", "date"]).await.unwrap();
+        assert_eq!(sorted, vec!["date", "éclair", "fig"]);
+
+        let sent = backend.requests()[0].task.clone().unwrap().items;
+        assert!(sent.contains(&Value::String("eclair".to_string())));
+    }
+}
@@ -0,0 +1,615 @@
+//! Verification that a sorted result is a permutation of its input.
+//!
+//! Elements are compared by their canonical JSON serialization, so any type
+//! implementing `Serialize` can be verified without requiring `Eq` or `Hash`.
+//!
+//! For very large outputs, [`check_sampled`] trades certainty for speed: it
+//! checks the boundaries of the output and a random sample of the rest, as
+//! configured by a [`Sampling`].
+//!
+//! Models sometimes echo a float with a slightly different last digit, so
+//! [`check_permutation_within`] accepts numbers within a relative tolerance.
+
+use crate::rng::SplitMix64;
+use crate::{JsonFormat, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::{DefaultHasher, Hasher};
+
+/// The difference between an input array and an output that should be a
+/// permutation of it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Elements of the input that are missing from the output (with repeats).
+    pub missing: Vec<Value>,
+
+    /// Elements of the output that were not in the input (with repeats).
+    pub unexpected: Vec<Value>,
+}
+
+impl Mismatch {
+    /// Returns `true` if the output is an exact permutation of the input.
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+/// Compares the multisets of elements in `input` and `output`.
+///
+/// # Errors
+///
+/// Returns [`VibesortError::JsonError`] if an element cannot be serialized.
+pub fn diff<T: Serialize>(input: &[T], output: &[T]) -> Result<Mismatch, VibesortError> {
+    diff_in(input, output, &JsonFormat::default())
+}
+
+/// Like [`diff`], with floats compared at the precision of `format`.
+pub(crate) fn diff_in<T: Serialize>(
+    input: &[T],
+    output: &[T],
+    format: &JsonFormat,
+) -> Result<Mismatch, VibesortError> {
+    let format = format.canonical();
+    let mut counts: BTreeMap<String, (Value, isize)> = BTreeMap::new();
+    for item in input {
+        let value = serde_json::to_value(item)?;
+        counts
+            .entry(format.to_string(&value)?)
+            .or_insert((value, 0))
+            .1 += 1;
+    }
+    for item in output {
+        let value = serde_json::to_value(item)?;
+        counts
+            .entry(format.to_string(&value)?)
+            .or_insert((value, 0))
+            .1 -= 1;
+    }
+
+    let mut mismatch = Mismatch::default();
+    for (value, count) in counts.into_values() {
+        let target = if count > 0 {
+            &mut mismatch.missing
+        } else {
+            &mut mismatch.unexpected
+        };
+        target.extend(std::iter::repeat_n(value, count.unsigned_abs()));
+    }
+    Ok(mismatch)
+}
+
+/// Checks that `output` is a permutation of `input`.
+///
+/// # Errors
+///
+/// Returns [`VibesortError::VerificationFailed`] describing the missing and
+/// unexpected elements if it is not.
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::verify::check_permutation;
+///
+/// assert!(check_permutation(&[3, 1, 2], &[1, 2, 3]).is_ok());
+/// assert!(check_permutation(&[1, 1, 2], &[1, 2]).is_err());
+/// ```
+pub fn check_permutation<T: Serialize>(input: &[T], output: &[T]) -> Result<(), VibesortError> {
+    check_permutation_in(input, output, &JsonFormat::default(), 0.0).map(drop)
+}
+
+/// Like [`check_permutation`], with floats considered equal if they differ
+/// by at most `tolerance` relative to the larger of them.
+///
+/// Floats are compared wherever they occur in the elements, including in
+/// arrays and objects, and an integer equals a float within the tolerance.
+/// Integers are still compared exactly with each other, so ids and counts
+/// must match.
+///
+/// # Errors
+///
+/// Returns [`VibesortError::VerificationFailed`] describing the missing and
+/// unexpected elements if the output is not a permutation of the input.
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::verify::check_permutation_within;
+///
+/// let input = [0.3, 0.1];
+/// assert!(check_permutation_within(&input, &[0.1, 0.30000000001], 1e-9).is_ok());
+/// assert!(check_permutation_within(&input, &[0.1, 0.31], 1e-9).is_err());
+/// ```
+pub fn check_permutation_within<T: Serialize>(
+    input: &[T],
+    output: &[T],
+    tolerance: f64,
+) -> Result<(), VibesortError> {
+    check_permutation_in(input, output, &JsonFormat::default(), tolerance).map(drop)
+}
+
+/// An element of an output that only equals an element of the input within
+/// the tolerance: its position in the output and the input element.
+pub(crate) type Substitution = (usize, Value);
+
+/// Like [`check_permutation_within`], with floats compared at the precision
+/// of `format`. A `tolerance` of zero compares exactly.
+///
+/// Returns the elements of the output that only matched within the
+/// tolerance, to be replaced by the input elements they matched.
+pub(crate) fn check_permutation_in<T: Serialize>(
+    input: &[T],
+    output: &[T],
+    format: &JsonFormat,
+    tolerance: f64,
+) -> Result<Vec<Substitution>, VibesortError> {
+    let mut mismatch = diff_in(input, output, format)?;
+    let pairs = if tolerance > 0.0 {
+        pair_within(&mut mismatch, tolerance)
+    } else {
+        Vec::new()
+    };
+    if !mismatch.is_empty() {
+        return Err(VibesortError::VerificationFailed(describe(&mismatch)));
+    }
+    locate(output, pairs, format)
+}
+
+/// Removes the pairs of missing and unexpected elements that are equal
+/// within `tolerance`, returning them as `(unexpected, missing)`.
+fn pair_within(mismatch: &mut Mismatch, tolerance: f64) -> Vec<(Value, Value)> {
+    let missing = &mut mismatch.missing;
+    let mut pairs = Vec::new();
+    mismatch.unexpected.retain(|found| {
+        match missing
+            .iter()
+            .position(|expected| approx_eq(expected, found, tolerance))
+        {
+            Some(index) => {
+                pairs.push((found.clone(), missing.remove(index)));
+                false
+            }
+            None => true,
+        }
+    });
+    pairs
+}
+
+/// Finds the unexpected element of every pair in `output`.
+fn locate<T: Serialize>(
+    output: &[T],
+    pairs: Vec<(Value, Value)>,
+    format: &JsonFormat,
+) -> Result<Vec<Substitution>, VibesortError> {
+    if pairs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let format = format.canonical();
+    let mut expected: HashMap<String, Vec<Value>> = HashMap::new();
+    for (found, input) in pairs {
+        expected
+            .entry(format.to_string(&found)?)
+            .or_default()
+            .push(input);
+    }
+    let mut substitutions = Vec::new();
+    for (position, item) in output.iter().enumerate() {
+        if let Some(inputs) = expected.get_mut(&format.to_string(item)?)
+            && let Some(input) = inputs.pop()
+        {
+            substitutions.push((position, input));
+        }
+    }
+    Ok(substitutions)
+}
+
+/// Returns `true` if the values are equal, with floats compared within a
+/// relative `tolerance`.
+fn approx_eq(a: &Value, b: &Value, tolerance: f64) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) if a.is_f64() || b.is_f64() => {
+            match (a.as_f64(), b.as_f64()) {
+                (Some(a), Some(b)) => (a - b).abs() <= tolerance * a.abs().max(b.abs()),
+                _ => false,
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| approx_eq(a, b, tolerance))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| approx_eq(a, b, tolerance)))
+        }
+        _ => a == b,
+    }
+}
+
+/// Restores the duplicates that `output` collapsed, such as `[1, 2, 3]`
+/// returned for `[1, 1, 2, 3]`: every element of the output is repeated as
+/// often as it occurs in the input, where it first occurs in the output.
+///
+/// Returns `None` unless the output is shorter than the input and only lacks
+/// further occurrences of elements it contains, so outputs that dropped or
+/// invented distinct elements are left to [`check_permutation`].
+///
+/// # Errors
+///
+/// Returns [`VibesortError::JsonError`] if an element cannot be serialized
+/// or deserialized.
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::verify::restore_duplicates;
+///
+/// let restored = restore_duplicates(&[2, 1, 2, 1, 3], &[1, 2, 3]).unwrap();
+/// assert_eq!(restored, Some(vec![1, 1, 2, 2, 3]));
+/// assert_eq!(restore_duplicates(&[2, 1, 3], &[1, 2]).unwrap(), None);
+/// ```
+pub fn restore_duplicates<T: Serialize + DeserializeOwned>(
+    input: &[T],
+    output: &[T],
+) -> Result<Option<Vec<T>>, VibesortError> {
+    restore_duplicates_in(input, output, &JsonFormat::default(), 0.0)
+}
+
+/// Like [`restore_duplicates`], with floats compared at the precision of
+/// `format` and within `tolerance` as in [`check_permutation_within`].
+///
+/// Restored elements are taken from the input, so an element the model
+/// rewrote within the tolerance is restored as it was in the input.
+pub(crate) fn restore_duplicates_in<T: Serialize + DeserializeOwned>(
+    input: &[T],
+    output: &[T],
+    format: &JsonFormat,
+    tolerance: f64,
+) -> Result<Option<Vec<T>>, VibesortError> {
+    if output.len() >= input.len() {
+        return Ok(None);
+    }
+    let format = format.canonical();
+
+    // The distinct elements of the input, with how often they occur there
+    // and in the output
+    let mut distinct: Vec<(Value, usize, usize)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for item in input {
+        let value = serde_json::to_value(item)?;
+        let position = match positions.entry(format.to_string(&value)?) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                distinct.push((value, 0, 0));
+                *entry.insert(distinct.len() - 1)
+            }
+        };
+        distinct[position].1 += 1;
+    }
+
+    let mut matched = Vec::with_capacity(output.len());
+    for item in output {
+        let value = serde_json::to_value(item)?;
+        let position = positions
+            .get(&format.to_string(&value)?)
+            .copied()
+            .filter(|&position| distinct[position].2 < distinct[position].1)
+            .or_else(|| {
+                (tolerance > 0.0).then(|| {
+                    distinct.iter().position(|(input, expected, found)| {
+                        found < expected && approx_eq(input, &value, tolerance)
+                    })
+                })?
+            });
+        let Some(position) = position else {
+            return Ok(None);
+        };
+        distinct[position].2 += 1;
+        matched.push(position);
+    }
+    if distinct.iter().any(|&(_, _, found)| found == 0) {
+        return Ok(None);
+    }
+
+    let mut restored = Vec::with_capacity(input.len());
+    let mut emitted = vec![false; distinct.len()];
+    for position in matched {
+        if !std::mem::replace(&mut emitted[position], true) {
+            let (value, count, _) = &distinct[position];
+            for _ in 0..*count {
+                restored.push(serde_json::from_value(value.clone())?);
+            }
+        }
+    }
+    Ok(Some(restored))
+}
+
+/// How many elements of an output [`check_sampled`] inspects.
+///
+/// The first and last [`boundary`](Self::boundary) elements are always
+/// checked, since truncated and garbled replies usually go wrong at the ends;
+/// a number of further positions are drawn at random.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sampling {
+    samples: usize,
+    boundary: usize,
+}
+
+impl Sampling {
+    /// Creates a sampling of `samples` random positions and boundaries of 16
+    /// elements.
+    pub fn new(samples: usize) -> Self {
+        Self {
+            samples,
+            boundary: 16,
+        }
+    }
+
+    /// Creates a sampling that detects an output in which a share
+    /// `corrupted` of the elements were altered or invented, with
+    /// probability at least `confidence`.
+    ///
+    /// Both arguments are clamped to the open interval from 0 to 1. For
+    /// example, 99% confidence of catching 1% of corrupted elements takes 459
+    /// samples, whatever the size of the output.
+    ///
+    /// ```
+    /// use vibesort_rs::verify::Sampling;
+    ///
+    /// assert_eq!(Sampling::with_confidence(0.99, 0.01).samples(), 459);
+    /// ```
+    pub fn with_confidence(confidence: f64, corrupted: f64) -> Self {
+        let open = |p: f64| p.clamp(f64::EPSILON, 1.0 - f64::EPSILON);
+        // Every sample misses the corruption with probability 1 - corrupted
+        let samples = ((1.0 - open(confidence)).ln() / (1.0 - open(corrupted)).ln()).ceil();
+        Self::new(samples as usize)
+    }
+
+    /// Sets how many elements at each end of the output are always checked.
+    pub fn boundary(mut self, boundary: usize) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Returns the number of random positions checked.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+}
+
+/// Checks that `output` is probably a permutation of `input`, inspecting only
+/// the positions chosen by `sampling`.
+///
+/// The lengths must be equal, and every inspected element of the output must
+/// occur in the input, no more often than there. Every element of the input
+/// is still serialized once, but only the sampled elements of the output are.
+/// Outputs too short to sample are checked in full with
+/// [`check_permutation`]. The `seed` selects the random positions.
+///
+/// # Errors
+///
+/// Returns [`VibesortError::VerificationFailed`] if a problem was found and
+/// [`VibesortError::JsonError`] if an element cannot be serialized.
+///
+/// # Example
+///
+/// ```
+/// use vibesort_rs::verify::{Sampling, check_sampled};
+///
+/// let input: Vec<u32> = (0..10_000).rev().collect();
+/// let mut output = input.clone();
+/// output.sort();
+/// assert!(check_sampled(&input, &output, &Sampling::new(100), 7).is_ok());
+///
+/// output[9_999] = 12_345;
+/// assert!(check_sampled(&input, &output, &Sampling::new(100), 7).is_err());
+/// ```
+pub fn check_sampled<T: Serialize>(
+    input: &[T],
+    output: &[T],
+    sampling: &Sampling,
+    seed: u64,
+) -> Result<(), VibesortError> {
+    check_sampled_in(input, output, sampling, seed, &JsonFormat::default(), 0.0).map(drop)
+}
+
+/// Like [`check_sampled`], with floats compared at the precision of `format`
+/// and numbers within `tolerance` as in [`check_permutation_within`].
+///
+/// Sampled elements are looked up exactly, so a sampled element that is not
+/// found falls back to the full check when there is a tolerance. Returns the
+/// substitutions of that check, if it ran.
+pub(crate) fn check_sampled_in<T: Serialize>(
+    input: &[T],
+    output: &[T],
+    sampling: &Sampling,
+    seed: u64,
+    format: &JsonFormat,
+    tolerance: f64,
+) -> Result<Vec<Substitution>, VibesortError> {
+    if input.len() != output.len() {
+        return Err(VibesortError::VerificationFailed(format!(
+            "expected {} elements, got {}",
+            input.len(),
+            output.len()
+        )));
+    }
+    let len = output.len();
+    if len <= 2 * sampling.boundary + sampling.samples {
+        return check_permutation_in(input, output, format, tolerance);
+    }
+
+    let format = format.canonical();
+    let mut positions: BTreeSet<usize> = (0..sampling.boundary)
+        .chain(len - sampling.boundary..len)
+        .collect();
+    let mut rng = SplitMix64::new(seed);
+    for _ in 0..sampling.samples {
+        positions.insert((rng.next_u64() % len as u64) as usize);
+    }
+
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    for item in input {
+        *counts.entry(fingerprint(item, &format)?).or_default() += 1;
+    }
+    for position in positions {
+        let item = &output[position];
+        match counts.get_mut(&fingerprint(item, &format)?) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ if tolerance > 0.0 => {
+                return check_permutation_in(input, output, &format, tolerance);
+            }
+            found => {
+                let problem = if found.is_some() {
+                    "occurs more often than in the input"
+                } else {
+                    "is not in the input"
+                };
+                return Err(VibesortError::VerificationFailed(format!(
+                    "element {} at position {} {}",
+                    serde_json::to_value(item)?,
+                    position,
+                    problem
+                )));
+            }
+        }
+    }
+    Ok(Vec::new())
+}
+
+/// Returns a hash of the serialization of `item` in the canonical `format`.
+fn fingerprint<T: Serialize>(item: &T, format: &JsonFormat) -> Result<u64, VibesortError> {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(format.to_string(item)?.as_bytes());
+    Ok(hasher.finish())
+}
+
+/// Formats a mismatch for error messages.
+fn describe(mismatch: &Mismatch) -> String {
+    let list = |values: &[Value]| {
+        values
+            .iter()
+            .map(Value::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!(
+        "missing [{}], unexpected [{}]",
+        list(&mismatch.missing),
+        list(&mismatch.unexpected)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_reports_missing_and_unexpected() {
+        let mismatch = diff(&[1, 1, 2, 3], &[1, 2, 3, 4]).unwrap();
+        assert_eq!(mismatch.missing, vec![serde_json::json!(1)]);
+        assert_eq!(mismatch.unexpected, vec![serde_json::json!(4)]);
+    }
+
+    #[test]
+    fn test_check_sampled() {
+        let input: Vec<u32> = (0..1000).collect();
+        let sampling = Sampling::new(10).boundary(5);
+        assert!(check_sampled(&input, &input, &sampling, 1).is_ok());
+
+        let mut duplicated = input.clone();
+        duplicated[1] = 0;
+        match check_sampled(&input, &duplicated, &sampling, 1).unwrap_err() {
+            VibesortError::VerificationFailed(msg) => {
+                assert!(msg.contains("position 1 occurs more often"))
+            }
+            _ => panic!("Expected VerificationFailed"),
+        }
+        assert!(check_sampled(&input, &input[1..], &sampling, 1).is_err());
+
+        // Short outputs are checked in full
+        assert!(check_sampled(&[1, 2, 3], &[3, 2, 4], &sampling, 1).is_err());
+    }
+
+    #[test]
+    fn test_restore_duplicates() {
+        let restored = restore_duplicates(&['b', 'a', 'b', 'b'], &['a', 'b', 'b']).unwrap();
+        assert_eq!(restored, Some(vec!['a', 'b', 'b', 'b']));
+
+        // Exact, dropped, invented, and over-repeated elements are not restored
+        assert_eq!(restore_duplicates(&[1, 2], &[2, 1]).unwrap(), None);
+        assert_eq!(restore_duplicates(&[1, 1, 2], &[1]).unwrap(), None);
+        assert_eq!(restore_duplicates(&[1, 1, 2], &[4, 2]).unwrap(), None);
+        assert_eq!(restore_duplicates(&[1, 1, 1, 2], &[1, 1]).unwrap(), None);
+
+        // Under a tolerance, rewritten floats match and are restored from
+        // the input
+        let format = JsonFormat::default();
+        let input = [0.2, 0.1, 0.1];
+        let output = [0.10000000001, 0.2];
+        assert_eq!(
+            restore_duplicates_in(&input, &output, &format, 0.0).unwrap(),
+            None
+        );
+        assert_eq!(
+            restore_duplicates_in(&input, &output, &format, 1e-9).unwrap(),
+            Some(vec![0.1, 0.1, 0.2])
+        );
+    }
+
+    #[test]
+    fn test_check_permutation_within() {
+        #[derive(Serialize)]
+        struct Reading {
+            sensor: &'static str,
+            values: Vec<f64>,
+        }
+        let reading = |sensor, values: &[f64]| Reading {
+            sensor,
+            values: values.to_vec(),
+        };
+
+        let input = [reading("a", &[0.1, 2.0]), reading("b", &[1e-12])];
+        let output = [reading("b", &[1.0000000001e-12]), reading("a", &[0.1, 2.0])];
+        assert!(check_permutation(&input, &output).is_err());
+        assert!(check_permutation_within(&input, &output, 1e-9).is_ok());
+
+        let output = [reading("a", &[0.1, 2.0]), reading("a", &[1e-12])];
+        assert!(check_permutation_within(&input, &output, 1e-9).is_err());
+
+        assert!(check_permutation_within(&[1.0, 2.0], &[2.0, 1.01], 1e-9).is_err());
+        assert!(check_permutation_within(&[1.0, 2.0], &[2.0, 1.01], 0.05).is_ok());
+
+        // Integers are compared exactly
+        let id: u64 = 1_000_000_000_000;
+        assert!(check_permutation_within(&[id], &[id + 1], 1e-9).is_err());
+        assert!(
+            check_permutation_within(&[serde_json::json!(2)], &[serde_json::json!(2.0)], 1e-9)
+                .is_ok()
+        );
+
+        // Elements matched within the tolerance are located for substitution
+        let format = JsonFormat::default();
+        let substitutions =
+            check_permutation_in(&[0.3, 0.1], &[0.1, 0.30000000001], &format, 1e-9).unwrap();
+        assert_eq!(substitutions, vec![(1, serde_json::json!(0.3))]);
+
+        // Misses of the sampled check are rechecked in full
+        let input: Vec<f64> = (1..1000).map(|i| f64::from(i) / 10.0).collect();
+        let mut output = input.clone();
+        output[0] *= 1.0 + 1e-12;
+        let sampling = Sampling::new(10).boundary(5);
+        assert!(check_sampled_in(&input, &output, &sampling, 1, &format, 0.0).is_err());
+        assert!(check_sampled_in(&input, &output, &sampling, 1, &format, 1e-9).is_ok());
+    }
+
+    #[test]
+    fn test_check_permutation() {
+        assert!(check_permutation(&["b", "a"], &["a", "b"]).is_ok());
+        match check_permutation(&["a"], &["a", "a"]).unwrap_err() {
+            VibesortError::VerificationFailed(msg) => assert!(msg.contains("unexpected [\"a\"]")),
+            _ => panic!("Expected VerificationFailed"),
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Sorting short texts and emoji along predefined vibe axes.
+
+use crate::{Order, Vibesort, VibesortError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::ops::Range;
+
+/// One end of a predefined vibe axis.
+///
+/// A vibe sort runs from one end to the other, given as a [`VibeRange`] such
+/// as `VibeAxis::Chaotic..VibeAxis::Calm`. The axes are energy (`Chaotic` and
+/// `Calm`), formality (`Formal` and `Casual`), and positivity (`Positive` and
+/// `Negative`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VibeAxis {
+    /// High-energy, wild, unpredictable.
+    Chaotic,
+
+    /// Low-energy, soothing, serene.
+    Calm,
+
+    /// Polished, professional, buttoned-up.
+    Formal,
+
+    /// Relaxed, playful, conversational.
+    Casual,
+
+    /// Upbeat, cheerful, optimistic.
+    Positive,
+
+    /// Gloomy, hostile, pessimistic.
+    Negative,
+}
+
+impl VibeAxis {
+    /// Returns the description of this end of the axis used in prompts.
+    fn describe(self) -> &'static str {
+        match self {
+            VibeAxis::Chaotic => "chaotic (high-energy, wild, unpredictable)",
+            VibeAxis::Calm => "calm (low-energy, soothing, serene)",
+            VibeAxis::Formal => "formal (polished, professional, buttoned-up)",
+            VibeAxis::Casual => "casual (relaxed, playful, conversational)",
+            VibeAxis::Positive => "positive (upbeat, cheerful, optimistic)",
+            VibeAxis::Negative => "negative (gloomy, hostile, pessimistic)",
+        }
+    }
+
+    /// Returns the end of the same axis at the other extreme.
+    fn opposite(self) -> VibeAxis {
+        match self {
+            VibeAxis::Chaotic => VibeAxis::Calm,
+            VibeAxis::Calm => VibeAxis::Chaotic,
+            VibeAxis::Formal => VibeAxis::Casual,
+            VibeAxis::Casual => VibeAxis::Formal,
+            VibeAxis::Positive => VibeAxis::Negative,
+            VibeAxis::Negative => VibeAxis::Positive,
+        }
+    }
+}
+
+/// A direction along one vibe axis, from one of its ends to the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VibeRange {
+    start: VibeAxis,
+    end: VibeAxis,
+}
+
+impl VibeRange {
+    /// Creates a range from `start` to `end`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VibesortError::InvalidInput`] unless `start` and `end` are
+    /// the two ends of the same axis, such as `Chaotic` and `Calm`.
+    pub fn new(start: VibeAxis, end: VibeAxis) -> Result<Self, VibesortError> {
+        if start.opposite() != end {
+            return Err(VibesortError::InvalidInput(format!(
+                "{:?} and {:?} are not the two ends of one vibe axis",
+                start, end
+            )));
+        }
+        Ok(VibeRange { start, end })
+    }
+
+    /// Returns the end the sort starts from.
+    pub fn start(&self) -> VibeAxis {
+        self.start
+    }
+
+    /// Returns the end the sort runs to.
+    pub fn end(&self) -> VibeAxis {
+        self.end
+    }
+}
+
+impl TryFrom<Range<VibeAxis>> for VibeRange {
+    type Error = VibesortError;
+
+    fn try_from(range: Range<VibeAxis>) -> Result<Self, Self::Error> {
+        VibeRange::new(range.start, range.end)
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts short texts or emoji along a vibe axis.
+    ///
+    /// The items are ordered from the element that best matches the start of
+    /// the range to the one that best matches its end, so
+    /// `VibeAxis::Calm..VibeAxis::Chaotic` reverses
+    /// `VibeAxis::Chaotic..VibeAxis::Calm`. The configured criterion and order
+    /// are replaced for this call. See [`VibeRange::new`] for the ranges that
+    /// are accepted.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{VibeAxis, VibeRange, Vibesort};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// );
+    ///
+    /// let emoji = ["🌋", "🧘", "🎉", "🌙"].map(String::from);
+    /// let axis = VibeRange::try_from(VibeAxis::Chaotic..VibeAxis::Calm)?;
+    /// let sorted = sorter.sort_by_vibe(&emoji, axis).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_by_vibe<T>(
+        &self,
+        items: &[T],
+        axis: VibeRange,
+    ) -> Result<Vec<T>, VibesortError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let criterion = format!(
+            "by vibe, from the most {} to the most {}",
+            axis.start.describe(),
+            axis.end.describe()
+        );
+        let sorter = self.clone().order(Order::Ascending).criterion(criterion);
+        Ok(sorter.sort_with_report(items).await?.items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sort_by_vibe_prompt() {
+        let backend = Arc::new(MockBackend::new().respond_with(r#"["🌋","🧘"]"#));
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(backend.clone())
+            .order(Order::Descending);
+
+        let sorted = sorter
+            .sort_by_vibe(
+                &["🧘", "🌋"].map(String::from),
+                VibeRange::new(VibeAxis::Chaotic, VibeAxis::Calm).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(sorted, vec!["🌋", "🧘"]);
+
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        let system = system.as_str().unwrap();
+        assert!(system.contains("ascending order"));
+        assert!(system.contains("from the most chaotic"));
+        assert!(system.contains("to the most calm"));
+    }
+
+    #[test]
+    fn test_vibe_range_rejects_mixed_axes() {
+        for (start, end) in [
+            (VibeAxis::Chaotic, VibeAxis::Formal),
+            (VibeAxis::Calm, VibeAxis::Calm),
+        ] {
+            let err = VibeRange::try_from(start..end).unwrap_err();
+            assert!(matches!(err, VibesortError::InvalidInput(_)));
+        }
+        let range = VibeRange::try_from(VibeAxis::Negative..VibeAxis::Positive).unwrap();
+        assert_eq!(
+            (range.start(), range.end()),
+            (VibeAxis::Negative, VibeAxis::Positive)
+        );
+    }
+}
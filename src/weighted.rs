@@ -0,0 +1,207 @@
+//! Sorting by several weighted criteria, combined locally.
+
+use crate::indexed::{ElementReply, describe_payload};
+use crate::prompt::Operation;
+use crate::{Order, Vibesort, VibesortError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// An element sorted by [`Vibesort::sort_weighted`], with its scores.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightedItem<T> {
+    /// The element.
+    pub item: T,
+
+    /// The weighted mean of the per-criterion scores, used for sorting.
+    pub score: f64,
+
+    /// The score the model gave the element for each criterion, from 0 to
+    /// 10.
+    pub scores: BTreeMap<String, f64>,
+}
+
+/// The scores of one element, as returned by the model.
+#[derive(Debug, Deserialize)]
+struct ElementScores {
+    index: usize,
+    scores: BTreeMap<String, f64>,
+}
+
+impl ElementReply for ElementScores {
+    fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts the items by several criteria, each with a weight.
+    ///
+    /// The model scores every element on every criterion from 0 to 10; the
+    /// scores are then combined locally into a weighted mean and the items
+    /// are sorted by it in the configured [`order`](Self::order), so
+    /// [`Order::Descending`] puts the best-scoring elements first. Ties keep
+    /// their input order. The configured criterion is not used.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the model does not
+    /// score every element exactly once, and [`VibesortError::ParseError`] if
+    /// a score for one of the criteria is missing.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{Order, Vibesort};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-3.5-turbo",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .order(Order::Descending);
+    ///
+    /// let articles = ["Rust 1.0 released", "Rust 2024 edition ships"].map(String::from);
+    /// let ranked = sorter
+    ///     .sort_weighted(&articles, &[("relevance", 0.7), ("recency", 0.3)])
+    ///     .await?;
+    /// for article in ranked {
+    ///     println!("{} ({:.1}, {:?})", article.item, article.score, article.scores);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_weighted<T>(
+        &self,
+        items: &[T],
+        criteria: &[(&str, f64)],
+    ) -> Result<Vec<WeightedItem<T>>, VibesortError>
+    where
+        T: Serialize + Clone,
+    {
+        let names: Vec<&str> = criteria.iter().map(|(name, _)| *name).collect();
+        let fields = serde_json::to_string(&names)?;
+        // Every element gets an index and a small object of scores
+        let reply_bytes = items.len() * (16 + 16 * criteria.len());
+        let prompt = || {
+            format!(
+                "You are a helpful assistant that scores elements. {} Score every element on each of these criteria: {}, from 0 (lowest) to 10 (highest). Return ONLY a JSON array of objects of the form {{\"index\": <number>, \"scores\": {{<criterion>: <number>, ...}}}}, with every index exactly once.",
+                describe_payload("element"),
+                fields
+            )
+        };
+        let mut scored = self
+            .describe_indexed(
+                Operation::Weighted,
+                items,
+                reply_bytes,
+                prompt,
+                |scored: Vec<ElementScores>| {
+                    if let Some(name) = names.iter().find(|name| {
+                        scored
+                            .iter()
+                            .any(|element| !element.scores.contains_key(**name))
+                    }) {
+                        return Err(VibesortError::ParseError(format!(
+                            "missing score for criterion {:?}",
+                            name
+                        )));
+                    }
+                    Ok(scored)
+                },
+            )
+            .await?;
+
+        // Combine the scores locally into a weighted mean
+        let total_weight: f64 = criteria.iter().map(|(_, weight)| weight).sum();
+        let mut ranked: Vec<WeightedItem<T>> = items
+            .iter()
+            .zip(scored.iter_mut())
+            .map(|(item, element)| {
+                element
+                    .scores
+                    .retain(|name, _| names.contains(&name.as_str()));
+                let sum: f64 = criteria
+                    .iter()
+                    .map(|(name, weight)| weight * element.scores[*name])
+                    .sum();
+                WeightedItem {
+                    item: item.clone(),
+                    score: if total_weight == 0.0 {
+                        0.0
+                    } else {
+                        sum / total_weight
+                    },
+                    scores: std::mem::take(&mut element.scores),
+                }
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| match self.order {
+            Order::Ascending => a.score.total_cmp(&b.score),
+            Order::Descending => b.score.total_cmp(&a.score),
+        });
+        Ok(ranked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexed::replying;
+
+    #[tokio::test]
+    async fn test_sort_weighted() {
+        let (sorter, backend) = replying(&[r#"[
+            {"index": 1, "scores": {"relevance": 4, "recency": 10}},
+            {"index": 0, "scores": {"relevance": 8, "recency": 0, "humor": 3}}
+        ]"#]);
+        let sorter = sorter.order(Order::Descending);
+
+        let items = ["old", "new"].map(String::from);
+        let ranked = sorter
+            .sort_weighted(&items, &[("relevance", 0.75), ("recency", 0.25)])
+            .await
+            .unwrap();
+        assert_eq!(ranked[0].item, "old");
+        assert_eq!(ranked[0].score, 6.0);
+        assert_eq!(ranked[1].item, "new");
+        assert_eq!(ranked[1].score, 5.5);
+        assert_eq!(ranked[1].scores["recency"], 10.0);
+        assert!(!ranked[0].scores.contains_key("humor"));
+
+        let system = backend.requests()[0].body["messages"][0]["content"].clone();
+        assert!(
+            system
+                .as_str()
+                .unwrap()
+                .contains(r#"["relevance","recency"]"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sort_weighted_rejects_missing_scores() {
+        let reply = r#"[
+            {"index": 0, "scores": {"relevance": 4}},
+            {"index": 1, "scores": {"relevance": 6}}
+        ]"#;
+        let (sorter, _) = replying(&[reply, reply, reply]);
+
+        let err = sorter
+            .sort_weighted(&[1, 2], &[("relevance", 1.0), ("recency", 1.0)])
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, VibesortError::ParseError(message) if message.contains("\"recency\""))
+        );
+
+        // Scores missing for an element fail the index check instead
+        let (sorter, _) = replying(&[r#"[{"index": 0, "scores": {"relevance": 4}}]"#]);
+        let err = sorter
+            .sort_weighted(&[1, 2], &[("relevance", 1.0)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::VerificationFailed(_)));
+    }
+}
@@ -0,0 +1,189 @@
+//! Sorting words for word games.
+
+use crate::prompt::Operation;
+use crate::{Order, Vibesort, VibesortError};
+use serde::Serialize;
+
+/// A criterion for [`Vibesort::sort_words`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WordCriterion {
+    /// The sum of the English Scrabble letter values. Computable, so the
+    /// model's order is verified locally.
+    ScrabbleScore,
+
+    /// The number of vowels (a, e, i, o, u). Computable, so the model's order
+    /// is verified locally.
+    VowelCount,
+
+    /// How rarely the word is used in everyday English, from common to
+    /// obscure. Left to the model.
+    Obscurity,
+}
+
+impl WordCriterion {
+    /// Returns the score of a word under this criterion, or `None` if the
+    /// criterion cannot be computed locally.
+    ///
+    /// Letters are compared case-insensitively; characters other than the
+    /// letters a to z score nothing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vibesort_rs::WordCriterion;
+    ///
+    /// assert_eq!(WordCriterion::ScrabbleScore.score("Quiz"), Some(22));
+    /// assert_eq!(WordCriterion::VowelCount.score("queue"), Some(4));
+    /// assert_eq!(WordCriterion::Obscurity.score("quiz"), None);
+    /// ```
+    pub fn score(&self, word: &str) -> Option<u32> {
+        let letters = word.chars().map(|c| c.to_ascii_lowercase());
+        match self {
+            WordCriterion::ScrabbleScore => Some(letters.map(scrabble_value).sum()),
+            WordCriterion::VowelCount => {
+                Some(letters.filter(|c| "aeiou".contains(*c)).count() as u32)
+            }
+            WordCriterion::Obscurity => None,
+        }
+    }
+
+    /// Returns the criterion as used in the prompt.
+    fn describe(&self) -> &'static str {
+        match self {
+            WordCriterion::ScrabbleScore => {
+                "by Scrabble score: the sum of the English Scrabble letter values (A, E, I, L, N, O, R, S, T, U = 1; D, G = 2; B, C, M, P = 3; F, H, V, W, Y = 4; K = 5; J, X = 8; Q, Z = 10)"
+            }
+            WordCriterion::VowelCount => "by the number of vowels (a, e, i, o, u) in the word",
+            WordCriterion::Obscurity => {
+                "by obscurity: how rarely the word is used in everyday English, from common to obscure"
+            }
+        }
+    }
+}
+
+/// Returns the English Scrabble value of a lowercase letter.
+fn scrabble_value(letter: char) -> u32 {
+    match letter {
+        'a' | 'e' | 'i' | 'l' | 'n' | 'o' | 'r' | 's' | 't' | 'u' => 1,
+        'd' | 'g' => 2,
+        'b' | 'c' | 'm' | 'p' => 3,
+        'f' | 'h' | 'v' | 'w' | 'y' => 4,
+        'k' => 5,
+        'j' | 'x' => 8,
+        'q' | 'z' => 10,
+        _ => 0,
+    }
+}
+
+impl<'a> Vibesort<'a> {
+    /// Sorts words by a [`WordCriterion`], for word-game tooling.
+    ///
+    /// The model only replies with the positions of the words, which are
+    /// returned exactly as given. For criteria that can be
+    /// [computed](WordCriterion::score), the reply is checked against the
+    /// computed scores, and an order that contradicts them counts as an
+    /// invalid reply that is retried under the
+    /// [retry policy](Self::retry_policy); words with equal scores may come
+    /// in any order. The configured [`order`](Self::order) applies; the
+    /// configured criterion is replaced.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the same errors as [`sort`](Self::sort).
+    /// [`VibesortError::VerificationFailed`] is returned if the reply does not
+    /// name every word exactly once or contradicts the computed scores.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vibesort_rs::{Order, Vibesort, WordCriterion};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let sorter = Vibesort::new(
+    ///     "your-api-key",
+    ///     "gpt-4o-mini",
+    ///     "https://api.openai.com/v1",
+    /// )
+    /// .order(Order::Descending);
+    ///
+    /// let guesses = ["crane", "quirk", "adieu", "sphinx"];
+    /// let best_first = sorter
+    ///     .sort_words(&guesses, WordCriterion::ScrabbleScore)
+    ///     .await?;
+    /// let rarest_first = sorter.sort_words(&guesses, WordCriterion::Obscurity).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sort_words<T>(
+        &self,
+        words: &[T],
+        criterion: WordCriterion,
+    ) -> Result<Vec<T>, VibesortError>
+    where
+        T: AsRef<str> + Serialize + Clone,
+    {
+        let instruction = self.sort_instruction_for(Some(criterion.describe()));
+        let scores: Option<Vec<u32>> = words
+            .iter()
+            .map(|word| criterion.score(word.as_ref()))
+            .collect();
+
+        let check = |indices: &[usize]| {
+            let Some(scores) = &scores else {
+                return Ok(());
+            };
+            let misplaced = indices.windows(2).find(|pair| match self.order {
+                Order::Ascending => scores[pair[0]] > scores[pair[1]],
+                Order::Descending => scores[pair[0]] < scores[pair[1]],
+            });
+            match misplaced {
+                Some(pair) => Err(VibesortError::VerificationFailed(format!(
+                    "{:?} (score {}) is placed before {:?} (score {})",
+                    words[pair[0]].as_ref(),
+                    scores[pair[0]],
+                    words[pair[1]].as_ref(),
+                    scores[pair[1]]
+                ))),
+                None => Ok(()),
+            }
+        };
+        let indices = self
+            .sort_indexed_checked(Operation::Words, words, &instruction, check)
+            .await?;
+        Ok(indices
+            .into_iter()
+            .map(|index| words[index].clone())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+
+    #[tokio::test]
+    async fn test_sort_words_verifies_computable_criteria() {
+        let sorter = Vibesort::new("key", "model", "http://mock").backend(
+            MockBackend::new()
+                .respond_with("[0, 1]")
+                .respond_with("[0, 1]")
+                .respond_with("[0, 1]"),
+        );
+
+        // "quiz" scores 22 and "tea" 3, so ascending order puts "tea" first
+        let err = sorter
+            .sort_words(&["quiz", "tea"], WordCriterion::ScrabbleScore)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VibesortError::VerificationFailed(_)));
+
+        let sorter = Vibesort::new("key", "model", "http://mock")
+            .backend(MockBackend::new().respond_with("[0, 1]"));
+        let sorted = sorter
+            .sort_words(&["quiz", "tea"], WordCriterion::Obscurity)
+            .await
+            .unwrap();
+        assert_eq!(sorted, ["quiz", "tea"]);
+    }
+}
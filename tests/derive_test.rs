@@ -0,0 +1,56 @@
+#![cfg(feature = "derive")]
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use vibesort_rs::testing::MockBackend;
+use vibesort_rs::{Order, Vibesort, Vibesortable};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Vibesortable)]
+struct Song {
+    #[vibesort(criterion = "by how catchy the title is", desc)]
+    title: String,
+    #[vibesort(key)]
+    year: u16,
+    artist: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Vibesortable)]
+#[vibesort(criterion = "by spiciness")]
+enum Pepper {
+    Jalapeno,
+    Habanero,
+}
+
+#[test]
+fn test_derived_criterion_and_order() {
+    assert_eq!(
+        Song::vibesort_criterion(),
+        "by how catchy the title is, then by the `year` field (reversed)"
+    );
+    assert_eq!(Song::vibesort_order(), Order::Descending);
+
+    assert_eq!(Pepper::vibesort_criterion(), "by spiciness");
+    assert_eq!(Pepper::vibesort_order(), Order::Ascending);
+}
+
+#[tokio::test]
+async fn test_sort_vibesortable_uses_derived_prompt() {
+    let backend = Arc::new(MockBackend::new().respond_with(r#"["Jalapeno","Habanero"]"#));
+    let sorter = Vibesort::new("key", "model", "http://mock")
+        .backend(backend.clone())
+        .order(Order::Descending);
+
+    let sorted = sorter
+        .sort_vibesortable(&[Pepper::Habanero, Pepper::Jalapeno])
+        .await
+        .unwrap();
+    assert_eq!(sorted, vec![Pepper::Jalapeno, Pepper::Habanero]);
+
+    let system = backend.requests()[0].body["messages"][0]["content"].clone();
+    assert!(
+        system
+            .as_str()
+            .unwrap()
+            .contains("ascending order according to this criterion: by spiciness")
+    );
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "test-util")]
+
+use proptest::prelude::*;
+use serde_json::Value;
+use vibesort_rs::Vibesort;
+use vibesort_rs::parse::parse_array;
+use vibesort_rs::testing::strategies::{json_array, llm_content, permutation_of};
+use vibesort_rs::testing::{MockBackend, assert_is_permutation, assert_sorted_by_oracle};
+use vibesort_rs::verify::check_permutation;
+
+proptest! {
+    #[test]
+    fn parses_decorated_llm_output(
+        (values, content) in json_array(16).prop_flat_map(|v| (Just(v.clone()), llm_content(v)))
+    ) {
+        let parsed: Vec<Value> = parse_array(&content).unwrap();
+        prop_assert_eq!(parsed, values);
+    }
+
+    #[test]
+    fn permutations_pass_verification(
+        (values, shuffled) in json_array(16).prop_flat_map(|v| (Just(v.clone()), permutation_of(v)))
+    ) {
+        prop_assert!(check_permutation(&values, &shuffled).is_ok());
+        assert_is_permutation(&values, &shuffled);
+    }
+
+    #[test]
+    fn dropped_elements_fail_verification(mut values in json_array(16), index in any::<prop::sample::Index>()) {
+        prop_assume!(!values.is_empty());
+        let input = values.clone();
+        values.remove(index.index(values.len()));
+        prop_assert!(check_permutation(&input, &values).is_err());
+    }
+}
+
+#[tokio::test]
+async fn mock_backend_matches_oracle() {
+    let sorter = Vibesort::new("key", "model", "http://mock")
+        .backend(MockBackend::new())
+        .verify(true);
+
+    let input = vec![5, -3, 12, 0, 5, 7];
+    let sorted = sorter.sort(&input).await.unwrap();
+    assert_sorted_by_oracle(&input, &sorted);
+}
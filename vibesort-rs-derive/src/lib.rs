@@ -0,0 +1,136 @@
+//! Derive macro for [`vibesort-rs`](https://docs.rs/vibesort-rs).
+//!
+//! Use the macro through the `derive` feature of `vibesort-rs`, which
+//! re-exports it as `vibesort_rs::Vibesortable`.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, parse_macro_input};
+
+/// Derives `vibesort_rs::Vibesortable`, generating the sort criterion from
+/// `#[vibesort(...)]` attributes.
+///
+/// On a field, `key` sorts by that field, `desc` sorts by it in descending
+/// order, and `criterion = "..."` describes how to judge it. Each of them
+/// marks the field as a key; keys are applied in declaration order. On the
+/// type, `criterion = "..."` and `desc` describe the ordering of the whole
+/// value instead.
+#[proc_macro_derive(Vibesortable, attributes(vibesort))]
+pub fn derive_vibesortable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// The options given in the `#[vibesort(...)]` attributes of a type or field.
+#[derive(Default)]
+struct Options {
+    key: bool,
+    desc: bool,
+    criterion: Option<String>,
+}
+
+impl Options {
+    fn parse(attrs: &[syn::Attribute], allow_key: bool) -> syn::Result<Self> {
+        let mut options = Options::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("vibesort")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("key") && allow_key {
+                    options.key = true;
+                } else if meta.path.is_ident("desc") {
+                    options.desc = true;
+                } else if meta.path.is_ident("criterion") {
+                    options.criterion = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else {
+                    return Err(meta.error("unsupported vibesort attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+
+    fn is_key(&self) -> bool {
+        self.key || self.desc || self.criterion.is_some()
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let container = Options::parse(&input.attrs, false)?;
+
+    let mut keys = Vec::new();
+    if let Data::Struct(data) = &input.data {
+        if let Fields::Named(fields) = &data.fields {
+            for field in &fields.named {
+                let options = Options::parse(&field.attrs, true)?;
+                if options.is_key() {
+                    let name = field.ident.as_ref().map(ToString::to_string);
+                    keys.push((name.unwrap_or_default(), options));
+                }
+            }
+        } else {
+            for field in data.fields.iter() {
+                if Options::parse(&field.attrs, true)?.is_key() {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "vibesort key fields must be named",
+                    ));
+                }
+            }
+        }
+    }
+
+    let (criterion, desc) = match (container.criterion, keys.first()) {
+        (Some(_), Some(_)) => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "a type-level vibesort criterion cannot be combined with key fields",
+            ));
+        }
+        (Some(criterion), None) => (criterion, container.desc),
+        (None, Some((_, first))) => {
+            let parts: Vec<String> = keys
+                .iter()
+                .map(|(name, options)| {
+                    let part = match &options.criterion {
+                        Some(criterion) => criterion.clone(),
+                        None => format!("by the `{}` field", name),
+                    };
+                    if options.desc == first.desc {
+                        part
+                    } else {
+                        format!("{} (reversed)", part)
+                    }
+                })
+                .collect();
+            (parts.join(", then "), first.desc)
+        }
+        (None, None) => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "expected #[vibesort(criterion = \"...\")] on the type or at least one #[vibesort(key)] field",
+            ));
+        }
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let order = if desc {
+        quote!(::vibesort_rs::Order::Descending)
+    } else {
+        quote!(::vibesort_rs::Order::Ascending)
+    };
+    Ok(quote! {
+        impl #impl_generics ::vibesort_rs::Vibesortable for #name #ty_generics #where_clause {
+            fn vibesort_criterion() -> &'static str {
+                #criterion
+            }
+
+            fn vibesort_order() -> ::vibesort_rs::Order {
+                #order
+            }
+        }
+    })
+}